@@ -0,0 +1,152 @@
+//! `#[derive(FixMessageSpec)]` for the `fix_checksum` crate.
+//!
+//! The struct names its message type with `#[fix(msg_type = "D")]` and every
+//! field its tag with `#[fix(tag = 11)]`. Field types implement
+//! `fix_checksum::spec::FixField`; `Option` fields are optional in the
+//! message. The macro works on the token stream directly, so it only accepts
+//! plain structs with named fields and no generics.
+
+extern crate proc_macro;
+
+use proc_macro::{Delimiter, TokenStream, TokenTree};
+
+/// Implements `fix_checksum::spec::FixMessageSpec` for a struct.
+#[proc_macro_derive(FixMessageSpec, attributes(fix))]
+pub fn derive_fix_message_spec(input: TokenStream) -> TokenStream {
+  let code = match MessageSpec::parse(input) {
+    Ok(spec) => spec.generate(),
+    Err(message) => format!("compile_error!({:?});", message),
+  };
+  code.parse().expect("generated code must be valid")
+}
+
+struct FieldSpec {
+  name: String,
+  tag: u32,
+}
+
+struct MessageSpec {
+  name: String,
+  msg_type: String,
+  fields: Vec<FieldSpec>,
+}
+
+impl MessageSpec {
+  fn parse(input: TokenStream) -> Result<MessageSpec, String> {
+    let mut msg_type = None;
+    let mut name = None;
+    let mut body = None;
+    let mut tokens = input.into_iter().peekable();
+
+    while let Some(token) = tokens.next() {
+      match token {
+        TokenTree::Punct(ref punct) if punct.as_char() == '#' => {
+          if let Some(TokenTree::Group(attribute)) = tokens.next() {
+            if let Some(value) = fix_attribute(attribute.stream(), "msg_type")? { msg_type = Some(value); }
+          }
+        },
+        TokenTree::Ident(ref ident) if ident.to_string() == "struct" => {
+          name = match tokens.next() {
+            Some(TokenTree::Ident(ident)) => Some(ident.to_string()),
+            _ => return Err("FixMessageSpec expects a struct name".to_string()),
+          };
+          body = match tokens.next() {
+            Some(TokenTree::Group(ref group)) if group.delimiter() == Delimiter::Brace => Some(group.stream()),
+            _ => return Err("FixMessageSpec supports structs with named fields and no generics".to_string()),
+          };
+        },
+        TokenTree::Ident(ref ident) if ident.to_string() == "enum" || ident.to_string() == "union" =>
+          return Err("FixMessageSpec can only be derived for structs".to_string()),
+        _ => {},
+      }
+    }
+
+    let name = name.ok_or("FixMessageSpec can only be derived for structs")?;
+    let msg_type = msg_type.ok_or("FixMessageSpec requires #[fix(msg_type = \"...\")] on the struct")?;
+    let fields = parse_fields(body.unwrap_or_default())?;
+    let msg_type = msg_type.trim_matches('"').to_string();
+    Ok(MessageSpec { name, msg_type, fields })
+  }
+
+  fn generate(&self) -> String {
+    let encode = self.fields.iter().map(|field| format!(
+      "if let Some(value) = ::fix_checksum::spec::FixField::encode(&self.{}) {{ fields.push(({}, value)); }}",
+      field.name, field.tag)).collect::<Vec<_>>().join("\n");
+    let decode = self.fields.iter().map(|field| format!(
+      "{}: ::fix_checksum::spec::FixField::decode({}, view.get({}))?,", field.name, field.tag, field.tag))
+      .collect::<Vec<_>>().join("\n");
+
+    format!("impl ::fix_checksum::spec::FixMessageSpec for {name} {{
+      const MSG_TYPE: &'static str = {msg_type:?};
+
+      fn encode_fields(&self) -> ::std::vec::Vec<(u32, ::std::string::String)> {{
+        let mut fields = ::std::vec::Vec::new();
+        {encode}
+        fields
+      }}
+
+      fn decode_fields(view: &::fix_checksum::message::MessageView)
+        -> ::std::result::Result<Self, ::fix_checksum::FIXChecksumValidatorError> {{
+        ::std::result::Result::Ok({name} {{ {decode} }})
+      }}
+    }}", name = self.name, msg_type = self.msg_type, encode = encode, decode = decode)
+  }
+}
+
+/// Splits the body of a struct into fields with their `fix` attributes.
+fn parse_fields(body: TokenStream) -> Result<Vec<FieldSpec>, String> {
+  let mut fields = Vec::new();
+  let mut tag = None;
+  let mut name = None;
+  let mut in_type = false;
+  let mut angle_depth = 0;
+
+  for token in body {
+    match token {
+      TokenTree::Group(ref group) if !in_type && group.delimiter() == Delimiter::Bracket => {
+        if let Some(value) = fix_attribute(group.stream(), "tag")? {
+          tag = Some(value.parse::<u32>().map_err(|_| format!("invalid FIX tag {}", value))?);
+        }
+      },
+      TokenTree::Ident(ref ident) if !in_type && ident.to_string() != "pub" => name = Some(ident.to_string()),
+      TokenTree::Punct(ref punct) if !in_type && punct.as_char() == ':' => in_type = true,
+      TokenTree::Punct(ref punct) if in_type && punct.as_char() == '<' => angle_depth += 1,
+      TokenTree::Punct(ref punct) if in_type && punct.as_char() == '>' => angle_depth -= 1,
+      TokenTree::Punct(ref punct) if in_type && angle_depth == 0 && punct.as_char() == ',' => {
+        fields.push(field_spec(name.take(), tag.take())?);
+        in_type = false;
+      },
+      _ => {},
+    }
+  }
+  if in_type { fields.push(field_spec(name.take(), tag.take())?); }
+  Ok(fields)
+}
+
+fn field_spec(name: Option<String>, tag: Option<u32>) -> Result<FieldSpec, String> {
+  let name = name.ok_or("FixMessageSpec expects named fields")?;
+  let tag = tag.ok_or_else(|| format!("field `{}` needs a #[fix(tag = ...)] attribute", name))?;
+  Ok(FieldSpec { name, tag })
+}
+
+/// Returns the value of `key` if `attribute` is `fix(key = value)`.
+fn fix_attribute(attribute: TokenStream, key: &str) -> Result<Option<String>, String> {
+  let mut tokens = attribute.into_iter();
+  match tokens.next() {
+    Some(TokenTree::Ident(ref ident)) if ident.to_string() == "fix" => {},
+    _ => return Ok(None),
+  }
+  let arguments = match tokens.next() {
+    Some(TokenTree::Group(group)) => group.stream().into_iter().collect::<Vec<_>>(),
+    _ => return Err("expected #[fix(key = value)]".to_string()),
+  };
+  match arguments.as_slice() {
+    [TokenTree::Ident(ident), TokenTree::Punct(punct), TokenTree::Literal(value)]
+      if punct.as_char() == '=' => {
+      let name = ident.to_string();
+      if name != "tag" && name != "msg_type" { return Err(format!("unknown fix attribute `{}`", name)); }
+      Ok(if name == key { Some(value.to_string()) } else { None })
+    },
+    _ => Err("expected #[fix(key = value)]".to_string()),
+  }
+}
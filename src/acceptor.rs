@@ -0,0 +1,156 @@
+//! A minimal FIX acceptor: the server side of a session.
+//!
+//! An `Acceptor` reads the Logon (A) opening a connection, checks its
+//! credentials with a callback, restores the sequence numbers of the
+//! session it names from the `MessageStore` and answers with its own
+//! Logon. The `Connection` it returns runs the session from there, see
+//! `connection`, with the heartbeat interval asked for by the initiator.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use std::net::TcpListener;
+//! use fix_checksum::acceptor::Acceptor;
+//! use fix_checksum::connection::Event;
+//! use fix_checksum::store::MemoryStore;
+//!
+//! let acceptor = Acceptor::new(|session, logon| match logon.get_str(554) {
+//!   Some("secret") if session.target_comp_id == "BRKR" => Ok(()),
+//!   _ => Err("invalid credentials".to_string()),
+//! });
+//! let listener = TcpListener::bind("127.0.0.1:9876").unwrap();
+//! let mut store = MemoryStore::new();
+//! loop {
+//!   let mut connection = acceptor.accept(&listener, &mut store).unwrap();
+//!   while let Ok(Event::Application(message)) = connection.next_event() {
+//!     println!("{}: {}", connection.session(), String::from_utf8_lossy(&message));
+//!   }
+//! }
+//! ```
+
+use std::io;
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::time::Duration;
+
+use super::connection::{Connection, parse};
+use super::message::MessageView;
+use super::session::SessionId;
+use super::store::MessageStore;
+
+/// Accepts the sessions whose Logon passes `authenticate`.
+///
+/// The callback gets the session as seen by the acceptor, its own
+/// SenderCompID (49) first, and the Logon message, to check fields such as
+/// Username (553) and Password (554). The text of an error is sent back in
+/// the Logout (5) rejecting the logon.
+pub struct Acceptor<F> {
+  authenticate: F,
+}
+
+impl<F: Fn(&SessionId, &MessageView) -> Result<(), String>> Acceptor<F> {
+  /// Creates an acceptor checking logons with `authenticate`.
+  pub fn new(authenticate: F) -> Acceptor<F> {
+    Acceptor { authenticate }
+  }
+
+  /// Waits for the next connection on `listener` and accepts its session,
+  /// setting the read timeout of the connection to the heartbeat interval.
+  pub fn accept<M: MessageStore>(&self, listener: &TcpListener, store: M) -> io::Result<Connection<TcpStream, M>> {
+    let (stream, _) = listener.accept()?;
+    let connection = self.accept_stream(stream, store)?;
+    let interval = connection.heartbeat_interval();
+    connection.get_ref().set_read_timeout(if interval.as_secs() == 0 { None } else { Some(interval) })?;
+    Ok(connection)
+  }
+
+  /// Reads the Logon opening `stream` and accepts its session. A first
+  /// message which is not a Logon fails with `InvalidData`, rejected
+  /// credentials with `PermissionDenied` after the Logout is sent.
+  pub fn accept_stream<S: Read + Write, M: MessageStore>(&self, stream: S, store: M) -> io::Result<Connection<S, M>> {
+    let mut connection = Connection::new(stream, SessionId::new("", ""), store);
+    let message = connection.read_frame()?;
+    let logon = parse(&message)?;
+    if logon.get(35) != Some(b"A") {
+      return Err(io::Error::new(io::ErrorKind::InvalidData, "first message is not a logon"));
+    }
+    let (sender, target) = match (logon.get_str(56), logon.get_str(49)) {
+      (Some(sender), Some(target)) => (sender, target),
+      _ => return Err(io::Error::new(io::ErrorKind::InvalidData, "logon without comp ids")),
+    };
+    connection.session = SessionId::new(sender, target);
+    connection.begin_string = logon.get_str(8).unwrap_or("FIX.4.2").to_string();
+    let heartbeat_interval = logon.get_str(108).and_then(|value| value.parse().ok()).unwrap_or(0);
+    connection.heartbeat_interval = Duration::from_secs(heartbeat_interval);
+    connection.checkpoint = connection.store.restore(&connection.session)?;
+    connection.receive(&message)?;
+
+    if let Err(text) = (self.authenticate)(&connection.session, &logon) {
+      connection.send_admin("5", |writer| { let _ = writer.text(58, &text); })?;
+      return Err(io::Error::new(io::ErrorKind::PermissionDenied, text));
+    }
+    connection.send_admin("A", |writer| {
+      writer.field(98, 0);
+      writer.field(108, heartbeat_interval);
+    })?;
+    Ok(connection)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::io;
+  use std::time::Duration;
+  use super::Acceptor;
+  use super::super::connection::Event;
+  use super::super::connection::tests::{Script, message, sent};
+  use super::super::message::MessageView;
+  use super::super::session::SessionId;
+  use super::super::store::{Checkpoint, MemoryStore, MessageStore};
+
+  fn initiator(seq_num: u64, msg_type: &str, fields: &[(u32, &str)]) -> Vec<u8> {
+    message("BRKR", "EXCH", seq_num, msg_type, fields)
+  }
+
+  fn acceptor() -> Acceptor<impl Fn(&SessionId, &MessageView) -> Result<(), String>> {
+    Acceptor::new(|session: &SessionId, logon: &MessageView| {
+      if session.target_comp_id == "BRKR" && logon.get_str(554) == Some("secret") { Ok(()) } else {
+        Err("invalid credentials".to_string())
+      }
+    })
+  }
+
+  #[test]
+  fn it_should_accept_authenticated_sessions() {
+    let session = SessionId::new("EXCH", "BRKR");
+    let mut store = MemoryStore::new();
+    let mut checkpoint = Checkpoint::new();
+    checkpoint.sent(7);
+    checkpoint.received(4);
+    store.checkpoint(&session, &checkpoint).unwrap();
+
+    let incoming = vec![initiator(5, "A", &[(98, "0"), (108, "20"), (554, "secret")]), initiator(6, "D", &[(11, "A1")]),
+      initiator(7, "5", &[])];
+    let mut connection = acceptor().accept_stream(Script::new(incoming), &mut store).unwrap();
+    assert_eq!(connection.session(), &session);
+    assert_eq!(connection.heartbeat_interval(), Duration::from_secs(20));
+    assert!(matches!(connection.next_event().unwrap(), Event::Application(_)));
+    assert_eq!(connection.next_event().unwrap(), Event::LoggedOut);
+    assert_eq!(sent(connection.get_ref()), vec![("A".to_string(), None), ("5".to_string(), None)]);
+    assert_eq!(connection.checkpoint().next_sender_seq_num(), 10);
+
+    let checkpoint = store.restore(&session).unwrap();
+    assert_eq!((checkpoint.last_sent, checkpoint.last_received), (9, 7));
+    assert_eq!(store.messages(&session, 8, 9).unwrap().len(), 2);
+  }
+
+  #[test]
+  fn it_should_reject_invalid_logons() {
+    let script = Script::new(vec![initiator(1, "A", &[(98, "0"), (108, "30"), (554, "guess")])]);
+    let err = acceptor().accept_stream(script, MemoryStore::new()).err().unwrap();
+    assert_eq!((err.kind(), err.to_string()), (io::ErrorKind::PermissionDenied, "invalid credentials".to_string()));
+
+    let script = Script::new(vec![initiator(1, "D", &[(11, "A1")])]);
+    assert_eq!(acceptor().accept_stream(script, MemoryStore::new()).err().unwrap().kind(), io::ErrorKind::InvalidData);
+  }
+}
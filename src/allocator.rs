@@ -0,0 +1,163 @@
+//! Owned messages and field arenas in a caller-chosen allocator, for
+//! huge-page or NUMA-pinned memory. Needs a nightly compiler for the
+//! unstable `allocator_api`.
+//!
+//! # Examples
+//!
+//! ```
+//! #![feature(allocator_api)]
+//! use std::alloc::Global;
+//! use fix_checksum::allocator::FixMessageIn;
+//!
+//! let message = FixMessageIn::parse_in(b"8=FIX.4.2|35=0|", '|', Global).unwrap();
+//! assert_eq!(message.get_str(35), Some("0"));
+//! ```
+
+use std::alloc::Allocator;
+
+use super::FIXChecksumValidatorError;
+use super::message::{ArenaMessage, FixMessage, split_fields};
+
+/// An owned message, see `FixMessage`, whose fields are allocated in `A`.
+#[derive(Clone, Debug)]
+pub struct FixMessageIn<A: Allocator + Clone> {
+  fields: Vec<(u32, Vec<u8, A>), A>,
+  delimiter: u8,
+  allocator: A,
+}
+
+impl<A: Allocator + Clone> FixMessageIn<A> {
+  /// Creates a message without fields.
+  pub fn new_in(delimiter: char, allocator: A) -> FixMessageIn<A> {
+    assert!(delimiter.is_ascii(), "FIX delimiter must be an ASCII character");
+    FixMessageIn { fields: Vec::new_in(allocator.clone()), delimiter: delimiter as u8, allocator }
+  }
+
+  /// Splits `message` into fields as `MessageView::parse` does and copies
+  /// them into `allocator`.
+  pub fn parse_in(message: &[u8], delimiter: char, allocator: A)
+    -> Result<FixMessageIn<A>, FIXChecksumValidatorError> {
+    let mut owned = FixMessageIn::new_in(delimiter, allocator);
+    let (fields, allocator) = (&mut owned.fields, &owned.allocator);
+    split_fields(message, owned.delimiter, |tag, start, end| {
+      let mut value = Vec::with_capacity_in(end - start, allocator.clone());
+      value.extend_from_slice(&message[start..end]);
+      fields.push((tag, value));
+    })?;
+    Ok(owned)
+  }
+
+  /// Returns the allocator of the fields.
+  pub fn allocator(&self) -> &A {
+    &self.allocator
+  }
+
+  /// Returns the value of the first occurrence of `tag`.
+  pub fn get(&self, tag: u32) -> Option<&[u8]> {
+    self.fields.iter().find(|field| field.0 == tag).map(|field| &field.1[..])
+  }
+
+  /// Returns the value of the first occurrence of `tag` if it is valid UTF-8.
+  pub fn get_str(&self, tag: u32) -> Option<&str> {
+    self.get(tag).and_then(|value| ::std::str::from_utf8(value).ok())
+  }
+
+  /// Returns the number of fields.
+  pub fn len(&self) -> usize {
+    self.fields.len()
+  }
+
+  /// Returns `true` if the message has no fields.
+  pub fn is_empty(&self) -> bool {
+    self.fields.is_empty()
+  }
+
+  /// Returns the fields in wire order.
+  pub fn fields(&self) -> impl Iterator<Item = (u32, &[u8])> {
+    self.fields.iter().map(|&(tag, ref value)| (tag, &value[..]))
+  }
+
+  /// Replaces the value of the first occurrence of `tag`, or appends the
+  /// field when the message does not have it.
+  pub fn set(&mut self, tag: u32, value: &[u8]) {
+    match self.fields.iter_mut().find(|field| field.0 == tag) {
+      Some(field) => { field.1.clear(); field.1.extend_from_slice(value); },
+      None => {
+        let mut owned = Vec::with_capacity_in(value.len(), self.allocator.clone());
+        owned.extend_from_slice(value);
+        self.fields.push((tag, owned));
+      },
+    }
+  }
+
+  /// Removes the first occurrence of `tag`. Returns `true` if it was found.
+  pub fn remove(&mut self, tag: u32) -> bool {
+    match self.fields.iter().position(|field| field.0 == tag) {
+      Some(index) => { self.fields.remove(index); true },
+      None => false,
+    }
+  }
+
+  /// Copies the fields into a `FixMessage` in the global allocator.
+  pub fn to_message(&self) -> FixMessage {
+    let mut message = FixMessage::new(self.delimiter as char);
+    for (tag, value) in self.fields() { message.push(tag, value); }
+    message
+  }
+}
+
+/// A field arena, see `FieldArena`, whose index is allocated in `A`.
+#[derive(Clone, Debug)]
+pub struct FieldArenaIn<A: Allocator> {
+  index: Vec<(u32, usize, usize), A>,
+}
+
+impl<A: Allocator> FieldArenaIn<A> {
+  /// Creates an arena with storage for `fields` fields in `allocator`.
+  pub fn with_capacity_in(fields: usize, allocator: A) -> FieldArenaIn<A> {
+    FieldArenaIn { index: Vec::with_capacity_in(fields, allocator) }
+  }
+
+  /// Returns how many fields fit without allocating.
+  pub fn capacity(&self) -> usize {
+    self.index.capacity()
+  }
+
+  /// Splits `message` into fields, replacing the fields of the previous
+  /// message.
+  pub fn parse<'a>(&'a mut self, message: &'a [u8], delimiter: char)
+    -> Result<ArenaMessage<'a>, FIXChecksumValidatorError> {
+    assert!(delimiter.is_ascii(), "FIX delimiter must be an ASCII character");
+    self.index.clear();
+    let index = &mut self.index;
+    split_fields(message, delimiter as u8, |tag, start, end| index.push((tag, start, end)))?;
+    Ok(ArenaMessage { message, index: &self.index, delimiter: delimiter as u8 })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::alloc::{Global, System};
+  use super::{FieldArenaIn, FixMessageIn};
+  use super::super::message::FixMessage;
+
+  #[test]
+  fn it_should_keep_messages_in_allocator() {
+    let wire = b"8=FIX.4.2|35=D|55=IBM|";
+    let mut message = FixMessageIn::parse_in(wire, '|', System).unwrap();
+    assert_eq!(message.to_message(), FixMessage::parse(wire, '|').unwrap());
+
+    message.set(55, b"MSFT");
+    message.set(54, b"1");
+    assert!(message.remove(35) && !message.remove(35));
+    let fields: Vec<(u32, &[u8])> = message.fields().collect();
+    assert_eq!(fields, vec![(8, &b"FIX.4.2"[..]), (55, &b"MSFT"[..]), (54, &b"1"[..])]);
+  }
+
+  #[test]
+  fn it_should_index_fields_in_allocator() {
+    let mut arena = FieldArenaIn::with_capacity_in(8, Global);
+    assert_eq!(arena.parse(b"8=FIX.4.2|35=0|", '|').unwrap().get_str(35), Some("0"));
+    assert_eq!(arena.capacity(), 8);
+  }
+}
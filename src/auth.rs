@@ -0,0 +1,141 @@
+//! Message authentication with an HMAC-SHA-256 carried in the message, for
+//! counterparties requiring authenticated FIX without TLS.
+//!
+//! The HMAC covers the body: every field except BeginString (8),
+//! BodyLength (9), CheckSum (10) and the authentication field itself, in
+//! wire order, joined with SOH whatever the delimiter of the options so
+//! that a message keeps verifying when logged or relayed with another one.
+//! It is written as lowercase hexadecimal before the checksum, by default
+//! as Signature (89) after SignatureLength (93).
+
+use super::{FIXChecksumValidatorError, ValidationOptions, finalize, validate_bytes_with};
+use super::FIXChecksumValidatorError::RequiredFieldMissing;
+use super::hash::{hmac_sha256, to_hex};
+use super::message::MessageView;
+
+/// Signs and verifies messages with a shared key.
+///
+/// # Examples
+///
+/// ```
+/// use fix_checksum::ValidationOptions;
+/// use fix_checksum::auth::HmacAuthenticator;
+///
+/// let options = ValidationOptions::new().delimiter('|');
+/// let authenticator = HmacAuthenticator::new(b"secret");
+/// let signed = authenticator.sign(b"8=FIX.4.2|35=0|49=BRKR|56=EXCH|", &options).unwrap();
+/// assert!(String::from_utf8_lossy(&signed).contains("|93=64|89="));
+/// assert!(authenticator.verify(&signed, &options).unwrap());
+/// assert!(!HmacAuthenticator::new(b"other").verify(&signed, &options).unwrap());
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct HmacAuthenticator {
+  key: Vec<u8>,
+  tag: u32,
+}
+
+impl HmacAuthenticator {
+  /// Creates an authenticator writing the HMAC to Signature (89).
+  pub fn new(key: &[u8]) -> HmacAuthenticator {
+    HmacAuthenticator { key: key.to_vec(), tag: 89 }
+  }
+
+  /// Carries the HMAC in `tag` instead, usually a user-defined tag.
+  pub fn tag(mut self, tag: u32) -> HmacAuthenticator {
+    self.tag = tag;
+    self
+  }
+
+  /// Returns `message` with its HMAC, replacing a previous one, and a
+  /// recomputed BodyLength and CheckSum.
+  pub fn sign(&self, message: &[u8], options: &ValidationOptions) -> Result<Vec<u8>, FIXChecksumValidatorError> {
+    let view = MessageView::parse(message, options.delimiter as char)?;
+    let mac = self.mac(&view);
+
+    let mut signed = Vec::with_capacity(message.len() + 80);
+    for (tag, value) in view.fields().filter(|field| self.is_signed(field.0)) {
+      write_field(&mut signed, tag, value, options.delimiter);
+    }
+    if self.tag == 89 { write_field(&mut signed, 93, mac.len().to_string().as_bytes(), options.delimiter); }
+    write_field(&mut signed, self.tag, mac.as_bytes(), options.delimiter);
+    finalize(&signed, options)
+  }
+
+  /// Returns `true` if the checksum of `message` is valid and its HMAC
+  /// matches. A message without the authentication field fails with
+  /// `RequiredFieldMissing`.
+  pub fn verify(&self, message: &[u8], options: &ValidationOptions) -> Result<bool, FIXChecksumValidatorError> {
+    if !validate_bytes_with(message, options)? { return Ok(false); }
+    let view = MessageView::parse(message, options.delimiter as char)?;
+    let carried = view.get(self.tag).ok_or(RequiredFieldMissing(self.tag))?;
+
+    let expected = self.mac(&view);
+    // Compare every byte so the time taken does not reveal the matching prefix
+    let difference = expected.bytes().zip(carried).fold(0, |difference, (a, b)| difference | (a ^ b));
+    Ok(difference == 0 && expected.len() == carried.len())
+  }
+
+  /// Returns `false` for the fields written by `sign` itself.
+  fn is_signed(&self, tag: u32) -> bool {
+    tag != 9 && tag != 10 && tag != self.tag && !(self.tag == 89 && tag == 93)
+  }
+
+  fn mac(&self, view: &MessageView) -> String {
+    let mut body = Vec::new();
+    for (tag, value) in view.fields().filter(|field| field.0 != 8 && self.is_signed(field.0)) {
+      write_field(&mut body, tag, value, b'\x01');
+    }
+    to_hex(&hmac_sha256(&self.key, &body))
+  }
+}
+
+pub(crate) fn write_field(message: &mut Vec<u8>, tag: u32, value: &[u8], delimiter: u8) {
+  message.extend_from_slice(tag.to_string().as_bytes());
+  message.push(b'=');
+  message.extend_from_slice(value);
+  message.push(delimiter);
+}
+
+#[cfg(test)]
+mod tests {
+  use super::HmacAuthenticator;
+  use super::super::{ValidationOptions, validate_bytes_with};
+  use super::super::FIXChecksumValidatorError::RequiredFieldMissing;
+
+  const HEARTBEAT: &[u8] = b"8=FIX.4.2|9=36|35=0|49=BRKR|56=EXCH|34=2|10=000|";
+
+  #[test]
+  fn it_should_sign_into_custom_tag() {
+    let options = ValidationOptions::new().delimiter('|').check_body_length(true).strict(true);
+    let authenticator = HmacAuthenticator::new(b"secret").tag(5001);
+    let signed = authenticator.sign(HEARTBEAT, &options).unwrap();
+    assert!(validate_bytes_with(&signed, &options).unwrap());
+    assert!(authenticator.verify(&signed, &options).unwrap());
+    assert_eq!(authenticator.sign(&signed, &options).unwrap(), signed);
+
+    let tampered = String::from_utf8(signed).unwrap().replace("34=2|", "34=3|");
+    let tampered = super::super::finalize(tampered.as_bytes(), &options).unwrap();
+    assert!(!authenticator.verify(&tampered, &options).unwrap());
+  }
+
+  #[test]
+  fn it_should_require_authentication_field() {
+    let options = ValidationOptions::new().delimiter('|');
+    let message = super::super::finalize(HEARTBEAT, &options).unwrap();
+    assert_eq!(HmacAuthenticator::new(b"secret").verify(&message, &options), Err(RequiredFieldMissing(89)));
+    assert_eq!(HmacAuthenticator::new(b"secret").verify(HEARTBEAT, &options), Ok(false));
+  }
+
+  #[test]
+  fn it_should_verify_whatever_the_delimiter() {
+    let (soh, pipe) = (ValidationOptions::new(), ValidationOptions::new().delimiter('|'));
+    let authenticator = HmacAuthenticator::new(b"secret");
+    let message: Vec<u8> = HEARTBEAT.iter().map(|b| if *b == b'|' { 1 } else { *b }).collect();
+    let signed = authenticator.sign(&message, &soh).unwrap();
+    assert!(authenticator.verify(&signed, &soh).unwrap());
+    let relayed: Vec<u8> = signed.iter().map(|b| if *b == 1 { b'|' } else { *b }).collect();
+    let relayed = super::super::finalize(&relayed, &pipe).unwrap();
+    assert!(authenticator.verify(&relayed, &pipe).unwrap());
+    assert_eq!(authenticator.sign(&relayed, &pipe).unwrap(), relayed);
+  }
+}
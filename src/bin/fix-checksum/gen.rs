@@ -0,0 +1,34 @@
+//! Generated load test corpora.
+
+use std::io;
+use std::io::Write;
+
+use fix_checksum::generator::CorpusGenerator;
+use fix_checksum::session::SessionId;
+
+/// Writes `count` messages of a BRKR to EXCH session mixing orders, cancels
+/// and executions as `mix` says, one per line.
+pub fn run<W: Write>(output: &mut W, count: usize, mix: (u32, u32, u32), seed: u64) -> io::Result<()> {
+  let generator = CorpusGenerator::new(SessionId::new("BRKR", "EXCH")).mix(mix.0, mix.1, mix.2).seed(seed);
+  for message in generator.take(count) {
+    output.write_all(&message)?;
+    output.write_all(b"\n")?;
+  }
+  output.flush()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::run;
+  use fix_checksum::ValidationOptions;
+  use fix_checksum::stream::{StreamValidator, Outcome};
+
+  #[test]
+  fn it_should_write_valid_messages() {
+    let mut output = Vec::new();
+    run(&mut output, 50, (2, 1, 2), 9).unwrap();
+    let results = StreamValidator::new(&output[..], ValidationOptions::new()).collect::<Result<Vec<_>, _>>().unwrap();
+    assert_eq!(results.len(), 50);
+    assert!(results.iter().all(|result| result.outcome == Outcome::Valid));
+  }
+}
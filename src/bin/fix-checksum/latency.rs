@@ -0,0 +1,40 @@
+//! Latency summaries of a message log.
+
+use std::io;
+use std::io::{BufRead, Write};
+
+use fix_checksum::ValidationOptions;
+use fix_checksum::latency::LatencyAnalyzer;
+use fix_checksum::message::MessageView;
+use fix_checksum::stream::{StreamValidator, Outcome};
+
+/// Writes a summary line per session, message type and latency kind of the
+/// valid messages of `input`.
+pub fn run<R: BufRead, W: Write>(input: R, output: &mut W, options: ValidationOptions, delimiter: char)
+  -> io::Result<()> {
+  let mut analyzer = LatencyAnalyzer::new();
+  for result in StreamValidator::new(input, options) {
+    let result = result?;
+    if result.outcome != Outcome::Valid { continue; }
+    if let Ok(message) = MessageView::parse(&result.message, delimiter) { analyzer.record(&message, None); }
+  }
+  for summary in analyzer.summaries() { writeln!(output, "{}", summary)?; }
+  output.flush()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::run;
+  use fix_checksum::{ValidationOptions, ErrorPolicy};
+
+  #[test]
+  fn it_should_summarize_latencies() {
+    let input = b"8=FIX.4.2|9=65|35=8|49=A|56=B|52=20240102-09:30:00.050|60=20240102-09:30:00.000|10=048|\n\
+      8=FIX.4.2|9=65|35=8|49=A|56=B|52=20240102-09:30:00.150|60=20240102-09:30:00.000|10=999|\n";
+    let mut output = Vec::new();
+    run(&input[..], &mut output, ValidationOptions::new().delimiter('|').error_policy(ErrorPolicy::Skip), '|')
+      .unwrap();
+    assert_eq!(String::from_utf8(output).unwrap(),
+      "A->B 8 transact-to-sending: count=1 min=50 p50=50 p90=50 p99=50 max=50 ms\n");
+  }
+}
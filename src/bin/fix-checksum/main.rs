@@ -0,0 +1,282 @@
+//! Command line tool around the `fix_checksum` crate.
+
+extern crate fix_checksum;
+
+mod gen;
+mod latency;
+mod metrics;
+mod pipe;
+mod reconcile;
+mod server;
+mod stats;
+mod tail;
+
+use std::env;
+use std::fs::File;
+use std::io;
+use std::process;
+use std::sync::Arc;
+use std::thread;
+
+use metrics::Metrics;
+
+use fix_checksum::{ValidationOptions, ErrorPolicy, LinePrefix};
+use fix_checksum::clock::parse_utc_timestamp;
+use fix_checksum::digest::DigestReader;
+use fix_checksum::filter::TimeRange;
+use fix_checksum::ingest::{IngestReader, Pipeline, TransformError};
+
+const USAGE: &str = "usage: fix-checksum <command> [options]
+
+commands:
+  serve [ADDRESS]  run the HTTP validation service (default address 127.0.0.1:8080)
+  pipe             validate messages from standard input to standard output
+  tail FILE        follow a growing log file and report failed messages
+  latency [FILE]   summarize TransactTime to SendingTime delays of a log (standard input by default)
+  stats [FILE]     count valid and failed messages of a log by failure kind (standard input by default)
+  reconcile PRIMARY DROPCOPY
+                   match the fills of a session log against its drop copy, exit 1 on discrepancies
+  gen COUNT        write COUNT generated orders, cancels and executions with valid trailers, one per line
+
+options:
+  --delimiter C    field delimiter, SOH by default
+  --repair         rewrite wrong checksums instead of reporting them
+  --line-prefix S  skip everything up to the separator S before messages starting a line
+  --prefix-width N skip N bytes before messages starting a line
+  --transform T    pipe, latency, stats: rewrite every line before validation, in the order given; T is trim,
+                   base64, prefix=SEPARATOR, prefix-width=N or unescape=SEQUENCE (replaced by SOH)
+  --messages       pipe: write valid and repaired messages instead of JSON results
+  --from TIME      pipe: skip messages with a SendingTime before TIME (YYYYMMDD-HH:MM:SS)
+  --to TIME        pipe: skip messages with a SendingTime at or after TIME
+  --digests        pipe: add the CRC-32 and SHA-256 of every message to its JSON result and print
+                   those of the whole input on standard error
+  --metrics ADDR   tail: serve Prometheus metrics on ADDR
+  --mix O:C:E      gen: relative frequencies of orders, cancels and executions, 6:1:3 by default
+  --seed N         gen: seed of the generated messages, 1 by default";
+
+/// Parsed command line.
+struct Args {
+  command: String,
+  positional: Vec<String>,
+  delimiter: char,
+  options: ValidationOptions,
+  messages_only: bool,
+  metrics_address: Option<String>,
+  time_range: Option<TimeRange>,
+  pipeline: Pipeline,
+  digests: bool,
+  mix: (u32, u32, u32),
+  seed: u64,
+}
+
+fn parse_args(args: &[String]) -> Result<Args, String> {
+  let mut iter = args.iter();
+  let command = iter.next().ok_or_else(|| "missing command".to_string())?.clone();
+  let mut parsed = Args {
+    command,
+    positional: Vec::new(),
+    delimiter: '\x01',
+    options: ValidationOptions::new().error_policy(ErrorPolicy::Skip),
+    messages_only: false,
+    metrics_address: None,
+    time_range: None,
+    pipeline: Pipeline::new(),
+    digests: false,
+    mix: (6, 1, 3),
+    seed: 1,
+  };
+
+  while let Some(arg) = iter.next() {
+    match arg.as_str() {
+      "--delimiter" => {
+        let delimiter = iter.next().ok_or_else(|| "--delimiter needs a value".to_string())?;
+        let mut chars = delimiter.chars();
+        match (chars.next(), chars.next()) {
+          (Some(c), None) if c.is_ascii() => {
+            parsed.delimiter = c;
+            parsed.options = parsed.options.delimiter(c);
+          },
+          _ => return Err("delimiter must be a single ASCII character".to_string()),
+        }
+      },
+      "--repair" => parsed.options = parsed.options.error_policy(ErrorPolicy::Repair),
+      "--line-prefix" => {
+        let separator = iter.next().ok_or_else(|| "--line-prefix needs a value".to_string())?;
+        parsed.options = parsed.options.line_prefix(LinePrefix::Separator(separator.clone()));
+      },
+      "--prefix-width" => {
+        let width = iter.next().and_then(|width| width.parse().ok())
+          .ok_or_else(|| "--prefix-width needs a number".to_string())?;
+        parsed.options = parsed.options.line_prefix(LinePrefix::FixedWidth(width));
+      },
+      "--transform" => {
+        let transform = iter.next().ok_or_else(|| "--transform needs a value".to_string())?;
+        parsed.pipeline = parsed.pipeline.then(transform.parse().map_err(|err: TransformError| err.to_string())?);
+      },
+      "--messages" => parsed.messages_only = true,
+      "--digests" => parsed.digests = true,
+      "--metrics" => {
+        parsed.metrics_address = Some(iter.next().ok_or_else(|| "--metrics needs a value".to_string())?.clone());
+      },
+      "--mix" => {
+        let mix = iter.next().ok_or_else(|| "--mix needs a value".to_string())?;
+        let weights = mix.split(':').map(|weight| weight.parse()).collect::<Result<Vec<u32>, _>>();
+        parsed.mix = match weights.as_ref().map(|weights| weights.as_slice()) {
+          Ok(&[orders, cancels, executions]) => (orders, cancels, executions),
+          _ => return Err("--mix needs three numbers separated by colons".to_string()),
+        };
+      },
+      "--seed" => {
+        parsed.seed = iter.next().and_then(|seed| seed.parse().ok())
+          .ok_or_else(|| "--seed needs a number".to_string())?;
+      },
+      "--from" | "--to" => {
+        let value = iter.next().ok_or_else(|| format!("{} needs a value", arg))?;
+        let time = parse_utc_timestamp(value).ok_or_else(|| format!("{} is not a UTCTimestamp", value))?;
+        let range = parsed.time_range.unwrap_or_default();
+        parsed.time_range = Some(if arg == "--from" { range.since(time) } else { range.until(time) });
+      },
+      flag if flag.starts_with("--") => return Err(format!("unknown option {}", flag)),
+      value => parsed.positional.push(value.to_string()),
+    }
+  }
+  Ok(parsed)
+}
+
+fn main() {
+  let args: Vec<String> = env::args().skip(1).collect();
+  let args = match parse_args(&args) {
+    Ok(args) => args,
+    Err(err) => {
+      eprintln!("fix-checksum: {}\n\n{}", err, USAGE);
+      process::exit(2);
+    },
+  };
+
+  let result = match args.command.as_str() {
+    "serve" => server::run(args.positional.first().map(|arg| arg.as_str()).unwrap_or("127.0.0.1:8080"),
+      Arc::new(Metrics::new())),
+    "pipe" => {
+      let stdin = io::stdin();
+      let stdout = io::stdout();
+      let (mut input, digests) = (DigestReader::new(stdin.lock()), args.digests);
+      pipe::run(IngestReader::new(&mut input, args.pipeline), &mut stdout.lock(), args.options, args.messages_only,
+        args.time_range, digests).map(|()| if digests {
+        eprintln!("fix-checksum: {} bytes read, {}", input.length(), input.digests());
+      })
+    },
+    "tail" => match args.positional.first().cloned() {
+      Some(path) => tail(&path, args),
+      None => {
+        eprintln!("fix-checksum: tail needs a file\n\n{}", USAGE);
+        process::exit(2);
+      },
+    },
+    "latency" => match args.positional.first() {
+      Some(path) => File::open(path).and_then(|file| {
+        latency::run(IngestReader::new(io::BufReader::new(file), args.pipeline), &mut io::stdout(), args.options,
+          args.delimiter)
+      }),
+      None => latency::run(IngestReader::new(io::stdin().lock(), args.pipeline), &mut io::stdout(), args.options,
+        args.delimiter),
+    },
+    "stats" => match args.positional.first() {
+      Some(path) => File::open(path).and_then(|file| {
+        stats::run(IngestReader::new(io::BufReader::new(file), args.pipeline), &mut io::stdout(), args.options)
+      }),
+      None => stats::run(IngestReader::new(io::stdin().lock(), args.pipeline), &mut io::stdout(), args.options),
+    },
+    "reconcile" => match (args.positional.first(), args.positional.get(1)) {
+      (Some(primary), Some(drop_copy)) => reconcile_logs(primary, drop_copy, &args.options),
+      _ => {
+        eprintln!("fix-checksum: reconcile needs a primary and a drop-copy file\n\n{}", USAGE);
+        process::exit(2);
+      },
+    },
+    "gen" => match args.positional.first().and_then(|count| count.parse().ok()) {
+      Some(count) => gen::run(&mut io::stdout().lock(), count, args.mix, args.seed),
+      None => {
+        eprintln!("fix-checksum: gen needs a message count\n\n{}", USAGE);
+        process::exit(2);
+      },
+    },
+    _ => {
+      eprintln!("{}", USAGE);
+      process::exit(2);
+    },
+  };
+
+  if let Err(err) = result {
+    eprintln!("fix-checksum: {}", err);
+    process::exit(1);
+  }
+}
+
+fn tail(path: &str, args: Args) -> io::Result<()> {
+  let metrics = Arc::new(Metrics::new());
+  if let Some(address) = args.metrics_address {
+    let metrics = metrics.clone();
+    thread::spawn(move || {
+      if let Err(err) = server::run(&address, metrics) {
+        eprintln!("fix-checksum: metrics: {}", err);
+      }
+    });
+  }
+  let stdout = io::stdout();
+  tail::run(path, &mut stdout.lock(), args.options, &metrics)
+}
+
+fn reconcile_logs(primary: &str, drop_copy: &str, options: &ValidationOptions) -> io::Result<()> {
+  let primary = io::BufReader::new(File::open(primary)?);
+  let drop_copy = io::BufReader::new(File::open(drop_copy)?);
+  if !reconcile::run(primary, drop_copy, &mut io::stdout(), options)? { process::exit(1); }
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::parse_args;
+  use fix_checksum::{ValidationOptions, ErrorPolicy, LinePrefix};
+  use fix_checksum::ingest::{Pipeline, Transform};
+
+  fn args(args: &[&str]) -> Vec<String> {
+    args.iter().map(|arg| arg.to_string()).collect()
+  }
+
+  #[test]
+  fn it_should_parse_command_line() {
+    let parsed = parse_args(&args(&["pipe", "--delimiter", "|", "--repair", "--messages"])).unwrap();
+    assert_eq!(parsed.command, "pipe");
+    assert_eq!(parsed.delimiter, '|');
+    assert_eq!(parsed.options, ValidationOptions::new().delimiter('|').error_policy(ErrorPolicy::Repair));
+    assert!(parsed.messages_only);
+    assert!(!parsed.digests);
+    assert!(parse_args(&args(&["pipe", "--digests"])).unwrap().digests);
+
+    let parsed = parse_args(&args(&["serve", "0.0.0.0:80"])).unwrap();
+    assert_eq!(parsed.positional, vec!["0.0.0.0:80".to_string()]);
+
+    let parsed = parse_args(&args(&["tail", "fix.log", "--metrics", "0.0.0.0:9100"])).unwrap();
+    assert_eq!(parsed.metrics_address, Some("0.0.0.0:9100".to_string()));
+
+    let parsed = parse_args(&args(&["pipe", "--line-prefix", " : "])).unwrap();
+    assert_eq!(parsed.options, ValidationOptions::new().error_policy(ErrorPolicy::Skip)
+      .line_prefix(LinePrefix::Separator(" : ".to_string())));
+    assert!(parse_args(&args(&["pipe", "--prefix-width", "wide"])).is_err());
+
+    let parsed = parse_args(&args(&["pipe", "--transform", "trim", "--transform", "base64"])).unwrap();
+    assert_eq!(parsed.pipeline, Pipeline::new().then(Transform::TrimLineEnd).then(Transform::Base64Decode));
+    assert!(parse_args(&args(&["pipe", "--transform", "rot13"])).is_err());
+
+    let parsed = parse_args(&args(&["stats", "--delimiter", "|", "fix.log"])).unwrap();
+    assert_eq!((parsed.command.as_str(), parsed.positional), ("stats", vec!["fix.log".to_string()]));
+
+    let parsed = parse_args(&args(&["gen", "100", "--mix", "1:0:1", "--seed", "5"])).unwrap();
+    assert_eq!((parsed.mix, parsed.seed), ((1, 0, 1), 5));
+    assert!(parse_args(&args(&["gen", "100", "--mix", "1:2"])).is_err());
+
+    assert!(parse_args(&args(&[])).is_err());
+    assert!(parse_args(&args(&["pipe", "--delimiter", "||"])).is_err());
+    assert!(parse_args(&args(&["pipe", "--unknown"])).is_err());
+  }
+}
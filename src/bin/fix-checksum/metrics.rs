@@ -0,0 +1,70 @@
+//! Validation counters exposed in the Prometheus text format.
+
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+#[derive(Default)]
+pub struct Metrics {
+  messages: AtomicUsize,
+  failures: Mutex<BTreeMap<&'static str, usize>>,
+}
+
+impl Metrics {
+  pub fn new() -> Metrics {
+    Metrics::default()
+  }
+
+  /// Counts one validated message, failed with `failure` kind if any.
+  pub fn record(&self, failure: Option<&'static str>) {
+    self.messages.fetch_add(1, Ordering::Relaxed);
+    if let Some(kind) = failure {
+      let mut failures = self.failures.lock().unwrap_or_else(|err| err.into_inner());
+      *failures.entry(kind).or_insert(0) += 1;
+    }
+  }
+
+  pub fn render(&self) -> String {
+    let messages = self.messages.load(Ordering::Relaxed);
+    let failures = self.failures.lock().unwrap_or_else(|err| err.into_inner());
+    let failed: usize = failures.values().sum();
+
+    let mut output = String::new();
+    output.push_str("# HELP fix_checksum_messages_total Messages validated.\n");
+    output.push_str("# TYPE fix_checksum_messages_total counter\n");
+    output.push_str(&format!("fix_checksum_messages_total {}\n", messages));
+    output.push_str("# HELP fix_checksum_failures_total Messages failing validation, by kind.\n");
+    output.push_str("# TYPE fix_checksum_failures_total counter\n");
+    for (kind, count) in failures.iter() {
+      output.push_str(&format!("fix_checksum_failures_total{{kind=\"{}\"}} {}\n", kind, count));
+    }
+    output.push_str("# HELP fix_checksum_failure_ratio Share of validated messages failing validation.\n");
+    output.push_str("# TYPE fix_checksum_failure_ratio gauge\n");
+    let ratio = if messages == 0 { 0.0 } else { failed as f64 / messages as f64 };
+    output.push_str(&format!("fix_checksum_failure_ratio {}\n", ratio));
+    output
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::Metrics;
+
+  #[test]
+  fn it_should_render_prometheus_metrics() {
+    let metrics = Metrics::new();
+    metrics.record(None);
+    metrics.record(Some("ChecksumMismatch"));
+    metrics.record(None);
+    metrics.record(Some("ChecksumMismatch"));
+    assert_eq!(metrics.render(), "# HELP fix_checksum_messages_total Messages validated.\n\
+      # TYPE fix_checksum_messages_total counter\n\
+      fix_checksum_messages_total 4\n\
+      # HELP fix_checksum_failures_total Messages failing validation, by kind.\n\
+      # TYPE fix_checksum_failures_total counter\n\
+      fix_checksum_failures_total{kind=\"ChecksumMismatch\"} 2\n\
+      # HELP fix_checksum_failure_ratio Share of validated messages failing validation.\n\
+      # TYPE fix_checksum_failure_ratio gauge\n\
+      fix_checksum_failure_ratio 0.5\n");
+  }
+}
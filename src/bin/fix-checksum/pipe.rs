@@ -0,0 +1,100 @@
+//! Stream validation between standard input and output.
+//!
+//! Meant to sit between a consumer and a producer of raw FIX payloads, for
+//! example to validate a Kafka topic into another one:
+//!
+//! ```text
+//! kafka-console-consumer --topic fix.raw ... \
+//!   | fix-checksum pipe --repair --messages \
+//!   | kafka-console-producer --topic fix.valid ...
+//! ```
+
+use std::io;
+use std::io::{BufRead, Write};
+
+use fix_checksum::ValidationOptions;
+use fix_checksum::filter::{TimeFilter, TimeRange};
+use fix_checksum::report::MessageRecord;
+use fix_checksum::stream::{StreamValidator, Outcome};
+
+/// Validates every message of `input`. With `messages_only`, valid and
+/// repaired messages are written one per line and failures are reported on
+/// standard error; otherwise a JSON result line is written per message.
+/// With a `time_range`, only messages sent within it are written. With
+/// `digests`, JSON results also hold the CRC-32 and SHA-256 of the message.
+pub fn run<R: BufRead, W: Write>(input: R, output: &mut W, options: ValidationOptions, messages_only: bool,
+  time_range: Option<TimeRange>, digests: bool) -> io::Result<()> {
+  let results: Box<dyn Iterator<Item = io::Result<_>>> = match time_range {
+    Some(range) => Box::new(TimeFilter::new(StreamValidator::new(input, options.clone()), range, &options)),
+    None => Box::new(StreamValidator::new(input, options)),
+  };
+  for result in results {
+    let result = result?;
+    if messages_only {
+      match result.outcome {
+        Outcome::Valid | Outcome::Repaired => {
+          output.write_all(&result.message)?;
+          output.write_all(b"\n")?;
+        },
+        _ => eprintln!("fix-checksum: message at offset {} dropped: {}", result.offset, result.outcome.kind()),
+      }
+    } else if digests {
+      writeln!(output, "{}", MessageRecord::new(&result).digests().to_json())?;
+    } else {
+      writeln!(output, "{}", MessageRecord::new(&result).to_json())?;
+    }
+  }
+  output.flush()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::run;
+  use fix_checksum::{ValidationOptions, ErrorPolicy};
+  use fix_checksum::clock::parse_utc_timestamp;
+  use fix_checksum::filter::TimeRange;
+
+  const INPUT: &[u8] = b"8=FIX.4.2|9=5|35=0|10=161|\n8=FIX.4.2|9=5|35=0|10=162|\n8=FIX.4.2|9=5|35=0|\n";
+
+  #[test]
+  fn it_should_write_json_results() {
+    let mut output = Vec::new();
+    run(INPUT, &mut output, ValidationOptions::new().delimiter('|').error_policy(ErrorPolicy::Skip), false,
+      None, false).unwrap();
+    assert_eq!(String::from_utf8(output).unwrap(),
+      "{\"version\":1,\"offset\":0,\"outcome\":\"Valid\",\"code\":null,\"message\":\"8=FIX.4.2|9=5|35=0|10=161|\",\
+      \"hypotheses\":[]}\n\
+      {\"version\":1,\"offset\":27,\"outcome\":\"ChecksumMismatch\",\"code\":null,\
+      \"message\":\"8=FIX.4.2|9=5|35=0|10=162|\",\"hypotheses\":[]}\n\
+      {\"version\":1,\"offset\":54,\"outcome\":\"ChecksumFieldNotFound\",\"code\":2,\
+      \"message\":\"8=FIX.4.2|9=5|35=0|\",\"hypotheses\":[]}\n");
+  }
+
+  #[test]
+  fn it_should_write_repaired_messages() {
+    let mut output = Vec::new();
+    run(INPUT, &mut output, ValidationOptions::new().delimiter('|').error_policy(ErrorPolicy::Repair), true,
+      None, false).unwrap();
+    assert_eq!(output, b"8=FIX.4.2|9=5|35=0|10=161|\n8=FIX.4.2|9=5|35=0|10=161|\n".to_vec());
+  }
+
+  #[test]
+  fn it_should_write_messages_sent_in_time_range() {
+    let input = b"8=FIX.4.2|9=26|35=0|52=20240102-09:29:59|10=231|\n\
+      8=FIX.4.2|9=26|35=0|52=20240102-09:30:00|10=209|\n";
+    let range = TimeRange::new().since(parse_utc_timestamp("20240102-09:30:00").unwrap());
+    let mut output = Vec::new();
+    run(&input[..], &mut output, ValidationOptions::new().delimiter('|'), true, Some(range), false).unwrap();
+    assert_eq!(output, b"8=FIX.4.2|9=26|35=0|52=20240102-09:30:00|10=209|\n".to_vec());
+  }
+
+  #[test]
+  fn it_should_write_message_digests() {
+    let mut output = Vec::new();
+    run(&INPUT[..27], &mut output, ValidationOptions::new().delimiter('|'), false, None, true).unwrap();
+    assert_eq!(String::from_utf8(output).unwrap(),
+      "{\"version\":1,\"offset\":0,\"outcome\":\"Valid\",\"code\":null,\"message\":\"8=FIX.4.2|9=5|35=0|10=161|\",\
+      \"hypotheses\":[],\"crc32\":\"9711af54\",\
+      \"sha256\":\"da76c65535a24289c0c5c45ffb9022a559989fa95efa3441e56ea9d64514f1c1\"}\n");
+  }
+}
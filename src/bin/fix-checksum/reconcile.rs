@@ -0,0 +1,38 @@
+//! Reconciliation of a primary session log with its drop copy.
+
+use std::io;
+use std::io::{BufRead, Write};
+
+use fix_checksum::ValidationOptions;
+use fix_checksum::reconcile::reconcile;
+
+/// Writes a line per discrepancy between the fills of both logs followed by
+/// a summary line. Returns `true` if the logs agree.
+pub fn run<P: BufRead, D: BufRead, W: Write>(primary: P, drop_copy: D, output: &mut W, options: &ValidationOptions)
+  -> io::Result<bool> {
+  let reconciliation = reconcile(primary, drop_copy, options)?;
+  for discrepancy in &reconciliation.discrepancies { writeln!(output, "{}", discrepancy)?; }
+  writeln!(output, "matched={} discrepancies={} invalid={}", reconciliation.matched,
+    reconciliation.discrepancies.len(), reconciliation.invalid)?;
+  output.flush()?;
+  Ok(reconciliation.is_clean())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::run;
+  use fix_checksum::ValidationOptions;
+
+  #[test]
+  fn it_should_report_discrepancies() {
+    let primary = b"8=FIX.4.2|9=42|35=8|37=O-1|17=E-1|150=2|39=2|14=100|6=10|10=099|\n";
+    let mut output = Vec::new();
+    assert!(!run(&primary[..], &b""[..], &mut output, &ValidationOptions::new().delimiter('|')).unwrap());
+    assert_eq!(String::from_utf8(output).unwrap(),
+      "order O-1 exec E-1: missing in drop copy\nmatched=0 discrepancies=1 invalid=0\n");
+
+    let mut output = Vec::new();
+    assert!(run(&primary[..], &primary[..], &mut output, &ValidationOptions::new().delimiter('|')).unwrap());
+    assert_eq!(String::from_utf8(output).unwrap(), "matched=1 discrepancies=0 invalid=0\n");
+  }
+}
@@ -0,0 +1,309 @@
+//! HTTP validation service.
+//!
+//! * `POST /validate` validates the message in the request body and returns
+//!   `{"valid":true}`, or `{"valid":false,"hypotheses":[...]}` on a checksum
+//!   mismatch.
+//! * `POST /finalize` returns the message in the request body with BodyLength
+//!   and CheckSum set, as `{"message":"..."}`.
+//! * `GET /metrics` returns validation counters in the Prometheus text
+//!   format.
+//!
+//! Both POST endpoints accept a `delimiter` query parameter for non-SOH
+//! delimited messages, e.g. `POST /validate?delimiter=%7C`. Messages which
+//! can not be processed are answered with status 422 and
+//! `{"error":{"code":..,"kind":..,"message":..}}`.
+//!
+//! Request lines and headers are limited to 8 KiB and 100 headers, larger
+//! heads are refused with status 400 or 431, and connections silent for 30
+//! seconds are dropped.
+
+use std::io;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use fix_checksum::{ValidationOptions, validate_bytes_with, finalize};
+use fix_checksum::diagnose::diagnose;
+use fix_checksum::json;
+
+use metrics::Metrics;
+
+const MAX_BODY_SIZE: usize = 1024 * 1024;
+/// Longest request or header line, line break included.
+const MAX_LINE_LENGTH: usize = 8 * 1024;
+const MAX_HEADERS: usize = 100;
+/// How long a connection may stay silent before it is dropped.
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+pub struct Request {
+  pub method: String,
+  pub path: String,
+  pub query: String,
+  pub body: Vec<u8>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Response {
+  pub status: u16,
+  pub content_type: &'static str,
+  pub body: String,
+}
+
+impl Response {
+  fn new(status: u16, body: String) -> Response {
+    Response { status, content_type: "application/json", body }
+  }
+
+  fn failure(status: u16, message: &str) -> Response {
+    Response::new(status, format!("{{\"error\":{{\"message\":{}}}}}", json::string(message)))
+  }
+}
+
+pub fn run(address: &str, metrics: Arc<Metrics>) -> io::Result<()> {
+  let listener = TcpListener::bind(address)?;
+  eprintln!("listening on {}", listener.local_addr()?);
+  for stream in listener.incoming() {
+    match stream {
+      Ok(stream) => {
+        let metrics = metrics.clone();
+        thread::spawn(move || serve_connection(stream, &metrics));
+      },
+      Err(err) => eprintln!("fix-checksum: {}", err),
+    }
+  }
+  Ok(())
+}
+
+fn serve_connection(stream: TcpStream, metrics: &Metrics) {
+  if stream.set_read_timeout(Some(TIMEOUT)).and_then(|_| stream.set_write_timeout(Some(TIMEOUT))).is_err() {
+    return;
+  }
+  let mut reader = BufReader::new(&stream);
+  let response = match read_request(&mut reader) {
+    Ok(Ok(request)) => handle(&request, metrics),
+    Ok(Err(response)) => response,
+    Err(_) => return,
+  };
+  let _ = write_response(&mut &stream, &response);
+}
+
+/// Reads one request. Malformed requests are returned as the response to send.
+pub fn read_request<R: BufRead>(reader: &mut R) -> io::Result<Result<Request, Response>> {
+  let mut line = String::new();
+  if read_line(reader, &mut line)?.is_none() { return Ok(Err(Response::failure(400, "request line too long"))); }
+  let mut parts = line.split_whitespace();
+  let (method, target) = match (parts.next(), parts.next()) {
+    (Some(method), Some(target)) => (method.to_string(), target.to_string()),
+    _ => return Ok(Err(Response::failure(400, "malformed request line"))),
+  };
+
+  let mut content_length = 0;
+  for headers in 0.. {
+    let read = read_line(reader, &mut line)?;
+    let header = line.trim_end();
+    if read == Some(0) || read.is_some() && header.is_empty() { break; }
+    if read.is_none() || headers == MAX_HEADERS {
+      return Ok(Err(Response::failure(431, "request header fields too large")));
+    }
+    if let Some(separator) = header.find(':') {
+      if header[..separator].eq_ignore_ascii_case("content-length") {
+        content_length = match header[separator + 1..].trim().parse() {
+          Ok(length) => length,
+          Err(_) => return Ok(Err(Response::failure(400, "invalid Content-Length"))),
+        };
+      }
+    }
+  }
+  if content_length > MAX_BODY_SIZE { return Ok(Err(Response::failure(413, "request body too large"))); }
+
+  let mut body = vec![0; content_length];
+  reader.read_exact(&mut body)?;
+  let (path, query) = match target.find('?') {
+    Some(separator) => (target[..separator].to_string(), target[separator + 1..].to_string()),
+    None => (target, String::new()),
+  };
+  Ok(Ok(Request { method, path, query, body }))
+}
+
+/// Reads a line of at most `MAX_LINE_LENGTH` bytes into `line`, returning
+/// its length, or none when it is longer.
+fn read_line<R: BufRead>(reader: &mut R, line: &mut String) -> io::Result<Option<usize>> {
+  line.clear();
+  let read = reader.by_ref().take(MAX_LINE_LENGTH as u64).read_line(line)?;
+  Ok(if read == MAX_LINE_LENGTH && !line.ends_with('\n') { None } else { Some(read) })
+}
+
+pub fn handle(request: &Request, metrics: &Metrics) -> Response {
+  if request.path == "/metrics" {
+    if request.method != "GET" { return Response::failure(405, "method not allowed"); }
+    return Response { status: 200, content_type: "text/plain; version=0.0.4", body: metrics.render() };
+  }
+  if request.path != "/validate" && request.path != "/finalize" {
+    return Response::failure(404, "not found");
+  }
+  if request.method != "POST" { return Response::failure(405, "method not allowed"); }
+
+  let options = match options(&request.query) {
+    Some(options) => options,
+    None => return Response::failure(400, "delimiter must be a single ASCII character"),
+  };
+
+  if request.path == "/validate" {
+    let result = validate_bytes_with(&request.body, &options);
+    metrics.record(match result {
+      Ok(true) => None,
+      Ok(false) => Some("ChecksumMismatch"),
+      Err(ref err) => Some(err.kind()),
+    });
+
+    match result {
+      Ok(true) => Response::new(200, "{\"valid\":true}".to_string()),
+      Ok(false) => {
+        let hypotheses = diagnose(&request.body, &options).into_iter().map(|hypothesis| hypothesis.to_string());
+        Response::new(200, format!("{{\"valid\":false,\"hypotheses\":{}}}", json::strings(hypotheses)))
+      },
+      Err(err) => Response::new(422, format!("{{\"error\":{}}}", json::error(&err))),
+    }
+  } else {
+    match finalize(&request.body, &options) {
+      Ok(message) => Response::new(200, format!("{{\"message\":{}}}", json::bytes(&message))),
+      Err(err) => Response::new(422, format!("{{\"error\":{}}}", json::error(&err))),
+    }
+  }
+}
+
+fn options(query: &str) -> Option<ValidationOptions> {
+  let mut options = ValidationOptions::new();
+  for parameter in query.split('&') {
+    if let Some(value) = parameter.strip_prefix("delimiter=") {
+      let delimiter = percent_decode(value)?;
+      if delimiter.len() != 1 || !delimiter[0].is_ascii() { return None; }
+      options = options.delimiter(delimiter[0] as char);
+    }
+  }
+  Some(options)
+}
+
+fn percent_decode(value: &str) -> Option<Vec<u8>> {
+  let bytes = value.as_bytes();
+  let mut decoded = Vec::with_capacity(bytes.len());
+  let mut index = 0;
+  while index < bytes.len() {
+    if bytes[index] == b'%' {
+      let hex = value.get(index + 1..index + 3)?;
+      decoded.push(u8::from_str_radix(hex, 16).ok()?);
+      index += 3;
+    } else {
+      decoded.push(bytes[index]);
+      index += 1;
+    }
+  }
+  Some(decoded)
+}
+
+fn write_response<W: Write>(writer: &mut W, response: &Response) -> io::Result<()> {
+  let reason = match response.status {
+    200 => "OK",
+    400 => "Bad Request",
+    404 => "Not Found",
+    405 => "Method Not Allowed",
+    413 => "Payload Too Large",
+    431 => "Request Header Fields Too Large",
+    _ => "Unprocessable Entity",
+  };
+  write!(writer, "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\n\
+    Connection: close\r\n\r\n{}", response.status, reason, response.content_type, response.body.len(),
+    response.body)?;
+  writer.flush()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{read_request, handle, write_response, Request, Response};
+  use metrics::Metrics;
+
+  fn json(status: u16, body: &str) -> Response {
+    Response { status, content_type: "application/json", body: body.to_string() }
+  }
+
+  fn post(target: &str, body: &[u8]) -> Response {
+    let mut raw = format!("POST {} HTTP/1.1\r\nHost: localhost\r\nContent-Length: {}\r\n\r\n",
+      target, body.len()).into_bytes();
+    raw.extend_from_slice(body);
+    let request = read_request(&mut &raw[..]).unwrap().unwrap();
+    handle(&request, &Metrics::new())
+  }
+
+  #[test]
+  fn it_should_validate_messages() {
+    assert_eq!(post("/validate?delimiter=%7C", b"8=FIX.4.2|9=5|35=0|10=161|"),
+      json(200, "{\"valid\":true}"));
+    assert_eq!(post("/validate", b"8=FIX.4.2\x019=5\x0135=0\x0110=1X1\x01").status, 422);
+
+    let response = post("/validate?delimiter=|", b"8=FIX.4.2|9=5|35=0|10=162|");
+    assert!(response.body.starts_with("{\"valid\":false,\"hypotheses\":[\"checksum value damaged, expected 161\""));
+
+    let response = post("/validate", b"8=FIX.4.2\x01");
+    assert_eq!(response, json(422, "{\"error\":{\"code\":2,\
+      \"kind\":\"ChecksumFieldNotFound\",\"message\":\"Checksum field not found.\"}}"));
+  }
+
+  #[test]
+  fn it_should_finalize_messages() {
+    assert_eq!(post("/finalize?delimiter=%7c", b"8=FIX.4.2|35=0|"),
+      json(200, "{\"message\":\"8=FIX.4.2|9=5|35=0|10=161|\"}"));
+    assert_eq!(post("/finalize", b"8=FIX.4.2\x0135=0\x01").body,
+      "{\"message\":\"8=FIX.4.2\\u00019=5\\u000135=0\\u000110=161\\u0001\"}");
+    assert_eq!(post("/finalize", b"35=0\x01").status, 422);
+  }
+
+  #[test]
+  fn it_should_reject_invalid_requests() {
+    assert_eq!(post("/other", b"").status, 404);
+    assert_eq!(post("/validate?delimiter=%7C%7C", b"").status, 400);
+    assert_eq!(post("/validate?delimiter=%G", b"").status, 400);
+
+    let request = Request { method: "GET".to_string(), path: "/validate".to_string(), query: String::new(),
+      body: Vec::new() };
+    assert_eq!(handle(&request, &Metrics::new()).status, 405);
+    assert_eq!(read_request(&mut &b"garbage\r\n\r\n"[..]).unwrap().err().unwrap().status, 400);
+  }
+
+  #[test]
+  fn it_should_limit_request_head() {
+    let status = |raw: Vec<u8>| read_request(&mut &raw[..]).unwrap().err().map(|response| response.status);
+    let header = format!("X-Padding: {}\r\n", "x".repeat(8 * 1024));
+    assert_eq!(status(format!("POST /validate HTTP/1.1\r\n{}\r\n", header).into_bytes()), Some(431));
+    assert_eq!(status(format!("POST /{} HTTP/1.1\r\n\r\n", "x".repeat(8 * 1024)).into_bytes()), Some(400));
+    let headers = "X-Padding: x\r\n".repeat(101);
+    assert_eq!(status(format!("POST /validate HTTP/1.1\r\n{}\r\n", headers).into_bytes()), Some(431));
+    let headers = "X-Padding: x\r\n".repeat(100);
+    assert_eq!(status(format!("POST /validate HTTP/1.1\r\n{}\r\n", headers).into_bytes()), None);
+  }
+
+  #[test]
+  fn it_should_count_validations_in_metrics() {
+    let metrics = Metrics::new();
+    let validate = Request { method: "POST".to_string(), path: "/validate".to_string(),
+      query: "delimiter=|".to_string(), body: b"8=FIX.4.2|9=5|35=0|10=162|".to_vec() };
+    handle(&validate, &metrics);
+    handle(&validate, &metrics);
+
+    let request = Request { method: "GET".to_string(), path: "/metrics".to_string(), query: String::new(),
+      body: Vec::new() };
+    let response = handle(&request, &metrics);
+    assert_eq!(response.content_type, "text/plain; version=0.0.4");
+    assert!(response.body.contains("fix_checksum_messages_total 2\n"));
+    assert!(response.body.contains("fix_checksum_failures_total{kind=\"ChecksumMismatch\"} 2\n"));
+  }
+
+  #[test]
+  fn it_should_write_response() {
+    let mut output = Vec::new();
+    write_response(&mut output, &json(200, "{}")).unwrap();
+    assert_eq!(String::from_utf8(output).unwrap(), "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\n\
+      Content-Length: 2\r\nConnection: close\r\n\r\n{}");
+  }
+}
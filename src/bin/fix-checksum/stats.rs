@@ -0,0 +1,36 @@
+//! Validation statistics of a message log.
+
+use std::io;
+use std::io::{BufRead, Write};
+
+use fix_checksum::{ValidationOptions, ErrorPolicy};
+use fix_checksum::stream::StreamValidator;
+use fix_checksum::summary::ValidationSummary;
+
+/// Validates every message of `input`, skipping failed ones, and writes the
+/// summary of the results.
+pub fn run<R: BufRead, W: Write>(input: R, output: &mut W, options: ValidationOptions) -> io::Result<()> {
+  let options = options.error_policy(ErrorPolicy::Skip);
+  let mut summary = ValidationSummary::new(&options);
+  for result in StreamValidator::new(input, options) {
+    summary.record(&result?);
+  }
+  write!(output, "{}", summary)?;
+  output.flush()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::run;
+  use fix_checksum::{ValidationOptions, ErrorPolicy};
+
+  #[test]
+  fn it_should_summarize_validations() {
+    let input = b"8=FIX.4.2|9=5|35=0|10=161|\n8=FIX.4.2|9=5|35=0|10=999|\n8=FIX.4.2|9=5|35=0|10=161|\n";
+    let mut output = Vec::new();
+    run(&input[..], &mut output, ValidationOptions::new().delimiter('|').error_policy(ErrorPolicy::Stop)).unwrap();
+    let output = String::from_utf8(output).unwrap();
+    assert!(output.starts_with("messages: 3, valid: 2, failed: 1\n  ChecksumMismatch: 1\n\
+      first failure at offset 27, last failure at offset 27\n"), "{}", output);
+  }
+}
@@ -0,0 +1,105 @@
+//! Validation of a growing log file, like `tail -f`.
+
+use std::fs::File;
+use std::io;
+use std::io::{BufReader, Read, Write};
+use std::thread;
+use std::time::Duration;
+
+use fix_checksum::ValidationOptions;
+use fix_checksum::report::MessageRecord;
+use fix_checksum::stream::StreamValidator;
+
+use metrics::Metrics;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// A reader which waits for more data at the end of the input instead of
+/// reporting end of file.
+pub struct Follow<R> {
+  inner: R,
+  interval: Duration,
+}
+
+impl<R: Read> Follow<R> {
+  pub fn new(inner: R, interval: Duration) -> Follow<R> {
+    Follow { inner, interval }
+  }
+}
+
+impl<R: Read> Read for Follow<R> {
+  fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+    loop {
+      match self.inner.read(buf)? {
+        0 if !buf.is_empty() => thread::sleep(self.interval),
+        read => return Ok(read),
+      }
+    }
+  }
+}
+
+/// Follows the file at `path`, counting every message in `metrics` and
+/// writing a JSON line for each failed one.
+pub fn run<W: Write>(path: &str, output: &mut W, options: ValidationOptions, metrics: &Metrics) -> io::Result<()> {
+  let file = File::open(path)?;
+  follow(BufReader::new(Follow::new(file, POLL_INTERVAL)), output, options, metrics)
+}
+
+fn follow<R: io::BufRead, W: Write>(input: R, output: &mut W, options: ValidationOptions, metrics: &Metrics)
+  -> io::Result<()> {
+  for result in StreamValidator::new(input, options) {
+    let result = result?;
+    let failure = if result.outcome.is_failure() { Some(result.outcome.kind()) } else { None };
+    metrics.record(failure);
+
+    if failure.is_some() {
+      writeln!(output, "{}", MessageRecord::new(&result).to_json())?;
+      output.flush()?;
+    }
+  }
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use std::io::Read;
+  use std::time::Duration;
+
+  use super::{follow, Follow};
+  use fix_checksum::{ValidationOptions, ErrorPolicy};
+  use metrics::Metrics;
+
+  /// Yields its chunks one read at a time, with an empty read in between.
+  struct Chunks(Vec<&'static [u8]>, bool);
+
+  impl Read for Chunks {
+    fn read(&mut self, buf: &mut [u8]) -> ::std::io::Result<usize> {
+      self.1 = !self.1;
+      if self.1 || self.0.is_empty() { return Ok(0); }
+      let chunk = self.0.remove(0);
+      buf[..chunk.len()].copy_from_slice(chunk);
+      Ok(chunk.len())
+    }
+  }
+
+  #[test]
+  fn it_should_wait_for_more_data() {
+    let mut reader = Follow::new(Chunks(vec![b"8=FIX", b".4.2|"], false), Duration::from_millis(1));
+    let mut buffer = [0; 16];
+    assert_eq!(reader.read(&mut buffer).unwrap(), 5);
+    assert_eq!(reader.read(&mut buffer).unwrap(), 5);
+  }
+
+  #[test]
+  fn it_should_report_failures_and_count_messages() {
+    let input = &b"8=FIX.4.2|9=5|35=0|10=161|\n8=FIX.4.2|9=5|35=0|10=162|\n"[..];
+    let metrics = Metrics::new();
+    let mut output = Vec::new();
+    follow(input, &mut output, ValidationOptions::new().delimiter('|').error_policy(ErrorPolicy::Skip), &metrics)
+      .unwrap();
+    assert_eq!(String::from_utf8(output).unwrap(),
+      "{\"version\":1,\"offset\":27,\"outcome\":\"ChecksumMismatch\",\"code\":null,\
+      \"message\":\"8=FIX.4.2|9=5|35=0|10=162|\",\"hypotheses\":[]}\n");
+    assert!(metrics.render().contains("fix_checksum_messages_total 2\n"));
+  }
+}
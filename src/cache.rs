@@ -0,0 +1,157 @@
+//! A least recently used cache of validation results.
+//!
+//! On a quiet session most of the traffic is Heartbeats (0) and
+//! TestRequests (1), and a counterparty sending them without MsgSeqNum or
+//! SendingTime changes, as some test harnesses and replays do, sends the
+//! same bytes again and again. A `ValidationCache` remembers the result of
+//! the last messages validated so that identical ones are not summed again.
+//! Stream validation uses one when `ValidationOptions::cache_size` is set.
+//!
+//! # Examples
+//!
+//! ```
+//! use fix_checksum::ValidationOptions;
+//! use fix_checksum::cache::ValidationCache;
+//!
+//! let mut cache = ValidationCache::new(16, ValidationOptions::new().delimiter('|'));
+//! assert!(cache.validate(b"8=FIX.4.2|9=5|35=0|10=161|").unwrap());
+//! assert!(cache.validate(b"8=FIX.4.2|9=5|35=0|10=161|").unwrap());
+//! assert_eq!((cache.hits(), cache.misses()), (1, 1));
+//! ```
+
+use std::collections::{BTreeMap, HashMap};
+
+use super::{FIXChecksumValidatorError, ValidationOptions, validate_bytes_with};
+
+/// Remembers the result of validating the last `capacity` distinct
+/// messages with one set of options.
+#[derive(Clone, Debug)]
+pub struct ValidationCache {
+  options: ValidationOptions,
+  capacity: usize,
+  entries: HashMap<Vec<u8>, Entry>,
+  // Messages by the tick they were last used at, oldest first
+  recency: BTreeMap<u64, Vec<u8>>,
+  tick: u64,
+  hits: u64,
+  misses: u64,
+}
+
+#[derive(Clone, Debug)]
+struct Entry {
+  result: Result<bool, FIXChecksumValidatorError>,
+  used: u64,
+}
+
+impl ValidationCache {
+  /// Creates a cache of up to `capacity` messages validated with `options`.
+  /// A cache of no messages validates every message.
+  pub fn new(capacity: usize, options: ValidationOptions) -> ValidationCache {
+    ValidationCache {
+      options,
+      capacity,
+      entries: HashMap::new(),
+      recency: BTreeMap::new(),
+      tick: 0,
+      hits: 0,
+      misses: 0,
+    }
+  }
+
+  /// Returns the options messages are validated with.
+  pub fn options(&self) -> &ValidationOptions {
+    &self.options
+  }
+
+  /// Validates `message` as `validate_bytes_with` does, returning the
+  /// remembered result if the same bytes were validated recently. The least
+  /// recently used message is forgotten when the cache is full.
+  pub fn validate(&mut self, message: &[u8]) -> Result<bool, FIXChecksumValidatorError> {
+    self.tick += 1;
+    if let Some(entry) = self.entries.get_mut(message) {
+      let key = self.recency.remove(&entry.used).expect("cached message has a recency");
+      self.recency.insert(self.tick, key);
+      entry.used = self.tick;
+      self.hits += 1;
+      return entry.result.clone();
+    }
+
+    self.misses += 1;
+    let result = validate_bytes_with(message, &self.options);
+    if self.capacity == 0 { return result; }
+    if self.entries.len() == self.capacity {
+      let oldest = *self.recency.keys().next().expect("full cache has a least recently used message");
+      let key = self.recency.remove(&oldest).expect("recency key exists");
+      self.entries.remove(&key);
+    }
+    self.entries.insert(message.to_vec(), Entry { result: result.clone(), used: self.tick });
+    self.recency.insert(self.tick, message.to_vec());
+    result
+  }
+
+  /// Returns the number of messages remembered.
+  pub fn len(&self) -> usize {
+    self.entries.len()
+  }
+
+  /// Returns `true` if no message is remembered.
+  pub fn is_empty(&self) -> bool {
+    self.entries.is_empty()
+  }
+
+  /// Returns the number of validations answered from the cache.
+  pub fn hits(&self) -> u64 {
+    self.hits
+  }
+
+  /// Returns the number of messages that had to be validated.
+  pub fn misses(&self) -> u64 {
+    self.misses
+  }
+
+  /// Forgets every message, keeping the counts.
+  pub fn clear(&mut self) {
+    self.entries.clear();
+    self.recency.clear();
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::ValidationCache;
+  use super::super::ValidationOptions;
+  use super::super::FIXChecksumValidatorError::ChecksumFieldNotFound;
+
+  const HEARTBEAT: &[u8] = b"8=FIX.4.2|9=5|35=0|10=161|";
+  const TEST_REQUEST: &[u8] = b"8=FIX.4.2|9=5|35=1|10=162|";
+  const BROKEN: &[u8] = b"8=FIX.4.2|9=5|35=2|";
+
+  #[test]
+  fn it_should_forget_least_recently_used() {
+    let mut cache = ValidationCache::new(2, ValidationOptions::new().delimiter('|'));
+    assert_eq!(cache.validate(HEARTBEAT), Ok(true));
+    assert_eq!(cache.validate(TEST_REQUEST), Ok(true));
+    assert_eq!(cache.validate(HEARTBEAT), Ok(true));
+    assert_eq!(cache.validate(BROKEN), Err(ChecksumFieldNotFound));
+    assert_eq!(cache.len(), 2);
+    assert_eq!((cache.hits(), cache.misses()), (1, 3));
+
+    assert_eq!(cache.validate(HEARTBEAT), Ok(true));
+    assert_eq!(cache.validate(BROKEN), Err(ChecksumFieldNotFound));
+    assert_eq!((cache.hits(), cache.misses()), (3, 3));
+    assert_eq!(cache.validate(TEST_REQUEST), Ok(true));
+    assert_eq!(cache.misses(), 4);
+
+    cache.clear();
+    assert!(cache.is_empty());
+  }
+
+  #[test]
+  fn it_should_not_remember_without_capacity() {
+    let mut cache = ValidationCache::new(0, ValidationOptions::new().delimiter('|'));
+    assert_eq!(cache.validate(HEARTBEAT), Ok(true));
+    assert_eq!(cache.validate(HEARTBEAT), Ok(true));
+    assert!(cache.is_empty());
+    assert_eq!((cache.hits(), cache.misses()), (0, 2));
+  }
+}
@@ -0,0 +1,157 @@
+//! Time sources and conversion of FIX UTC timestamps.
+
+use std::cell::Cell;
+use std::rc::Rc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use super::dictionary::is_utc_timestamp;
+
+/// A source of the current time, replaceable in tests and replays.
+pub trait Clock {
+  fn now(&self) -> SystemTime;
+}
+
+/// The system wall clock.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+  fn now(&self) -> SystemTime {
+    SystemTime::now()
+  }
+}
+
+/// A clock stopped at a given time.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FixedClock(pub SystemTime);
+
+impl Clock for FixedClock {
+  fn now(&self) -> SystemTime {
+    self.0
+  }
+}
+
+/// A clock moved forward by hand, for simulations. Clones share the time,
+/// so that the owner of one moves the clock of all.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::{Duration, UNIX_EPOCH};
+/// use fix_checksum::clock::{Clock, VirtualClock};
+///
+/// let clock = VirtualClock::new(UNIX_EPOCH);
+/// let shared = clock.clone();
+/// clock.advance(Duration::from_secs(30));
+/// assert_eq!(shared.now(), UNIX_EPOCH + Duration::from_secs(30));
+/// ```
+#[derive(Clone, Debug)]
+pub struct VirtualClock(Rc<Cell<SystemTime>>);
+
+impl VirtualClock {
+  /// Creates a clock stopped at `start`.
+  pub fn new(start: SystemTime) -> VirtualClock {
+    VirtualClock(Rc::new(Cell::new(start)))
+  }
+
+  /// Moves the clock forward by `duration`.
+  pub fn advance(&self, duration: Duration) {
+    self.0.set(self.0.get() + duration);
+  }
+
+  /// Sets the clock to `time`, also backwards.
+  pub fn set(&self, time: SystemTime) {
+    self.0.set(time);
+  }
+}
+
+impl Clock for VirtualClock {
+  fn now(&self) -> SystemTime {
+    self.0.get()
+  }
+}
+
+/// Converts a UTCTimestamp (`YYYYMMDD-HH:MM:SS` with optional
+/// milliseconds) to a system time.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::{Duration, UNIX_EPOCH};
+/// use fix_checksum::clock::parse_utc_timestamp;
+///
+/// assert_eq!(parse_utc_timestamp("19700102-00:00:01.500"), Some(UNIX_EPOCH + Duration::from_millis(86_401_500)));
+/// assert_eq!(parse_utc_timestamp("1970-01-02"), None);
+/// ```
+pub fn parse_utc_timestamp(value: &str) -> Option<SystemTime> {
+  if !is_utc_timestamp(value) { return None; }
+  let number = |start: usize, end: usize| value[start..end].parse::<i64>().unwrap_or(0);
+  let days = days_from_civil(number(0, 4), number(4, 6), number(6, 8));
+  if days < 0 { return None; }
+  let seconds = days * 86_400 + number(9, 11) * 3_600 + number(12, 14) * 60 + number(15, 17);
+  let millis = if value.len() > 17 { number(18, 21) } else { 0 };
+  Some(UNIX_EPOCH + Duration::from_millis((seconds * 1_000 + millis) as u64))
+}
+
+/// Formats a system time as a UTCTimestamp with milliseconds.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::{Duration, UNIX_EPOCH};
+/// use fix_checksum::clock::format_utc_timestamp;
+///
+/// assert_eq!(format_utc_timestamp(UNIX_EPOCH + Duration::from_millis(951_782_400_250)), "20000229-00:00:00.250");
+/// ```
+pub fn format_utc_timestamp(time: SystemTime) -> String {
+  let millis = time.duration_since(UNIX_EPOCH).map(|since| since.as_millis() as i64).unwrap_or(0);
+  let (days, millis_of_day) = (millis / 86_400_000, millis % 86_400_000);
+  let (year, month, day) = civil_from_days(days);
+  let seconds = millis_of_day / 1_000;
+  format!("{:04}{:02}{:02}-{:02}:{:02}:{:02}.{:03}", year, month, day, seconds / 3_600, seconds / 60 % 60,
+    seconds % 60, millis_of_day % 1_000)
+}
+
+/// Days since 1970-01-01 of a proleptic Gregorian date.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+  let year = if month <= 2 { year - 1 } else { year };
+  let era = year.div_euclid(400);
+  let year_of_era = year - era * 400;
+  let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+  let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+  era * 146_097 + day_of_era - 719_468
+}
+
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+  let days = days + 719_468;
+  let era = days.div_euclid(146_097);
+  let day_of_era = days - era * 146_097;
+  let year_of_era = (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+  let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+  let month_index = (5 * day_of_year + 2) / 153;
+  let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+  let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+  (year_of_era + era * 400 + (month <= 2) as i64, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+  use std::time::{Duration, UNIX_EPOCH};
+  use super::{Clock, FixedClock, format_utc_timestamp, parse_utc_timestamp};
+
+  #[test]
+  fn it_should_convert_utc_timestamps() {
+    for timestamp in &["19700101-00:00:00.000", "19991231-23:59:59.999", "20240229-12:30:45.001",
+      "21000301-00:00:00.000"] {
+      assert_eq!(format_utc_timestamp(parse_utc_timestamp(timestamp).unwrap()), *timestamp);
+    }
+    assert_eq!(parse_utc_timestamp("19980604-07:58:28"), Some(UNIX_EPOCH + Duration::from_secs(896_947_108)));
+    assert_eq!(parse_utc_timestamp("19691231-23:59:59"), None);
+  }
+
+  #[test]
+  fn it_should_return_fixed_time() {
+    let clock = FixedClock(UNIX_EPOCH + Duration::from_secs(5));
+    assert_eq!(clock.now(), UNIX_EPOCH + Duration::from_secs(5));
+  }
+}
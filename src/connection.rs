@@ -0,0 +1,551 @@
+//! An established FIX session over a stream, as opened by `initiator` or
+//! `acceptor`.
+//!
+//! A `Connection` answers Heartbeats (0) and TestRequests (1), sends a
+//! Heartbeat when it has been quiet for the heartbeat interval and hands
+//! the application messages it receives to the caller. Every message sent
+//! is kept in a `MessageStore` with the session checkpoint, so that a
+//! restarted session carries on with the next sequence numbers. Messages
+//! are built with the same header as the builders of `orders` and framed
+//! and validated with `framing`.
+//!
+//! Sequence gaps are recovered both ways. A ResendRequest (2) of the
+//! counterparty is answered from the store, application messages sent
+//! again with PossDupFlag (43) and OrigSendingTime (122) and the
+//! administrative ones skipped with a SequenceReset-GapFill (4). A message
+//! received past the next expected number gets the missed ones requested
+//! with a ResendRequest, the range staying open in the checkpoint until
+//! they or a GapFill arrive. Messages are handed over in the order they
+//! arrive, resent ones with their PossDupFlag.
+//!
+//! Reads block: set a read timeout on the stream, such as
+//! `TcpStream::set_read_timeout` with the heartbeat interval, so that the
+//! connection gets the chance to send its heartbeats. `Initiator::connect`
+//! and `Acceptor::accept` do so.
+
+use std::io;
+use std::io::{Read, Write};
+use std::time::{Duration, SystemTime};
+
+use super::{FIXChecksumValidatorError, ValidationOptions, finalize, validate_bytes_with};
+use super::clock::{Clock, SystemClock, format_utc_timestamp};
+use super::framing::{decode, read_fix_message_with};
+use super::message::{FixMessage, MessageView};
+use super::orders::{FieldWriter, Header};
+use super::session::SessionId;
+use super::store::{Checkpoint, MessageStore};
+
+/// MsgType (35) of the administrative messages, which are not sent again.
+pub(crate) const ADMIN_MSG_TYPES: [&[u8]; 7] = [b"0", b"1", b"2", b"3", b"4", b"5", b"A"];
+
+/// What the counterparty sent, as returned by `Connection::next_event`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Event {
+  /// An application message, already validated and counted.
+  Application(Vec<u8>),
+  /// The counterparty ended the session with a Logout (5), answered if the
+  /// Logout was not asked for with `Connection::logout`.
+  LoggedOut,
+}
+
+/// The session layer of a connection over `stream`.
+pub struct Connection<S, M, C = SystemClock> {
+  pub(crate) stream: S,
+  pub(crate) store: M,
+  pub(crate) clock: C,
+  pub(crate) session: SessionId,
+  pub(crate) begin_string: String,
+  pub(crate) heartbeat_interval: Duration,
+  options: ValidationOptions,
+  pub(crate) checkpoint: Checkpoint,
+  buffer: Vec<u8>,
+  last_sent: Option<SystemTime>,
+  logging_out: bool,
+  // Gap to request with the next read
+  pending_resend: Option<(u64, u64)>,
+}
+
+impl<S: Read + Write, M: MessageStore> Connection<S, M> {
+  /// Creates the connection of `session` over `stream`, not logged on yet,
+  /// with FIX.4.2 and a heartbeat every 30 seconds.
+  pub(crate) fn new(stream: S, session: SessionId, store: M) -> Connection<S, M> {
+    Connection {
+      stream,
+      store,
+      clock: SystemClock,
+      session,
+      begin_string: "FIX.4.2".to_string(),
+      heartbeat_interval: Duration::from_secs(30),
+      options: ValidationOptions::new().strict(true).check_body_length(true),
+      checkpoint: Checkpoint::new(),
+      buffer: Vec::new(),
+      last_sent: None,
+      logging_out: false,
+      pending_resend: None,
+    }
+  }
+}
+
+impl<S: Read + Write, M: MessageStore, C: Clock> Connection<S, M, C> {
+  /// Takes the SendingTime (52) of messages and the time of heartbeats
+  /// from `clock`.
+  pub fn clock<D: Clock>(self, clock: D) -> Connection<S, M, D> {
+    Connection {
+      stream: self.stream,
+      store: self.store,
+      clock,
+      session: self.session,
+      begin_string: self.begin_string,
+      heartbeat_interval: self.heartbeat_interval,
+      options: self.options,
+      checkpoint: self.checkpoint,
+      buffer: self.buffer,
+      last_sent: self.last_sent,
+      logging_out: self.logging_out,
+      pending_resend: self.pending_resend,
+    }
+  }
+
+  /// Returns the session, SenderCompID (49) being this side.
+  pub fn session(&self) -> &SessionId {
+    &self.session
+  }
+
+  /// Returns the HeartBtInt (108) of the session.
+  pub fn heartbeat_interval(&self) -> Duration {
+    self.heartbeat_interval
+  }
+
+  /// Returns the sequence numbers of the session.
+  pub fn checkpoint(&self) -> &Checkpoint {
+    &self.checkpoint
+  }
+
+  /// Returns the store of sent messages.
+  pub fn store(&self) -> &M {
+    &self.store
+  }
+
+  /// Returns the underlying stream.
+  pub fn get_ref(&self) -> &S {
+    &self.stream
+  }
+
+  /// Sends the message built by `build` with the next header of the
+  /// session, such as `|header| order.build(header)`, and returns its
+  /// MsgSeqNum (34).
+  pub fn send<F>(&mut self, build: F) -> io::Result<u64>
+    where F: FnOnce(&Header) -> Result<Vec<u8>, FIXChecksumValidatorError> {
+    let header = self.next_header();
+    let message = build(&header).map_err(invalid_input)?;
+    self.write(header.msg_seq_num, &message)?;
+    Ok(header.msg_seq_num)
+  }
+
+  /// Sends a Logout (5). The session ends when `next_event` returns the
+  /// Logout of the counterparty.
+  pub fn logout(&mut self) -> io::Result<()> {
+    self.logging_out = true;
+    self.send_admin("5", |_| {})
+  }
+
+  /// Reads until the counterparty sends an application message or a Logout,
+  /// answering the administrative messages and sending heartbeats on the
+  /// way.
+  pub fn next_event(&mut self) -> io::Result<Event> {
+    loop {
+      let message = self.read_message()?;
+      if let Some(event) = self.handle(message)? { return Ok(event); }
+    }
+  }
+
+  /// Answers a message read and counted, returning the event it makes.
+  pub(crate) fn handle(&mut self, message: Vec<u8>) -> io::Result<Option<Event>> {
+    let (application, logout) = {
+      let view = parse(&message)?;
+      if self.session_of(&view) != Some(true) {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "message of another session"));
+      }
+      (!self.answer(&view)?, view.get(35) == Some(b"5"))
+    };
+    if application { return Ok(Some(Event::Application(message))); }
+    if logout {
+      if !self.logging_out { self.send_admin("5", |_| {})?; }
+      return Ok(Some(Event::LoggedOut));
+    }
+    Ok(None)
+  }
+
+  /// Handles `message` as if it was read from the stream, dropping it if it
+  /// is garbled or a duplicate.
+  pub(crate) fn deliver(&mut self, message: Vec<u8>) -> io::Result<Option<Event>> {
+    if !validate_bytes_with(&message, &self.options).unwrap_or(false) || !self.receive(&message)? {
+      return Ok(None);
+    }
+    self.request_resend()?;
+    self.handle(message)
+  }
+
+  /// Returns `Some(true)` if `view` was sent to this side by the
+  /// counterparty of the session.
+  fn session_of(&self, view: &MessageView) -> Option<bool> {
+    Some(view.get_str(49)? == self.session.target_comp_id && view.get_str(56)? == self.session.sender_comp_id)
+  }
+
+  /// Answers an administrative message, returning `false` for application
+  /// messages.
+  pub(crate) fn answer(&mut self, view: &MessageView) -> io::Result<bool> {
+    match view.get(35) {
+      Some(b"1") => {
+        let test_req_id = view.get_str(112).unwrap_or("").to_string();
+        self.send_admin("0", |writer| { let _ = writer.text(112, &test_req_id); })?;
+      },
+      Some(b"2") => self.resend(view)?,
+      Some(msg_type) if ADMIN_MSG_TYPES.contains(&msg_type) => {},
+      _ => return Ok(false),
+    }
+    Ok(true)
+  }
+
+  /// Sends the messages BeginSeqNo (7) to EndSeqNo (16) of a
+  /// ResendRequest again, 0 standing for the last one sent.
+  fn resend(&mut self, view: &MessageView) -> io::Result<()> {
+    let begin = seq_num_field(view, 7)?.max(1);
+    let end = match seq_num_field(view, 16)? {
+      0 => self.checkpoint.last_sent,
+      end => end.min(self.checkpoint.last_sent),
+    };
+    let mut next = begin;
+    for (seq_num, message) in self.store.messages(&self.session, begin, end)? {
+      let message = FixMessage::parse(&message, '\x01').map_err(invalid_data)?;
+      if message.get(35).is_none_or(|msg_type| ADMIN_MSG_TYPES.contains(&msg_type)) { continue; }
+      if seq_num > next { self.gap_fill(next, seq_num)?; }
+      let sending_time = format_utc_timestamp(self.clock.now());
+      let message = possible_duplicate(message, &sending_time).map_err(invalid_data)?;
+      self.transmit(&message)?;
+      next = seq_num + 1;
+    }
+    if next <= end { self.gap_fill(next, end + 1)?; }
+    Ok(())
+  }
+
+  /// Sends a SequenceReset-GapFill numbered `seq_num` skipping to
+  /// `new_seq_num`.
+  fn gap_fill(&mut self, seq_num: u64, new_seq_num: u64) -> io::Result<()> {
+    let mut header = self.next_header();
+    header.msg_seq_num = seq_num;
+    let message = gap_fill(&header, new_seq_num).map_err(invalid_input)?;
+    self.transmit(&message)
+  }
+
+  /// Reads the next valid message which is not a duplicate and counts it,
+  /// requesting a gap found before it first.
+  pub(crate) fn read_message(&mut self) -> io::Result<Vec<u8>> {
+    loop {
+      self.request_resend()?;
+      let message = self.read_frame()?;
+      if self.receive(&message)? { return Ok(message); }
+    }
+  }
+
+  /// Sends the ResendRequest of the last gap found, if not sent yet.
+  pub(crate) fn request_resend(&mut self) -> io::Result<()> {
+    if let Some((begin, end)) = self.pending_resend.take() {
+      self.send_admin("2", |writer| {
+        writer.field(7, begin);
+        writer.field(16, end);
+      })?;
+    }
+    Ok(())
+  }
+
+  /// Checks the MsgSeqNum (34) of `message` and records it as received,
+  /// returning `false` for a possible duplicate of a message received
+  /// already. A lower number without PossDupFlag (43) fails with
+  /// `InvalidData`.
+  pub(crate) fn receive(&mut self, message: &[u8]) -> io::Result<bool> {
+    let view = parse(message)?;
+    let seq_num = seq_num_field(&view, 34)?;
+    let expected = self.checkpoint.next_target_seq_num();
+    let sequence_reset = view.get(35) == Some(b"4");
+    if sequence_reset && view.get(123) != Some(b"Y") {
+      // SequenceReset-Reset moves to NewSeqNo (36) whatever its MsgSeqNum
+      let new_seq_num = seq_num_field(&view, 36)?;
+      if new_seq_num < expected {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "SequenceReset to a lower MsgSeqNum"));
+      }
+      self.checkpoint.close_resend(1, new_seq_num - 1);
+      self.checkpoint.received(new_seq_num - 1);
+      self.store.checkpoint(&self.session, &self.checkpoint)?;
+      return Ok(true);
+    }
+
+    if seq_num > expected {
+      self.checkpoint.open_resend(expected, seq_num - 1);
+      let begin = self.pending_resend.map_or(expected, |(begin, _)| begin);
+      self.pending_resend = Some((begin, seq_num - 1));
+    } else if seq_num < expected && !self.checkpoint.resend_ranges.iter().any(|&(begin, end)| {
+      begin <= seq_num && seq_num <= end
+    }) {
+      if view.get(43) == Some(b"Y") { return Ok(false); }
+      return Err(io::Error::new(io::ErrorKind::InvalidData, "MsgSeqNum lower than expected"));
+    }
+    self.checkpoint.received(seq_num);
+    if sequence_reset {
+      let new_seq_num = seq_num_field(&view, 36)?;
+      if new_seq_num > seq_num {
+        self.checkpoint.close_resend(seq_num, new_seq_num - 1);
+        self.checkpoint.received(new_seq_num - 1);
+      }
+    }
+    self.store.checkpoint(&self.session, &self.checkpoint)?;
+    Ok(true)
+  }
+
+  /// Reads the next valid message without counting it. Garbled messages are
+  /// dropped, as the counterparty sends them again on a ResendRequest (2).
+  pub(crate) fn read_frame(&mut self) -> io::Result<Vec<u8>> {
+    loop {
+      match read_fix_message_with(&mut self.stream, &mut self.buffer, &self.options) {
+        Ok(message) => return Ok(message),
+        Err(ref err) if err.kind() == io::ErrorKind::WouldBlock || err.kind() == io::ErrorKind::TimedOut => {
+          self.heartbeat_if_due()?;
+        },
+        Err(ref err) if err.kind() == io::ErrorKind::InvalidData && decode(&self.buffer, &self.options).is_ok() => {},
+        Err(err) => return Err(err),
+      }
+    }
+  }
+
+  pub(crate) fn heartbeat_if_due(&mut self) -> io::Result<()> {
+    let now = self.clock.now();
+    let due = self.last_sent.is_none_or(|last_sent| {
+      now.duration_since(last_sent).unwrap_or_default() >= self.heartbeat_interval
+    });
+    if due { self.send_admin("0", |_| {})?; }
+    Ok(())
+  }
+
+  fn next_header(&self) -> Header {
+    Header::new(&self.begin_string, &self.session.sender_comp_id, &self.session.target_comp_id,
+      self.checkpoint.next_sender_seq_num(), &format_utc_timestamp(self.clock.now()))
+  }
+
+  pub(crate) fn send_admin<F: FnOnce(&mut FieldWriter)>(&mut self, msg_type: &str, fields: F) -> io::Result<()> {
+    let header = self.next_header();
+    let mut writer = FieldWriter::new(&header, msg_type).map_err(invalid_input)?;
+    fields(&mut writer);
+    let message = writer.finish().map_err(invalid_input)?;
+    self.write(header.msg_seq_num, &message)
+  }
+
+  /// Stores and writes a message, then saves the checkpoint.
+  fn write(&mut self, seq_num: u64, message: &[u8]) -> io::Result<()> {
+    self.store.store(&self.session, seq_num, message)?;
+    self.transmit(message)?;
+    self.checkpoint.sent(seq_num);
+    self.store.checkpoint(&self.session, &self.checkpoint)
+  }
+
+  /// Writes a message without counting it, as resent messages are.
+  fn transmit(&mut self, message: &[u8]) -> io::Result<()> {
+    self.stream.write_all(message)?;
+    self.stream.flush()?;
+    self.last_sent = Some(self.clock.now());
+    Ok(())
+  }
+}
+
+/// Returns `message` to be sent again at `sending_time`, with PossDupFlag
+/// (43) and its first SendingTime (52) as OrigSendingTime (122).
+pub(crate) fn possible_duplicate(mut message: FixMessage, sending_time: &str)
+  -> Result<Vec<u8>, FIXChecksumValidatorError> {
+  message.remove(43);
+  message.remove(122);
+  let position = message.fields.iter().position(|field| field.0 == 52)
+    .ok_or(FIXChecksumValidatorError::RequiredFieldMissing(52))?;
+  let original_sending_time = message.fields[position].1.clone();
+  message.set(52, sending_time.as_bytes());
+  message.insert(position + 1, 122, &original_sending_time);
+  message.insert(position, 43, b"Y");
+  finalize(&message.serialize(), &ValidationOptions::new())
+}
+
+/// Returns the SequenceReset-GapFill (4) with `header` skipping to
+/// `new_seq_num`.
+pub(crate) fn gap_fill(header: &Header, new_seq_num: u64) -> Result<Vec<u8>, FIXChecksumValidatorError> {
+  let mut writer = FieldWriter::new(header, "4")?;
+  writer.field(43, "Y");
+  writer.field(122, &header.sending_time);
+  writer.field(123, "Y");
+  writer.field(36, new_seq_num);
+  writer.finish()
+}
+
+fn seq_num_field(view: &MessageView, tag: u32) -> io::Result<u64> {
+  view.get_str(tag).and_then(|value| value.parse().ok())
+    .ok_or_else(|| invalid_data(FIXChecksumValidatorError::RequiredFieldMissing(tag)))
+}
+
+pub(crate) fn parse(message: &[u8]) -> io::Result<MessageView<'_>> {
+  MessageView::parse(message, '\x01').map_err(invalid_data)
+}
+
+fn invalid_data(err: FIXChecksumValidatorError) -> io::Error {
+  io::Error::new(io::ErrorKind::InvalidData, err)
+}
+
+fn invalid_input(err: FIXChecksumValidatorError) -> io::Error {
+  io::Error::new(io::ErrorKind::InvalidInput, err)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+  use std::io;
+  use std::io::{Read, Write};
+  use super::{Connection, Event};
+  use super::super::message::MessageView;
+  use super::super::{ValidationOptions, validate_bytes_with};
+  use super::super::orders::{FieldWriter, Header, NewOrderSingle, OrdType, Side};
+  use super::super::session::SessionId;
+  use super::super::store::{MemoryStore, MessageStore};
+
+  /// A stream replaying the messages of the counterparty, with a read
+  /// timeout wherever they hold an empty message, and keeping what is
+  /// written.
+  pub(crate) struct Script {
+    incoming: Vec<Vec<u8>>,
+    pub(crate) outgoing: Vec<u8>,
+  }
+
+  impl Script {
+    pub(crate) fn new(incoming: Vec<Vec<u8>>) -> Script {
+      Script { incoming, outgoing: Vec::new() }
+    }
+  }
+
+  impl Read for Script {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+      if self.incoming.is_empty() { return Ok(0); }
+      let next = self.incoming.remove(0);
+      if next.is_empty() { return Err(io::Error::new(io::ErrorKind::WouldBlock, "timed out")); }
+      buf[..next.len()].copy_from_slice(&next);
+      Ok(next.len())
+    }
+  }
+
+  impl Write for Script {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+      self.outgoing.extend_from_slice(buf);
+      Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+      Ok(())
+    }
+  }
+
+  /// Returns a message of `sender` to `target`.
+  pub(crate) fn message(sender: &str, target: &str, seq_num: u64, msg_type: &str, fields: &[(u32, &str)]) -> Vec<u8> {
+    let header = Header::new("FIX.4.2", sender, target, seq_num, "20240102-09:30:00");
+    let mut writer = FieldWriter::new(&header, msg_type).unwrap();
+    for &(tag, value) in fields { writer.text(tag, value).unwrap(); }
+    writer.finish().unwrap()
+  }
+
+  /// Returns a message of EXCH to BRKR.
+  pub(crate) fn counterparty(seq_num: u64, msg_type: &str, fields: &[(u32, &str)]) -> Vec<u8> {
+    message("EXCH", "BRKR", seq_num, msg_type, fields)
+  }
+
+  /// Returns the messages written.
+  pub(crate) fn written(script: &Script) -> Vec<&[u8]> {
+    frames(&script.outgoing)
+  }
+
+  /// Splits SOH delimited messages written one after another.
+  pub(crate) fn frames(mut bytes: &[u8]) -> Vec<&[u8]> {
+    let mut frames = Vec::new();
+    while let Some(end) = bytes.windows(4).position(|window| window == b"\x0110=").map(|start| start + 8) {
+      frames.push(&bytes[..end]);
+      bytes = &bytes[end..];
+    }
+    frames
+  }
+
+  /// Returns the MsgType (35) and TestReqID (112) of the messages written.
+  pub(crate) fn sent(script: &Script) -> Vec<(String, Option<String>)> {
+    written(script).into_iter().map(|message| {
+      let view = MessageView::parse(message, '\x01').unwrap();
+      (view.get_str(35).unwrap().to_string(), view.get_str(112).map(|id| id.to_string()))
+    }).collect()
+  }
+
+  #[test]
+  fn it_should_reject_messages_of_other_sessions() {
+    let garbled = String::from_utf8(counterparty(1, "0", &[])).unwrap().replace("35=0", "35=1");
+    let incoming = vec![garbled.into_bytes(), counterparty(2, "8", &[]), message("OTHR", "BRKR", 3, "8", &[])];
+    let mut connection = Connection::new(Script::new(incoming), SessionId::new("BRKR", "EXCH"), MemoryStore::new());
+    assert!(matches!(connection.next_event().unwrap(), Event::Application(_)));
+    assert_eq!(connection.checkpoint().last_received, 2);
+    assert_eq!(connection.next_event().unwrap_err().kind(), io::ErrorKind::InvalidData);
+    assert_eq!(connection.next_event().unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+  }
+
+  #[test]
+  fn it_should_answer_resend_requests_from_store() {
+    let incoming = vec![counterparty(1, "2", &[(7, "1"), (16, "0")]), counterparty(2, "5", &[])];
+    let mut connection = Connection::new(Script::new(incoming), SessionId::new("BRKR", "EXCH"), MemoryStore::new());
+    for cl_ord_id in &["ORD-1", "ORD-2"] {
+      connection.send(|header| NewOrderSingle::new(cl_ord_id, "IBM", Side::Buy, "100", OrdType::Market).build(header))
+        .unwrap();
+      connection.send_admin("0", |_| {}).unwrap();
+    }
+    assert_eq!(connection.next_event().unwrap(), Event::LoggedOut);
+
+    let options = ValidationOptions::new().strict(true).check_body_length(true);
+    let written = written(connection.get_ref());
+    assert!(written.iter().all(|message| validate_bytes_with(message, &options).unwrap()));
+    let resent: Vec<_> = written[4..8].iter().map(|message| MessageView::parse(message, '\x01').unwrap()).collect();
+    let fields: Vec<_> = resent.iter().map(|view| (view.get_str(35), view.get_str(34), view.get_str(36))).collect();
+    assert_eq!(fields, vec![(Some("D"), Some("1"), None), (Some("4"), Some("2"), Some("3")),
+      (Some("D"), Some("3"), None), (Some("4"), Some("4"), Some("5"))]);
+    assert!(resent.iter().all(|view| view.get(43) == Some(b"Y")));
+    assert_eq!(resent[0].get(122), MessageView::parse(written[0], '\x01').unwrap().get(52));
+    assert_eq!(sent(connection.get_ref())[8], ("5".to_string(), None));
+    assert_eq!(connection.checkpoint().last_sent, 5);
+  }
+
+  #[test]
+  fn it_should_recover_gaps_with_resend_requests() {
+    let session = SessionId::new("BRKR", "EXCH");
+    let incoming = vec![counterparty(1, "8", &[]), counterparty(4, "8", &[(11, "ORD-4")]),
+      counterparty(2, "4", &[(43, "Y"), (123, "Y"), (36, "3")]), counterparty(3, "8", &[(43, "Y"), (11, "ORD-3")]),
+      counterparty(4, "8", &[(43, "Y"), (11, "ORD-4")]), counterparty(5, "5", &[])];
+    let mut connection = Connection::new(Script::new(incoming), session.clone(), MemoryStore::new());
+    assert!(matches!(connection.next_event().unwrap(), Event::Application(_)));
+    assert!(matches!(connection.next_event().unwrap(), Event::Application(_)));
+    assert_eq!(connection.checkpoint().resend_ranges, vec![(2, 3)]);
+    match connection.next_event().unwrap() {
+      Event::Application(message) => assert!(message.windows(8).any(|window| window == b"11=ORD-3")),
+      event => panic!("unexpected {:?}", event),
+    }
+    assert_eq!(connection.next_event().unwrap(), Event::LoggedOut);
+    assert_eq!(connection.store().restore(&session).unwrap().resend_ranges, vec![]);
+    assert_eq!(connection.checkpoint().last_received, 5);
+
+    let request = MessageView::parse(written(connection.get_ref())[0], '\x01').unwrap();
+    assert_eq!((request.get_str(35), request.get_str(7), request.get_str(16)), (Some("2"), Some("2"), Some("3")));
+    assert_eq!(sent(connection.get_ref()).len(), 2);
+  }
+
+  #[test]
+  fn it_should_follow_sequence_resets() {
+    let incoming = vec![counterparty(1, "4", &[(36, "10")]), counterparty(10, "8", &[]), counterparty(9, "8", &[])];
+    let mut connection = Connection::new(Script::new(incoming), SessionId::new("BRKR", "EXCH"), MemoryStore::new());
+    assert!(matches!(connection.next_event().unwrap(), Event::Application(_)));
+    assert_eq!(connection.checkpoint().last_received, 10);
+    assert_eq!(connection.next_event().unwrap_err().kind(), io::ErrorKind::InvalidData);
+    assert!(connection.get_ref().outgoing.is_empty());
+  }
+}
@@ -0,0 +1,241 @@
+//! Chaos testing: valid messages damaged on purpose.
+//!
+//! A `Corruptor` takes finalized messages and injects the faults it is
+//! configured with, chosen at random by weight, into a given share of them.
+//! Each result is labelled with the fault injected, so that a test feeding
+//! a consuming system can check it rejected exactly the damaged messages,
+//! and for the right reason.
+//!
+//! # Examples
+//!
+//! ```
+//! use fix_checksum::validate_bytes_with;
+//! use fix_checksum::ValidationOptions;
+//! use fix_checksum::corruptor::{Corruptor, Fault};
+//!
+//! let mut corruptor = Corruptor::new().fault(Fault::FlipByte, 0).rate(0.5).seed(3);
+//! let options = ValidationOptions::new().check_body_length(true);
+//! for _ in 0..100 {
+//!   let corrupted = corruptor.corrupt(b"8=FIX.4.2\x019=5\x0135=0\x0110=161\x01").unwrap();
+//!   let valid = validate_bytes_with(&corrupted.message, &options).unwrap_or(false);
+//!   assert_eq!(valid, corrupted.fault.is_none(), "{:?}", corrupted.fault);
+//! }
+//! ```
+
+use std::fmt;
+
+use super::{FIXChecksumValidatorError, FIX_CHECKSUM_FIELD, checksum_bytes, checksum_digits, checksum_field_start,
+  declared_body_length};
+use super::FIXChecksumValidatorError::{ChecksumFieldNotFound, FieldInvalidFormat};
+use super::generator::XorShift;
+
+/// A way of damaging a message.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Fault {
+  /// One bit of a tag or value byte of the body is flipped, so that the
+  /// CheckSum (10) does not match while the fields stay apart.
+  FlipByte,
+  /// The CheckSum (10) field is removed.
+  DropTrailer,
+  /// The last fields of the body are removed, the trailer is kept.
+  TruncateBody,
+  /// The BodyLength (9) value is off, the CheckSum (10) is recomputed to
+  /// match it.
+  BreakBodyLength,
+}
+
+impl Fault {
+  /// Every fault.
+  pub const ALL: [Fault; 4] = [Fault::FlipByte, Fault::DropTrailer, Fault::TruncateBody, Fault::BreakBodyLength];
+
+  /// Returns the label of the fault, such as `flip-byte`.
+  pub fn label(&self) -> &'static str {
+    match *self {
+      Fault::FlipByte => "flip-byte",
+      Fault::DropTrailer => "drop-trailer",
+      Fault::TruncateBody => "truncate-body",
+      Fault::BreakBodyLength => "break-body-length",
+    }
+  }
+}
+
+impl fmt::Display for Fault {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    f.write_str(self.label())
+  }
+}
+
+/// A message out of a `Corruptor`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Corrupted {
+  /// The fault injected, none for a message passed through unchanged.
+  pub fault: Option<Fault>,
+  pub message: Vec<u8>,
+}
+
+/// Injector of faults into valid messages, see the module documentation.
+#[derive(Clone, Debug)]
+pub struct Corruptor {
+  weights: Vec<(Fault, u32)>,
+  rate: f64,
+  delimiter: u8,
+  random: XorShift,
+}
+
+impl Default for Corruptor {
+  fn default() -> Self {
+    Corruptor::new()
+  }
+}
+
+impl Corruptor {
+  /// Creates a corruptor injecting every fault with the same weight into
+  /// every SOH delimited message.
+  pub fn new() -> Corruptor {
+    Corruptor {
+      weights: Fault::ALL.iter().map(|&fault| (fault, 1)).collect(),
+      rate: 1.0,
+      delimiter: b'\x01',
+      random: XorShift::new(1),
+    }
+  }
+
+  /// Sets the relative frequency of `fault`, zero for never.
+  pub fn fault(mut self, fault: Fault, weight: u32) -> Corruptor {
+    if let Some(entry) = self.weights.iter_mut().find(|entry| entry.0 == fault) { entry.1 = weight; }
+    self
+  }
+
+  /// Sets the share of messages damaged, between 0 and 1. The others pass
+  /// through unchanged.
+  pub fn rate(mut self, rate: f64) -> Corruptor {
+    self.rate = rate.clamp(0.0, 1.0);
+    self
+  }
+
+  /// Sets the field delimiter of the messages.
+  pub fn delimiter(mut self, delimiter: char) -> Corruptor {
+    assert!(delimiter.is_ascii(), "FIX delimiter must be an ASCII character");
+    self.delimiter = delimiter as u8;
+    self
+  }
+
+  /// Seeds the pseudo random choices of faults and of where they hit.
+  pub fn seed(mut self, seed: u64) -> Corruptor {
+    self.random = XorShift::new(seed);
+    self
+  }
+
+  /// Damages `message` with a fault drawn by weight, or passes it through
+  /// as the rate says or when every weight is zero. Fails if the message has
+  /// no BodyLength (9) or CheckSum (10) field.
+  pub fn corrupt(&mut self, message: &[u8]) -> Result<Corrupted, FIXChecksumValidatorError> {
+    let total = self.weights.iter().map(|&(_, weight)| u64::from(weight)).sum::<u64>();
+    if total == 0 || (self.random.below(1 << 24) as f64) >= self.rate * f64::from(1 << 24) {
+      return Ok(Corrupted { fault: None, message: message.to_vec() });
+    }
+    let mut choice = self.random.below(total);
+    let fault = self.weights.iter().find(|&&(_, weight)| {
+      if choice < u64::from(weight) { return true; }
+      choice -= u64::from(weight);
+      false
+    }).map(|&(fault, _)| fault).expect("choice below the total weight");
+    Ok(Corrupted { fault: Some(fault), message: self.inject(fault, message)? })
+  }
+
+  /// Damages `message` with `fault`.
+  pub fn inject(&mut self, fault: Fault, message: &[u8]) -> Result<Vec<u8>, FIXChecksumValidatorError> {
+    let delimiter = self.delimiter;
+    let (declared, body_start) = declared_body_length(message, delimiter)?;
+    let trailer_start = checksum_field_start(message, delimiter).ok_or(ChecksumFieldNotFound)?;
+    if trailer_start <= body_start { return Err(ChecksumFieldNotFound); }
+
+    let mut damaged = message.to_vec();
+    match fault {
+      Fault::FlipByte => {
+        let keeps_fields = |b: u8| b != delimiter && b != b'=';
+        let indexes = (body_start..trailer_start).filter(|&index| keeps_fields(message[index])).collect::<Vec<_>>();
+        if indexes.is_empty() { return Err(FieldInvalidFormat); }
+        let index = indexes[self.random.below(indexes.len() as u64) as usize];
+        let masks = (0..7).map(|bit| 1 << bit).filter(|mask| keeps_fields(message[index] ^ mask)).collect::<Vec<u8>>();
+        damaged[index] ^= masks[self.random.below(masks.len() as u64) as usize];
+      },
+      Fault::DropTrailer => damaged.truncate(trailer_start),
+      Fault::TruncateBody => {
+        // Cut after a field delimiter, or at the start of the body.
+        let body = &message[body_start..trailer_start - 1];
+        let boundaries = body.iter().enumerate().filter(|&(_, &b)| b == delimiter).map(|(index, _)| index + 1);
+        let boundaries = Some(0).into_iter().chain(boundaries).collect::<Vec<_>>();
+        let cut = body_start + boundaries[self.random.below(boundaries.len() as u64) as usize];
+        damaged = [&message[..cut], &message[trailer_start..]].concat();
+      },
+      Fault::BreakBodyLength => {
+        let offset = 1 + self.random.below(9) as usize;
+        let wrong = if declared >= offset && self.random.below(2) == 0 { declared - offset } else { declared + offset };
+        let value_start = body_start - 1 - declared.to_string().len();
+        damaged = [&message[..value_start], wrong.to_string().as_bytes(), &message[body_start - 1..trailer_start]]
+          .concat();
+        let checksum = checksum_bytes(&damaged, delimiter);
+        damaged.extend_from_slice(FIX_CHECKSUM_FIELD.as_bytes());
+        damaged.extend_from_slice(checksum_digits(checksum));
+        damaged.push(delimiter);
+      },
+    }
+    Ok(damaged)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::collections::HashMap;
+  use super::{Corruptor, Fault};
+  use super::super::{ValidationOptions, validate_bytes_with};
+  use super::super::FIXChecksumValidatorError::{BodyLengthMismatch, ChecksumFieldNotFound};
+
+  const MESSAGE: &[u8] = b"8=FIX.4.2|9=42|35=D|49=BRKR|56=EXCH|34=7|11=ORD-1|55=IBM|10=210|";
+
+  fn options() -> ValidationOptions {
+    ValidationOptions::new().delimiter('|').check_body_length(true)
+  }
+
+  #[test]
+  fn it_should_inject_labelled_faults() {
+    assert!(validate_bytes_with(MESSAGE, &options()).unwrap());
+    let mut corruptor = Corruptor::new().delimiter('|').seed(11);
+    let mut counts = HashMap::new();
+    for _ in 0..400 {
+      let corrupted = corruptor.corrupt(MESSAGE).unwrap();
+      let fault = corrupted.fault.unwrap();
+      *counts.entry(fault).or_insert(0) += 1;
+      let result = validate_bytes_with(&corrupted.message, &options());
+      match fault {
+        Fault::FlipByte => assert_eq!(result, Ok(false), "{:?}", corrupted.message),
+        Fault::DropTrailer => assert_eq!(result, Err(ChecksumFieldNotFound)),
+        Fault::TruncateBody => {
+          assert_eq!(result, Err(BodyLengthMismatch));
+          assert!(corrupted.message.ends_with(b"|10=210|"));
+        },
+        Fault::BreakBodyLength => {
+          assert_eq!(result, Err(BodyLengthMismatch));
+          assert!(validate_bytes_with(&corrupted.message, &options().check_body_length(false)).unwrap());
+        },
+      }
+    }
+    assert_eq!(counts.len(), 4);
+    assert!(counts.values().all(|&count| count > 60), "{:?}", counts);
+    assert_eq!(Fault::BreakBodyLength.to_string(), "break-body-length");
+  }
+
+  #[test]
+  fn it_should_corrupt_configured_share() {
+    let mut corruptor = Corruptor::new().delimiter('|').fault(Fault::FlipByte, 0).fault(Fault::TruncateBody, 0)
+      .fault(Fault::BreakBodyLength, 0).rate(0.25);
+    let damaged = (0..1_000).filter(|_| corruptor.corrupt(MESSAGE).unwrap().fault.is_some()).count();
+    assert!(damaged > 200 && damaged < 300, "{}", damaged);
+
+    let mut corruptor = corruptor.fault(Fault::DropTrailer, 0).rate(1.0);
+    assert_eq!(corruptor.corrupt(MESSAGE).unwrap().message, MESSAGE.to_vec());
+    assert_eq!(corruptor.inject(Fault::DropTrailer, MESSAGE).unwrap(), MESSAGE[..MESSAGE.len() - 7].to_vec());
+    assert_eq!(corruptor.inject(Fault::FlipByte, b"8=FIX.4.2|9=5|35=0|"), Err(ChecksumFieldNotFound));
+  }
+}
@@ -0,0 +1,301 @@
+//! Heuristics explaining checksum mismatches.
+//!
+//! A checksum mismatch only says that some bytes changed. Most corruption
+//! seen in practice is simple though: delimiters rewritten by a log viewer,
+//! a message cut short or a single mangled digit. The functions of this
+//! module check whether such a change explains the difference between the
+//! declared and the computed checksum.
+
+use std::fmt;
+
+use super::{FIXChecksumValidatorError, ValidationOptions, checksum_bytes, checksum_digits, checksum_field_start,
+  declared_body_length};
+use super::stream::Outcome;
+
+/// Bytes commonly substituted for SOH by logging and display tools.
+const DELIMITER_SUBSTITUTES: &[u8] = b"|^ ";
+/// Above this number of candidates a single byte change is not worth reporting.
+const MAX_BYTE_CANDIDATES: usize = 8;
+
+/// A possible explanation of a checksum mismatch.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Hypothesis {
+  /// `count` delimiters were replaced by `byte`.
+  DelimiterSubstituted { byte: u8, count: usize },
+  /// The body is `missing` bytes shorter than BodyLength declares.
+  Truncated { missing: usize },
+  /// The body is `extra` bytes longer than BodyLength declares, as after a
+  /// field was injected.
+  FieldInjected { extra: usize },
+  /// The digit at `offset` was `original` before corruption.
+  ByteChanged { offset: usize, original: u8 },
+  /// A single digit of the checksum value itself is damaged.
+  ChecksumValueChanged { expected: String },
+}
+
+impl fmt::Display for Hypothesis {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      Hypothesis::DelimiterSubstituted { byte, count } =>
+        write!(f, "{} delimiter(s) replaced by {:?}", count, byte as char),
+      Hypothesis::Truncated { missing } => write!(f, "message truncated by {} byte(s)", missing),
+      Hypothesis::FieldInjected { extra } => write!(f, "{} byte(s) injected in the body", extra),
+      Hypothesis::ByteChanged { offset, original } =>
+        write!(f, "byte at offset {} was {:?}", offset, original as char),
+      Hypothesis::ChecksumValueChanged { ref expected } =>
+        write!(f, "checksum value damaged, expected {}", expected),
+    }
+  }
+}
+
+/// This function looks for simple corruptions explaining why the checksum of
+/// a message does not match. It returns an empty vector when nothing fits.
+///
+/// # Examples
+///
+/// ```
+/// use fix_checksum::ValidationOptions;
+/// use fix_checksum::diagnose::{diagnose, Hypothesis};
+///
+/// let message = b"8=FIX.4.2\x019=5|35=0\x0110=161\x01";
+/// assert_eq!(diagnose(message, &ValidationOptions::new()),
+///   vec![Hypothesis::DelimiterSubstituted { byte: b'|', count: 1 }]);
+/// ```
+pub fn diagnose(message: &[u8], options: &ValidationOptions) -> Vec<Hypothesis> {
+  let delimiter = options.delimiter;
+  let mut hypotheses = Vec::new();
+  let split_index = match checksum_field_start(message, delimiter) {
+    Some(split_index) => split_index,
+    None => return hypotheses,
+  };
+  let (head, value) = (&message[..split_index], checksum_value(&message[split_index..], delimiter));
+
+  if let Ok((declared, body_start)) = declared_body_length(head, delimiter) {
+    let measured = head.len() - body_start;
+    if declared > measured { hypotheses.push(Hypothesis::Truncated { missing: declared - measured }); }
+    if declared < measured { hypotheses.push(Hypothesis::FieldInjected { extra: measured - declared }); }
+  }
+
+  let computed = checksum_bytes(head, delimiter);
+  let expected = checksum_digits(computed);
+  let changed_digits = value.iter().zip(expected).filter(|&(a, b)| a != b).count();
+  if value.len() == 3 && changed_digits == 1 {
+    hypotheses.push(Hypothesis::ChecksumValueChanged { expected: String::from_utf8_lossy(expected).into_owned() });
+  }
+
+  let declared = match String::from_utf8_lossy(value).parse::<u32>() {
+    Ok(declared) if value.iter().all(|b| b.is_ascii_digit()) => declared % 256,
+    _ => return hypotheses,
+  };
+  let delta = (computed + 256 - declared) % 256;
+  if delta == 0 { return hypotheses; }
+
+  for byte in DELIMITER_SUBSTITUTES {
+    let occurrences = head.iter().filter(|b| *b == byte).count();
+    let step = *byte as u32 - 1;
+    if let Some(count) = (1..occurrences + 1).find(|count| (*count as u32 * step) % 256 == delta) {
+      hypotheses.push(Hypothesis::DelimiterSubstituted { byte: *byte, count });
+    }
+  }
+
+  let shift = if delta < 128 { delta as i32 } else { delta as i32 - 256 };
+  if shift.abs() <= 9 {
+    let candidates = changed_digit_candidates(head, delimiter, shift);
+    if candidates.len() <= MAX_BYTE_CANDIDATES { hypotheses.extend(candidates); }
+  }
+
+  hypotheses
+}
+
+/// The BodyLength (9) of a message against its actual body, which runs
+/// from the field after BodyLength up to the CheckSum (10) field.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BodyLengthReport {
+  /// Value of BodyLength.
+  pub declared: usize,
+  /// Actual length of the body.
+  pub measured: usize,
+  /// Offset of the first byte of the body.
+  pub body_start: usize,
+  /// Offset where the body actually ends, the start of the CheckSum field.
+  pub body_end: usize,
+}
+
+impl BodyLengthReport {
+  /// Returns `true` if BodyLength matches the body.
+  pub fn is_match(&self) -> bool {
+    self.declared == self.measured
+  }
+
+  /// Returns the offset where the body ends according to BodyLength.
+  pub fn declared_end(&self) -> usize {
+    self.body_start.saturating_add(self.declared)
+  }
+}
+
+impl fmt::Display for BodyLengthReport {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "declared {}, measured {}", self.declared, self.measured)?;
+    if self.measured > self.declared {
+      write!(f, ", body ends at offset {} instead of {}: {} extra byte(s)", self.body_end, self.declared_end(),
+        self.measured - self.declared)?;
+    } else if self.measured < self.declared {
+      write!(f, ", body ends at offset {} instead of {}: {} missing byte(s)", self.body_end, self.declared_end(),
+        self.declared - self.measured)?;
+    }
+    Ok(())
+  }
+}
+
+/// This function compares the BodyLength of a message with its body. It
+/// returns `None` when the message has no BodyLength or CheckSum field.
+///
+/// # Examples
+///
+/// ```
+/// use fix_checksum::ValidationOptions;
+/// use fix_checksum::diagnose::body_length;
+///
+/// let report = body_length(b"8=FIX.4.2|9=5|35=0|58=hi|10=161|", &ValidationOptions::new().delimiter('|')).unwrap();
+/// assert_eq!((report.declared, report.measured, report.body_end), (5, 11, 25));
+/// assert_eq!(report.to_string(), "declared 5, measured 11, body ends at offset 25 instead of 19: 6 extra byte(s)");
+/// ```
+pub fn body_length(message: &[u8], options: &ValidationOptions) -> Option<BodyLengthReport> {
+  let body_end = checksum_field_start(message, options.delimiter)?;
+  let (declared, body_start) = declared_body_length(&message[..body_end], options.delimiter).ok()?;
+  Some(BodyLengthReport { declared, measured: body_end - body_start, body_start, body_end })
+}
+
+/// This function returns the offset of the bytes of `message` most likely
+/// at fault for `outcome`: a digit a single change explains, where the body
+/// and BodyLength (9) disagree, or the CheckSum (10) value. Failures which
+/// can not be located point at the start of the message, valid messages at
+/// nothing.
+///
+/// # Examples
+///
+/// ```
+/// use fix_checksum::ValidationOptions;
+/// use fix_checksum::FIXChecksumValidatorError::BodyLengthMismatch;
+/// use fix_checksum::diagnose::failure_offset;
+/// use fix_checksum::stream::Outcome;
+///
+/// let options = ValidationOptions::new().delimiter('|');
+/// let message = b"8=FIX.4.2|9=5|35=0|58=hi|10=161|";
+/// assert_eq!(failure_offset(message, &Outcome::Error(BodyLengthMismatch), &options), Some(19));
+/// assert_eq!(failure_offset(message, &Outcome::ChecksumMismatch, &options), Some(28));
+/// assert_eq!(failure_offset(message, &Outcome::Valid, &options), None);
+/// ```
+pub fn failure_offset(message: &[u8], outcome: &Outcome, options: &ValidationOptions) -> Option<usize> {
+  let checksum_value_start = checksum_field_start(message, options.delimiter).map(|start| start + 3);
+  let offset = match *outcome {
+    Outcome::Valid => return None,
+    Outcome::ChecksumMismatch => {
+      let changed = diagnose(message, options).into_iter().filter_map(|hypothesis| match hypothesis {
+        Hypothesis::ByteChanged { offset, .. } => Some(offset),
+        _ => None,
+      }).collect::<Vec<_>>();
+      if changed.len() == 1 { Some(changed[0]) } else { checksum_value_start }
+    },
+    Outcome::Repaired => checksum_value_start,
+    Outcome::Error(FIXChecksumValidatorError::BodyLengthMismatch) =>
+      body_length(message, options).map(|report| report.body_end.min(report.declared_end())),
+    Outcome::Error(FIXChecksumValidatorError::ChecksumFieldNotFound) => Some(message.len().saturating_sub(1)),
+    Outcome::Error(FIXChecksumValidatorError::ChecksumFieldInvalidFormat(_)) |
+    Outcome::Error(FIXChecksumValidatorError::ChecksumFieldNotLast) => checksum_value_start,
+    Outcome::Error(_) => None,
+  };
+  Some(offset.unwrap_or(0))
+}
+
+fn checksum_value(trailer: &[u8], delimiter: u8) -> &[u8] {
+  let value = &trailer[trailer.len().min(3)..];
+  match value.iter().position(|b| *b == delimiter) {
+    Some(end) => &value[..end],
+    None => value,
+  }
+}
+
+/// Digits in field values which, shifted back by `shift`, are still digits.
+fn changed_digit_candidates(head: &[u8], delimiter: u8, shift: i32) -> Vec<Hypothesis> {
+  let mut candidates = Vec::new();
+  let mut in_value = false;
+  for (offset, byte) in head.iter().enumerate() {
+    if *byte == delimiter { in_value = false; continue; }
+    if *byte == b'=' && !in_value { in_value = true; continue; }
+    if !in_value || !byte.is_ascii_digit() { continue; }
+
+    let original = *byte as i32 - shift;
+    if original >= b'0' as i32 && original <= b'9' as i32 {
+      candidates.push(Hypothesis::ByteChanged { offset, original: original as u8 });
+    }
+  }
+  candidates
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{body_length, diagnose, failure_offset, BodyLengthReport, Hypothesis};
+  use super::super::ValidationOptions;
+  use super::super::FIXChecksumValidatorError::{ChecksumFieldNotFound, FieldInvalidFormat};
+  use super::super::stream::Outcome;
+
+  #[test]
+  fn it_should_detect_substituted_delimiters() {
+    let message = b"8=FIX.4.2|9=5|35=0\x0110=161\x01";
+    assert_eq!(diagnose(message, &ValidationOptions::new()), vec![
+      Hypothesis::ChecksumValueChanged { expected: "151".to_string() },
+      Hypothesis::DelimiterSubstituted { byte: b'|', count: 2 },
+    ]);
+  }
+
+  #[test]
+  fn it_should_detect_truncated_message() {
+    let message = b"8=FIX.4.2\x019=9\x0135=0\x0110=161\x01";
+    assert!(diagnose(message, &ValidationOptions::new()).contains(&Hypothesis::Truncated { missing: 4 }));
+  }
+
+  #[test]
+  fn it_should_report_body_length_mismatches() {
+    let options = ValidationOptions::new().delimiter('|');
+    let truncated = body_length(b"8=FIX.4.2|9=9|35=0|10=161|", &options).unwrap();
+    assert_eq!(truncated, BodyLengthReport { declared: 9, measured: 5, body_start: 14, body_end: 19 });
+    assert_eq!(truncated.to_string(),
+      "declared 9, measured 5, body ends at offset 19 instead of 23: 4 missing byte(s)");
+
+    let injected = b"8=FIX.4.2|9=5|35=0|58=hi|10=161|";
+    assert!(diagnose(injected, &options).contains(&Hypothesis::FieldInjected { extra: 6 }));
+    assert!(body_length(b"8=FIX.4.2|9=5|35=0|10=161|", &options).unwrap().is_match());
+    assert_eq!(body_length(b"8=FIX.4.2|9=5|35=0|10=161|", &options).unwrap().to_string(), "declared 5, measured 5");
+    assert_eq!(body_length(b"8=FIX.4.2|35=0|10=161|", &options), None);
+  }
+
+  #[test]
+  fn it_should_detect_changed_digit() {
+    let message = b"8=FIX.4.2\x019=5\x0135=3\x0110=161\x01";
+    assert_eq!(diagnose(message, &ValidationOptions::new()), vec![
+      Hypothesis::ChecksumValueChanged { expected: "164".to_string() },
+      Hypothesis::ByteChanged { offset: 6, original: b'1' },
+      Hypothesis::ByteChanged { offset: 12, original: b'2' },
+      Hypothesis::ByteChanged { offset: 17, original: b'0' },
+    ]);
+  }
+
+  #[test]
+  fn it_should_detect_damaged_checksum_value() {
+    let message = b"8=FIX.4.2\x019=5\x0135=0\x0110=1X1\x01";
+    assert_eq!(diagnose(message, &ValidationOptions::new()),
+      vec![Hypothesis::ChecksumValueChanged { expected: "161".to_string() }]);
+    assert!(diagnose(b"8=FIX.4.2\x019=5\x0135=0\x0110=161\x01", &ValidationOptions::new()).is_empty());
+  }
+
+  #[test]
+  fn it_should_locate_failures() {
+    let options = ValidationOptions::new().delimiter('|');
+    let message = b"8=FIX.4.2|9=5|35=0|10=161|";
+    assert_eq!(failure_offset(message, &Outcome::Repaired, &options), Some(22));
+    assert_eq!(failure_offset(&message[..19], &Outcome::Error(ChecksumFieldNotFound), &options), Some(18));
+    assert_eq!(failure_offset(b"8=FIX.4.2|garbage", &Outcome::Error(FieldInvalidFormat), &options), Some(0));
+    assert_eq!(failure_offset(b"", &Outcome::Error(ChecksumFieldNotFound), &options), Some(0));
+  }
+}
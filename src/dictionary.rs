@@ -0,0 +1,440 @@
+//! Names and value formats of standard FIX tags.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use super::FIXChecksumValidatorError;
+use super::FIXChecksumValidatorError::{FieldValueInvalid, TagAlreadyDefined};
+
+/// Standard tags and their names, sorted by tag.
+const TAG_NAMES: &[(u32, &str)] = &[
+  (1, "Account"), (6, "AvgPx"), (7, "BeginSeqNo"), (8, "BeginString"), (9, "BodyLength"),
+  (10, "CheckSum"), (11, "ClOrdID"), (14, "CumQty"), (15, "Currency"), (16, "EndSeqNo"),
+  (17, "ExecID"), (31, "LastPx"), (32, "LastQty"), (34, "MsgSeqNum"), (35, "MsgType"),
+  (36, "NewSeqNo"), (37, "OrderID"), (38, "OrderQty"), (39, "OrdStatus"), (40, "OrdType"),
+  (41, "OrigClOrdID"), (43, "PossDupFlag"), (44, "Price"), (45, "RefSeqNum"), (49, "SenderCompID"),
+  (52, "SendingTime"), (54, "Side"), (55, "Symbol"), (56, "TargetCompID"), (58, "Text"),
+  (59, "TimeInForce"), (60, "TransactTime"), (89, "Signature"), (90, "SecureDataLen"),
+  (91, "SecureData"), (93, "SignatureLength"), (95, "RawDataLength"), (96, "RawData"),
+  (97, "PossResend"), (98, "EncryptMethod"), (108, "HeartBtInt"), (112, "TestReqID"),
+  (120, "SettlCurrency"), (122, "OrigSendingTime"), (123, "GapFillFlag"), (141, "ResetSeqNumFlag"),
+  (146, "NoRelatedSym"), (150, "ExecType"), (151, "LeavesQty"), (152, "CashOrderQty"),
+  (212, "XmlDataLen"), (213, "XmlData"), (262, "MDReqID"), (263, "SubscriptionRequestType"),
+  (264, "MarketDepth"), (267, "NoMDEntryTypes"), (268, "NoMDEntries"), (269, "MDEntryType"),
+  (270, "MDEntryPx"), (271, "MDEntrySize"), (272, "MDEntryDate"), (273, "MDEntryTime"),
+  (278, "MDEntryID"), (279, "MDUpdateAction"), (447, "PartyIDSource"), (448, "PartyID"),
+  (452, "PartyRole"), (453, "NoPartyIDs"), (553, "Username"), (554, "Password"),
+];
+
+/// Legal values of standard enumerated tags, sorted by tag.
+const ENUM_VALUES: &[(u32, &[&str])] = &[
+  (39, &["0", "1", "2", "3", "4", "5", "6", "7", "8", "9", "A", "B", "C", "D", "E"]),
+  (40, &["1", "2", "3", "4", "5", "6", "7", "8", "9", "A", "B", "C", "D", "E", "F", "G", "H", "I", "J", "K", "P"]),
+  (54, &["1", "2", "3", "4", "5", "6", "7", "8", "9", "A", "B", "C", "D", "E", "F", "G"]),
+  (59, &["0", "1", "2", "3", "4", "5", "6", "7"]),
+  (150, &["0", "1", "2", "3", "4", "5", "6", "7", "8", "9", "A", "B", "C", "D", "E", "F", "G", "H", "I"]),
+  (263, &["0", "1", "2"]),
+  (269, &["0", "1", "2", "3", "4", "5", "6", "7", "8", "9", "A", "B", "C"]),
+  (279, &["0", "1", "2"]),
+];
+
+/// Tags required by standard message types beyond the standard header and
+/// trailer, sorted by MsgType (35).
+const CONDITIONAL_FIELDS: &[(&str, &[u32])] = &[
+  ("1", &[112]), ("2", &[7, 16]), ("3", &[45]), ("4", &[36]), ("8", &[37, 17, 150, 39, 54, 55]),
+  ("A", &[98, 108]), ("D", &[11, 54, 55, 40]), ("F", &[41, 11, 54, 55]), ("G", &[41, 11, 54, 55, 40]),
+  ("V", &[262, 263, 264]),
+];
+
+/// Active ISO 4217 currency codes, sorted.
+const CURRENCY_CODES: &[&str] = &[
+  "AED", "AFN", "ALL", "AMD", "ANG", "AOA", "ARS", "AUD", "AWG", "AZN", "BAM", "BBD", "BDT", "BGN",
+  "BHD", "BIF", "BMD", "BND", "BOB", "BOV", "BRL", "BSD", "BTN", "BWP", "BYN", "BZD", "CAD", "CDF",
+  "CHE", "CHF", "CHW", "CLF", "CLP", "CNY", "COP", "COU", "CRC", "CUP", "CVE", "CZK", "DJF", "DKK",
+  "DOP", "DZD", "EGP", "ERN", "ETB", "EUR", "FJD", "FKP", "GBP", "GEL", "GHS", "GIP", "GMD", "GNF",
+  "GTQ", "GYD", "HKD", "HNL", "HTG", "HUF", "IDR", "ILS", "INR", "IQD", "IRR", "ISK", "JMD", "JOD",
+  "JPY", "KES", "KGS", "KHR", "KMF", "KPW", "KRW", "KWD", "KYD", "KZT", "LAK", "LBP", "LKR", "LRD",
+  "LSL", "LYD", "MAD", "MDL", "MGA", "MKD", "MMK", "MNT", "MOP", "MRU", "MUR", "MVR", "MWK", "MXN",
+  "MXV", "MYR", "MZN", "NAD", "NGN", "NIO", "NOK", "NPR", "NZD", "OMR", "PAB", "PEN", "PGK", "PHP",
+  "PKR", "PLN", "PYG", "QAR", "RON", "RSD", "RUB", "RWF", "SAR", "SBD", "SCR", "SDG", "SEK", "SGD",
+  "SHP", "SLE", "SLL", "SOS", "SRD", "SSP", "STN", "SVC", "SYP", "SZL", "THB", "TJS", "TMT", "TND",
+  "TOP", "TRY", "TTD", "TWD", "TZS", "UAH", "UGX", "USD", "USN", "UYI", "UYU", "UYW", "UZS", "VED",
+  "VES", "VND", "VUV", "WST", "XAF", "XAG", "XAU", "XBA", "XBB", "XBC", "XBD", "XCD", "XCG", "XDR",
+  "XOF", "XPD", "XPF", "XPT", "XSU", "XTS", "XUA", "XXX", "YER", "ZAR", "ZMW", "ZWG", "ZWL",
+];
+
+/// Returns the name of a standard tag.
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(fix_checksum::dictionary::tag_name(35), Some("MsgType"));
+/// assert_eq!(fix_checksum::dictionary::tag_name(5001), None);
+/// ```
+pub fn tag_name(tag: u32) -> Option<&'static str> {
+  TAG_NAMES.binary_search_by_key(&tag, |entry| entry.0).ok().map(|index| TAG_NAMES[index].1)
+}
+
+/// Returns the legal values of a standard enumerated tag.
+///
+/// # Examples
+///
+/// ```
+/// let sides = fix_checksum::dictionary::legal_values(54).unwrap();
+/// assert!(sides.contains(&"1") && !sides.contains(&"0"));
+/// assert_eq!(fix_checksum::dictionary::legal_values(55), None);
+/// ```
+pub fn legal_values(tag: u32) -> Option<&'static [&'static str]> {
+  ENUM_VALUES.binary_search_by_key(&tag, |entry| entry.0).ok().map(|index| ENUM_VALUES[index].1)
+}
+
+/// Returns the tags a standard message type requires beyond the standard
+/// header and trailer, none for unknown types.
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(fix_checksum::dictionary::required_fields("F"), &[41, 11, 54, 55]);
+/// assert!(fix_checksum::dictionary::required_fields("U1").is_empty());
+/// ```
+pub fn required_fields(msg_type: &str) -> &'static [u32] {
+  CONDITIONAL_FIELDS.binary_search_by_key(&msg_type, |entry| entry.0).ok()
+    .map_or(&[], |index| CONDITIONAL_FIELDS[index].1)
+}
+
+/// Returns `true` for an active ISO 4217 currency code.
+///
+/// # Examples
+///
+/// ```
+/// assert!(fix_checksum::dictionary::is_currency_code("EUR"));
+/// assert!(!fix_checksum::dictionary::is_currency_code("eur"));
+/// ```
+pub fn is_currency_code(code: &str) -> bool {
+  CURRENCY_CODES.binary_search(&code).is_ok()
+}
+
+/// Value type of a field.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FieldType {
+  String,
+  Char,
+  Boolean,
+  Int,
+  /// Prices, quantities and amounts.
+  Decimal,
+  UtcTimestamp,
+  /// Raw bytes whose length is given by a preceding field.
+  Data,
+}
+
+impl FieldType {
+  /// Returns `true` if `value` is well-formed for the type.
+  pub fn is_valid(&self, value: &[u8]) -> bool {
+    let text = ::std::str::from_utf8(value);
+    match *self {
+      FieldType::String => !value.is_empty(),
+      FieldType::Char => value.len() == 1,
+      FieldType::Boolean => value == b"Y" || value == b"N",
+      FieldType::Int => {
+        let digits = value.strip_prefix(b"-").unwrap_or(value);
+        !digits.is_empty() && digits.iter().all(|b| b.is_ascii_digit())
+      },
+      FieldType::Decimal => text.is_ok_and(is_decimal),
+      FieldType::UtcTimestamp => text.is_ok_and(is_utc_timestamp),
+      FieldType::Data => true,
+    }
+  }
+}
+
+impl fmt::Display for FieldType {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let name = match *self {
+      FieldType::String => "String",
+      FieldType::Char => "char",
+      FieldType::Boolean => "Boolean",
+      FieldType::Int => "int",
+      FieldType::Decimal => "decimal",
+      FieldType::UtcTimestamp => "UTCTimestamp",
+      FieldType::Data => "data",
+    };
+    write!(f, "{}", name)
+  }
+}
+
+/// A field value decoded according to its type.
+#[derive(Clone, Debug, PartialEq)]
+pub enum FieldValue<'a> {
+  String(&'a str),
+  Char(char),
+  Boolean(bool),
+  Int(i64),
+  Decimal(f64),
+  UtcTimestamp(&'a str),
+  Data(&'a [u8]),
+}
+
+/// Returns the value type of a standard tag.
+///
+/// # Examples
+///
+/// ```
+/// use fix_checksum::dictionary::{field_type, FieldType};
+///
+/// assert_eq!(field_type(44), Some(FieldType::Decimal));
+/// assert_eq!(field_type(55), Some(FieldType::String));
+/// assert_eq!(field_type(5001), None);
+/// ```
+pub fn field_type(tag: u32) -> Option<FieldType> {
+  tag_name(tag)?;
+  Some(match tag {
+    7 | 9 | 16 | 34 | 36 | 45 | 90 | 93 | 95 | 98 | 108 | 146 | 212 | 264 | 267 | 268 | 452 | 453 =>
+      FieldType::Int,
+    6 | 14 | 31 | 32 | 38 | 44 | 151 | 152 | 270 | 271 => FieldType::Decimal,
+    39 | 40 | 54 | 59 | 150 | 263 | 269 | 279 | 447 => FieldType::Char,
+    43 | 97 | 123 | 141 => FieldType::Boolean,
+    52 | 60 | 122 => FieldType::UtcTimestamp,
+    89 | 91 | 96 | 213 => FieldType::Data,
+    _ => FieldType::String,
+  })
+}
+
+/// Standard tags extended with user-defined ones.
+///
+/// # Examples
+///
+/// ```
+/// use fix_checksum::dictionary::{Dictionary, FieldType, FieldValue};
+///
+/// let mut dictionary = Dictionary::new();
+/// dictionary.register(5001, "StrategyID", FieldType::Int).unwrap();
+/// assert_eq!(dictionary.tag_name(5001), Some("StrategyID"));
+/// assert_eq!(dictionary.decode(5001, b"42").unwrap(), FieldValue::Int(42));
+/// assert!(dictionary.register(55, "Ticker", FieldType::String).is_err());
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Dictionary {
+  custom: BTreeMap<u32, (String, FieldType)>,
+  values: BTreeMap<u32, Vec<String>>,
+  required: BTreeMap<String, Vec<u32>>,
+}
+
+impl Dictionary {
+  /// Creates a dictionary of the standard tags.
+  pub fn new() -> Dictionary {
+    Dictionary { custom: BTreeMap::new(), values: BTreeMap::new(), required: BTreeMap::new() }
+  }
+
+  /// Registers a user-defined tag. Standard and already registered tags
+  /// cannot be redefined.
+  pub fn register(&mut self, tag: u32, name: &str, field_type: FieldType) -> Result<(), FIXChecksumValidatorError> {
+    if tag_name(tag).is_some() || self.custom.contains_key(&tag) { return Err(TagAlreadyDefined(tag)); }
+    self.custom.insert(tag, (name.to_string(), field_type));
+    Ok(())
+  }
+
+  /// Restricts a tag to the given values. Tags with standard or already
+  /// registered values cannot be redefined.
+  pub fn register_values(&mut self, tag: u32, values: &[&str]) -> Result<(), FIXChecksumValidatorError> {
+    if legal_values(tag).is_some() || self.values.contains_key(&tag) { return Err(TagAlreadyDefined(tag)); }
+    self.values.insert(tag, values.iter().map(|value| value.to_string()).collect());
+    Ok(())
+  }
+
+  /// Requires the given tags on messages of type `msg_type`, in addition to
+  /// the standard ones.
+  pub fn register_required(&mut self, msg_type: &str, tags: &[u32]) {
+    let required = self.required.entry(msg_type.to_string()).or_default();
+    for tag in tags {
+      if !required_fields(msg_type).contains(tag) && !required.contains(tag) { required.push(*tag); }
+    }
+  }
+
+  /// Returns the standard and registered tags required on messages of type
+  /// `msg_type`.
+  pub fn required_fields(&self, msg_type: &str) -> Vec<u32> {
+    let registered = self.required.get(msg_type).map_or(&[][..], |tags| &tags[..]);
+    required_fields(msg_type).iter().chain(registered).cloned().collect()
+  }
+
+  /// Returns `Some(true)` if `value` is legal for an enumerated tag and
+  /// `None` if the tag is not enumerated.
+  pub fn is_legal_value(&self, tag: u32, value: &[u8]) -> Option<bool> {
+    match legal_values(tag) {
+      Some(values) => Some(values.iter().any(|legal| legal.as_bytes() == value)),
+      None => self.values.get(&tag).map(|values| values.iter().any(|legal| legal.as_bytes() == value)),
+    }
+  }
+
+  /// Returns the name of a standard or registered tag.
+  pub fn tag_name(&self, tag: u32) -> Option<&str> {
+    tag_name(tag).or_else(|| self.custom.get(&tag).map(|entry| &entry.0[..]))
+  }
+
+  /// Returns the value type of a standard or registered tag.
+  pub fn field_type(&self, tag: u32) -> Option<FieldType> {
+    field_type(tag).or_else(|| self.custom.get(&tag).map(|entry| entry.1))
+  }
+
+  /// Decodes `value` according to the type of `tag`. Unknown tags decode
+  /// as strings.
+  pub fn decode<'a>(&self, tag: u32, value: &'a [u8]) -> Result<FieldValue<'a>, FIXChecksumValidatorError> {
+    let field_type = self.field_type(tag).unwrap_or(FieldType::String);
+    if !field_type.is_valid(value) { return Err(FieldValueInvalid(tag)); }
+    let text = || ::std::str::from_utf8(value).map_err(|_| FieldValueInvalid(tag));
+    Ok(match field_type {
+      FieldType::String => FieldValue::String(text()?),
+      FieldType::Char => FieldValue::Char(value[0] as char),
+      FieldType::Boolean => FieldValue::Boolean(value == b"Y"),
+      FieldType::Int => FieldValue::Int(text()?.parse().map_err(|_| FieldValueInvalid(tag))?),
+      FieldType::Decimal => FieldValue::Decimal(text()?.parse().map_err(|_| FieldValueInvalid(tag))?),
+      FieldType::UtcTimestamp => FieldValue::UtcTimestamp(text()?),
+      FieldType::Data => FieldValue::Data(value),
+    })
+  }
+}
+
+/// Where a field belongs within a message.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum TagClass {
+  Header,
+  Body,
+  Trailer,
+  /// Tags 5000-39999, reserved for user-defined and internal fields.
+  UserDefined,
+}
+
+/// Returns where `tag` belongs within a message. Tags not known to be in the
+/// standard header or trailer are body tags.
+///
+/// # Examples
+///
+/// ```
+/// use fix_checksum::dictionary::{classify, TagClass};
+///
+/// assert_eq!(classify(49), TagClass::Header);
+/// assert_eq!(classify(55), TagClass::Body);
+/// assert_eq!(classify(10), TagClass::Trailer);
+/// assert_eq!(classify(5001), TagClass::UserDefined);
+/// ```
+pub fn classify(tag: u32) -> TagClass {
+  match tag {
+    8 | 9 | 35 | 34 | 43 | 49 | 50 | 52 | 56 | 57 | 90 | 91 | 97 | 115 | 116 | 122 | 128 | 129 | 142 | 143
+      | 144 | 145 | 212 | 213 | 347 | 369 | 627 | 628 | 629 | 630 | 1128 | 1129 | 1156 => TagClass::Header,
+    10 | 89 | 93 => TagClass::Trailer,
+    5000..=39999 => TagClass::UserDefined,
+    _ => TagClass::Body,
+  }
+}
+
+/// Length fields and the data fields following them, whose values may
+/// contain the delimiter.
+const DATA_FIELDS: &[(u32, u32)] = &[(90, 91), (93, 89), (95, 96), (212, 213)];
+
+/// Returns the data field whose length is given by `length_tag`.
+pub(crate) fn data_field(length_tag: u32) -> Option<u32> {
+  DATA_FIELDS.iter().find(|fields| fields.0 == length_tag).map(|fields| fields.1)
+}
+
+/// Returns `true` for an optionally negative number with at most one decimal point.
+pub(crate) fn is_decimal(value: &str) -> bool {
+  let digits = value.strip_prefix('-').unwrap_or(value);
+  let mut parts = digits.splitn(2, '.');
+  let integer = parts.next().unwrap_or("");
+  let fraction = parts.next().unwrap_or("");
+  !(integer.is_empty() && fraction.is_empty())
+    && integer.bytes().chain(fraction.bytes()).all(|b| b.is_ascii_digit())
+    && !digits.ends_with('.')
+}
+
+/// Returns `true` for a UTCTimestamp: `YYYYMMDD-HH:MM:SS` with optional milliseconds.
+pub(crate) fn is_utc_timestamp(value: &str) -> bool {
+  let bytes = value.as_bytes();
+  let number = |range: ::std::ops::Range<usize>| -> Option<u32> {
+    let digits = &bytes[range];
+    if !digits.iter().all(|b| b.is_ascii_digit()) { return None; }
+    Some(digits.iter().fold(0, |number, b| number * 10 + (*b - b'0') as u32))
+  };
+  if bytes.len() != 17 && !(bytes.len() == 21 && bytes[17] == b'.') { return false; }
+  if bytes[8] != b'-' || bytes[11] != b':' || bytes[14] != b':' { return false; }
+  if bytes.len() == 21 && number(18..21).is_none() { return false; }
+  match (number(4..6), number(6..8), number(9..11), number(12..14), number(15..17), number(0..4)) {
+    (Some(month), Some(day), Some(hour), Some(minute), Some(second), Some(_)) =>
+      (1..13).contains(&month) && (1..32).contains(&day) && hour < 24 && minute < 60 && second < 61,
+    _ => false,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{Dictionary, FieldType, FieldValue, TagClass, CONDITIONAL_FIELDS, CURRENCY_CODES, ENUM_VALUES, TAG_NAMES, classify, field_type, is_decimal,
+    is_utc_timestamp};
+  use super::super::FIXChecksumValidatorError::{FieldValueInvalid, TagAlreadyDefined};
+
+  #[test]
+  fn it_should_keep_tag_names_sorted() {
+    assert!(TAG_NAMES.windows(2).all(|pair| pair[0].0 < pair[1].0));
+    assert!(ENUM_VALUES.windows(2).all(|pair| pair[0].0 < pair[1].0));
+    assert!(CURRENCY_CODES.windows(2).all(|pair| pair[0] < pair[1]));
+    assert!(CONDITIONAL_FIELDS.windows(2).all(|pair| pair[0].0 < pair[1].0));
+  }
+
+  #[test]
+  fn it_should_check_value_formats() {
+    assert!(is_decimal("10") && is_decimal("-1.25") && is_decimal(".5"));
+    assert!(!is_decimal("") && !is_decimal("-") && !is_decimal("1.") && !is_decimal("1.2.3") && !is_decimal("1e3"));
+    assert!(is_utc_timestamp("20240102-23:59:60") && is_utc_timestamp("20240102-09:30:00.000"));
+    assert!(!is_utc_timestamp("20241302-09:30:00") && !is_utc_timestamp("20240102-09:30:00.00"));
+    assert!(!is_utc_timestamp("20240102-24:00:00") && !is_utc_timestamp("2024010209:30:00"));
+  }
+
+  #[test]
+  fn it_should_type_standard_tags() {
+    assert_eq!(field_type(34), Some(FieldType::Int));
+    assert_eq!(field_type(54), Some(FieldType::Char));
+    assert_eq!(field_type(52), Some(FieldType::UtcTimestamp));
+    assert!(FieldType::Int.is_valid(b"-12") && !FieldType::Int.is_valid(b"1.5") && !FieldType::Int.is_valid(b"-"));
+    assert!(FieldType::Boolean.is_valid(b"N") && !FieldType::Char.is_valid(b"") && !FieldType::String.is_valid(b""));
+  }
+
+  #[test]
+  fn it_should_register_user_defined_tags() {
+    let mut dictionary = Dictionary::new();
+    dictionary.register(5001, "StrategyID", FieldType::Int).unwrap();
+    dictionary.register(9001, "Urgent", FieldType::Boolean).unwrap();
+    assert_eq!(dictionary.register(5001, "Other", FieldType::String), Err(TagAlreadyDefined(5001)));
+    assert_eq!(dictionary.register(8, "Other", FieldType::String), Err(TagAlreadyDefined(8)));
+
+    assert_eq!(dictionary.tag_name(35), Some("MsgType"));
+    assert_eq!(dictionary.field_type(9001), Some(FieldType::Boolean));
+    assert_eq!(dictionary.decode(9001, b"Y"), Ok(FieldValue::Boolean(true)));
+    assert_eq!(dictionary.decode(5001, b"x"), Err(FieldValueInvalid(5001)));
+    assert_eq!(dictionary.decode(44, b"1.25"), Ok(FieldValue::Decimal(1.25)));
+    assert_eq!(dictionary.decode(7777, b"abc"), Ok(FieldValue::String("abc")));
+    assert_eq!(Dictionary::new().tag_name(5001), None);
+
+    dictionary.register_values(5001, &["1", "2"]).unwrap();
+    assert_eq!(dictionary.register_values(5001, &["3"]), Err(TagAlreadyDefined(5001)));
+    assert_eq!(dictionary.register_values(54, &["3"]), Err(TagAlreadyDefined(54)));
+    assert_eq!(dictionary.is_legal_value(5001, b"2"), Some(true));
+    assert_eq!(dictionary.is_legal_value(54, b"Z"), Some(false));
+    assert_eq!(dictionary.is_legal_value(55, b"IBM"), None);
+
+    dictionary.register_required("F", &[5001, 41, 5001]);
+    dictionary.register_required("U1", &[58]);
+    assert_eq!(dictionary.required_fields("F"), vec![41, 11, 54, 55, 5001]);
+    assert_eq!(dictionary.required_fields("U1"), vec![58]);
+    assert_eq!(Dictionary::new().required_fields("G"), vec![41, 11, 54, 55, 40]);
+  }
+
+  #[test]
+  fn it_should_classify_tags() {
+    assert_eq!(classify(8), TagClass::Header);
+    assert_eq!(classify(52), TagClass::Header);
+    assert_eq!(classify(93), TagClass::Trailer);
+    assert_eq!(classify(1), TagClass::Body);
+    assert_eq!(classify(4999), TagClass::Body);
+    assert_eq!(classify(20000), TagClass::UserDefined);
+    assert_eq!(classify(40000), TagClass::Body);
+  }
+}
@@ -0,0 +1,191 @@
+//! CRC-32 and SHA-256 digests of messages and logs for audit trails.
+//!
+//! The CheckSum (10) of FIX catches most transmission errors but anyone can
+//! recompute it after editing a message, and one edit in 256 keeps it
+//! unchanged. Audit trails recording what was sent and received keep
+//! stronger digests next to the messages: a CRC-32 to detect corruption
+//! cheaply and a SHA-256 as evidence that the bytes were not altered since.
+//! `DigestReader` computes the same digests over a whole log file while it
+//! is being validated.
+//!
+//! # Examples
+//!
+//! ```
+//! use std::io::BufRead;
+//! use fix_checksum::digest::{Digests, DigestReader};
+//!
+//! let message = b"8=FIX.4.2|9=5|35=0|10=161|";
+//! assert_eq!(Digests::of(message).to_string(),
+//!   "crc32:9711af54 sha256:da76c65535a24289c0c5c45ffb9022a559989fa95efa3441e56ea9d64514f1c1");
+//!
+//! let mut log = DigestReader::new(&b"8=FIX.4.2|9=5|35=0|10=161|\n8=FIX.4.2|9=5|35=1|10=162|\n"[..]);
+//! assert_eq!((&mut log).lines().count(), 2);
+//! assert_eq!(log.length(), 54);
+//! ```
+
+use std::fmt;
+use std::io;
+use std::io::{BufRead, Read};
+
+use super::hash::{Crc32, Sha256, to_hex};
+
+/// The CRC-32 and SHA-256 digests of some bytes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Digests {
+  /// CRC-32 (IEEE 802.3) of the bytes.
+  pub crc32: u32,
+  /// SHA-256 of the bytes.
+  pub sha256: [u8; 32],
+}
+
+impl Digests {
+  /// Returns the digests of `bytes`.
+  pub fn of(bytes: &[u8]) -> Digests {
+    let mut digester = Digester::new();
+    digester.update(bytes);
+    digester.digests()
+  }
+
+  /// Returns the SHA-256 as 64 lowercase hexadecimal digits.
+  pub fn sha256_hex(&self) -> String {
+    to_hex(&self.sha256)
+  }
+}
+
+/// Formats the digests as `crc32:<8 hex digits> sha256:<64 hex digits>`.
+impl fmt::Display for Digests {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "crc32:{:08x} sha256:{}", self.crc32, self.sha256_hex())
+  }
+}
+
+/// Running digests of the bytes passed to `update`.
+#[derive(Clone, Debug)]
+pub struct Digester {
+  crc32: Crc32,
+  sha256: Sha256,
+  length: u64,
+}
+
+impl Digester {
+  /// Creates a digester of no bytes yet.
+  pub fn new() -> Digester {
+    Digester { crc32: Crc32::new(), sha256: Sha256::new(), length: 0 }
+  }
+
+  /// Adds `bytes` to the digests.
+  pub fn update(&mut self, bytes: &[u8]) {
+    self.crc32.update(bytes);
+    self.sha256.update(bytes);
+    self.length += bytes.len() as u64;
+  }
+
+  /// Returns the number of bytes digested.
+  pub fn length(&self) -> u64 {
+    self.length
+  }
+
+  /// Returns the digests of the bytes passed so far. More bytes can be
+  /// added afterwards.
+  pub fn digests(&self) -> Digests {
+    Digests { crc32: self.crc32.value(), sha256: self.sha256.clone().finish() }
+  }
+}
+
+impl Default for Digester {
+  fn default() -> Digester {
+    Digester::new()
+  }
+}
+
+/// A reader digesting every byte read through it, to record the digests of
+/// a log file as it is validated.
+#[derive(Debug)]
+pub struct DigestReader<R> {
+  inner: R,
+  digester: Digester,
+}
+
+impl<R> DigestReader<R> {
+  /// Creates a reader digesting the bytes read from `inner`.
+  pub fn new(inner: R) -> DigestReader<R> {
+    DigestReader { inner, digester: Digester::new() }
+  }
+
+  /// Returns the number of bytes read.
+  pub fn length(&self) -> u64 {
+    self.digester.length()
+  }
+
+  /// Returns the digests of the bytes read so far.
+  pub fn digests(&self) -> Digests {
+    self.digester.digests()
+  }
+
+  /// Returns the underlying reader.
+  pub fn into_inner(self) -> R {
+    self.inner
+  }
+}
+
+impl<R: Read> Read for DigestReader<R> {
+  fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+    let read = self.inner.read(buf)?;
+    self.digester.update(&buf[..read]);
+    Ok(read)
+  }
+}
+
+/// Bytes are digested when consumed, so only what callers actually read
+/// counts.
+impl<R: BufRead> BufRead for DigestReader<R> {
+  fn fill_buf(&mut self) -> io::Result<&[u8]> {
+    self.inner.fill_buf()
+  }
+
+  fn consume(&mut self, amount: usize) {
+    if let Ok(buffer) = self.inner.fill_buf() {
+      let amount = amount.min(buffer.len());
+      self.digester.update(&buffer[..amount]);
+    }
+    self.inner.consume(amount);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::io::{BufRead, Read};
+  use super::{Digests, DigestReader, Digester};
+  use super::super::ValidationOptions;
+  use super::super::stream::StreamValidator;
+
+  #[test]
+  fn it_should_digest_messages() {
+    let digests = Digests::of(b"123456789");
+    assert_eq!(digests.crc32, 0xcbf4_3926);
+    assert_eq!(digests.sha256_hex(), "15e2b0d3c33891ebb0f1ef609ec419420c20e320ce94c65fbc8c3312448eb225");
+
+    let mut digester = Digester::new();
+    digester.update(b"1234");
+    assert_eq!(digester.digests(), Digests::of(b"1234"));
+    digester.update(b"56789");
+    assert_eq!(digester.digests(), digests);
+    assert_eq!(digester.length(), 9);
+  }
+
+  #[test]
+  fn it_should_digest_log_files_while_validating() {
+    let log = b"8=FIX.4.2|9=5|35=0|10=161|\n8=FIX.4.2|9=5|35=1|10=162|\n";
+    let mut reader = DigestReader::new(&log[..]);
+    let results = StreamValidator::new(&mut reader, ValidationOptions::new().delimiter('|')).count();
+    assert_eq!(results, 2);
+    assert_eq!(reader.digests(), Digests::of(log));
+
+    let mut reader = DigestReader::new(&log[..]);
+    let mut line = String::new();
+    reader.read_line(&mut line).unwrap();
+    assert_eq!(reader.length(), 27);
+    reader.read_to_string(&mut line).unwrap();
+    assert_eq!(reader.digests(), Digests::of(log));
+  }
+}
@@ -0,0 +1,388 @@
+//! Ed25519 signatures (RFC 8032), after the field and group arithmetic of
+//! TweetNaCl. Keys and signatures are the 32 and 64 byte encodings of the
+//! RFC. Secret scalars go through the same operations whatever their value
+//! but the arithmetic is not audited for timing leaks at machine level.
+
+use super::hash::sha512;
+
+/// An element of the field of integers modulo 2^255 - 19, in 16 limbs of 16
+/// bits held in wider integers to delay carries.
+type Gf = [i64; 16];
+
+/// A point of the curve in extended coordinates (X, Y, Z, T).
+type Point = [Gf; 4];
+
+const GF0: Gf = [0; 16];
+const GF1: Gf = [1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+
+/// The curve constant d = -121665 / 121666.
+const D: Gf = [
+  0x78a3, 0x1359, 0x4dca, 0x75eb, 0xd8ab, 0x4141, 0x0a4d, 0x0070,
+  0xe898, 0x7779, 0x4079, 0x8cc7, 0xfe73, 0x2b6f, 0x6cee, 0x5203,
+];
+
+/// 2 * d.
+const D2: Gf = [
+  0xf159, 0x26b2, 0x9b94, 0xebd6, 0xb156, 0x8283, 0x149a, 0x00e0,
+  0xd130, 0xeef3, 0x80f2, 0x198e, 0xfce7, 0x56df, 0xd9dc, 0x2406,
+];
+
+/// The coordinates of the base point.
+const X: Gf = [
+  0xd51a, 0x8f25, 0x2d60, 0xc956, 0xa7b2, 0x9525, 0xc760, 0x692c,
+  0xdc5c, 0xfdd6, 0xe231, 0xc0a4, 0x53fe, 0xcd6e, 0x36d3, 0x2169,
+];
+const Y: Gf = [
+  0x6658, 0x6666, 0x6666, 0x6666, 0x6666, 0x6666, 0x6666, 0x6666,
+  0x6666, 0x6666, 0x6666, 0x6666, 0x6666, 0x6666, 0x6666, 0x6666,
+];
+
+/// A square root of -1.
+const I: Gf = [
+  0xa0b0, 0x4a0e, 0x1b27, 0xc4ee, 0xe478, 0xad2f, 0x1806, 0x2f43,
+  0xd7a7, 0x3dfb, 0x0099, 0x2b4d, 0xdf0b, 0x4fc1, 0x2480, 0x2b83,
+];
+
+/// The order of the base point, little endian.
+const L: [i64; 32] = [
+  0xed, 0xd3, 0xf5, 0x5c, 0x1a, 0x63, 0x12, 0x58, 0xd6, 0x9c, 0xf7, 0xa2, 0xde, 0xf9, 0xde, 0x14,
+  0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x10,
+];
+
+/// Returns the public key of the 32 byte secret `seed`.
+pub(crate) fn public_key(seed: &[u8; 32]) -> [u8; 32] {
+  let scalar = secret_scalar(seed);
+  let mut key = [0; 32];
+  pack(&mut key, &scalar_base(&scalar));
+  key
+}
+
+/// Returns the signature of `message` under the secret `seed` whose public
+/// key is `public`.
+pub(crate) fn sign(seed: &[u8; 32], public: &[u8; 32], message: &[u8]) -> [u8; 64] {
+  let expanded = sha512(seed);
+  let scalar = secret_scalar(seed);
+
+  let mut nonce = sha512(&[&expanded[32..], message].concat());
+  reduce(&mut nonce);
+  let mut signature = [0; 64];
+  let mut r = [0; 32];
+  r.copy_from_slice(&nonce[..32]);
+  let mut encoded = [0; 32];
+  pack(&mut encoded, &scalar_base(&r));
+  signature[..32].copy_from_slice(&encoded);
+
+  let mut challenge = sha512(&[&encoded[..], public, message].concat());
+  reduce(&mut challenge);
+  let mut x = [0i64; 64];
+  for (x, r) in x.iter_mut().zip(&r) { *x = *r as i64; }
+  for i in 0..32 {
+    for j in 0..32 { x[i + j] += challenge[i] as i64 * scalar[j] as i64; }
+  }
+  let mut s = [0; 32];
+  mod_l(&mut s, &mut x);
+  signature[32..].copy_from_slice(&s);
+  signature
+}
+
+/// Returns `true` if `signature` is a signature of `message` under the
+/// `public` key. Non canonical signatures are rejected.
+pub(crate) fn verify(public: &[u8; 32], message: &[u8], signature: &[u8; 64]) -> bool {
+  let mut q = match unpack_negated(public) {
+    Some(point) => point,
+    None => return false,
+  };
+  if !is_canonical_scalar(&signature[32..]) { return false; }
+
+  let mut challenge = sha512(&[&signature[..32], public, message].concat());
+  reduce(&mut challenge);
+  let mut h = [0; 32];
+  h.copy_from_slice(&challenge[..32]);
+  let mut s = [0; 32];
+  s.copy_from_slice(&signature[32..]);
+
+  let mut p = scalar_mult(&mut q, &h);
+  add(&mut p, &scalar_base(&s));
+  let mut encoded = [0; 32];
+  pack(&mut encoded, &p);
+  encoded[..] == signature[..32]
+}
+
+fn secret_scalar(seed: &[u8; 32]) -> [u8; 32] {
+  let expanded = sha512(seed);
+  let mut scalar = [0; 32];
+  scalar.copy_from_slice(&expanded[..32]);
+  scalar[0] &= 248;
+  scalar[31] &= 127;
+  scalar[31] |= 64;
+  scalar
+}
+
+fn is_canonical_scalar(s: &[u8]) -> bool {
+  for i in (0..32).rev() {
+    if (s[i] as i64) != L[i] { return (s[i] as i64) < L[i]; }
+  }
+  false
+}
+
+fn carry(o: &mut Gf) {
+  for i in 0..16 {
+    o[i] += 1 << 16;
+    let c = o[i] >> 16;
+    if i < 15 { o[i + 1] += c - 1 } else { o[0] += 38 * (c - 1) }
+    o[i] -= c << 16;
+  }
+}
+
+/// Swaps `p` and `q` if `b` is 1, in the same time as when it is 0.
+fn select(p: &mut Gf, q: &mut Gf, b: i64) {
+  let mask = !(b - 1);
+  for (p, q) in p.iter_mut().zip(q.iter_mut()) {
+    let t = mask & (*p ^ *q);
+    *p ^= t;
+    *q ^= t;
+  }
+}
+
+fn pack25519(o: &mut [u8; 32], n: &Gf) {
+  let mut t = *n;
+  carry(&mut t);
+  carry(&mut t);
+  carry(&mut t);
+  for _ in 0..2 {
+    let mut m = GF0;
+    m[0] = t[0] - 0xffed;
+    for i in 1..15 {
+      m[i] = t[i] - 0xffff - ((m[i - 1] >> 16) & 1);
+      m[i - 1] &= 0xffff;
+    }
+    m[15] = t[15] - 0x7fff - ((m[14] >> 16) & 1);
+    let b = (m[15] >> 16) & 1;
+    m[14] &= 0xffff;
+    select(&mut t, &mut m, 1 - b);
+  }
+  for i in 0..16 {
+    o[2 * i] = t[i] as u8;
+    o[2 * i + 1] = (t[i] >> 8) as u8;
+  }
+}
+
+fn differ(a: &Gf, b: &Gf) -> bool {
+  let (mut c, mut d) = ([0; 32], [0; 32]);
+  pack25519(&mut c, a);
+  pack25519(&mut d, b);
+  c != d
+}
+
+fn parity(a: &Gf) -> u8 {
+  let mut d = [0; 32];
+  pack25519(&mut d, a);
+  d[0] & 1
+}
+
+fn unpack25519(n: &[u8; 32]) -> Gf {
+  let mut o = GF0;
+  for i in 0..16 { o[i] = n[2 * i] as i64 + ((n[2 * i + 1] as i64) << 8); }
+  o[15] &= 0x7fff;
+  o
+}
+
+fn sum(a: &Gf, b: &Gf) -> Gf {
+  let mut o = GF0;
+  for i in 0..16 { o[i] = a[i] + b[i]; }
+  o
+}
+
+fn difference(a: &Gf, b: &Gf) -> Gf {
+  let mut o = GF0;
+  for i in 0..16 { o[i] = a[i] - b[i]; }
+  o
+}
+
+fn product(a: &Gf, b: &Gf) -> Gf {
+  let mut t = [0i64; 31];
+  for i in 0..16 {
+    for j in 0..16 { t[i + j] += a[i] * b[j]; }
+  }
+  for i in 0..15 { t[i] += 38 * t[i + 16]; }
+  let mut o = GF0;
+  o.copy_from_slice(&t[..16]);
+  carry(&mut o);
+  carry(&mut o);
+  o
+}
+
+fn square(a: &Gf) -> Gf {
+  product(a, a)
+}
+
+fn inverse(i: &Gf) -> Gf {
+  let mut c = *i;
+  for a in (0..254).rev() {
+    c = square(&c);
+    if a != 2 && a != 4 { c = product(&c, i); }
+  }
+  c
+}
+
+/// Returns `i` to the power (p - 5) / 8, for square roots.
+fn pow2523(i: &Gf) -> Gf {
+  let mut c = *i;
+  for a in (0..251).rev() {
+    c = square(&c);
+    if a != 1 { c = product(&c, i); }
+  }
+  c
+}
+
+fn add(p: &mut Point, q: &Point) {
+  let a = product(&difference(&p[1], &p[0]), &difference(&q[1], &q[0]));
+  let b = product(&sum(&p[0], &p[1]), &sum(&q[0], &q[1]));
+  let c = product(&product(&p[3], &q[3]), &D2);
+  let d = product(&p[2], &q[2]);
+  let d = sum(&d, &d);
+  let (e, f, g, h) = (difference(&b, &a), difference(&d, &c), sum(&d, &c), sum(&b, &a));
+  p[0] = product(&e, &f);
+  p[1] = product(&h, &g);
+  p[2] = product(&g, &f);
+  p[3] = product(&e, &h);
+}
+
+fn swap(p: &mut Point, q: &mut Point, b: i64) {
+  for (p, q) in p.iter_mut().zip(q.iter_mut()) { select(p, q, b); }
+}
+
+fn pack(r: &mut [u8; 32], p: &Point) {
+  let z = inverse(&p[2]);
+  let (x, y) = (product(&p[0], &z), product(&p[1], &z));
+  pack25519(r, &y);
+  r[31] ^= parity(&x) << 7;
+}
+
+fn scalar_mult(q: &mut Point, s: &[u8; 32]) -> Point {
+  let mut p = [GF0, GF1, GF1, GF0];
+  for i in (0..256).rev() {
+    let b = ((s[i / 8] >> (i & 7)) & 1) as i64;
+    swap(&mut p, q, b);
+    add(q, &p);
+    let doubled = p;
+    add(&mut p, &doubled);
+    swap(&mut p, q, b);
+  }
+  p
+}
+
+fn scalar_base(s: &[u8; 32]) -> Point {
+  let mut base = [X, Y, GF1, product(&X, &Y)];
+  scalar_mult(&mut base, s)
+}
+
+/// Reduces the 512-bit little endian integer held in `x` modulo `L`.
+fn mod_l(r: &mut [u8; 32], x: &mut [i64; 64]) {
+  for i in (32..64).rev() {
+    let mut c = 0;
+    for j in (i - 32)..(i - 12) {
+      x[j] += c - 16 * x[i] * L[j - (i - 32)];
+      c = (x[j] + 128) >> 8;
+      x[j] -= c << 8;
+    }
+    x[i - 12] += c;
+    x[i] = 0;
+  }
+  let mut c = 0;
+  for j in 0..32 {
+    x[j] += c - (x[31] >> 4) * L[j];
+    c = x[j] >> 8;
+    x[j] &= 255;
+  }
+  for j in 0..32 { x[j] -= c * L[j]; }
+  for i in 0..32 {
+    x[i + 1] += x[i] >> 8;
+    r[i] = (x[i] & 255) as u8;
+  }
+}
+
+/// Reduces a SHA-512 digest modulo `L`, leaving the result in its first 32
+/// bytes and zeroes after.
+fn reduce(r: &mut [u8; 64]) {
+  let mut x = [0i64; 64];
+  for (x, r) in x.iter_mut().zip(r.iter()) { *x = *r as i64; }
+  let mut reduced = [0; 32];
+  mod_l(&mut reduced, &mut x);
+  *r = [0; 64];
+  r[..32].copy_from_slice(&reduced);
+}
+
+/// Decodes the point `p` and negates it, or returns `None` if it is not on
+/// the curve.
+fn unpack_negated(p: &[u8; 32]) -> Option<Point> {
+  let y = unpack25519(p);
+  let num = square(&y);
+  let den = product(&num, &D);
+  let num = difference(&num, &GF1);
+  let den = sum(&GF1, &den);
+
+  let den2 = square(&den);
+  let den4 = square(&den2);
+  let den6 = product(&den4, &den2);
+  let t = product(&product(&den6, &num), &den);
+  let t = product(&product(&pow2523(&t), &num), &den);
+  let mut x = product(&product(&t, &den), &den);
+
+  if differ(&product(&square(&x), &den), &num) { x = product(&x, &I); }
+  if differ(&product(&square(&x), &den), &num) { return None; }
+  if parity(&x) == p[31] >> 7 { x = difference(&GF0, &x); }
+  let t = product(&x, &y);
+  Some([x, y, GF1, t])
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{public_key, sign, verify};
+  use super::super::hash::to_hex;
+
+  fn seed(hex: &str) -> [u8; 32] {
+    let mut seed = [0; 32];
+    for (byte, i) in seed.iter_mut().zip((0..hex.len()).step_by(2)) {
+      *byte = u8::from_str_radix(&hex[i..i + 2], 16).unwrap();
+    }
+    seed
+  }
+
+  #[test]
+  fn it_should_match_rfc8032_vectors() {
+    let vectors = [
+      ("9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60",
+        "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a", &b""[..],
+        concat!("e5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e06522490155",
+          "5fb8821590a33bacc61e39701cf9b46bd25bf5f0595bbe24655141438e7a100b")),
+      ("4ccd089b28ff96da9db6c346ec114e0f5b8a319f35aba624da8cf6ed4fb8a6fb",
+        "3d4017c3e843895a92b70aa74d1b7ebc9c982ccf2ec4968cc0cd55f12af4660c", &b"\x72"[..],
+        concat!("92a009a9f0d4cab8720e820b5f642540a2b27b5416503f8fb3762223ebdb69da",
+          "085ac1e43e15996e458f3613d0f11d8c387b2eaeb4302aeeb00d291612bb0c00")),
+    ];
+    for &(seed, public, message, signature) in &vectors {
+      let seed = self::seed(seed);
+      let key = public_key(&seed);
+      assert_eq!(to_hex(&key), public);
+      let signed = sign(&seed, &key, message);
+      assert_eq!(to_hex(&signed), signature);
+      assert!(verify(&key, message, &signed));
+    }
+  }
+
+  #[test]
+  fn it_should_reject_altered_signatures() {
+    let seed = [7; 32];
+    let key = public_key(&seed);
+    let signature = sign(&seed, &key, b"8=FIX.4.2|35=0|");
+    assert!(verify(&key, b"8=FIX.4.2|35=0|", &signature));
+    assert!(!verify(&key, b"8=FIX.4.2|35=1|", &signature));
+    assert!(!verify(&public_key(&[8; 32]), b"8=FIX.4.2|35=0|", &signature));
+    for i in &[0, 31, 32, 63] {
+      let mut altered = signature;
+      altered[*i] ^= 1;
+      assert!(!verify(&key, b"8=FIX.4.2|35=0|", &altered));
+    }
+  }
+}
@@ -0,0 +1,199 @@
+//! Typed access to ExecutionReport (35=8) messages.
+
+use super::FIXChecksumValidatorError;
+use super::FIXChecksumValidatorError::{RequiredFieldMissing, FieldValueInvalid};
+use super::message::{MessageView, optional_str, required_str, optional_decimal};
+
+/// ExecType (150) of an execution report.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ExecType {
+  New,
+  PartialFill,
+  Fill,
+  DoneForDay,
+  Canceled,
+  Replaced,
+  PendingCancel,
+  Stopped,
+  Rejected,
+  Suspended,
+  PendingNew,
+  Calculated,
+  Expired,
+  Restated,
+  PendingReplace,
+  Trade,
+  TradeCorrect,
+  TradeCancel,
+  OrderStatus,
+}
+
+impl ExecType {
+  /// Decodes the wire value of an exec type.
+  pub fn from_value(value: &[u8]) -> Option<ExecType> {
+    Some(match value {
+      b"0" => ExecType::New,
+      b"1" => ExecType::PartialFill,
+      b"2" => ExecType::Fill,
+      b"3" => ExecType::DoneForDay,
+      b"4" => ExecType::Canceled,
+      b"5" => ExecType::Replaced,
+      b"6" => ExecType::PendingCancel,
+      b"7" => ExecType::Stopped,
+      b"8" => ExecType::Rejected,
+      b"9" => ExecType::Suspended,
+      b"A" => ExecType::PendingNew,
+      b"B" => ExecType::Calculated,
+      b"C" => ExecType::Expired,
+      b"D" => ExecType::Restated,
+      b"E" => ExecType::PendingReplace,
+      b"F" => ExecType::Trade,
+      b"G" => ExecType::TradeCorrect,
+      b"H" => ExecType::TradeCancel,
+      b"I" => ExecType::OrderStatus,
+      _ => return None,
+    })
+  }
+
+  /// Returns `true` for exec types reporting a fill.
+  pub fn is_fill(&self) -> bool {
+    matches!(*self, ExecType::PartialFill | ExecType::Fill | ExecType::Trade)
+  }
+}
+
+/// OrdStatus (39) of an order.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OrdStatus {
+  New,
+  PartiallyFilled,
+  Filled,
+  DoneForDay,
+  Canceled,
+  Replaced,
+  PendingCancel,
+  Stopped,
+  Rejected,
+  Suspended,
+  PendingNew,
+  Calculated,
+  Expired,
+  AcceptedForBidding,
+  PendingReplace,
+}
+
+impl OrdStatus {
+  /// Decodes the wire value of an order status.
+  pub fn from_value(value: &[u8]) -> Option<OrdStatus> {
+    Some(match value {
+      b"0" => OrdStatus::New,
+      b"1" => OrdStatus::PartiallyFilled,
+      b"2" => OrdStatus::Filled,
+      b"3" => OrdStatus::DoneForDay,
+      b"4" => OrdStatus::Canceled,
+      b"5" => OrdStatus::Replaced,
+      b"6" => OrdStatus::PendingCancel,
+      b"7" => OrdStatus::Stopped,
+      b"8" => OrdStatus::Rejected,
+      b"9" => OrdStatus::Suspended,
+      b"A" => OrdStatus::PendingNew,
+      b"B" => OrdStatus::Calculated,
+      b"C" => OrdStatus::Expired,
+      b"D" => OrdStatus::AcceptedForBidding,
+      b"E" => OrdStatus::PendingReplace,
+      _ => return None,
+    })
+  }
+
+  /// Returns `true` once the order can no longer be filled.
+  pub fn is_terminal(&self) -> bool {
+    matches!(*self, OrdStatus::Filled | OrdStatus::Canceled | OrdStatus::Rejected | OrdStatus::Expired
+      | OrdStatus::DoneForDay)
+  }
+}
+
+/// Decoded fields of an execution report. Identifiers borrow from the
+/// message buffer, quantities and prices are decoded once.
+///
+/// # Examples
+///
+/// ```
+/// use fix_checksum::execution::{ExecType, ExecutionReport, OrdStatus};
+///
+/// let message = b"8=FIX.4.2|9=73|35=8|37=O-1|17=E-1|150=1|39=1|55=IBM|14=40|151=60|6=125.5|\
+///   31=125.5|32=40|10=049|";
+/// let report = ExecutionReport::parse(message, '|').unwrap();
+/// assert_eq!(report.exec_type, ExecType::PartialFill);
+/// assert_eq!(report.ord_status, OrdStatus::PartiallyFilled);
+/// assert_eq!((report.cum_qty, report.avg_px, report.last_px), (40.0, 125.5, Some(125.5)));
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExecutionReport<'a> {
+  pub order_id: &'a str,
+  pub exec_id: &'a str,
+  pub cl_ord_id: Option<&'a str>,
+  pub symbol: Option<&'a str>,
+  pub exec_type: ExecType,
+  pub ord_status: OrdStatus,
+  pub cum_qty: f64,
+  pub avg_px: f64,
+  pub leaves_qty: Option<f64>,
+  pub last_qty: Option<f64>,
+  pub last_px: Option<f64>,
+}
+
+impl<'a> ExecutionReport<'a> {
+  /// Parses `message` and decodes it as an execution report.
+  pub fn parse(message: &'a [u8], delimiter: char) -> Result<ExecutionReport<'a>, FIXChecksumValidatorError> {
+    ExecutionReport::from_view(&MessageView::parse(message, delimiter)?)
+  }
+
+  /// Decodes an execution report from the fields of `view`. Fails with
+  /// `FieldValueInvalid(35)` for other message types.
+  pub fn from_view(view: &MessageView<'a>) -> Result<ExecutionReport<'a>, FIXChecksumValidatorError> {
+    if view.get(35).ok_or(RequiredFieldMissing(35))? != b"8" { return Err(FieldValueInvalid(35)); }
+
+    let exec_type = view.get(150).ok_or(RequiredFieldMissing(150))?;
+    let ord_status = view.get(39).ok_or(RequiredFieldMissing(39))?;
+    Ok(ExecutionReport {
+      order_id: required_str(view, 37)?,
+      exec_id: required_str(view, 17)?,
+      cl_ord_id: optional_str(view, 11)?,
+      symbol: optional_str(view, 55)?,
+      exec_type: ExecType::from_value(exec_type).ok_or(FieldValueInvalid(150))?,
+      ord_status: OrdStatus::from_value(ord_status).ok_or(FieldValueInvalid(39))?,
+      cum_qty: optional_decimal(view, 14)?.ok_or(RequiredFieldMissing(14))?,
+      avg_px: optional_decimal(view, 6)?.ok_or(RequiredFieldMissing(6))?,
+      leaves_qty: optional_decimal(view, 151)?,
+      last_qty: optional_decimal(view, 32)?,
+      last_px: optional_decimal(view, 31)?,
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{ExecType, ExecutionReport, OrdStatus};
+  use super::super::FIXChecksumValidatorError::{RequiredFieldMissing, FieldValueInvalid};
+
+  #[test]
+  fn it_should_decode_execution_report() {
+    let message = b"8=FIX.4.2|35=8|37=O-1|17=E-2|11=C-1|150=2|39=2|14=100|6=10.25|151=0|10=000|";
+    let report = ExecutionReport::parse(message, '|').unwrap();
+    assert_eq!((report.order_id, report.exec_id, report.cl_ord_id, report.symbol), ("O-1", "E-2", Some("C-1"), None));
+    assert_eq!((report.exec_type, report.ord_status), (ExecType::Fill, OrdStatus::Filled));
+    assert_eq!((report.cum_qty, report.avg_px, report.leaves_qty), (100.0, 10.25, Some(0.0)));
+    assert_eq!((report.last_qty, report.last_px), (None, None));
+    assert!(report.exec_type.is_fill() && report.ord_status.is_terminal());
+  }
+
+  #[test]
+  fn it_should_reject_invalid_execution_report() {
+    let parse = |message: &[u8]| ExecutionReport::parse(message, '|').unwrap_err();
+    assert_eq!(parse(b"8=FIX.4.2|35=D|10=000|"), FieldValueInvalid(35));
+    assert_eq!(parse(b"8=FIX.4.2|35=8|37=O-1|17=E-1|39=0|14=0|6=0|"), RequiredFieldMissing(150));
+    assert_eq!(parse(b"8=FIX.4.2|35=8|37=O-1|17=E-1|150=Z|39=0|14=0|6=0|"), FieldValueInvalid(150));
+    assert_eq!(parse(b"8=FIX.4.2|35=8|37=O-1|17=E-1|150=0|39=0|6=0|"), RequiredFieldMissing(14));
+    assert_eq!(parse(b"8=FIX.4.2|35=8|37=O-1|17=E-1|150=0|39=0|14=0|6=1e3|"), FieldValueInvalid(6));
+    assert_eq!(parse(b"8=FIX.4.2|35=8|17=E-1|150=0|39=0|14=0|6=0|"), RequiredFieldMissing(37));
+  }
+}
@@ -0,0 +1,205 @@
+//! Selection of messages from logs.
+
+use std::collections::HashSet;
+use std::io;
+use std::time::SystemTime;
+
+use super::ValidationOptions;
+use super::clock::parse_utc_timestamp;
+use super::message::MessageView;
+use super::session::SessionId;
+use super::stream::StreamResult;
+
+/// A window of time from `start` included to `end` excluded, unbounded on
+/// a side without a time.
+///
+/// # Examples
+///
+/// ```
+/// use fix_checksum::clock::parse_utc_timestamp;
+/// use fix_checksum::filter::TimeRange;
+/// use fix_checksum::message::MessageView;
+///
+/// let range = TimeRange::new().since(parse_utc_timestamp("20240102-09:30:00").unwrap())
+///   .until(parse_utc_timestamp("20240102-09:35:00").unwrap());
+/// let message = MessageView::parse(b"8=FIX.4.2|35=0|52=20240102-09:31:10.250|", '|').unwrap();
+/// assert!(range.matches(&message));
+/// assert!(!range.matches(&MessageView::parse(b"8=FIX.4.2|35=0|", '|').unwrap()));
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TimeRange {
+  start: Option<SystemTime>,
+  end: Option<SystemTime>,
+}
+
+impl TimeRange {
+  /// Creates a range of all times.
+  pub fn new() -> TimeRange {
+    TimeRange::default()
+  }
+
+  /// Starts the range at `start`.
+  pub fn since(mut self, start: SystemTime) -> TimeRange {
+    self.start = Some(start);
+    self
+  }
+
+  /// Ends the range before `end`.
+  pub fn until(mut self, end: SystemTime) -> TimeRange {
+    self.end = Some(end);
+    self
+  }
+
+  /// Returns `true` if `time` is in the range.
+  pub fn contains(&self, time: SystemTime) -> bool {
+    self.start.is_none_or(|start| time >= start) && self.end.is_none_or(|end| time < end)
+  }
+
+  /// Returns `true` if the SendingTime (52) of `message` is in the range.
+  /// Messages without a valid SendingTime never match.
+  pub fn matches(&self, message: &MessageView) -> bool {
+    message.get_str(52).and_then(parse_utc_timestamp).is_some_and(|sent| self.contains(sent))
+  }
+}
+
+/// Passes on the stream results whose message has its SendingTime (52) in
+/// a time range, dropping the others.
+///
+/// # Examples
+///
+/// ```
+/// use fix_checksum::ValidationOptions;
+/// use fix_checksum::clock::parse_utc_timestamp;
+/// use fix_checksum::filter::{TimeFilter, TimeRange};
+/// use fix_checksum::stream::StreamValidator;
+///
+/// let log = "8=FIX.4.2|9=26|35=0|52=20240102-09:29:59|10=231|\n\
+///   8=FIX.4.2|9=26|35=0|52=20240102-09:30:00|10=209|\n";
+/// let options = ValidationOptions::new().delimiter('|');
+/// let range = TimeRange::new().since(parse_utc_timestamp("20240102-09:30:00").unwrap());
+/// let results: Vec<_> = TimeFilter::new(StreamValidator::new(log.as_bytes(), options.clone()), range, &options)
+///   .collect();
+/// assert_eq!(results.len(), 1);
+/// assert_eq!(results[0].as_ref().unwrap().offset, 49);
+/// ```
+#[derive(Debug)]
+pub struct TimeFilter<I> {
+  inner: I,
+  range: TimeRange,
+  delimiter: char,
+}
+
+impl<I: Iterator<Item = io::Result<StreamResult>>> TimeFilter<I> {
+  /// Filters the results of `inner`, read with `options`.
+  pub fn new(inner: I, range: TimeRange, options: &ValidationOptions) -> TimeFilter<I> {
+    TimeFilter { inner, range, delimiter: options.delimiter as char }
+  }
+}
+
+impl<I: Iterator<Item = io::Result<StreamResult>>> Iterator for TimeFilter<I> {
+  type Item = io::Result<StreamResult>;
+
+  fn next(&mut self) -> Option<io::Result<StreamResult>> {
+    loop {
+      match self.inner.next()? {
+        Ok(result) => {
+          let matches = MessageView::parse(&result.message, self.delimiter)
+            .is_ok_and(|message| self.range.matches(&message));
+          if matches { return Some(Ok(result)); }
+        },
+        Err(err) => return Some(Err(err)),
+      }
+    }
+  }
+}
+
+/// Drops exact repeats of stream results, messages of the same session
+/// with the same MsgSeqNum (34) and CheckSum (10), as when merging logs
+/// captured at several points of a network. Messages without session
+/// identifiers or MsgSeqNum are passed on.
+///
+/// # Examples
+///
+/// ```
+/// use fix_checksum::ValidationOptions;
+/// use fix_checksum::filter::Dedup;
+/// use fix_checksum::stream::StreamValidator;
+///
+/// let heartbeat = "8=FIX.4.2|9=28|35=0|34=7|49=BRKR|56=INVMGR|10=010|\n";
+/// let log = heartbeat.repeat(3);
+/// let options = ValidationOptions::new().delimiter('|');
+/// assert_eq!(Dedup::new(StreamValidator::new(log.as_bytes(), options.clone()), &options).count(), 1);
+/// ```
+#[derive(Debug)]
+pub struct Dedup<I> {
+  inner: I,
+  seen: HashSet<(SessionId, u64, Vec<u8>)>,
+  delimiter: char,
+}
+
+impl<I: Iterator<Item = io::Result<StreamResult>>> Dedup<I> {
+  /// De-duplicates the results of `inner`, read with `options`.
+  pub fn new(inner: I, options: &ValidationOptions) -> Dedup<I> {
+    Dedup { inner, seen: HashSet::new(), delimiter: options.delimiter as char }
+  }
+
+  /// Returns `true` the first time the key of `message` is seen.
+  fn is_first(&mut self, message: &[u8]) -> bool {
+    let message = match MessageView::parse(message, self.delimiter) {
+      Ok(message) => message,
+      Err(_) => return true,
+    };
+    let seq_num = message.get_str(34).and_then(|value| value.parse().ok());
+    match (SessionId::from_message(&message), seq_num) {
+      (Some(session), Some(seq_num)) =>
+        self.seen.insert((session, seq_num, message.get(10).unwrap_or(b"").to_vec())),
+      _ => true,
+    }
+  }
+}
+
+impl<I: Iterator<Item = io::Result<StreamResult>>> Iterator for Dedup<I> {
+  type Item = io::Result<StreamResult>;
+
+  fn next(&mut self) -> Option<io::Result<StreamResult>> {
+    loop {
+      match self.inner.next()? {
+        Ok(result) => if self.is_first(&result.message) { return Some(Ok(result)); },
+        Err(err) => return Some(Err(err)),
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{Dedup, TimeRange};
+  use super::super::{ErrorPolicy, ValidationOptions};
+  use super::super::clock::parse_utc_timestamp;
+  use super::super::stream::StreamValidator;
+
+  #[test]
+  fn it_should_include_start_and_exclude_end() {
+    let time = |value| parse_utc_timestamp(value).unwrap();
+    let range = TimeRange::new().since(time("20240102-09:30:00")).until(time("20240102-09:31:00"));
+    assert!(range.contains(time("20240102-09:30:00")));
+    assert!(range.contains(time("20240102-09:30:59.999")));
+    assert!(!range.contains(time("20240102-09:31:00")));
+    assert!(!range.contains(time("20240102-09:29:59.999")));
+    assert!(TimeRange::new().until(time("20240102-09:31:00")).contains(time("19700101-00:00:00")));
+  }
+
+  #[test]
+  fn it_should_drop_exact_repeats_only() {
+    let log = "8=FIX.4.2|9=28|35=0|34=7|49=BRKR|56=INVMGR|10=010|\n\
+      8=FIX.4.2|9=28|35=0|34=7|49=INVMGR|56=BRKR|10=010|\n\
+      8=FIX.4.2|9=28|35=0|34=7|49=BRKR|56=INVMGR|10=000|\n\
+      8=FIX.4.2|9=5|35=0|10=161|\n\
+      8=FIX.4.2|9=5|35=0|10=161|\n\
+      8=FIX.4.2|9=28|35=0|34=7|49=BRKR|56=INVMGR|10=010|\n";
+    let options = ValidationOptions::new().delimiter('|').error_policy(ErrorPolicy::Skip);
+    let offsets: Vec<usize> = Dedup::new(StreamValidator::new(log.as_bytes(), options.clone()), &options)
+      .map(|result| result.unwrap().offset).collect();
+    assert_eq!(offsets, vec![0, 51, 102, 153, 180]);
+  }
+}
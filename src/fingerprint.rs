@@ -0,0 +1,134 @@
+//! Stable fingerprints of messages for deduplication and matching.
+//!
+//! The CheckSum (10) of FIX sums bytes modulo 256: one message in 256
+//! shares it with any other, so it can not tell messages apart. A
+//! fingerprint is the first 128 bits of the SHA-256 of the fields of a
+//! message, leaving out the fields that differ between copies of the same
+//! message, such as the BodyLength, CheckSum and, when configured, the
+//! fields set again on a resend. Fingerprints do not depend on the
+//! delimiter nor on the crate version or platform, so they can be stored
+//! and compared across logs.
+//!
+//! # Examples
+//!
+//! ```
+//! use fix_checksum::ValidationOptions;
+//! use fix_checksum::fingerprint::Fingerprinter;
+//!
+//! let original = b"8=FIX.4.2|9=37|35=D|34=7|52=20240102-09:30:00|11=A1|10=213|";
+//! let resent = b"8=FIX.4.2|9=43|35=D|34=12|43=Y|52=20240102-09:31:00|11=A1|10=253|";
+//! let fingerprinter = Fingerprinter::new(&ValidationOptions::new().delimiter('|')).ignore_resend_fields();
+//! assert_eq!(fingerprinter.fingerprint(original).unwrap(), fingerprinter.fingerprint(resent).unwrap());
+//! ```
+
+use std::collections::BTreeSet;
+use std::fmt;
+
+use super::{FIXChecksumValidatorError, ValidationOptions};
+use super::hash::sha256;
+use super::message::MessageView;
+
+/// Fields of a message set again when it is resent: MsgSeqNum (34),
+/// PossDupFlag (43), SendingTime (52), PossResend (97) and OrigSendingTime
+/// (122).
+const RESEND_FIELDS: &[u32] = &[34, 43, 52, 97, 122];
+
+/// A 128-bit message fingerprint.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Fingerprint(u128);
+
+impl Fingerprint {
+  /// Returns the 128 bits of the fingerprint.
+  pub fn value(&self) -> u128 {
+    self.0
+  }
+
+  /// Returns the high 64 bits of the fingerprint, for indexes where a
+  /// collision once in 2^32 messages is acceptable.
+  pub fn short(&self) -> u64 {
+    (self.0 >> 64) as u64
+  }
+}
+
+/// Formats the fingerprint as 32 lowercase hexadecimal digits.
+impl fmt::Display for Fingerprint {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{:032x}", self.0)
+  }
+}
+
+/// Computes fingerprints of messages, leaving out configured tags.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Fingerprinter {
+  ignored: BTreeSet<u32>,
+  delimiter: char,
+}
+
+impl Fingerprinter {
+  /// Creates a fingerprinter of messages read with `options`, leaving out
+  /// BodyLength (9) and CheckSum (10).
+  pub fn new(options: &ValidationOptions) -> Fingerprinter {
+    Fingerprinter { ignored: [9, 10].iter().cloned().collect(), delimiter: options.delimiter as char }
+  }
+
+  /// Leaves `tag` out of fingerprints.
+  pub fn ignore(mut self, tag: u32) -> Fingerprinter {
+    self.ignored.insert(tag);
+    self
+  }
+
+  /// Leaves out the fields set again when a message is resent, so that a
+  /// message and its resends share a fingerprint.
+  pub fn ignore_resend_fields(self) -> Fingerprinter {
+    RESEND_FIELDS.iter().fold(self, |fingerprinter, tag| fingerprinter.ignore(*tag))
+  }
+
+  /// Returns the fingerprint of `message`, failing if it can not be split
+  /// into fields.
+  pub fn fingerprint(&self, message: &[u8]) -> Result<Fingerprint, FIXChecksumValidatorError> {
+    Ok(self.fingerprint_view(&MessageView::parse(message, self.delimiter)?))
+  }
+
+  /// Returns the fingerprint of a parsed message.
+  pub fn fingerprint_view(&self, message: &MessageView) -> Fingerprint {
+    // Lengths make the encoding unambiguous whatever bytes the values hold
+    let mut encoded = Vec::new();
+    for &(tag, value) in message.fields.iter().filter(|field| !self.ignored.contains(&field.0)) {
+      encoded.extend_from_slice(&tag.to_be_bytes());
+      encoded.extend_from_slice(&(value.len() as u64).to_be_bytes());
+      encoded.extend_from_slice(value);
+    }
+    let digest = sha256(&encoded);
+    let mut high = [0; 16];
+    high.copy_from_slice(&digest[..16]);
+    Fingerprint(u128::from_be_bytes(high))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::Fingerprinter;
+  use super::super::ValidationOptions;
+
+  #[test]
+  fn it_should_be_stable_and_delimiter_independent() {
+    let fingerprinter = Fingerprinter::new(&ValidationOptions::new());
+    let fingerprint = fingerprinter.fingerprint(b"8=FIX.4.2\x019=5\x0135=0\x0110=161\x01").unwrap();
+    assert_eq!(fingerprint.to_string(), "5bd8f38c19b08009e30bf07c20ac181a");
+    assert_eq!(fingerprint.short(), 0x5bd8f38c19b08009);
+
+    let piped = Fingerprinter::new(&ValidationOptions::new().delimiter('|'));
+    assert_eq!(piped.fingerprint(b"8=FIX.4.2|9=99|35=0|10=000|").unwrap(), fingerprint);
+    assert_ne!(piped.fingerprint(b"8=FIX.4.2|35=1|").unwrap(), fingerprint);
+    assert_ne!(piped.fingerprint(b"8=FIX.4.2|35=0|52=20240102-09:30:00|").unwrap(), fingerprint);
+    assert_eq!(piped.ignore(52).fingerprint(b"8=FIX.4.2|35=0|52=20240102-09:30:00|").unwrap(), fingerprint);
+  }
+
+  #[test]
+  fn it_should_not_confuse_field_boundaries() {
+    let fingerprinter = Fingerprinter::new(&ValidationOptions::new().delimiter('|'));
+    assert_ne!(fingerprinter.fingerprint(b"8=FIX.4.2|58=a|").unwrap(),
+      fingerprinter.fingerprint(b"8=FIX.4.2|5=8=a|").unwrap());
+    assert!(fingerprinter.fingerprint(b"8=FIX.4.2|garbage|").is_err());
+  }
+}
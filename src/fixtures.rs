@@ -0,0 +1,181 @@
+//! Golden fixtures: sample messages shipped with the crate.
+//!
+//! Every supported BeginString (8) has a curated set of messages: valid
+//! session and application messages, subtly corrupted ones such as a
+//! checksum off by one or transposed digits the checksum can not detect,
+//! and edge cases such as SOH within RawData (96) or a checksum of 000.
+//! Each fixture records the result `validate_bytes_with` gives with the
+//! options returned by `options`, so that downstream projects can run their
+//! own parsers and engines against the same corpus.
+//!
+//! A corpus is a text file with one fixture per line, `<category> <name>
+//! <expected> <message>`, the message written with `^A` for SOH and `\r`
+//! and `\n` for line breaks. `parse` reads corpora in the same format.
+//!
+//! # Examples
+//!
+//! ```
+//! use fix_checksum::validate_bytes_with;
+//! use fix_checksum::fixtures;
+//! use fix_checksum::fixtures::Category;
+//!
+//! let corpus = fixtures::load("FIX.4.4");
+//! assert!(corpus.iter().any(|fixture| fixture.category == Category::EdgeCase));
+//! for fixture in &corpus {
+//!   let result = validate_bytes_with(&fixture.message, &fixtures::options());
+//!   assert!(fixture.expected.matches(&result), "{}", fixture.name);
+//! }
+//! ```
+
+use std::fmt;
+
+use super::{FIXChecksumValidatorError, ValidationOptions};
+
+/// The bundled corpora by BeginString (8).
+const CORPORA: [(&str, &str); 3] = [
+  ("FIX.4.2", include_str!("../fixtures/FIX.4.2.txt")),
+  ("FIX.4.4", include_str!("../fixtures/FIX.4.4.txt")),
+  ("FIXT.1.1", include_str!("../fixtures/FIXT.1.1.txt")),
+];
+
+/// What a fixture is an example of.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Category {
+  /// A well formed message.
+  Valid,
+  /// A message damaged the way transmission or buggy engines damage them.
+  Corrupted,
+  /// A valid message exercising parsing corner cases.
+  EdgeCase,
+}
+
+/// The result of validating a fixture.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Expected {
+  /// The checksum matches.
+  Valid,
+  /// The checksum is well formed but does not match.
+  Mismatch,
+  /// Validation fails with the error of this kind, see
+  /// `FIXChecksumValidatorError::kind`.
+  Error(String),
+}
+
+impl Expected {
+  /// Returns `true` if `result` is the one expected.
+  pub fn matches(&self, result: &Result<bool, FIXChecksumValidatorError>) -> bool {
+    match (self, result) {
+      (Expected::Valid, Ok(true)) | (Expected::Mismatch, Ok(false)) => true,
+      (Expected::Error(kind), Err(err)) => kind == err.kind(),
+      _ => false,
+    }
+  }
+}
+
+/// A sample message with the result of validating it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Fixture {
+  pub name: String,
+  pub category: Category,
+  pub expected: Expected,
+  /// The message, SOH delimited.
+  pub message: Vec<u8>,
+}
+
+/// Error of parsing a corpus.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseFixtureError {
+  pub line: usize,
+}
+
+impl fmt::Display for ParseFixtureError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "Invalid fixture at line {}.", self.line)
+  }
+}
+
+impl ::std::error::Error for ParseFixtureError {}
+
+/// Returns the options the expectations of the bundled fixtures hold for:
+/// SOH delimited, strict, with BodyLength checks.
+pub fn options() -> ValidationOptions {
+  ValidationOptions::new().strict(true).check_body_length(true)
+}
+
+/// Returns the BeginString (8) values with a bundled corpus.
+pub fn versions() -> Vec<&'static str> {
+  CORPORA.iter().map(|&(version, _)| version).collect()
+}
+
+/// Returns the bundled fixtures of `begin_string`, none for a version
+/// without a corpus.
+pub fn load(begin_string: &str) -> Vec<Fixture> {
+  CORPORA.iter().find(|&&(version, _)| version == begin_string)
+    .map(|&(_, corpus)| parse(corpus).expect("bundled corpus is valid"))
+    .unwrap_or_default()
+}
+
+/// Returns the bundled fixtures of every version.
+pub fn all() -> Vec<Fixture> {
+  versions().into_iter().flat_map(load).collect()
+}
+
+/// Parses a corpus, skipping blank lines and lines starting with `#`.
+pub fn parse(corpus: &str) -> Result<Vec<Fixture>, ParseFixtureError> {
+  let mut fixtures = Vec::new();
+  for (index, line) in corpus.lines().enumerate() {
+    if line.trim().is_empty() || line.starts_with('#') { continue; }
+    let error = ParseFixtureError { line: index + 1 };
+    let mut columns = line.splitn(4, ' ');
+    let (category, name, expected, message) = match (columns.next(), columns.next(), columns.next(), columns.next()) {
+      (Some(category), Some(name), Some(expected), Some(message)) => (category, name, expected, message),
+      _ => return Err(error),
+    };
+    let category = match category {
+      "valid" => Category::Valid,
+      "corrupted" => Category::Corrupted,
+      "edge" => Category::EdgeCase,
+      _ => return Err(error),
+    };
+    let expected = match expected {
+      "valid" => Expected::Valid,
+      "mismatch" => Expected::Mismatch,
+      kind => Expected::Error(kind.to_string()),
+    };
+    let message = message.replace("^A", "\x01").replace("\\r", "\r").replace("\\n", "\n").into_bytes();
+    fixtures.push(Fixture { name: name.to_string(), category, expected, message });
+  }
+  Ok(fixtures)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{Category, Expected, ParseFixtureError, all, load, options, parse, versions};
+  use super::super::{FIXChecksumValidatorError, validate_bytes_with};
+
+  #[test]
+  fn it_should_match_bundled_expectations() {
+    for version in versions() {
+      let corpus = load(version);
+      assert!(corpus.len() >= 10, "{}", version);
+      for fixture in &corpus {
+        let result = validate_bytes_with(&fixture.message, &options());
+        assert!(fixture.expected.matches(&result), "{} {}: {:?}", version, fixture.name, result);
+        if fixture.category == Category::Valid { assert_eq!(fixture.expected, Expected::Valid); }
+      }
+    }
+    assert_eq!(all().len(), versions().iter().map(|version| load(version).len()).sum::<usize>());
+    assert!(load("FIX.4.0").is_empty());
+  }
+
+  #[test]
+  fn it_should_parse_corpora() {
+    let corpus = parse("# comment\n\nedge pipe valid 8=FIX.4.2|9=5|35=0|10=161|^A\\r\\n\n").unwrap();
+    assert_eq!(corpus[0].message, b"8=FIX.4.2|9=5|35=0|10=161|\x01\r\n".to_vec());
+    assert_eq!(parse("valid heartbeat\n"), Err(ParseFixtureError { line: 1 }));
+    assert_eq!(parse("\nbroken x valid 8=\n"), Err(ParseFixtureError { line: 2 }));
+    let not_found = Expected::Error("ChecksumFieldNotFound".to_string());
+    assert!(not_found.matches(&Err(FIXChecksumValidatorError::ChecksumFieldNotFound)));
+    assert!(!not_found.matches(&Ok(false)));
+  }
+}
@@ -0,0 +1,419 @@
+//! Framing of messages in byte buffers filled from the network.
+//!
+//! A network reader appends what it receives to a buffer and decodes the
+//! messages at its start. A buffer ending mid-message is not an error: the
+//! decoder tells how many bytes the message needs in all, so the reader
+//! knows how much more to read before retrying. `peek_frame_len` gives the
+//! same length from the first two fields alone, to size the next read.
+//! `read_fix_message` runs that loop over a blocking reader such as a
+//! `TcpStream`, for tools that just need the next valid message.
+//!
+//! # Examples
+//!
+//! ```
+//! use fix_checksum::ValidationOptions;
+//! use fix_checksum::framing::{decode, Decoded};
+//! use fix_checksum::stream::Outcome;
+//!
+//! let options = ValidationOptions::new().delimiter('|');
+//! let received = b"8=FIX.4.2|9=5|35=0|10=161|8=FIX.4.2|9=5|35";
+//! let consumed = match decode(received, &options).unwrap() {
+//!   Decoded::Message { bytes, outcome, consumed } => {
+//!     assert_eq!(bytes, b"8=FIX.4.2|9=5|35=0|10=161|");
+//!     assert_eq!(outcome, Outcome::Valid);
+//!     consumed
+//!   },
+//!   Decoded::NeedMoreBytes { .. } => unreachable!(),
+//! };
+//! assert_eq!(decode(&received[consumed..], &options).unwrap(), Decoded::NeedMoreBytes { have: 16, need: 26 });
+//! ```
+
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::io::Read;
+use std::num;
+
+use super::{FIXChecksumValidatorError, ValidationOptions, validate_bytes_with, declared_body_length,
+  UNTRUSTED_MAX_MESSAGE_SIZE};
+use super::stream::Outcome;
+
+/// Length of the CheckSum (10) field with its delimiter.
+const TRAILER_LENGTH: usize = 7;
+
+/// Bytes `read_fix_message` asks the reader for at once.
+const READ_CHUNK: usize = 4096;
+
+/// What the start of a buffer holds.
+#[derive(Debug, PartialEq)]
+pub enum Decoded<'a> {
+  /// A whole message, framed by its BodyLength (9), and the result of
+  /// validating it. The next message starts `consumed` bytes into the
+  /// buffer, past the line breaks before this one and this one.
+  Message { bytes: &'a [u8], outcome: Outcome, consumed: usize },
+  /// The buffer ends mid-message: it has `have` bytes of the `need` the
+  /// message takes. Until BodyLength is read, `need` is only a lower bound.
+  NeedMoreBytes { have: usize, need: usize },
+}
+
+/// This function decodes the message at the start of `buffer`, after any
+/// line breaks. It fails when the buffer does not start with a BeginString
+/// (8) followed by a BodyLength (9) field, as there is no telling where the
+/// message ends then, and when the message declares a length over the
+/// maximum message size of `options` or the buffer reaches that size before
+/// BodyLength ends.
+pub fn decode<'a>(buffer: &'a [u8], options: &ValidationOptions) -> Result<Decoded<'a>, FIXChecksumValidatorError> {
+  let start = buffer.iter().take_while(|b| **b == b'\r' || **b == b'\n').count();
+  let message = &buffer[start..];
+  let length = match peek_frame_len_with(message, options)? {
+    Some(length) if message.len() >= length => length,
+    Some(length) => return Ok(Decoded::NeedMoreBytes { have: buffer.len(), need: start.saturating_add(length) }),
+    None if options.max_message_size.is_some_and(|max_size| buffer.len() >= max_size) =>
+      return Err(FIXChecksumValidatorError::MessageTooLarge),
+    None => return Ok(Decoded::NeedMoreBytes { have: buffer.len(), need: buffer.len() + 1 }),
+  };
+
+  let bytes = &message[..length];
+  let outcome = match validate_bytes_with(bytes, options) {
+    Ok(true) => Outcome::Valid,
+    Ok(false) => Outcome::ChecksumMismatch,
+    Err(err) => Outcome::Error(err),
+  };
+  Ok(Decoded::Message { bytes, outcome, consumed: start + length })
+}
+
+/// Why the length of a frame can not be known.
+#[derive(Clone, Debug, PartialEq)]
+pub enum FrameError {
+  /// The buffer does not start with a BeginString (8) field.
+  BeginStringNotFound,
+  /// BeginString is not followed by a BodyLength (9) field.
+  BodyLengthNotFound,
+  /// The value of BodyLength is not a number.
+  BodyLengthInvalid(num::ParseIntError),
+  /// The message would be longer than the maximum message size.
+  MessageTooLarge,
+}
+
+impl fmt::Display for FrameError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      FrameError::BeginStringNotFound => write!(f, "Frame does not start with a begin string field."),
+      FrameError::BodyLengthNotFound => write!(f, "Body length field not found after the begin string."),
+      FrameError::BodyLengthInvalid(ref err) => write!(f, "Body length value invalid format: {}", err),
+      FrameError::MessageTooLarge => write!(f, "Body length exceeds the maximum message size."),
+    }
+  }
+}
+
+impl Error for FrameError {
+  fn source(&self) -> Option<&(dyn Error + 'static)> {
+    match *self {
+      FrameError::BodyLengthInvalid(ref err) => Some(err),
+      _ => None,
+    }
+  }
+}
+
+impl From<FrameError> for FIXChecksumValidatorError {
+  fn from(err: FrameError) -> FIXChecksumValidatorError {
+    match err {
+      FrameError::BeginStringNotFound => FIXChecksumValidatorError::BeginStringFieldNotFound,
+      FrameError::BodyLengthNotFound => FIXChecksumValidatorError::BodyLengthFieldNotFound,
+      FrameError::BodyLengthInvalid(err) => FIXChecksumValidatorError::BodyLengthInvalidFormat(err),
+      FrameError::MessageTooLarge => FIXChecksumValidatorError::MessageTooLarge,
+    }
+  }
+}
+
+/// This function returns the length of the SOH delimited message starting
+/// `buffer`, trailer included, from its BeginString (8) and BodyLength (9)
+/// fields alone. It returns `None` while these fields are incomplete.
+///
+/// # Examples
+///
+/// ```
+/// use fix_checksum::framing::{peek_frame_len, FrameError};
+///
+/// assert_eq!(peek_frame_len(b"8=FIX.4.2\x019=5\x0135"), Ok(Some(26)));
+/// assert_eq!(peek_frame_len(b"8=FIX.4.2\x019="), Ok(None));
+/// assert_eq!(peek_frame_len(b"9=5\x01"), Err(FrameError::BeginStringNotFound));
+/// ```
+pub fn peek_frame_len(buffer: &[u8]) -> Result<Option<usize>, FrameError> {
+  peek_frame_len_with(buffer, &ValidationOptions::new())
+}
+
+/// This function returns the length of the message starting `buffer` using
+/// the delimiter of `options`. It fails when the length exceeds the maximum
+/// message size of `options`, known as soon as the buffer reaches it with
+/// BeginString or BodyLength still incomplete.
+pub fn peek_frame_len_with(buffer: &[u8], options: &ValidationOptions) -> Result<Option<usize>, FrameError> {
+  let delimiter = options.delimiter;
+  let starts_with = |bytes: &[u8], field: &[u8]| bytes.starts_with(field) || field.starts_with(bytes);
+  let incomplete = || match options.max_message_size {
+    Some(max_size) if buffer.len() >= max_size => Err(FrameError::MessageTooLarge),
+    _ => Ok(None),
+  };
+  if !starts_with(buffer, b"8=") { return Err(FrameError::BeginStringNotFound); }
+  let begin_string_end = match buffer.iter().position(|b| *b == delimiter) {
+    Some(end) => end,
+    None => return incomplete(),
+  };
+  if !starts_with(&buffer[begin_string_end + 1..], b"9=") { return Err(FrameError::BodyLengthNotFound); }
+  if !buffer[begin_string_end + 1..].contains(&delimiter) { return incomplete(); }
+
+  let (declared, body_start) = declared_body_length(buffer, delimiter).map_err(|err| match err {
+    FIXChecksumValidatorError::BodyLengthInvalidFormat(err) => FrameError::BodyLengthInvalid(err),
+    _ => FrameError::BodyLengthNotFound,
+  })?;
+  let length = body_start.saturating_add(declared).saturating_add(TRAILER_LENGTH);
+  match options.max_message_size {
+    Some(max_size) if length > max_size => Err(FrameError::MessageTooLarge),
+    _ => Ok(Some(length)),
+  }
+}
+
+/// This function reads from `stream` until `buffer` starts with a whole SOH
+/// delimited message and returns it, keeping the bytes after it in `buffer`
+/// for the next call.
+///
+/// A message failing validation is dropped from the buffer and returned as
+/// an `InvalidData` error, so that the next call reads the following one. A
+/// buffer not starting with a message fails with `InvalidData` as well but
+/// is left as it is, as the start of the next message is unknown. The end
+/// of the stream fails with `UnexpectedEof`.
+///
+/// The buffer grows a chunk at a time as bytes arrive, never by the length
+/// a message declares. A message declaring a length over the maximum
+/// message size of the options, 1 MiB unless they set one, fails with
+/// `InvalidData` before any of it is read, and so does a buffer reaching
+/// that size before BodyLength ends. The buffer is decoded again only once
+/// the bytes read could end BodyLength, so that a slow header is not
+/// rescanned for every chunk.
+///
+/// # Examples
+///
+/// ```
+/// use fix_checksum::framing::read_fix_message;
+///
+/// let mut stream = &b"8=FIX.4.2\x019=5\x0135=0\x0110=161\x018=FIX.4.2\x019=5\x0135=1\x0110=162\x01"[..];
+/// let mut buffer = Vec::new();
+/// assert_eq!(read_fix_message(&mut stream, &mut buffer).unwrap(), b"8=FIX.4.2\x019=5\x0135=0\x0110=161\x01");
+/// assert_eq!(read_fix_message(&mut stream, &mut buffer).unwrap(), b"8=FIX.4.2\x019=5\x0135=1\x0110=162\x01");
+/// assert!(buffer.is_empty());
+/// ```
+pub fn read_fix_message<R: Read>(stream: &mut R, buffer: &mut Vec<u8>) -> io::Result<Vec<u8>> {
+  read_fix_message_with(stream, buffer, &ValidationOptions::new())
+}
+
+/// This function reads the next message from `stream` using `options`, see
+/// `read_fix_message`.
+pub fn read_fix_message_with<R: Read>(stream: &mut R, buffer: &mut Vec<u8>, options: &ValidationOptions)
+  -> io::Result<Vec<u8>> {
+  let limited;
+  let options = match options.max_message_size {
+    Some(_) => options,
+    None => {
+      limited = options.clone().max_message_size(UNTRUSTED_MAX_MESSAGE_SIZE);
+      &limited
+    },
+  };
+  let is_delimiter = |b: &u8| *b == options.delimiter;
+  // BodyLength ends at the second delimiter: until then decoding again only
+  // tells something new once a delimiter arrives or the buffer is full
+  let mut delimiters = buffer.iter().filter(|b| is_delimiter(b)).take(2).count();
+  let mut changed = true;
+  loop {
+    let full = options.max_message_size.is_some_and(|max_size| buffer.len() >= max_size);
+    if changed || delimiters >= 2 || full {
+      match decode(buffer, options).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))? {
+        Decoded::Message { bytes, outcome, consumed } => {
+          let result = match outcome {
+            Outcome::Valid => Ok(bytes.to_vec()),
+            Outcome::ChecksumMismatch => Err(io::Error::new(io::ErrorKind::InvalidData,
+              FIXChecksumValidatorError::ChecksumMismatch)),
+            Outcome::Error(err) => Err(io::Error::new(io::ErrorKind::InvalidData, err)),
+            Outcome::Repaired => unreachable!("decoding does not repair"),
+          };
+          buffer.drain(..consumed);
+          return result;
+        },
+        Decoded::NeedMoreBytes { .. } => {},
+      }
+    }
+
+    let filled = buffer.len();
+    buffer.resize(filled + READ_CHUNK, 0);
+    let read = stream.read(&mut buffer[filled..]);
+    buffer.truncate(filled + read.as_ref().map_or(0, |read| *read));
+    let fresh = buffer[filled..].iter().filter(|b| is_delimiter(b)).count();
+    delimiters += fresh;
+    changed = fresh > 0;
+    match read {
+      Ok(0) => return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "stream ended before a whole message")),
+      Ok(_) => {},
+      Err(ref err) if err.kind() == io::ErrorKind::Interrupted => {},
+      Err(err) => return Err(err),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::io;
+  use std::io::Read;
+  use super::{decode, peek_frame_len, peek_frame_len_with, read_fix_message, read_fix_message_with, Decoded,
+    FrameError};
+  use super::super::ValidationOptions;
+  use super::super::FIXChecksumValidatorError::{BeginStringFieldNotFound, BodyLengthFieldNotFound, MessageTooLarge};
+  use super::super::stream::Outcome;
+
+  #[test]
+  fn it_should_ask_for_more_bytes() {
+    let options = ValidationOptions::new().delimiter('|');
+    let message = b"\r\n8=FIX.4.2|9=5|35=0|10=161|";
+    assert_eq!(decode(b"", &options), Ok(Decoded::NeedMoreBytes { have: 0, need: 1 }));
+    assert_eq!(decode(&message[..12], &options), Ok(Decoded::NeedMoreBytes { have: 12, need: 13 }));
+    for have in 16..message.len() {
+      assert_eq!(decode(&message[..have], &options), Ok(Decoded::NeedMoreBytes { have, need: 28 }));
+    }
+    assert_eq!(decode(message, &options),
+      Ok(Decoded::Message { bytes: &message[2..], outcome: Outcome::Valid, consumed: 28 }));
+  }
+
+  #[test]
+  fn it_should_frame_by_body_length() {
+    let options = ValidationOptions::new().delimiter('|');
+    assert_eq!(decode(b"8=FIX.4.2|9=5|35=0|10=162|junk", &options),
+      Ok(Decoded::Message { bytes: b"8=FIX.4.2|9=5|35=0|10=162|", outcome: Outcome::ChecksumMismatch, consumed: 26 }));
+    assert_eq!(decode(b"8=FIX.4.2|9=4|35=0|10=161|", &options),
+      Ok(Decoded::Message { bytes: b"8=FIX.4.2|9=4|35=0|10=161", outcome: Outcome::ChecksumMismatch, consumed: 25 }));
+    assert_eq!(decode(b"35=0|8=FIX.4.2|", &options), Err(BeginStringFieldNotFound));
+    assert_eq!(decode(b"8=FIX.4.2|3", &options), Err(BodyLengthFieldNotFound));
+    assert!(decode(b"8=FIX.4.2|9=x|", &options).is_err());
+  }
+
+  #[test]
+  fn it_should_reject_frames_over_max_message_size() {
+    let options = ValidationOptions::new().delimiter('|').max_message_size(26);
+    assert!(matches!(decode(b"8=FIX.4.2|9=5|35=0|10=161|", &options), Ok(Decoded::Message { consumed: 26, .. })));
+    assert_eq!(decode(b"8=FIX.4.2|9=6|", &options), Err(MessageTooLarge));
+    assert_eq!(decode(b"8=FIX.4.2|9=18446744073709551615|", &options), Err(MessageTooLarge));
+    assert_eq!(peek_frame_len_with(b"8=FIX.4.2|9=1000000000|", &options), Err(FrameError::MessageTooLarge));
+    assert_eq!(peek_frame_len_with(b"8=FIX.4.2|9=100", &options), Ok(None));
+  }
+
+  #[test]
+  fn it_should_advance_by_consumed_bytes() {
+    let options = ValidationOptions::new().delimiter('|');
+    let buffer = b"8=FIX.4.2|9=5|35=0|10=161|\r\n8=FIX.4.2|9=5|35=1|10=162|\n8=FIX.4.2|9=5|";
+    let (mut cursor, mut messages) = (0, Vec::new());
+    while let Ok(Decoded::Message { bytes, consumed, .. }) = decode(&buffer[cursor..], &options) {
+      messages.push(bytes);
+      cursor += consumed;
+    }
+    assert_eq!(messages, vec![&b"8=FIX.4.2|9=5|35=0|10=161|"[..], &b"8=FIX.4.2|9=5|35=1|10=162|"[..]]);
+    assert_eq!(cursor, 54);
+    assert_eq!(decode(&buffer[cursor..], &options), Ok(Decoded::NeedMoreBytes { have: 15, need: 27 }));
+  }
+
+  #[test]
+  fn it_should_peek_frame_length() {
+    let message = b"8=FIX.4.2\x019=5\x0135=0\x0110=161\x01";
+    assert_eq!(peek_frame_len(b""), Ok(None));
+    assert_eq!(peek_frame_len(&message[..13]), Ok(None));
+    assert_eq!(peek_frame_len(&message[..14]), Ok(Some(message.len())));
+    assert_eq!(peek_frame_len(message), Ok(Some(message.len())));
+    assert_eq!(peek_frame_len(b"8=FIX.4.2\x0135=0\x01"), Err(FrameError::BodyLengthNotFound));
+    assert!(matches!(peek_frame_len(b"8=FIX.4.2\x019=five\x01"), Err(FrameError::BodyLengthInvalid(_))));
+    assert_eq!(peek_frame_len_with(b"8=FIX.4.4|9=120|", &ValidationOptions::new().delimiter('|')), Ok(Some(143)));
+  }
+
+  /// Hands out its bytes a few at a time, as a socket would.
+  struct Trickle<'a>(&'a [u8], usize);
+
+  impl<'a> Read for Trickle<'a> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+      let read = self.0.len().min(self.1).min(buf.len());
+      buf[..read].copy_from_slice(&self.0[..read]);
+      self.0 = &self.0[read..];
+      Ok(read)
+    }
+  }
+
+  #[test]
+  fn it_should_read_messages_from_stream() {
+    let options = ValidationOptions::new().delimiter('|');
+    let received = b"8=FIX.4.2|9=5|35=0|10=161|\n8=FIX.4.2|9=5|35=1|10=000|8=FIX.4.2|9=5|35=2|10=163|8=FIX.4.2|9=";
+    let (mut stream, mut buffer) = (Trickle(received, 3), Vec::new());
+    assert_eq!(read_fix_message_with(&mut stream, &mut buffer, &options).unwrap(), b"8=FIX.4.2|9=5|35=0|10=161|");
+    assert_eq!(read_fix_message_with(&mut stream, &mut buffer, &options).unwrap_err().kind(),
+      io::ErrorKind::InvalidData);
+    assert_eq!(read_fix_message_with(&mut stream, &mut buffer, &options).unwrap(), b"8=FIX.4.2|9=5|35=2|10=163|");
+    assert_eq!(read_fix_message_with(&mut stream, &mut buffer, &options).unwrap_err().kind(),
+      io::ErrorKind::UnexpectedEof);
+    assert_eq!(buffer, b"8=FIX.4.2|9=");
+
+    let mut buffer = b"35=0|".to_vec();
+    assert_eq!(read_fix_message(&mut &b""[..], &mut buffer).unwrap_err().kind(), io::ErrorKind::InvalidData);
+    assert_eq!(buffer, b"35=0|");
+  }
+
+  #[test]
+  fn it_should_reject_hostile_body_lengths_before_reading() {
+    let limited = ValidationOptions::new().max_message_size(4096);
+    let hostile = [&b"8=FIX.4.2\x019=18446744073709551615\x01"[..], &b"8=FIX.4.2\x019=1000000000\x0135=0\x01"[..]];
+    for options in &[ValidationOptions::new(), limited] {
+      for received in &hostile {
+        let (mut stream, mut buffer) = (&received[..], Vec::new());
+        let err = read_fix_message_with(&mut stream, &mut buffer, options).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(buffer.capacity() < 1 << 16);
+      }
+    }
+  }
+
+  #[test]
+  fn it_should_grow_buffer_by_chunks() {
+    let message = b"8=FIX.4.2\x019=100000\x01";
+    let mut stream = Trickle(message, message.len());
+    let mut buffer = Vec::new();
+    assert_eq!(read_fix_message(&mut stream, &mut buffer).unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+    assert_eq!(buffer, &message[..]);
+    assert!(buffer.capacity() < 1 << 16);
+  }
+
+  /// Sends `prefix`, then `fill` for ever, counting the bytes sent.
+  struct Endless(&'static [u8], u8, usize);
+
+  impl Read for Endless {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+      for (index, byte) in buf.iter_mut().enumerate() {
+        *byte = self.0.get(self.2 + index).cloned().unwrap_or(self.1);
+      }
+      self.2 += buf.len();
+      Ok(buf.len())
+    }
+  }
+
+  #[test]
+  fn it_should_reject_unterminated_headers_over_max_message_size() {
+    let limited = ValidationOptions::new().max_message_size(4096);
+    for options in &[ValidationOptions::new(), limited] {
+      let limit = options.max_message_size.unwrap_or(1 << 20);
+      for &(prefix, fill) in &[(&b"8="[..], b'x'), (&b"8=FIX.4.2\x019="[..], b'9'), (&b""[..], b'\n')] {
+        let (mut stream, mut buffer) = (Endless(prefix, fill, 0), Vec::new());
+        let err = read_fix_message_with(&mut stream, &mut buffer, options).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(err.to_string().contains("exceeds maximum size"), "{}", err);
+        assert!(stream.2 >= limit && stream.2 <= limit + 4096);
+      }
+    }
+
+    let options = ValidationOptions::new().delimiter('|').max_message_size(16);
+    assert_eq!(decode(b"8=FIX.4.2|9=0000", &options), Err(MessageTooLarge));
+    assert_eq!(decode(b"\r\n\r\n\r\n\r\n\r\n\r\n\r\n\r\n", &options), Err(MessageTooLarge));
+    assert_eq!(peek_frame_len_with(b"8=FIX.4.2|9=0000", &options), Err(FrameError::MessageTooLarge));
+    assert_eq!(peek_frame_len_with(b"8=FIX.4.2|9=000", &options), Ok(None));
+  }
+}
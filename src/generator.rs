@@ -0,0 +1,322 @@
+//! Load test corpora: endless, realistic order flow for soak testing.
+//!
+//! A `CorpusGenerator` is an iterator over the messages of one direction of
+//! a session: NewOrderSingles (D), OrderCancelRequests (F) and
+//! ExecutionReports (8) mixed in the ratios given to `mix`. Cancels and
+//! executions refer to orders sent earlier and still open, executions fill
+//! them in lots of 100 until they are done. Every message is finalized with
+//! its BodyLength (9) and CheckSum (10), MsgSeqNum (34) increases by one
+//! and SendingTime (52) by the interval set with `interval`.
+//!
+//! The same seed always produces the same messages, their sending times
+//! aside when the start time is left to the current time.
+//!
+//! # Examples
+//!
+//! ```
+//! use std::time::UNIX_EPOCH;
+//! use fix_checksum::validate_bytes_with;
+//! use fix_checksum::ValidationOptions;
+//! use fix_checksum::generator::CorpusGenerator;
+//! use fix_checksum::session::SessionId;
+//!
+//! let generator = CorpusGenerator::new(SessionId::new("BRKR", "EXCH"))
+//!   .mix(6, 1, 3)
+//!   .seed(42)
+//!   .start_time(UNIX_EPOCH);
+//! let options = ValidationOptions::new().strict(true).check_body_length(true);
+//! for message in generator.take(1_000) {
+//!   assert!(validate_bytes_with(&message, &options).unwrap());
+//! }
+//! ```
+
+use std::collections::VecDeque;
+use std::time::{Duration, SystemTime};
+
+use super::clock::format_utc_timestamp;
+use super::orders::{FieldWriter, Header, NewOrderSingle, OrderCancelRequest, OrdType, Side};
+use super::session::SessionId;
+
+/// Orders kept open for cancels and executions, the oldest expiring first.
+const MAX_OPEN_ORDERS: usize = 1_024;
+
+/// An order sent and neither cancelled nor filled yet.
+#[derive(Clone, Debug)]
+struct OpenOrder {
+  cl_ord_id: String,
+  order_id: String,
+  symbol: String,
+  side: Side,
+  quantity: u64,
+  filled: u64,
+  /// Price in hundredths.
+  price: u64,
+}
+
+/// Generator of order flow, see the module documentation.
+#[derive(Clone, Debug)]
+pub struct CorpusGenerator {
+  session: SessionId,
+  begin_string: String,
+  weights: [u32; 3],
+  symbols: Vec<String>,
+  random: XorShift,
+  next_seq_num: u64,
+  time: SystemTime,
+  interval: Duration,
+  next_id: u64,
+  open: VecDeque<OpenOrder>,
+}
+
+impl CorpusGenerator {
+  /// Creates a generator of the messages `session` sends, with FIX.4.2,
+  /// orders only, from MsgSeqNum 1 and the current time on.
+  pub fn new(session: SessionId) -> CorpusGenerator {
+    CorpusGenerator {
+      session,
+      begin_string: "FIX.4.2".to_string(),
+      weights: [1, 0, 0],
+      symbols: ["IBM", "MSFT", "AAPL", "GOOG", "AMZN"].iter().map(|symbol| symbol.to_string()).collect(),
+      random: XorShift(1),
+      next_seq_num: 1,
+      time: SystemTime::now(),
+      interval: Duration::from_millis(1),
+      next_id: 1,
+      open: VecDeque::new(),
+    }
+  }
+
+  /// Sets the BeginString (8) of the messages.
+  pub fn begin_string(mut self, begin_string: &str) -> CorpusGenerator {
+    self.begin_string = begin_string.to_string();
+    self
+  }
+
+  /// Sets the relative frequencies of orders, cancels and executions. A
+  /// cancel or an execution due while no order is open is replaced with an
+  /// order, and a mix of zeros generates orders only.
+  pub fn mix(mut self, orders: u32, cancels: u32, executions: u32) -> CorpusGenerator {
+    self.weights = [orders, cancels, executions];
+    self
+  }
+
+  /// Sets the Symbols (55) orders are drawn from, five large caps by
+  /// default. An empty list keeps the default.
+  pub fn symbols(mut self, symbols: &[&str]) -> CorpusGenerator {
+    if !symbols.is_empty() { self.symbols = symbols.iter().map(|symbol| symbol.to_string()).collect(); }
+    self
+  }
+
+  /// Seeds the pseudo random choices of messages and their fields.
+  pub fn seed(mut self, seed: u64) -> CorpusGenerator {
+    self.random = XorShift::new(seed);
+    self
+  }
+
+  /// Sets the MsgSeqNum (34) of the first message.
+  pub fn first_seq_num(mut self, seq_num: u64) -> CorpusGenerator {
+    self.next_seq_num = seq_num;
+    self
+  }
+
+  /// Sets the SendingTime (52) of the first message.
+  pub fn start_time(mut self, time: SystemTime) -> CorpusGenerator {
+    self.time = time;
+    self
+  }
+
+  /// Sets the time between two messages, 1 ms by default.
+  pub fn interval(mut self, interval: Duration) -> CorpusGenerator {
+    self.interval = interval;
+    self
+  }
+
+  /// Returns the MsgSeqNum (34) of the next message.
+  pub fn next_seq_num(&self) -> u64 {
+    self.next_seq_num
+  }
+
+  fn random(&mut self, bound: u64) -> u64 {
+    self.random.below(bound)
+  }
+
+  fn next_id(&mut self) -> u64 {
+    self.next_id += 1;
+    self.next_id - 1
+  }
+
+  fn order(&mut self, header: &Header) -> Vec<u8> {
+    let id = self.next_id();
+    let index = self.random(self.symbols.len() as u64) as usize;
+    let symbol = self.symbols[index].clone();
+    let side = if self.random(2) == 0 { Side::Buy } else { Side::Sell };
+    let quantity = 100 * (1 + self.random(10));
+    let price = 5_000 + self.random(10_000);
+    let order = OpenOrder {
+      cl_ord_id: format!("ORD-{}", id),
+      order_id: format!("O-{}", id),
+      symbol,
+      side,
+      quantity,
+      filled: 0,
+      price,
+    };
+    let message = NewOrderSingle::new(&order.cl_ord_id, &order.symbol, side, &quantity.to_string(), OrdType::Limit)
+      .price(&format_price(price))
+      .build(header)
+      .expect("generated order is valid");
+    if self.open.len() == MAX_OPEN_ORDERS { self.open.pop_front(); }
+    self.open.push_back(order);
+    message
+  }
+
+  fn cancel(&mut self, header: &Header) -> Vec<u8> {
+    let index = self.random(self.open.len() as u64) as usize;
+    let order = self.open.remove(index).expect("index of an open order");
+    let cl_ord_id = format!("CXL-{}", self.next_id());
+    OrderCancelRequest::new(&order.cl_ord_id, &cl_ord_id, &order.symbol, order.side, &order.quantity.to_string())
+      .build(header)
+      .expect("generated cancel is valid")
+  }
+
+  fn execution(&mut self, header: &Header) -> Vec<u8> {
+    let index = self.random(self.open.len() as u64) as usize;
+    let leaves = self.open[index].quantity - self.open[index].filled;
+    let last_qty = 100 * (1 + self.random(leaves / 100));
+    let exec_id = format!("E-{}", self.next_id());
+    let order = &mut self.open[index];
+    order.filled += last_qty;
+    let done = order.filled == order.quantity;
+    // ExecType 1 and 2 became Trade (F) with FIX.4.3.
+    let exec_type = match self.begin_string.as_str() {
+      "FIX.4.0" | "FIX.4.1" | "FIX.4.2" => if done { "2" } else { "1" },
+      _ => "F",
+    };
+
+    let mut message = FieldWriter::new(header, "8").expect("generated header is valid");
+    message.field(37, &order.order_id);
+    message.field(11, &order.cl_ord_id);
+    message.field(17, exec_id);
+    message.field(150, exec_type);
+    message.field(39, if done { "2" } else { "1" });
+    message.field(55, &order.symbol);
+    message.field(54, order.side.value());
+    message.field(38, order.quantity);
+    message.field(32, last_qty);
+    message.field(31, format_price(order.price));
+    message.field(151, order.quantity - order.filled);
+    message.field(14, order.filled);
+    message.field(6, format_price(order.price));
+    message.field(60, &header.sending_time);
+    if done { self.open.remove(index); }
+    message.finish().expect("generated execution is valid")
+  }
+}
+
+impl Iterator for CorpusGenerator {
+  type Item = Vec<u8>;
+
+  fn next(&mut self) -> Option<Vec<u8>> {
+    let header = Header::new(&self.begin_string, &self.session.sender_comp_id, &self.session.target_comp_id,
+      self.next_seq_num, &format_utc_timestamp(self.time));
+    let total = self.weights.iter().map(|&weight| u64::from(weight)).sum::<u64>();
+    let choice = if total == 0 { 0 } else { self.random(total) };
+    let message = if self.open.is_empty() || choice < u64::from(self.weights[0]) {
+      self.order(&header)
+    } else if choice < u64::from(self.weights[0] + self.weights[1]) {
+      self.cancel(&header)
+    } else {
+      self.execution(&header)
+    };
+    self.next_seq_num += 1;
+    self.time += self.interval;
+    Some(message)
+  }
+}
+
+/// The xorshift64* pseudo random number generator, small and good enough
+/// to vary test data.
+#[derive(Clone, Debug)]
+pub(crate) struct XorShift(u64);
+
+impl XorShift {
+  pub(crate) fn new(seed: u64) -> XorShift {
+    // xorshift never leaves the all zero state.
+    XorShift(if seed == 0 { 0x9E37_79B9_7F4A_7C15 } else { seed })
+  }
+
+  /// Returns a pseudo random number below `bound`, which is not zero.
+  pub(crate) fn below(&mut self, bound: u64) -> u64 {
+    self.0 ^= self.0 >> 12;
+    self.0 ^= self.0 << 25;
+    self.0 ^= self.0 >> 27;
+    self.0.wrapping_mul(0x2545_F491_4F6C_DD1D) % bound
+  }
+}
+
+fn format_price(hundredths: u64) -> String {
+  format!("{}.{:02}", hundredths / 100, hundredths % 100)
+}
+
+#[cfg(test)]
+mod tests {
+  use std::collections::HashSet;
+  use std::time::{Duration, UNIX_EPOCH};
+  use super::CorpusGenerator;
+  use super::super::{ValidationOptions, validate_bytes_with};
+  use super::super::execution::{ExecutionReport, OrdStatus};
+  use super::super::message::MessageView;
+  use super::super::session::SessionId;
+
+  fn generator() -> CorpusGenerator {
+    CorpusGenerator::new(SessionId::new("BRKR", "EXCH")).seed(7).start_time(UNIX_EPOCH)
+  }
+
+  #[test]
+  fn it_should_generate_valid_message_mixes() {
+    let options = ValidationOptions::new().strict(true).check_body_length(true);
+    let (mut counts, mut orders, mut filled) = ([0; 3], HashSet::new(), 0);
+    for (index, message) in generator().mix(5, 1, 4).take(10_000).enumerate() {
+      assert!(validate_bytes_with(&message, &options).unwrap());
+      let view = MessageView::parse(&message, '\x01').unwrap();
+      assert_eq!(view.get_str(34), Some((index + 1).to_string().as_str()));
+      match view.get(35).unwrap() {
+        b"D" => {
+          counts[0] += 1;
+          orders.insert(view.get_str(11).unwrap().to_string());
+        },
+        b"F" => {
+          counts[1] += 1;
+          assert!(orders.contains(view.get_str(41).unwrap()));
+        },
+        _ => {
+          counts[2] += 1;
+          let report = ExecutionReport::from_view(&view).unwrap();
+          assert!(orders.contains(report.cl_ord_id.unwrap()));
+          if report.ord_status == OrdStatus::Filled { filled += 1; }
+        },
+      }
+    }
+    assert!(counts[0] > 4_500 && counts[0] < 5_500, "{:?}", counts);
+    assert!(counts[1] > 800 && counts[1] < 1_200, "{:?}", counts);
+    assert!(counts[2] > 3_500 && counts[2] < 4_500, "{:?}", counts);
+    assert!(filled > 0);
+  }
+
+  #[test]
+  fn it_should_repeat_seeded_corpora() {
+    let corpus = |seed| generator().mix(1, 1, 1).seed(seed).take(100).collect::<Vec<_>>();
+    assert_eq!(corpus(3), corpus(3));
+    assert_ne!(corpus(3), corpus(4));
+
+    let mut generator = generator().begin_string("FIX.4.4").first_seq_num(50).interval(Duration::from_secs(1))
+      .symbols(&["VOD"]);
+    let first = generator.next().unwrap();
+    let view = MessageView::parse(&first, '\x01').unwrap();
+    assert_eq!((view.get_str(8), view.get_str(34), view.get_str(55)), (Some("FIX.4.4"), Some("50"), Some("VOD")));
+    assert_eq!(view.get_str(52), Some("19700101-00:00:00.000"));
+    assert_eq!(generator.next_seq_num(), 51);
+    let second = generator.next().unwrap();
+    assert_eq!(MessageView::parse(&second, '\x01').unwrap().get_str(52), Some("19700101-00:00:01.000"));
+  }
+}
@@ -0,0 +1,276 @@
+//! CRC-32 (IEEE 802.3), SHA-256, SHA-512 and HMAC-SHA-256 (FIPS 180-4, RFC 2104).
+
+const K: [u32; 64] = [
+  0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+  0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+  0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+  0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+  0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+  0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+  0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+  0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+const H: [u32; 8] = [0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19];
+
+#[cfg(feature = "signing")]
+const K512: [u64; 80] = [
+  0x428a2f98d728ae22, 0x7137449123ef65cd, 0xb5c0fbcfec4d3b2f, 0xe9b5dba58189dbbc,
+  0x3956c25bf348b538, 0x59f111f1b605d019, 0x923f82a4af194f9b, 0xab1c5ed5da6d8118,
+  0xd807aa98a3030242, 0x12835b0145706fbe, 0x243185be4ee4b28c, 0x550c7dc3d5ffb4e2,
+  0x72be5d74f27b896f, 0x80deb1fe3b1696b1, 0x9bdc06a725c71235, 0xc19bf174cf692694,
+  0xe49b69c19ef14ad2, 0xefbe4786384f25e3, 0x0fc19dc68b8cd5b5, 0x240ca1cc77ac9c65,
+  0x2de92c6f592b0275, 0x4a7484aa6ea6e483, 0x5cb0a9dcbd41fbd4, 0x76f988da831153b5,
+  0x983e5152ee66dfab, 0xa831c66d2db43210, 0xb00327c898fb213f, 0xbf597fc7beef0ee4,
+  0xc6e00bf33da88fc2, 0xd5a79147930aa725, 0x06ca6351e003826f, 0x142929670a0e6e70,
+  0x27b70a8546d22ffc, 0x2e1b21385c26c926, 0x4d2c6dfc5ac42aed, 0x53380d139d95b3df,
+  0x650a73548baf63de, 0x766a0abb3c77b2a8, 0x81c2c92e47edaee6, 0x92722c851482353b,
+  0xa2bfe8a14cf10364, 0xa81a664bbc423001, 0xc24b8b70d0f89791, 0xc76c51a30654be30,
+  0xd192e819d6ef5218, 0xd69906245565a910, 0xf40e35855771202a, 0x106aa07032bbd1b8,
+  0x19a4c116b8d2d0c8, 0x1e376c085141ab53, 0x2748774cdf8eeb99, 0x34b0bcb5e19b48a8,
+  0x391c0cb3c5c95a63, 0x4ed8aa4ae3418acb, 0x5b9cca4f7763e373, 0x682e6ff3d6b2b8a3,
+  0x748f82ee5defb2fc, 0x78a5636f43172f60, 0x84c87814a1f0ab72, 0x8cc702081a6439ec,
+  0x90befffa23631e28, 0xa4506cebde82bde9, 0xbef9a3f7b2c67915, 0xc67178f2e372532b,
+  0xca273eceea26619c, 0xd186b8c721c0c207, 0xeada7dd6cde0eb1e, 0xf57d4f7fee6ed178,
+  0x06f067aa72176fba, 0x0a637dc5a2c898a6, 0x113f9804bef90dae, 0x1b710b35131c471b,
+  0x28db77f523047d84, 0x32caab7b40c72493, 0x3c9ebe0a15c9bebc, 0x431d67c49c100d4c,
+  0x4cc5d4becb3e42b6, 0x597f299cfc657e2a, 0x5fcb6fab3ad6faec, 0x6c44198c4a475817,
+];
+
+#[cfg(feature = "signing")]
+const H512: [u64; 8] = [
+  0x6a09e667f3bcc908, 0xbb67ae8584caa73b, 0x3c6ef372fe94f82b, 0xa54ff53a5f1d36f1,
+  0x510e527fade682d1, 0x9b05688c2b3e6c1f, 0x1f83d9abfb41bd6b, 0x5be0cd19137e2179,
+];
+
+const CRC32_TABLE: [u32; 256] = crc32_table();
+
+const fn crc32_table() -> [u32; 256] {
+  let mut table = [0u32; 256];
+  let mut i = 0;
+  while i < 256 {
+    let mut crc = i as u32;
+    let mut bit = 0;
+    while bit < 8 {
+      crc = if crc & 1 == 1 { (crc >> 1) ^ 0xedb8_8320 } else { crc >> 1 };
+      bit += 1;
+    }
+    table[i] = crc;
+    i += 1;
+  }
+  table
+}
+
+/// A running CRC-32 of the bytes passed to `update`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Crc32(u32);
+
+impl Crc32 {
+  pub(crate) fn new() -> Crc32 {
+    Crc32(0xffff_ffff)
+  }
+
+  pub(crate) fn update(&mut self, data: &[u8]) {
+    for b in data {
+      self.0 = CRC32_TABLE[((self.0 ^ *b as u32) & 0xff) as usize] ^ (self.0 >> 8);
+    }
+  }
+
+  pub(crate) fn value(&self) -> u32 {
+    !self.0
+  }
+}
+
+/// A running SHA-256 of the bytes passed to `update`.
+#[derive(Clone, Debug)]
+pub(crate) struct Sha256 {
+  state: [u32; 8],
+  block: [u8; 64],
+  filled: usize,
+  length: u64,
+}
+
+impl Sha256 {
+  pub(crate) fn new() -> Sha256 {
+    Sha256 { state: H, block: [0; 64], filled: 0, length: 0 }
+  }
+
+  pub(crate) fn update(&mut self, mut data: &[u8]) {
+    self.length = self.length.wrapping_add(data.len() as u64);
+    while !data.is_empty() {
+      let taken = data.len().min(64 - self.filled);
+      self.block[self.filled..self.filled + taken].copy_from_slice(&data[..taken]);
+      self.filled += taken;
+      data = &data[taken..];
+      if self.filled == 64 {
+        compress(&mut self.state, &self.block);
+        self.filled = 0;
+      }
+    }
+  }
+
+  pub(crate) fn finish(mut self) -> [u8; 32] {
+    let bits = self.length.wrapping_mul(8);
+    let mut padding = vec![0x80];
+    padding.resize(if self.filled < 56 { 56 - self.filled } else { 120 - self.filled }, 0);
+    self.update(&padding);
+    self.update(&bits.to_be_bytes());
+
+    let mut digest = [0u8; 32];
+    for (bytes, word) in digest.chunks_mut(4).zip(&self.state) { bytes.copy_from_slice(&word.to_be_bytes()); }
+    digest
+  }
+}
+
+fn compress(state: &mut [u32; 8], block: &[u8; 64]) {
+  let mut w = [0u32; 64];
+  for (i, word) in block.chunks(4).enumerate() {
+    w[i] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+  }
+  for i in 16..64 {
+    let s0 = w[i - 15].rotate_right(7) ^ w[i - 15].rotate_right(18) ^ (w[i - 15] >> 3);
+    let s1 = w[i - 2].rotate_right(17) ^ w[i - 2].rotate_right(19) ^ (w[i - 2] >> 10);
+    w[i] = w[i - 16].wrapping_add(s0).wrapping_add(w[i - 7]).wrapping_add(s1);
+  }
+
+  let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = *state;
+  for i in 0..64 {
+    let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+    let choice = (e & f) ^ (!e & g);
+    let t1 = h.wrapping_add(s1).wrapping_add(choice).wrapping_add(K[i]).wrapping_add(w[i]);
+    let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+    let majority = (a & b) ^ (a & c) ^ (b & c);
+    let t2 = s0.wrapping_add(majority);
+    h = g;
+    g = f;
+    f = e;
+    e = d.wrapping_add(t1);
+    d = c;
+    c = b;
+    b = a;
+    a = t1.wrapping_add(t2);
+  }
+  for (word, value) in state.iter_mut().zip(&[a, b, c, d, e, f, g, h]) { *word = word.wrapping_add(*value); }
+}
+
+/// Returns the SHA-256 digest of `data`.
+pub(crate) fn sha256(data: &[u8]) -> [u8; 32] {
+  let mut sha = Sha256::new();
+  sha.update(data);
+  sha.finish()
+}
+
+/// Returns the SHA-512 digest of `data`.
+#[cfg(feature = "signing")]
+pub(crate) fn sha512(data: &[u8]) -> [u8; 64] {
+  let mut padded = data.to_vec();
+  padded.push(0x80);
+  while padded.len() % 128 != 112 { padded.push(0); }
+  padded.extend_from_slice(&((data.len() as u128) * 8).to_be_bytes());
+
+  let mut state = H512;
+  for block in padded.chunks(128) {
+    let mut w = [0u64; 80];
+    for (i, word) in block.chunks(8).enumerate() {
+      let mut bytes = [0; 8];
+      bytes.copy_from_slice(word);
+      w[i] = u64::from_be_bytes(bytes);
+    }
+    for i in 16..80 {
+      let s0 = w[i - 15].rotate_right(1) ^ w[i - 15].rotate_right(8) ^ (w[i - 15] >> 7);
+      let s1 = w[i - 2].rotate_right(19) ^ w[i - 2].rotate_right(61) ^ (w[i - 2] >> 6);
+      w[i] = w[i - 16].wrapping_add(s0).wrapping_add(w[i - 7]).wrapping_add(s1);
+    }
+
+    let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = state;
+    for i in 0..80 {
+      let s1 = e.rotate_right(14) ^ e.rotate_right(18) ^ e.rotate_right(41);
+      let choice = (e & f) ^ (!e & g);
+      let t1 = h.wrapping_add(s1).wrapping_add(choice).wrapping_add(K512[i]).wrapping_add(w[i]);
+      let s0 = a.rotate_right(28) ^ a.rotate_right(34) ^ a.rotate_right(39);
+      let majority = (a & b) ^ (a & c) ^ (b & c);
+      let t2 = s0.wrapping_add(majority);
+      h = g;
+      g = f;
+      f = e;
+      e = d.wrapping_add(t1);
+      d = c;
+      c = b;
+      b = a;
+      a = t1.wrapping_add(t2);
+    }
+    for (word, value) in state.iter_mut().zip(&[a, b, c, d, e, f, g, h]) { *word = word.wrapping_add(*value); }
+  }
+
+  let mut digest = [0u8; 64];
+  for (bytes, word) in digest.chunks_mut(8).zip(&state) { bytes.copy_from_slice(&word.to_be_bytes()); }
+  digest
+}
+
+/// Returns the HMAC-SHA-256 of `data` under `key`.
+pub(crate) fn hmac_sha256(key: &[u8], data: &[u8]) -> [u8; 32] {
+  let mut block = [0u8; 64];
+  if key.len() > 64 {
+    block[..32].copy_from_slice(&sha256(key));
+  } else {
+    block[..key.len()].copy_from_slice(key);
+  }
+
+  let mut inner: Vec<u8> = block.iter().map(|b| b ^ 0x36).collect();
+  inner.extend_from_slice(data);
+  let mut outer: Vec<u8> = block.iter().map(|b| b ^ 0x5c).collect();
+  outer.extend_from_slice(&sha256(&inner));
+  sha256(&outer)
+}
+
+/// Formats bytes as lowercase hexadecimal.
+pub(crate) fn to_hex(bytes: &[u8]) -> String {
+  bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{Crc32, Sha256, hmac_sha256, sha256, to_hex};
+
+  #[test]
+  fn it_should_compute_crc32() {
+    let mut crc = Crc32::new();
+    assert_eq!(crc.value(), 0);
+    crc.update(b"1234");
+    crc.update(b"56789");
+    assert_eq!(crc.value(), 0xcbf4_3926);
+  }
+
+  #[test]
+  fn it_should_compute_sha256() {
+    assert_eq!(to_hex(&sha256(b"")), "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855");
+    assert_eq!(to_hex(&sha256(b"abc")), "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+    assert_eq!(to_hex(&sha256(b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq")),
+      "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1");
+
+    let data: Vec<u8> = (0..200u8).collect();
+    for split in &[0, 1, 55, 56, 64, 65, 128, 200] {
+      let mut sha = Sha256::new();
+      sha.update(&data[..*split]);
+      sha.update(&data[*split..]);
+      assert_eq!(sha.finish(), sha256(&data));
+    }
+  }
+
+  #[cfg(feature = "signing")]
+  #[test]
+  fn it_should_compute_sha512() {
+    assert_eq!(to_hex(&super::sha512(b"abc")), "ddaf35a193617abacc417349ae20413112e6fa4e89a97ea20a9eeee64b55d39a\
+      2192992a274fc1a836ba3c23a3feebbd454d4423643ce80e2a9ac94fa54ca49f");
+    let data = vec![b'a'; 111];
+    assert_eq!(to_hex(&super::sha512(&data))[..16], "fa9121c7b32b9e01"[..]);
+  }
+
+  #[test]
+  fn it_should_compute_hmac_sha256() {
+    assert_eq!(to_hex(&hmac_sha256(b"Jefe", b"what do ya want for nothing?")),
+      "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843");
+    assert_eq!(to_hex(&hmac_sha256(&[0xaa; 131], b"Test Using Larger Than Block-Size Key - Hash Key First")),
+      "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54");
+  }
+}
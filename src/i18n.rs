@@ -0,0 +1,146 @@
+//! Translated error messages for operator-facing tools.
+//!
+//! Error codes and kinds stay the same in every language, only the text
+//! shown to operators changes. A `Translator` supplies the template of an
+//! error kind in their language, with the placeholders of
+//! `FIXChecksumValidatorError::message_template`, and `localized` fills it
+//! in. Kinds without a translation fall back to English.
+//!
+//! A `Catalog` is a translator read from text with one `<kind> = <template>`
+//! entry per line, so that shops can ship translations as plain files.
+//!
+//! # Examples
+//!
+//! ```
+//! use fix_checksum::FIXChecksumValidatorError::{ChecksumMismatch, RequiredFieldMissing};
+//! use fix_checksum::i18n::Catalog;
+//!
+//! let catalog = Catalog::parse("de", "# Deutsch\nRequiredFieldMissing = Pflichtfeld {tag} fehlt.\n").unwrap();
+//! assert_eq!(RequiredFieldMissing(35).localized(&catalog), "Pflichtfeld 35 fehlt.");
+//! assert_eq!(ChecksumMismatch.localized(&catalog), "Checksum does not match message.");
+//! assert_eq!(RequiredFieldMissing(35).error_code(), 11);
+//! ```
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use super::FIXChecksumValidatorError;
+
+/// Source of translated message templates.
+pub trait Translator {
+  /// Returns the template of errors of `kind`, see
+  /// `FIXChecksumValidatorError::kind`, or none to fall back to English.
+  fn template(&self, kind: &str) -> Option<&str>;
+}
+
+/// Templates of one language by error kind.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Catalog {
+  language: String,
+  templates: BTreeMap<String, String>,
+}
+
+/// Error of parsing a catalog.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseCatalogError {
+  pub line: usize,
+}
+
+impl fmt::Display for ParseCatalogError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "Invalid catalog entry at line {}.", self.line)
+  }
+}
+
+impl ::std::error::Error for ParseCatalogError {}
+
+impl Catalog {
+  /// Creates an empty catalog of `language`, such as `de` or `pt-BR`.
+  pub fn new(language: &str) -> Catalog {
+    Catalog { language: language.to_string(), templates: BTreeMap::new() }
+  }
+
+  /// Parses the entries of a catalog, skipping blank lines and lines
+  /// starting with `#`. Entries of unknown kinds are rejected.
+  pub fn parse(language: &str, text: &str) -> Result<Catalog, ParseCatalogError> {
+    let mut catalog = Catalog::new(language);
+    for (index, line) in text.lines().enumerate() {
+      if line.trim().is_empty() || line.starts_with('#') { continue; }
+      let mut entry = line.splitn(2, '=');
+      match (entry.next().map(str::trim), entry.next().map(str::trim)) {
+        (Some(kind), Some(template)) if is_kind(kind) && !template.is_empty() => {
+          catalog.templates.insert(kind.to_string(), template.to_string());
+        },
+        _ => return Err(ParseCatalogError { line: index + 1 }),
+      }
+    }
+    Ok(catalog)
+  }
+
+  /// Sets the template of errors of `kind`.
+  pub fn translate(mut self, kind: &str, template: &str) -> Catalog {
+    self.templates.insert(kind.to_string(), template.to_string());
+    self
+  }
+
+  /// Returns the language of the catalog.
+  pub fn language(&self) -> &str {
+    &self.language
+  }
+}
+
+impl Translator for Catalog {
+  fn template(&self, kind: &str) -> Option<&str> {
+    self.templates.get(kind).map(|template| &template[..])
+  }
+}
+
+impl FIXChecksumValidatorError {
+  /// Returns the message of the error in the language of `translator`, in
+  /// English if it has no template for the kind of the error.
+  pub fn localized<T: Translator + ?Sized>(&self, translator: &T) -> String {
+    self.fill_template(translator.template(self.kind()).unwrap_or_else(|| self.message_template()))
+  }
+}
+
+/// Returns `true` if `kind` is the kind of an error of the crate.
+fn is_kind(kind: &str) -> bool {
+  (1..).map_while(FIXChecksumValidatorError::from_code).any(|err| err.kind() == kind)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{Catalog, ParseCatalogError, Translator};
+  use super::super::FIXChecksumValidatorError;
+  use super::super::FIXChecksumValidatorError::{BodyLengthInvalidFormat, InvalidEmptyMessage, TagAlreadyDefined};
+
+  /// Shouts every English message.
+  struct Shouting;
+
+  impl Translator for Shouting {
+    fn template(&self, _: &str) -> Option<&str> {
+      Some("ERROR!")
+    }
+  }
+
+  #[test]
+  fn it_should_localize_errors() {
+    let catalog = Catalog::parse("fr", "\nTagAlreadyDefined = Le tag {tag} est déjà défini.\n\
+      BodyLengthInvalidFormat = Longueur invalide : {detail}\n").unwrap()
+      .translate("InvalidEmptyMessage", "Message vide.");
+    assert_eq!(catalog.language(), "fr");
+    assert_eq!(TagAlreadyDefined(5001).localized(&catalog), "Le tag 5001 est déjà défini.");
+    assert_eq!(BodyLengthInvalidFormat("x".parse::<u32>().unwrap_err()).localized(&catalog),
+      "Longueur invalide : invalid digit found in string");
+    assert_eq!(InvalidEmptyMessage.localized(&catalog), "Message vide.");
+    assert_eq!(InvalidEmptyMessage.localized(&Shouting), "ERROR!");
+    for code in 1..15 {
+      let err = FIXChecksumValidatorError::from_code(code).unwrap();
+      assert_eq!(err.localized(&Catalog::new("en")), err.to_string());
+    }
+
+    assert_eq!(Catalog::parse("fr", "# fr\nChecksumMismatch\n"), Err(ParseCatalogError { line: 2 }));
+    assert_eq!(Catalog::parse("fr", "Mismatch = Non.\n"), Err(ParseCatalogError { line: 1 }));
+    assert_eq!(Catalog::parse("fr", "ChecksumMismatch =\n"), Err(ParseCatalogError { line: 1 }));
+  }
+}
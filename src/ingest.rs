@@ -0,0 +1,259 @@
+//! Preprocessing of vendor log formats before validation.
+//!
+//! A `Pipeline` applies transforms to every line of a log, so that logs
+//! with prefixes, escaped delimiters or base64 encoded messages can be
+//! read by a `StreamValidator` through an `IngestReader`:
+//!
+//! ```
+//! use fix_checksum::ValidationOptions;
+//! use fix_checksum::ingest::{IngestReader, Pipeline, Transform};
+//! use fix_checksum::stream::{StreamValidator, Outcome};
+//!
+//! let log = "IN  8=FIX.4.2^A9=5^A35=0^A10=161^A\r\nOUT 8=FIX.4.2^A9=5^A35=0^A10=161^A\r\n";
+//! let pipeline = Pipeline::new()
+//!   .then(Transform::TrimLineEnd)
+//!   .then("prefix-width=4".parse().unwrap())
+//!   .then(Transform::Unescape("^A".to_string(), '\x01'));
+//! let reader = IngestReader::new(log.as_bytes(), pipeline);
+//! let outcomes: Vec<Outcome> = StreamValidator::new(reader, ValidationOptions::new())
+//!   .map(|result| result.unwrap().outcome)
+//!   .collect();
+//! assert_eq!(outcomes, vec![Outcome::Valid, Outcome::Valid]);
+//! ```
+
+use std::fmt;
+use std::io;
+use std::io::{BufRead, Read};
+use std::str::FromStr;
+
+use super::LinePrefix;
+
+/// One step of a `Pipeline`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Transform {
+  /// Removes a prefix; lines without the prefix are passed on unchanged.
+  StripPrefix(LinePrefix),
+  /// Replaces every occurrence of an escape sequence, such as `^A` or
+  /// `<SOH>`, by a character.
+  Unescape(String, char),
+  /// Decodes a line holding a message in standard base64.
+  Base64Decode,
+  /// Removes trailing carriage returns and whitespace.
+  TrimLineEnd,
+}
+
+impl Transform {
+  fn apply(&self, line: Vec<u8>) -> Result<Vec<u8>, TransformError> {
+    match *self {
+      Transform::StripPrefix(ref prefix) => Ok(match prefix.length(&line) {
+        Some(length) => line[length..].to_vec(),
+        None => line,
+      }),
+      Transform::Unescape(ref sequence, replacement) => Ok(replace(&line, sequence.as_bytes(), replacement)),
+      Transform::Base64Decode => base64_decode(&line).ok_or(TransformError::InvalidBase64),
+      Transform::TrimLineEnd => {
+        let end = line.iter().rposition(|b| !b.is_ascii_whitespace()).map_or(0, |last| last + 1);
+        let mut line = line;
+        line.truncate(end);
+        Ok(line)
+      },
+    }
+  }
+}
+
+/// Parses a transform from the command line syntax: `trim`, `base64`,
+/// `prefix=SEPARATOR`, `prefix-width=N` or `unescape=SEQUENCE`, the last
+/// one replacing the sequence by SOH.
+impl FromStr for Transform {
+  type Err = TransformError;
+
+  fn from_str(s: &str) -> Result<Transform, TransformError> {
+    let (name, value) = match s.split_once('=') {
+      Some((name, value)) => (name, Some(value)),
+      None => (s, None),
+    };
+    match (name, value) {
+      ("trim", None) => Ok(Transform::TrimLineEnd),
+      ("base64", None) => Ok(Transform::Base64Decode),
+      ("prefix", Some(separator)) if !separator.is_empty() =>
+        Ok(Transform::StripPrefix(LinePrefix::Separator(separator.to_string()))),
+      ("prefix-width", Some(width)) => width.parse().map(|width| Transform::StripPrefix(LinePrefix::FixedWidth(width)))
+        .map_err(|_| TransformError::Unknown(s.to_string())),
+      ("unescape", Some(sequence)) if !sequence.is_empty() => Ok(Transform::Unescape(sequence.to_string(), '\x01')),
+      _ => Err(TransformError::Unknown(s.to_string())),
+    }
+  }
+}
+
+/// Why a line could not be transformed or a transform parsed.
+#[derive(Clone, Debug, PartialEq)]
+pub enum TransformError {
+  /// The line is not valid base64.
+  InvalidBase64,
+  /// The description of a transform is not understood.
+  Unknown(String),
+}
+
+impl fmt::Display for TransformError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      TransformError::InvalidBase64 => write!(f, "Line is not valid base64."),
+      TransformError::Unknown(ref transform) => write!(f, "Unknown transform {:?}.", transform),
+    }
+  }
+}
+
+impl ::std::error::Error for TransformError {}
+
+/// Transforms applied in order to every line of a log.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Pipeline {
+  transforms: Vec<Transform>,
+}
+
+impl Pipeline {
+  /// Creates a pipeline passing lines on unchanged.
+  pub fn new() -> Pipeline {
+    Pipeline::default()
+  }
+
+  /// Appends a transform to the pipeline.
+  pub fn then(mut self, transform: Transform) -> Pipeline {
+    self.transforms.push(transform);
+    self
+  }
+
+  /// Returns `true` if the pipeline has no transforms.
+  pub fn is_empty(&self) -> bool {
+    self.transforms.is_empty()
+  }
+
+  /// Applies the transforms to `line`, given without its line break.
+  pub fn apply(&self, line: &[u8]) -> Result<Vec<u8>, TransformError> {
+    self.transforms.iter().try_fold(line.to_vec(), |line, transform| transform.apply(line))
+  }
+}
+
+/// Reads a log line by line through a `Pipeline`, each transformed line
+/// followed by a line break. Lines failing a transform end the read with
+/// an `InvalidData` error.
+#[derive(Debug)]
+pub struct IngestReader<R> {
+  inner: R,
+  pipeline: Pipeline,
+  line: Vec<u8>,
+  buffer: Vec<u8>,
+  position: usize,
+}
+
+impl<R: BufRead> IngestReader<R> {
+  /// Creates a reader applying `pipeline` to the lines of `inner`.
+  pub fn new(inner: R, pipeline: Pipeline) -> IngestReader<R> {
+    IngestReader { inner, pipeline, line: Vec::new(), buffer: Vec::new(), position: 0 }
+  }
+}
+
+impl<R: BufRead> Read for IngestReader<R> {
+  fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+    let available = self.fill_buf()?;
+    let length = available.len().min(buf.len());
+    buf[..length].copy_from_slice(&available[..length]);
+    self.consume(length);
+    Ok(length)
+  }
+}
+
+impl<R: BufRead> BufRead for IngestReader<R> {
+  fn fill_buf(&mut self) -> io::Result<&[u8]> {
+    while self.position >= self.buffer.len() {
+      self.line.clear();
+      if self.inner.read_until(b'\n', &mut self.line)? == 0 { return Ok(&[]); }
+      let line = self.line.strip_suffix(b"\n").unwrap_or(&self.line);
+      self.buffer = self.pipeline.apply(line).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+      self.buffer.push(b'\n');
+      self.position = 0;
+    }
+    Ok(&self.buffer[self.position..])
+  }
+
+  fn consume(&mut self, amount: usize) {
+    self.position = (self.position + amount).min(self.buffer.len());
+  }
+}
+
+fn replace(line: &[u8], sequence: &[u8], replacement: char) -> Vec<u8> {
+  let mut replaced = Vec::with_capacity(line.len());
+  let mut rest = line;
+  while !rest.is_empty() {
+    if rest.starts_with(sequence) {
+      let mut encoded = [0; 4];
+      replaced.extend_from_slice(replacement.encode_utf8(&mut encoded).as_bytes());
+      rest = &rest[sequence.len()..];
+    } else {
+      replaced.push(rest[0]);
+      rest = &rest[1..];
+    }
+  }
+  replaced
+}
+
+/// Decodes standard base64 with optional padding.
+fn base64_decode(encoded: &[u8]) -> Option<Vec<u8>> {
+  let value = |b: u8| match b {
+    b'A'..=b'Z' => Some(b - b'A'),
+    b'a'..=b'z' => Some(b - b'a' + 26),
+    b'0'..=b'9' => Some(b - b'0' + 52),
+    b'+' => Some(62),
+    b'/' => Some(63),
+    _ => None,
+  };
+  let encoded = encoded.strip_suffix(b"==").or_else(|| encoded.strip_suffix(b"=")).unwrap_or(encoded);
+  if encoded.len() % 4 == 1 { return None; }
+
+  let mut decoded = Vec::with_capacity(encoded.len() * 3 / 4);
+  for chunk in encoded.chunks(4) {
+    let mut bits = 0u32;
+    for &b in chunk { bits = bits << 6 | value(b)? as u32; }
+    bits <<= 6 * (4 - chunk.len()) as u32;
+    decoded.extend_from_slice(&bits.to_be_bytes()[1..chunk.len()]);
+  }
+  Some(decoded)
+}
+
+#[cfg(test)]
+mod tests {
+  use std::io::Read;
+  use super::{base64_decode, IngestReader, Pipeline, Transform, TransformError};
+  use super::super::LinePrefix;
+
+  #[test]
+  fn it_should_decode_base64() {
+    assert_eq!(base64_decode(b"OD1GSVguNC4yATk9NQE="), Some(b"8=FIX.4.2\x019=5\x01".to_vec()));
+    assert_eq!(base64_decode(b"Zm9vYg"), Some(b"foob".to_vec()));
+    assert_eq!(base64_decode(b""), Some(Vec::new()));
+    assert_eq!(base64_decode(b"Zm9v*mFy"), None);
+    assert_eq!(base64_decode(b"Zm9vY"), None);
+  }
+
+  #[test]
+  fn it_should_transform_lines() {
+    let pipeline = Pipeline::new().then(Transform::TrimLineEnd)
+      .then(Transform::StripPrefix(LinePrefix::Separator(" : ".to_string())))
+      .then(Transform::Base64Decode);
+    let mut reader = IngestReader::new(&b"07:58:28 : OD1GSVguNC4yATk9NQE=\r\nZm9vYg\n"[..], pipeline);
+    let mut output = Vec::new();
+    reader.read_to_end(&mut output).unwrap();
+    assert_eq!(output, b"8=FIX.4.2\x019=5\x01\nfoob\n".to_vec());
+
+    let mut reader = IngestReader::new(&b"not base64\n"[..], Pipeline::new().then(Transform::Base64Decode));
+    assert_eq!(reader.read_to_end(&mut Vec::new()).unwrap_err().kind(), ::std::io::ErrorKind::InvalidData);
+  }
+
+  #[test]
+  fn it_should_parse_transforms() {
+    assert_eq!("trim".parse(), Ok(Transform::TrimLineEnd));
+    assert_eq!("prefix-width=24".parse(), Ok(Transform::StripPrefix(LinePrefix::FixedWidth(24))));
+    assert_eq!("unescape=<SOH>".parse(), Ok(Transform::Unescape("<SOH>".to_string(), '\x01')));
+    assert_eq!("base64=1".parse::<Transform>(), Err(TransformError::Unknown("base64=1".to_string())));
+  }
+}
@@ -0,0 +1,163 @@
+//! A minimal FIX initiator: the client side of a session.
+//!
+//! An `Initiator` sends the Logon (A) over a connected stream and waits for
+//! the one of the counterparty, restoring the sequence numbers of the
+//! session from its `MessageStore` first. The `Connection` it returns runs
+//! the session from there, see `connection`.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use std::time::Duration;
+//! use fix_checksum::connection::Event;
+//! use fix_checksum::initiator::Initiator;
+//! use fix_checksum::orders::{NewOrderSingle, OrdType, Side};
+//! use fix_checksum::session::SessionId;
+//! use fix_checksum::store::MemoryStore;
+//!
+//! let mut connection = Initiator::connect("127.0.0.1:9876", SessionId::new("BRKR", "EXCH"), MemoryStore::new(),
+//!   Duration::from_secs(30)).unwrap().logon().unwrap();
+//! connection.send(|header| NewOrderSingle::new("ORD-1", "IBM", Side::Buy, "100", OrdType::Market).build(header))
+//!   .unwrap();
+//! while let Event::Application(message) = connection.next_event().unwrap() {
+//!   println!("{}", String::from_utf8_lossy(&message));
+//! }
+//! ```
+
+use std::io;
+use std::io::{Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+use super::clock::{Clock, SystemClock};
+use super::connection::{Connection, parse};
+use super::session::SessionId;
+use super::store::MessageStore;
+
+/// Logs on to the counterparty of a session over `stream`.
+pub struct Initiator<S, M, C = SystemClock> {
+  connection: Connection<S, M, C>,
+}
+
+impl<M: MessageStore> Initiator<TcpStream, M> {
+  /// Connects to `address` for `session` and sets the read timeout of the
+  /// connection to `heartbeat_interval`.
+  pub fn connect<A: ToSocketAddrs>(address: A, session: SessionId, store: M, heartbeat_interval: Duration)
+    -> io::Result<Initiator<TcpStream, M>> {
+    let stream = TcpStream::connect(address)?;
+    stream.set_read_timeout(Some(heartbeat_interval))?;
+    Ok(Initiator::new(stream, session, store).heartbeat_interval(heartbeat_interval))
+  }
+}
+
+impl<S: Read + Write, M: MessageStore> Initiator<S, M> {
+  /// Creates the initiator of `session` over a connected `stream`, with
+  /// SenderCompID (49) `session.sender_comp_id`, FIX.4.2 and a heartbeat
+  /// every 30 seconds.
+  pub fn new(stream: S, session: SessionId, store: M) -> Initiator<S, M> {
+    Initiator { connection: Connection::new(stream, session, store) }
+  }
+}
+
+impl<S: Read + Write, M: MessageStore, C: Clock> Initiator<S, M, C> {
+  /// Sets the BeginString (8) of the session.
+  pub fn begin_string(mut self, begin_string: &str) -> Initiator<S, M, C> {
+    self.connection.begin_string = begin_string.to_string();
+    self
+  }
+
+  /// Sets the HeartBtInt (108) asked for at logon, in whole seconds.
+  pub fn heartbeat_interval(mut self, interval: Duration) -> Initiator<S, M, C> {
+    self.connection.heartbeat_interval = interval;
+    self
+  }
+
+  /// Takes the SendingTime (52) of messages and the time of heartbeats
+  /// from `clock`.
+  pub fn clock<D: Clock>(self, clock: D) -> Initiator<S, M, D> {
+    Initiator { connection: self.connection.clock(clock) }
+  }
+
+  /// Restores the checkpoint of the session from the store, sends a Logon
+  /// (A) and waits for the one of the counterparty. A Logout (5) instead
+  /// fails with `PermissionDenied`.
+  pub fn logon(mut self) -> io::Result<Connection<S, M, C>> {
+    let connection = &mut self.connection;
+    connection.checkpoint = connection.store.restore(&connection.session)?;
+    let heartbeat_interval = connection.heartbeat_interval.as_secs();
+    connection.send_admin("A", |writer| {
+      writer.field(98, 0);
+      writer.field(108, heartbeat_interval);
+    })?;
+
+    loop {
+      let message = connection.read_message()?;
+      let view = parse(&message)?;
+      match view.get(35) {
+        Some(b"A") => return Ok(self.connection),
+        Some(b"5") => {
+          let text = view.get_str(58).unwrap_or("logon rejected").to_string();
+          return Err(io::Error::new(io::ErrorKind::PermissionDenied, text));
+        },
+        _ => { connection.answer(&view)?; },
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::cell::Cell;
+  use std::io;
+  use std::time::{Duration, SystemTime, UNIX_EPOCH};
+  use super::Initiator;
+  use super::super::clock::Clock;
+  use super::super::connection::Event;
+  use super::super::connection::tests::{Script, counterparty, sent};
+  use super::super::orders::{NewOrderSingle, OrdType, Side};
+  use super::super::session::SessionId;
+  use super::super::store::{MemoryStore, MessageStore};
+
+  /// A clock moving ten seconds forward every time it is read.
+  struct Ticking(Cell<u64>);
+
+  impl Clock for Ticking {
+    fn now(&self) -> SystemTime {
+      self.0.set(self.0.get() + 10);
+      UNIX_EPOCH + Duration::from_secs(self.0.get())
+    }
+  }
+
+  #[test]
+  fn it_should_run_a_session() {
+    let incoming = vec![counterparty(1, "A", &[(98, "0"), (108, "30")]), counterparty(2, "1", &[(112, "PING")]),
+      Vec::new(), Vec::new(), Vec::new(), counterparty(3, "8", &[(11, "ORD-1")]), counterparty(4, "5", &[])];
+    let session = SessionId::new("BRKR", "EXCH");
+    let mut connection = Initiator::new(Script::new(incoming), session.clone(), MemoryStore::new())
+      .heartbeat_interval(Duration::from_secs(30)).clock(Ticking(Cell::new(0))).logon().unwrap();
+
+    let seq_num = connection.send(|header| NewOrderSingle::new("ORD-1", "IBM", Side::Buy, "100", OrdType::Market)
+      .build(header)).unwrap();
+    assert_eq!(seq_num, 2);
+    match connection.next_event().unwrap() {
+      Event::Application(message) => assert!(message.windows(6).any(|window| window == b"11=ORD")),
+      event => panic!("unexpected {:?}", event),
+    }
+    assert_eq!(connection.next_event().unwrap(), Event::LoggedOut);
+
+    assert_eq!(sent(connection.get_ref()), vec![("A".to_string(), None), ("D".to_string(), None),
+      ("0".to_string(), Some("PING".to_string())), ("0".to_string(), None), ("5".to_string(), None)]);
+    let checkpoint = connection.store().restore(&session).unwrap();
+    assert_eq!((checkpoint.last_sent, checkpoint.last_received), (5, 4));
+    assert_eq!(connection.store().messages(&session, 1, 5).unwrap().len(), 5);
+    assert_eq!(connection.checkpoint(), &checkpoint);
+  }
+
+  #[test]
+  fn it_should_fail_rejected_logon() {
+    let script = Script::new(vec![counterparty(1, "5", &[(58, "unknown session")])]);
+    let err = Initiator::new(script, SessionId::new("BRKR", "EXCH"), MemoryStore::new()).logon().err().unwrap();
+    assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+    assert_eq!(err.to_string(), "unknown session");
+  }
+}
@@ -0,0 +1,116 @@
+//! A vector storing its first elements inline.
+
+use std::fmt;
+use std::iter::FromIterator;
+use std::ops::{Deref, DerefMut};
+use std::slice;
+
+/// A vector keeping up to `N` elements inline and moving them to the heap
+/// past that, so that collections usually small do not allocate.
+#[derive(Clone)]
+pub(crate) struct InlineVec<T: Copy + Default, const N: usize> {
+  storage: Storage<T, N>,
+}
+
+#[derive(Clone)]
+enum Storage<T: Copy + Default, const N: usize> {
+  Inline([T; N], usize),
+  Heap(Vec<T>),
+}
+
+impl<T: Copy + Default, const N: usize> InlineVec<T, N> {
+  pub(crate) fn new() -> InlineVec<T, N> {
+    InlineVec { storage: Storage::Inline([T::default(); N], 0) }
+  }
+
+  pub(crate) fn push(&mut self, value: T) {
+    match self.storage {
+      Storage::Inline(ref mut values, ref mut len) if *len < N => {
+        values[*len] = value;
+        *len += 1;
+      },
+      Storage::Inline(ref values, len) => {
+        let mut heap = Vec::with_capacity(2 * N.max(1));
+        heap.extend_from_slice(&values[..len]);
+        heap.push(value);
+        self.storage = Storage::Heap(heap);
+      },
+      Storage::Heap(ref mut values) => values.push(value),
+    }
+  }
+
+  /// Returns `true` while the elements are stored inline.
+  #[cfg(test)]
+  pub(crate) fn is_inline(&self) -> bool {
+    matches!(self.storage, Storage::Inline(..))
+  }
+}
+
+impl<T: Copy + Default, const N: usize> Deref for InlineVec<T, N> {
+  type Target = [T];
+
+  fn deref(&self) -> &[T] {
+    match self.storage {
+      Storage::Inline(ref values, len) => &values[..len],
+      Storage::Heap(ref values) => values,
+    }
+  }
+}
+
+impl<T: Copy + Default, const N: usize> DerefMut for InlineVec<T, N> {
+  fn deref_mut(&mut self) -> &mut [T] {
+    match self.storage {
+      Storage::Inline(ref mut values, len) => &mut values[..len],
+      Storage::Heap(ref mut values) => values,
+    }
+  }
+}
+
+impl<T: Copy + Default, const N: usize> FromIterator<T> for InlineVec<T, N> {
+  fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> InlineVec<T, N> {
+    let mut vec = InlineVec::new();
+    for value in iter { vec.push(value); }
+    vec
+  }
+}
+
+impl<'a, T: Copy + Default, const N: usize> IntoIterator for &'a InlineVec<T, N> {
+  type Item = &'a T;
+  type IntoIter = slice::Iter<'a, T>;
+
+  fn into_iter(self) -> slice::Iter<'a, T> {
+    self.iter()
+  }
+}
+
+impl<T: Copy + Default + PartialEq, const N: usize> PartialEq for InlineVec<T, N> {
+  fn eq(&self, other: &InlineVec<T, N>) -> bool {
+    self[..] == other[..]
+  }
+}
+
+impl<T: Copy + Default + fmt::Debug, const N: usize> fmt::Debug for InlineVec<T, N> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    f.debug_list().entries(self.iter()).finish()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::InlineVec;
+
+  #[test]
+  fn it_should_move_to_heap_past_capacity() {
+    let mut vec: InlineVec<u32, 2> = InlineVec::new();
+    vec.push(1);
+    vec.push(2);
+    assert!(vec.is_inline());
+    vec.push(3);
+    assert!(!vec.is_inline());
+    assert_eq!(&vec[..], &[1, 2, 3]);
+    vec[0] = 4;
+    assert_eq!(vec, [4, 2, 3].iter().cloned().collect());
+    assert_ne!(vec, [4, 2].iter().cloned().collect());
+    assert_eq!(format!("{:?}", vec), "[4, 2, 3]");
+  }
+}
@@ -0,0 +1,113 @@
+//! Conversion traits for messages produced by other FIX engines.
+//!
+//! Implementing `EncodedMessage` for the message type of an engine (fefix,
+//! quickfix bindings, in-house encoders) lets its output be validated
+//! without copying it into a string by hand; implementing `DecodeMessage`
+//! additionally lets repaired bytes be turned back into the engine's type.
+//!
+//! # Examples
+//!
+//! ```
+//! use std::borrow::Cow;
+//! use fix_checksum::ValidationOptions;
+//! use fix_checksum::interop::{EncodedMessage, DecodeMessage, validate_encoded, repair_encoded};
+//!
+//! struct EngineMessage(Vec<u8>);
+//!
+//! impl EncodedMessage for EngineMessage {
+//!   fn wire_bytes(&self) -> Cow<'_, [u8]> { Cow::Borrowed(&self.0) }
+//! }
+//!
+//! impl DecodeMessage for EngineMessage {
+//!   type Error = ();
+//!   fn from_wire_bytes(bytes: &[u8]) -> Result<EngineMessage, ()> { Ok(EngineMessage(bytes.to_vec())) }
+//! }
+//!
+//! let options = ValidationOptions::new().delimiter('|');
+//! let message = EngineMessage(b"8=FIX.4.2|9=5|35=0|10=000|".to_vec());
+//! assert!(!validate_encoded(&message, &options).unwrap());
+//!
+//! let repaired = repair_encoded(&message, &options).unwrap().unwrap();
+//! assert!(validate_encoded(&repaired, &options).unwrap());
+//! ```
+
+use std::borrow::Cow;
+
+use super::{FIXChecksumValidatorError, ValidationOptions, validate_bytes_with};
+use super::stream::repair;
+
+/// A message which can expose its tag-value wire encoding.
+pub trait EncodedMessage {
+  /// Returns the encoded message, from BeginString through the checksum field.
+  fn wire_bytes(&self) -> Cow<'_, [u8]>;
+}
+
+/// A message which can be built from its tag-value wire encoding.
+pub trait DecodeMessage: Sized {
+  type Error;
+
+  fn from_wire_bytes(bytes: &[u8]) -> Result<Self, Self::Error>;
+}
+
+impl EncodedMessage for [u8] {
+  fn wire_bytes(&self) -> Cow<'_, [u8]> { Cow::Borrowed(self) }
+}
+
+impl EncodedMessage for Vec<u8> {
+  fn wire_bytes(&self) -> Cow<'_, [u8]> { Cow::Borrowed(self) }
+}
+
+impl EncodedMessage for str {
+  fn wire_bytes(&self) -> Cow<'_, [u8]> { Cow::Borrowed(self.as_bytes()) }
+}
+
+impl EncodedMessage for String {
+  fn wire_bytes(&self) -> Cow<'_, [u8]> { Cow::Borrowed(self.as_bytes()) }
+}
+
+impl DecodeMessage for Vec<u8> {
+  type Error = ();
+
+  fn from_wire_bytes(bytes: &[u8]) -> Result<Vec<u8>, ()> { Ok(bytes.to_vec()) }
+}
+
+impl DecodeMessage for String {
+  type Error = ::std::string::FromUtf8Error;
+
+  fn from_wire_bytes(bytes: &[u8]) -> Result<String, Self::Error> { String::from_utf8(bytes.to_vec()) }
+}
+
+/// This function validates checksum of a message of another engine
+pub fn validate_encoded<M: EncodedMessage + ?Sized>(message: &M, options: &ValidationOptions)
+  -> Result<bool, FIXChecksumValidatorError> {
+  validate_bytes_with(&message.wire_bytes(), options)
+}
+
+/// This function returns a copy of the message with a corrected checksum.
+/// It returns `Ok(None)` when the message has no checksum field or fails
+/// other checks enabled in `options`.
+pub fn repair_encoded<M: EncodedMessage + DecodeMessage>(message: &M, options: &ValidationOptions)
+  -> Result<Option<M>, M::Error> {
+  match repair(&message.wire_bytes(), options) {
+    Some(repaired) => M::from_wire_bytes(&repaired).map(Some),
+    None => Ok(None),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{validate_encoded, repair_encoded};
+  use super::super::ValidationOptions;
+
+  #[test]
+  fn it_should_validate_and_repair_std_types() {
+    let options = ValidationOptions::new().delimiter('|');
+    let message = "8=FIX.4.2|9=5|35=0|10=162|".to_string();
+    assert!(!validate_encoded(&message, &options).unwrap());
+    assert!(!validate_encoded(message.as_str(), &options).unwrap());
+    assert_eq!(repair_encoded(&message, &options).unwrap(), Some("8=FIX.4.2|9=5|35=0|10=161|".to_string()));
+
+    let message = b"8=FIX.4.2|9=5|35=0|".to_vec();
+    assert_eq!(repair_encoded(&message, &options).unwrap(), None);
+  }
+}
@@ -0,0 +1,634 @@
+//! Write-ahead journal of finalized messages with integrity checks.
+//!
+//! A journal starts with the magic `FIXJ`, followed by one record per
+//! message: the message length as a big-endian `u32`, the message, and the
+//! CRC-32 of every length and message of the journal so far as a big-endian
+//! `u32`. A torn or corrupted tail is found at the first record whose CRC
+//! does not match; every message is checked against its FIX checksum too.
+//!
+//! A CRC detects accidents, not tampering: anyone can recompute it. A
+//! chained journal starts with the magic `FIXC` and the hash it continues
+//! from, zeroes for a new chain. Every record also holds the SHA-256 of the
+//! previous record between the message and the CRC. The first record holds
+//! the hash the journal continues from instead. The hash of a record covers
+//! its length, message and previous hash, so changing, inserting or
+//! removing a record changes the hash of every record after it:
+//! `verify_chain` finds records not chaining to their predecessor and
+//! returns the hash of the last record, which detects changes at the tail
+//! once it is compared with a copy kept elsewhere, for example signed or
+//! sent to an auditor.
+//!
+//! A `RotatingJournal` spreads the records over numbered segment files, each
+//! one a journal of its own which can be verified without the others.
+
+use std::fs;
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::io::{BufReader, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use super::{ValidationOptions, validate_bytes_with};
+use super::hash::{Crc32, sha256};
+
+const MAGIC: &[u8] = b"FIXJ";
+const CHAINED_MAGIC: &[u8] = b"FIXC";
+
+/// Length of the hash of the previous record in chained journals.
+const HASH_LENGTH: usize = 32;
+
+/// Appends messages to a journal.
+///
+/// # Examples
+///
+/// ```
+/// use fix_checksum::ValidationOptions;
+/// use fix_checksum::journal::{Journal, JournalReader};
+///
+/// let options = ValidationOptions::new().delimiter('|');
+/// let mut journal = Journal::new(Vec::new(), options.clone()).unwrap();
+/// journal.append(b"8=FIX.4.2|9=5|35=0|10=161|").unwrap();
+/// assert!(journal.append(b"8=FIX.4.2|9=5|35=0|10=162|").is_err());
+///
+/// let bytes = journal.into_inner();
+/// let messages: Vec<Vec<u8>> = JournalReader::new(&bytes[..], options).unwrap().map(|m| m.unwrap()).collect();
+/// assert_eq!(messages, vec![b"8=FIX.4.2|9=5|35=0|10=161|".to_vec()]);
+/// ```
+#[derive(Debug)]
+pub struct Journal<W> {
+  inner: W,
+  crc: Crc32,
+  options: ValidationOptions,
+  records: u64,
+  size: u64,
+  head: Option<[u8; 32]>,
+}
+
+impl Journal<File> {
+  /// Opens the journal at `path` for appending, creating it when missing.
+  /// Records after the last one passing recovery are cut off, so that
+  /// appending continues a valid journal.
+  pub fn open<P: AsRef<Path>>(path: P, options: ValidationOptions) -> io::Result<Journal<File>> {
+    Journal::open_with(path, options, false)
+  }
+
+  /// Opens the journal at `path` like `open`, starting a chained journal
+  /// when it is missing. An existing journal keeps its format.
+  pub fn open_chained<P: AsRef<Path>>(path: P, options: ValidationOptions) -> io::Result<Journal<File>> {
+    Journal::open_with(path, options, true)
+  }
+
+  fn open_with<P: AsRef<Path>>(path: P, options: ValidationOptions, chained: bool) -> io::Result<Journal<File>> {
+    let mut file = OpenOptions::new().read(true).write(true).create(true).truncate(false).open(path)?;
+    if file.metadata()?.len() == 0 {
+      return if chained { Journal::chained(file, options, [0; 32]) } else { Journal::new(file, options) };
+    }
+
+    let mut reader = JournalReader::new(BufReader::new(&file), options.clone())?;
+    let records = reader.by_ref().take_while(|message| message.is_ok()).count() as u64;
+    let (length, crc) = (reader.offset(), reader.crc);
+    let head = reader.head;
+    file.set_len(length)?;
+    file.seek(SeekFrom::End(0))?;
+    Ok(Journal { inner: file, crc, options, records, size: length, head })
+  }
+
+  /// Flushes the journal and waits until the records are on disk.
+  pub fn sync(&mut self) -> io::Result<()> {
+    self.inner.flush()?;
+    self.inner.sync_data()
+  }
+}
+
+impl<W: Write> Journal<W> {
+  /// Starts an empty journal on `inner`.
+  pub fn new(mut inner: W, options: ValidationOptions) -> io::Result<Journal<W>> {
+    inner.write_all(MAGIC)?;
+    Ok(Journal { inner, crc: Crc32::new(), options, records: 0, size: MAGIC.len() as u64, head: None })
+  }
+
+  /// Starts an empty chained journal on `inner` whose first record chains
+  /// to `previous`: zeroes for a new chain, or the head of the journal it
+  /// continues.
+  pub fn chained(mut inner: W, options: ValidationOptions, previous: [u8; 32]) -> io::Result<Journal<W>> {
+    inner.write_all(CHAINED_MAGIC)?;
+    inner.write_all(&previous)?;
+    Ok(Journal { inner, crc: Crc32::new(), options, records: 0, size: (CHAINED_MAGIC.len() + HASH_LENGTH) as u64,
+      head: Some(previous) })
+  }
+
+  /// Appends a message. Messages failing validation with the options of
+  /// the journal are rejected with `InvalidInput`.
+  pub fn append(&mut self, message: &[u8]) -> io::Result<()> {
+    match validate_bytes_with(message, &self.options) {
+      Ok(true) => {},
+      Ok(false) => return Err(io::Error::new(io::ErrorKind::InvalidInput, "checksum mismatch")),
+      Err(err) => return Err(io::Error::new(io::ErrorKind::InvalidInput, err)),
+    }
+    if message.len() > u32::MAX as usize {
+      return Err(io::Error::new(io::ErrorKind::InvalidInput, "message too large for journal"));
+    }
+
+    let length = (message.len() as u32).to_be_bytes();
+    self.crc.update(&length);
+    self.crc.update(message);
+    self.inner.write_all(&length)?;
+    self.inner.write_all(message)?;
+    if let Some(previous) = self.head {
+      self.crc.update(&previous);
+      self.inner.write_all(&previous)?;
+      self.head = Some(record_hash(&length, message, &previous));
+      self.size += HASH_LENGTH as u64;
+    }
+    self.inner.write_all(&self.crc.value().to_be_bytes())?;
+    self.records += 1;
+    self.size += 8 + message.len() as u64;
+    Ok(())
+  }
+
+  /// Returns the hash of the last record of a chained journal, or the hash
+  /// its first record chains to while it is empty. Plain journals have no
+  /// chain.
+  pub fn head(&self) -> Option<[u8; 32]> {
+    self.head
+  }
+
+  /// Returns the number of records, including recovered ones.
+  pub fn records(&self) -> u64 {
+    self.records
+  }
+
+  /// Returns the length of the journal in bytes.
+  pub fn size(&self) -> u64 {
+    self.size
+  }
+
+  /// Flushes buffered records to the underlying writer.
+  pub fn flush(&mut self) -> io::Result<()> {
+    self.inner.flush()
+  }
+
+  /// Returns the underlying writer.
+  pub fn into_inner(self) -> W {
+    self.inner
+  }
+}
+
+/// Reads the messages of a journal back, verifying the CRC of every record
+/// and the checksum of every message. Iteration ends after the first error.
+#[derive(Debug)]
+pub struct JournalReader<R> {
+  inner: R,
+  crc: Crc32,
+  options: ValidationOptions,
+  offset: u64,
+  failed: bool,
+  chained: bool,
+  origin: Option<[u8; 32]>,
+  head: Option<[u8; 32]>,
+}
+
+impl<R: Read> JournalReader<R> {
+  /// Starts reading a plain or chained journal, failing with `InvalidData`
+  /// if `inner` does not start with a journal magic.
+  pub fn new(mut inner: R, options: ValidationOptions) -> io::Result<JournalReader<R>> {
+    let mut magic = [0; 4];
+    inner.read_exact(&mut magic)?;
+    if magic != MAGIC && magic != CHAINED_MAGIC {
+      return Err(io::Error::new(io::ErrorKind::InvalidData, "not a journal"));
+    }
+    let mut reader = JournalReader { inner, crc: Crc32::new(), options, offset: magic.len() as u64, failed: false,
+      chained: magic == CHAINED_MAGIC, origin: None, head: None };
+    if reader.chained {
+      let mut origin = [0; HASH_LENGTH];
+      reader.inner.read_exact(&mut origin)?;
+      reader.offset += HASH_LENGTH as u64;
+      reader.origin = Some(origin);
+      reader.head = Some(origin);
+    }
+    Ok(reader)
+  }
+
+  /// Returns the length of the verified part of the journal.
+  pub fn offset(&self) -> u64 {
+    self.offset
+  }
+
+  /// Returns whether the journal is chained.
+  pub fn is_chained(&self) -> bool {
+    self.chained
+  }
+
+  fn read_record(&mut self) -> io::Result<Option<Vec<u8>>> {
+    let mut length = [0; 4];
+    match self.inner.read(&mut length[..1])? {
+      0 => return Ok(None),
+      _ => self.inner.read_exact(&mut length[1..])?,
+    }
+    let size = u32::from_be_bytes(length) as usize;
+    if self.options.max_message_size.is_some_and(|max_size| size > max_size) {
+      return Err(io::Error::new(io::ErrorKind::InvalidData, format!("record at {} too large", self.offset)));
+    }
+
+    let mut message = Vec::new();
+    (&mut self.inner).take(size as u64).read_to_end(&mut message)?;
+    let mut stored = [0; 4];
+    if message.len() < size { return Err(io::ErrorKind::UnexpectedEof.into()); }
+    let mut previous = [0; HASH_LENGTH];
+    if self.chained { self.inner.read_exact(&mut previous)?; }
+    self.inner.read_exact(&mut stored)?;
+
+    if self.chained && self.head.is_some_and(|head| head != previous) {
+      return Err(io::Error::new(io::ErrorKind::InvalidData,
+        format!("record at {} does not chain to the previous record", self.offset)));
+    }
+    let mut crc = self.crc;
+    crc.update(&length);
+    crc.update(&message);
+    if self.chained { crc.update(&previous); }
+    if crc.value() != u32::from_be_bytes(stored) {
+      return Err(io::Error::new(io::ErrorKind::InvalidData, format!("CRC mismatch in record at {}", self.offset)));
+    }
+    match validate_bytes_with(&message, &self.options) {
+      Ok(true) => {},
+      Ok(false) => return Err(io::Error::new(io::ErrorKind::InvalidData,
+        format!("checksum mismatch in record at {}", self.offset))),
+      Err(err) => return Err(io::Error::new(io::ErrorKind::InvalidData,
+        format!("invalid message in record at {}: {}", self.offset, err))),
+    }
+
+    self.crc = crc;
+    self.offset += 8 + size as u64;
+    if self.chained {
+      self.head = Some(record_hash(&length, &message, &previous));
+      self.offset += HASH_LENGTH as u64;
+    }
+    Ok(Some(message))
+  }
+}
+
+impl<R: Read> Iterator for JournalReader<R> {
+  type Item = io::Result<Vec<u8>>;
+
+  fn next(&mut self) -> Option<io::Result<Vec<u8>>> {
+    if self.failed { return None; }
+    match self.read_record() {
+      Ok(message) => message.map(Ok),
+      Err(err) => {
+        self.failed = true;
+        Some(Err(err))
+      },
+    }
+  }
+}
+
+/// Reads the journal at `path` and returns its number of records, failing
+/// at the first record not passing verification.
+pub fn verify<P: AsRef<Path>>(path: P, options: ValidationOptions) -> io::Result<u64> {
+  let reader = JournalReader::new(BufReader::new(File::open(path)?), options)?;
+  let mut records = 0;
+  for message in reader {
+    message?;
+    records += 1;
+  }
+  Ok(records)
+}
+
+/// The verified chain of a journal, see `verify_chain`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Chain {
+  /// Number of records.
+  pub records: u64,
+  /// Hash the first record chains to, zeroes for a new chain.
+  pub origin: [u8; 32],
+  /// Hash of the last record, `origin` for an empty journal.
+  pub head: [u8; 32],
+}
+
+/// Reads the chained journal at `path` and verifies that every record
+/// chains to the one before. Compare the returned head with a copy taken
+/// when the journal was written to detect changes to the last records, and
+/// the origin with the head of the previous journal when one continues
+/// another. Fails with `InvalidData` for plain journals and at the first
+/// record not passing verification.
+///
+/// # Examples
+///
+/// ```
+/// use std::env;
+/// use fix_checksum::ValidationOptions;
+/// use fix_checksum::journal::{verify_chain, Journal};
+///
+/// let path = env::temp_dir().join(format!("fix-checksum-chain-doc-{}.journal", std::process::id()));
+/// let options = ValidationOptions::new().delimiter('|');
+/// let mut journal = Journal::open_chained(&path, options.clone()).unwrap();
+/// journal.append(b"8=FIX.4.2|9=5|35=0|10=161|").unwrap();
+/// journal.sync().unwrap();
+///
+/// let chain = verify_chain(&path, options).unwrap();
+/// assert_eq!((chain.records, chain.origin), (1, [0; 32]));
+/// assert_eq!(Some(chain.head), journal.head());
+/// # std::fs::remove_file(&path).unwrap();
+/// ```
+pub fn verify_chain<P: AsRef<Path>>(path: P, options: ValidationOptions) -> io::Result<Chain> {
+  let mut reader = JournalReader::new(BufReader::new(File::open(path)?), options)?;
+  if !reader.chained { return Err(io::Error::new(io::ErrorKind::InvalidData, "not a chained journal")); }
+  let mut records = 0;
+  for message in reader.by_ref() {
+    message?;
+    records += 1;
+  }
+  let origin = reader.origin.unwrap_or([0; 32]);
+  Ok(Chain { records, origin, head: reader.head.unwrap_or(origin) })
+}
+
+/// Returns the hash of a record of a chained journal.
+fn record_hash(length: &[u8], message: &[u8], previous: &[u8]) -> [u8; 32] {
+  sha256(&[length, message, previous].concat())
+}
+
+/// When a `RotatingJournal` starts a new segment and how many it keeps.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RotationPolicy {
+  max_size: Option<u64>,
+  max_age: Option<Duration>,
+  retain: Option<usize>,
+}
+
+impl RotationPolicy {
+  /// Creates a policy which never rotates and keeps every segment.
+  pub fn new() -> RotationPolicy {
+    RotationPolicy::default()
+  }
+
+  /// Starts a new segment instead of growing one past `size` bytes. A
+  /// segment always takes at least one record.
+  pub fn max_size(mut self, size: u64) -> RotationPolicy {
+    self.max_size = Some(size);
+    self
+  }
+
+  /// Starts a new segment once the current one was written to for `age`.
+  pub fn max_age(mut self, age: Duration) -> RotationPolicy {
+    self.max_age = Some(age);
+    self
+  }
+
+  /// Removes the oldest segments beyond the last `segments`.
+  pub fn retain(mut self, segments: usize) -> RotationPolicy {
+    self.retain = Some(segments.max(1));
+    self
+  }
+}
+
+/// Appends messages to numbered segments named `<prefix>.<number>.journal`
+/// in a directory.
+///
+/// # Examples
+///
+/// ```no_run
+/// use fix_checksum::ValidationOptions;
+/// use fix_checksum::journal::{RotatingJournal, RotationPolicy};
+///
+/// let policy = RotationPolicy::new().max_size(64 << 20).retain(10);
+/// let mut journal = RotatingJournal::open("journal", "FIX.4.2-BRKR-EXCH", ValidationOptions::new(), policy)
+///   .unwrap();
+/// journal.append(b"8=FIX.4.2\x019=5\x0135=0\x0110=161\x01").unwrap();
+/// journal.sync().unwrap();
+/// ```
+#[derive(Debug)]
+pub struct RotatingJournal {
+  directory: PathBuf,
+  prefix: String,
+  options: ValidationOptions,
+  policy: RotationPolicy,
+  segment: u64,
+  current: Journal<File>,
+  started: Instant,
+}
+
+impl RotatingJournal {
+  /// Opens the segments of `prefix` in `directory`, recovering the last one
+  /// and appending to it, or starting the first one.
+  pub fn open<P: AsRef<Path>>(directory: P, prefix: &str, options: ValidationOptions, policy: RotationPolicy)
+    -> io::Result<RotatingJournal> {
+    let directory = directory.as_ref().to_path_buf();
+    fs::create_dir_all(&directory)?;
+    let segment = segments(&directory, prefix)?.last().map_or(1, |&(number, _)| number);
+    let current = Journal::open(segment_path(&directory, prefix, segment), options.clone())?;
+    Ok(RotatingJournal { directory, prefix: prefix.to_string(), options, policy, segment, current,
+      started: Instant::now() })
+  }
+
+  /// Appends a message, first starting a new segment if the policy says so.
+  pub fn append(&mut self, message: &[u8]) -> io::Result<()> {
+    if self.current.records() > 0 {
+      let too_large = self.policy.max_size.is_some_and(|max_size| self.current.size() + 8 + message.len() as u64
+        > max_size);
+      let too_old = self.policy.max_age.is_some_and(|max_age| self.started.elapsed() >= max_age);
+      if too_large || too_old { self.rotate()?; }
+    }
+    self.current.append(message)
+  }
+
+  /// Closes the current segment and starts the next one, removing segments
+  /// beyond the retention of the policy.
+  pub fn rotate(&mut self) -> io::Result<()> {
+    self.current.sync()?;
+    let path = segment_path(&self.directory, &self.prefix, self.segment + 1);
+    let file = OpenOptions::new().write(true).create_new(true).open(path)?;
+    self.current = Journal::new(file, self.options.clone())?;
+    self.segment += 1;
+    self.started = Instant::now();
+
+    if let Some(retain) = self.policy.retain {
+      let segments = segments(&self.directory, &self.prefix)?;
+      for (_, path) in &segments[..segments.len().saturating_sub(retain)] { fs::remove_file(path)?; }
+    }
+    Ok(())
+  }
+
+  /// Returns the number of the current segment.
+  pub fn segment(&self) -> u64 {
+    self.segment
+  }
+
+  /// Returns the paths of the segments, oldest first.
+  pub fn segments(&self) -> io::Result<Vec<PathBuf>> {
+    Ok(segments(&self.directory, &self.prefix)?.into_iter().map(|(_, path)| path).collect())
+  }
+
+  /// Flushes the current segment and waits until it is on disk.
+  pub fn sync(&mut self) -> io::Result<()> {
+    self.current.sync()
+  }
+}
+
+fn segment_path(directory: &Path, prefix: &str, segment: u64) -> PathBuf {
+  directory.join(format!("{}.{:06}.journal", prefix, segment))
+}
+
+/// Segments of `prefix` in `directory` sorted by number.
+fn segments(directory: &Path, prefix: &str) -> io::Result<Vec<(u64, PathBuf)>> {
+  let mut segments = Vec::new();
+  for entry in fs::read_dir(directory)? {
+    let path = entry?.path();
+    let number = path.file_name().and_then(|name| name.to_str())
+      .and_then(|name| name.strip_prefix(prefix))
+      .and_then(|name| name.strip_prefix('.'))
+      .and_then(|name| name.strip_suffix(".journal"))
+      .filter(|number| !number.is_empty() && number.bytes().all(|b| b.is_ascii_digit()))
+      .and_then(|number| number.parse().ok());
+    if let Some(number) = number { segments.push((number, path)); }
+  }
+  segments.sort();
+  Ok(segments)
+}
+
+#[cfg(test)]
+mod tests {
+  use std::env;
+  use std::fs;
+  use std::fs::File;
+  use std::io::ErrorKind;
+  use std::time::Duration;
+  use super::{verify, verify_chain, Chain, Journal, JournalReader, RotatingJournal, RotationPolicy};
+  use super::super::ValidationOptions;
+
+  const HEARTBEAT: &[u8] = b"8=FIX.4.2|9=5|35=0|10=161|";
+  const TEST_REQUEST: &[u8] = b"8=FIX.4.2|9=11|35=1|112=A|10=226|";
+
+  fn options() -> ValidationOptions {
+    ValidationOptions::new().delimiter('|')
+  }
+
+  fn journal(messages: &[&[u8]]) -> Vec<u8> {
+    let mut journal = Journal::new(Vec::new(), options()).unwrap();
+    for message in messages { journal.append(message).unwrap(); }
+    journal.into_inner()
+  }
+
+  #[test]
+  fn it_should_detect_corrupted_records() {
+    let mut bytes = journal(&[HEARTBEAT, TEST_REQUEST]);
+    let messages: Vec<_> = JournalReader::new(&bytes[..], options()).unwrap().collect();
+    assert_eq!(messages.len(), 2);
+
+    let last = bytes.len() - 10;
+    bytes[last] ^= 1;
+    let mut reader = JournalReader::new(&bytes[..], options()).unwrap();
+    assert_eq!(reader.next().unwrap().unwrap(), HEARTBEAT.to_vec());
+    assert_eq!(reader.next().unwrap().unwrap_err().kind(), ErrorKind::InvalidData);
+    assert!(reader.next().is_none());
+    assert_eq!(reader.offset(), 4 + 8 + HEARTBEAT.len() as u64);
+
+    let torn = &bytes[..bytes.len() - 2];
+    assert_eq!(JournalReader::new(torn, options()).unwrap().nth(1).unwrap().unwrap_err().kind(),
+      ErrorKind::UnexpectedEof);
+    assert_eq!(JournalReader::new(&b"FIXK"[..], options()).unwrap_err().kind(), ErrorKind::InvalidData);
+  }
+
+  #[test]
+  fn it_should_recover_journal_file() {
+    let path = env::temp_dir().join(format!("fix-checksum-journal-{}.log", ::std::process::id()));
+    let mut bytes = journal(&[HEARTBEAT]);
+    bytes.extend_from_slice(&[0, 0, 0]);
+    fs::write(&path, &bytes).unwrap();
+
+    let mut journal = Journal::open(&path, options()).unwrap();
+    assert_eq!(journal.records(), 1);
+    journal.append(TEST_REQUEST).unwrap();
+    journal.sync().unwrap();
+    drop(journal);
+
+    let contents = fs::read(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(contents, self::journal(&[HEARTBEAT, TEST_REQUEST]));
+  }
+
+  fn chained(messages: &[&[u8]]) -> (Vec<u8>, [u8; 32]) {
+    let mut journal = Journal::chained(Vec::new(), options(), [0; 32]).unwrap();
+    for message in messages { journal.append(message).unwrap(); }
+    let head = journal.head().unwrap();
+    (journal.into_inner(), head)
+  }
+
+  #[test]
+  fn it_should_verify_chain() {
+    let path = env::temp_dir().join(format!("fix-checksum-chain-{}.journal", ::std::process::id()));
+    let (bytes, head) = chained(&[HEARTBEAT, TEST_REQUEST, HEARTBEAT]);
+    fs::write(&path, &bytes).unwrap();
+    let chain = verify_chain(&path, options()).unwrap();
+    assert_eq!((chain.records, chain.origin, chain.head), (3, [0; 32], head));
+
+    let mut journal = Journal::open(&path, options()).unwrap();
+    assert_eq!(journal.head(), Some(head));
+    journal.append(TEST_REQUEST).unwrap();
+    assert_eq!(journal.head(), Some(chained(&[HEARTBEAT, TEST_REQUEST, HEARTBEAT, TEST_REQUEST]).1));
+    drop(journal);
+
+    // A record removed from the middle leaves the next one chaining to it
+    let first = 36 + 40 + HEARTBEAT.len();
+    let second = first + 40 + TEST_REQUEST.len();
+    fs::write(&path, [&bytes[..first], &bytes[second..]].concat()).unwrap();
+    let err = verify_chain(&path, options()).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+    assert!(err.to_string().contains("does not chain"));
+
+    // A rewritten chain is consistent but ends at another head
+    fs::write(&path, chained(&[HEARTBEAT, HEARTBEAT, HEARTBEAT]).0).unwrap();
+    assert_ne!(verify_chain(&path, options()).unwrap().head, head);
+
+    fs::write(&path, self::journal(&[HEARTBEAT])).unwrap();
+    assert_eq!(verify_chain(&path, options()).unwrap_err().kind(), ErrorKind::InvalidData);
+    fs::remove_file(&path).unwrap();
+  }
+
+  #[test]
+  fn it_should_keep_origin_of_empty_chained_journal() {
+    let path = env::temp_dir().join(format!("fix-checksum-origin-{}.journal", ::std::process::id()));
+    let (_, previous) = chained(&[HEARTBEAT]);
+    let journal = Journal::chained(File::create(&path).unwrap(), options(), previous).unwrap();
+    assert_eq!(journal.size(), 36);
+    drop(journal);
+
+    let mut journal = Journal::open(&path, options()).unwrap();
+    assert_eq!((journal.records(), journal.head()), (0, Some(previous)));
+    journal.append(TEST_REQUEST).unwrap();
+    let head = journal.head().unwrap();
+    drop(journal);
+    assert_eq!(verify_chain(&path, options()).unwrap(), Chain { records: 1, origin: previous, head });
+
+    // Removing the first record leaves the next one not chaining to the origin
+    let (bytes, _) = chained(&[HEARTBEAT, TEST_REQUEST]);
+    let first = 36 + 40 + HEARTBEAT.len();
+    fs::write(&path, [&bytes[..36], &bytes[first..]].concat()).unwrap();
+    assert_eq!(verify_chain(&path, options()).unwrap_err().kind(), ErrorKind::InvalidData);
+    fs::remove_file(&path).unwrap();
+  }
+
+  #[test]
+  fn it_should_rotate_and_retain_segments() {
+    let directory = env::temp_dir().join(format!("fix-checksum-segments-{}", ::std::process::id()));
+    let policy = RotationPolicy::new().max_size(4 + 2 * (8 + HEARTBEAT.len() as u64)).retain(2);
+    let mut journal = RotatingJournal::open(&directory, "BRKR", options(), policy.clone()).unwrap();
+    for _ in 0..5 { journal.append(HEARTBEAT).unwrap(); }
+    journal.sync().unwrap();
+    assert_eq!(journal.segment(), 3);
+    drop(journal);
+
+    let mut journal = RotatingJournal::open(&directory, "BRKR", options(), policy).unwrap();
+    journal.append(TEST_REQUEST).unwrap();
+    let segments = journal.segments().unwrap();
+    assert_eq!(segments.len(), 2);
+    assert!(segments[0].ends_with("BRKR.000003.journal"));
+    let records: Vec<u64> = segments.iter().map(|path| verify(path, options()).unwrap()).collect();
+    assert_eq!(records, vec![1, 1]);
+
+    let mut journal = RotatingJournal::open(&directory, "BRKR", options(),
+      RotationPolicy::new().max_age(Duration::from_secs(0))).unwrap();
+    journal.append(HEARTBEAT).unwrap();
+    assert_eq!(journal.segment(), 5);
+    fs::remove_dir_all(&directory).unwrap();
+  }
+}
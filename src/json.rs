@@ -0,0 +1,53 @@
+//! Minimal JSON output helpers, for reports read by other programs.
+
+use super::FIXChecksumValidatorError;
+
+/// Returns `value` as a quoted JSON string.
+pub fn string(value: &str) -> String {
+  let mut quoted = String::with_capacity(value.len() + 2);
+  quoted.push('"');
+  for c in value.chars() {
+    match c {
+      '"' => quoted.push_str("\\\""),
+      '\\' => quoted.push_str("\\\\"),
+      '\n' => quoted.push_str("\\n"),
+      '\r' => quoted.push_str("\\r"),
+      '\t' => quoted.push_str("\\t"),
+      c if (c as u32) < 0x20 => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+      c => quoted.push(c),
+    }
+  }
+  quoted.push('"');
+  quoted
+}
+
+/// Returns `value` as a quoted JSON string, replacing invalid UTF-8.
+pub fn bytes(value: &[u8]) -> String {
+  string(&String::from_utf8_lossy(value))
+}
+
+/// Returns a JSON array of strings.
+pub fn strings<I: IntoIterator<Item = String>>(values: I) -> String {
+  let values: Vec<String> = values.into_iter().map(|value| string(&value)).collect();
+  format!("[{}]", values.join(","))
+}
+
+/// Returns the JSON object describing a validation error.
+pub fn error(err: &FIXChecksumValidatorError) -> String {
+  format!("{{\"code\":{},\"kind\":{},\"message\":{}}}", err.error_code(), string(err.kind()),
+    string(&err.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{string, strings, error};
+  use super::super::FIXChecksumValidatorError::ChecksumFieldNotFound;
+
+  #[test]
+  fn it_should_escape_json_strings() {
+    assert_eq!(string("8=FIX.4.2\x01\"\\\n"), "\"8=FIX.4.2\\u0001\\\"\\\\\\n\"");
+    assert_eq!(strings(vec!["a".to_string(), "b".to_string()]), "[\"a\",\"b\"]");
+    assert_eq!(error(&ChecksumFieldNotFound),
+      "{\"code\":2,\"kind\":\"ChecksumFieldNotFound\",\"message\":\"Checksum field not found.\"}");
+  }
+}
@@ -0,0 +1,176 @@
+//! Latency distributions from the timestamps carried by messages.
+//!
+//! The delay between TransactTime (60) and SendingTime (52) shows how long a
+//! counterparty held an event before sending it. When the capture time of a
+//! message is known, the delay between SendingTime and capture shows the
+//! transport latency.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::time::SystemTime;
+
+use super::clock::parse_utc_timestamp;
+use super::message::MessageView;
+use super::session::SessionId;
+
+/// Which pair of timestamps a delay is measured between.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum LatencyKind {
+  /// From TransactTime (60) to SendingTime (52).
+  TransactToSending,
+  /// From SendingTime (52) to the capture of the message.
+  SendingToCapture,
+}
+
+impl LatencyKind {
+  /// Returns a short name of the kind.
+  pub fn name(&self) -> &'static str {
+    match *self {
+      LatencyKind::TransactToSending => "transact-to-sending",
+      LatencyKind::SendingToCapture => "sending-to-capture",
+    }
+  }
+}
+
+/// Percentiles of the delays of one session, message type and kind, in
+/// milliseconds. Delays are negative when the later timestamp is earlier.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LatencySummary {
+  pub session: SessionId,
+  pub msg_type: String,
+  pub kind: LatencyKind,
+  pub count: usize,
+  pub min: i64,
+  pub p50: i64,
+  pub p90: i64,
+  pub p99: i64,
+  pub max: i64,
+}
+
+impl fmt::Display for LatencySummary {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{} {} {}: count={} min={} p50={} p90={} p99={} max={} ms", self.session, self.msg_type,
+      self.kind.name(), self.count, self.min, self.p50, self.p90, self.p99, self.max)
+  }
+}
+
+/// Collects delays per session and MsgType (35).
+///
+/// # Examples
+///
+/// ```
+/// use fix_checksum::latency::{LatencyAnalyzer, LatencyKind};
+/// use fix_checksum::message::MessageView;
+///
+/// let mut analyzer = LatencyAnalyzer::new();
+/// for sent in &["09:30:00.005", "09:30:00.010", "09:30:00.250"] {
+///   let message = format!("8=FIX.4.2|35=8|49=EXCH|56=BRKR|52=20240102-{}|60=20240102-09:30:00.000|", sent);
+///   analyzer.record(&MessageView::parse(message.as_bytes(), '|').unwrap(), None);
+/// }
+/// let summaries = analyzer.summaries();
+/// assert_eq!(summaries.len(), 1);
+/// assert_eq!(summaries[0].kind, LatencyKind::TransactToSending);
+/// assert_eq!((summaries[0].min, summaries[0].p50, summaries[0].max), (5, 10, 250));
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LatencyAnalyzer {
+  samples: BTreeMap<(SessionId, String, LatencyKind), Vec<i64>>,
+}
+
+impl LatencyAnalyzer {
+  /// Creates an analyzer without samples.
+  pub fn new() -> LatencyAnalyzer {
+    LatencyAnalyzer { samples: BTreeMap::new() }
+  }
+
+  /// Records the delays of `message`. Messages without SendingTime or
+  /// session identifiers are ignored, the transact delay needs TransactTime.
+  pub fn record(&mut self, message: &MessageView, captured: Option<SystemTime>) {
+    let session = match SessionId::from_message(message) {
+      Some(session) => session,
+      None => return,
+    };
+    let timestamp = |tag| message.get_str(tag).and_then(parse_utc_timestamp);
+    let sent = match timestamp(52) {
+      Some(sent) => sent,
+      None => return,
+    };
+    let msg_type = message.get_str(35).unwrap_or("").to_string();
+
+    if let Some(transacted) = timestamp(60) {
+      self.add(session.clone(), msg_type.clone(), LatencyKind::TransactToSending, delay(transacted, sent));
+    }
+    if let Some(captured) = captured {
+      self.add(session, msg_type, LatencyKind::SendingToCapture, delay(sent, captured));
+    }
+  }
+
+  fn add(&mut self, session: SessionId, msg_type: String, kind: LatencyKind, delay: i64) {
+    self.samples.entry((session, msg_type, kind)).or_default().push(delay);
+  }
+
+  /// Returns the summaries sorted by session, message type and kind.
+  pub fn summaries(&self) -> Vec<LatencySummary> {
+    self.samples.iter().map(|(&(ref session, ref msg_type, kind), samples)| {
+      let mut sorted = samples.clone();
+      sorted.sort();
+      LatencySummary {
+        session: session.clone(),
+        msg_type: msg_type.clone(),
+        kind,
+        count: sorted.len(),
+        min: sorted[0],
+        p50: percentile(&sorted, 50),
+        p90: percentile(&sorted, 90),
+        p99: percentile(&sorted, 99),
+        max: sorted[sorted.len() - 1],
+      }
+    }).collect()
+  }
+}
+
+/// Milliseconds from `earlier` to `later`.
+fn delay(earlier: SystemTime, later: SystemTime) -> i64 {
+  match later.duration_since(earlier) {
+    Ok(delay) => delay.as_millis() as i64,
+    Err(ahead) => -(ahead.duration().as_millis() as i64),
+  }
+}
+
+/// Nearest-rank percentile of sorted samples.
+fn percentile(sorted: &[i64], percent: usize) -> i64 {
+  let rank = (percent * sorted.len()).div_ceil(100);
+  sorted[rank.max(1) - 1]
+}
+
+#[cfg(test)]
+mod tests {
+  use std::time::Duration;
+  use super::{LatencyAnalyzer, LatencyKind, percentile};
+  use super::super::clock::parse_utc_timestamp;
+  use super::super::message::MessageView;
+
+  #[test]
+  fn it_should_compute_percentiles() {
+    let samples: Vec<i64> = (1..101).collect();
+    assert_eq!((percentile(&samples, 50), percentile(&samples, 90), percentile(&samples, 99)), (50, 90, 99));
+    assert_eq!(percentile(&[7], 99), 7);
+  }
+
+  #[test]
+  fn it_should_summarize_per_session_and_kind() {
+    let mut analyzer = LatencyAnalyzer::new();
+    let captured = parse_utc_timestamp("20240102-09:30:01").map(|time| time + Duration::from_millis(20));
+    let message = b"8=FIX.4.2|35=D|49=BRKR|56=EXCH|52=20240102-09:30:01|60=20240102-09:30:01.200|";
+    analyzer.record(&MessageView::parse(message, '|').unwrap(), captured);
+    analyzer.record(&MessageView::parse(b"8=FIX.4.2|35=0|49=EXCH|56=BRKR|52=20240102-09:30:01|", '|').unwrap(), None);
+    analyzer.record(&MessageView::parse(b"8=FIX.4.2|35=0|52=20240102-09:30:01|", '|').unwrap(), None);
+
+    let summaries = analyzer.summaries();
+    assert_eq!(summaries.len(), 2);
+    assert_eq!(summaries[0].to_string(), "BRKR->EXCH D transact-to-sending: count=1 min=-200 p50=-200 p90=-200 \
+      p99=-200 max=-200 ms");
+    assert_eq!(summaries[1].kind, LatencyKind::SendingToCapture);
+    assert_eq!(summaries[1].p50, 20);
+  }
+}
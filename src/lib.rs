@@ -15,7 +15,7 @@
 //! let mut message: String = message_parts
 //!   .iter()
 //!   .fold(String::new(), |msg, msg_part| msg.to_string() + msg_part + "\x01");
-//! assert_eq!(validate(&message).unwrap(), true);
+//! assert!(validate(&message).unwrap());
 //!
 //! // Generator
 //! message = message_parts
@@ -25,47 +25,296 @@
 //! assert_eq!("236", generate(&message));
 //! ```
 
+#![cfg_attr(feature = "allocator_api", feature(allocator_api))]
+
+#[cfg(feature = "derive")]
+extern crate fix_checksum_derive;
+
+use std::borrow::Cow;
 use std::error::Error;
 use std::fmt;
+use std::io::IoSlice;
 use std::num;
 
 use self::FIXChecksumValidatorError::{InvalidEmptyMessage, ChecksumFieldNotFound,
-  ChecksumFieldInvalidFormat};
+  ChecksumFieldInvalidFormat, MessageTooLarge, BodyLengthFieldNotFound, BodyLengthInvalidFormat,
+  BodyLengthMismatch, ChecksumFieldNotLast, FieldInvalidFormat, BeginStringFieldNotFound,
+  RequiredFieldMissing, FieldValueInvalid, TagAlreadyDefined, ChecksumMismatch};
+
+mod hash;
+mod inline;
+mod options;
+pub mod stream;
+pub mod framing;
+pub mod diagnose;
+pub mod message;
+pub mod session;
+pub mod summary;
+pub mod dictionary;
+pub mod pretty;
+pub mod json;
+pub mod interop;
+pub mod orders;
+pub mod execution;
+pub mod market_data;
+pub mod spec;
+pub mod validator;
+pub mod rules;
+pub mod profile;
+pub mod clock;
+pub mod latency;
+pub mod metrics;
+pub mod reconcile;
+pub mod filter;
+pub mod fingerprint;
+pub mod cache;
+pub mod writer;
+pub mod digest;
+pub mod ingest;
+pub mod quickfix;
+pub mod pool;
+pub mod auth;
+pub mod journal;
+pub mod store;
+pub mod connection;
+pub mod initiator;
+pub mod acceptor;
+pub mod replay;
+pub mod fixtures;
+pub mod generator;
+pub mod corruptor;
+pub mod report;
+#[cfg(any(test, feature = "mock"))]
+pub mod mock;
+#[cfg(feature = "i18n")]
+pub mod i18n;
+#[cfg(feature = "allocator_api")]
+pub mod allocator;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+#[cfg(feature = "signing")]
+mod ed25519;
+#[cfg(feature = "signing")]
+pub mod signing;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(feature = "node")]
+pub mod node;
+
+pub use options::{ValidationOptions, ErrorPolicy, LinePrefix};
+pub use spec::FixMessageSpec;
+#[cfg(feature = "derive")]
+pub use fix_checksum_derive::FixMessageSpec;
+
+const FIX_CHECKSUM_FIELD: &str = "\x31\x30\x3D";
+const FIX_BODY_LENGTH_FIELD: &str = "\x39\x3D";
+
+/// The three ASCII digits of every checksum value, so formatting a trailer
+/// is a table lookup.
+const CHECKSUM_DIGITS: [[u8; 3]; 256] = checksum_digits_table();
+
+const fn checksum_digits_table() -> [[u8; 3]; 256] {
+  let mut table = [[0u8; 3]; 256];
+  let mut i = 0;
+  while i < 256 {
+    table[i] = [b'0' + (i / 100) as u8, b'0' + (i / 10 % 10) as u8, b'0' + (i % 10) as u8];
+    i += 1;
+  }
+  table
+}
 
-const FIX_MESSAGE_DELIMITER: char = '\x01';
-const FIX_CHECKSUM_FIELD: &'static str = "\x31\x30\x3D";
+/// Returns the zero-padded digits of a checksum computed by `checksum_bytes`.
+fn checksum_digits(checksum: u32) -> &'static [u8; 3] {
+  &CHECKSUM_DIGITS[(checksum % 256) as usize]
+}
 
 fn checksum(message: &str) -> u32 {
+  checksum_bytes(message.as_bytes(), b'\x01')
+}
+
+fn checksum_bytes(message: &[u8], delimiter: u8) -> u32 {
+  // Wrapping arithmetic keeps the sum modulo 256 right for messages of any size
   let mut cs: u32 = 0;
-  for b in message.as_bytes() {
-    cs += *b as u32;
+  for b in message {
+    cs = cs.wrapping_add(if *b == delimiter { 1 } else { *b as u32 });
   }
-  cs %= 256;
-  return cs;
+  if delimiter != b'\x01' {
+    // A delimiter within a data field is a data byte, not a stand-in for SOH
+    let mut previous = 0;
+    let _ = message::split_fields(message, delimiter, |tag, start, end| {
+      if dictionary::data_field(previous) == Some(tag) {
+        let count = message[start..end].iter().filter(|b| **b == delimiter).count() as u32;
+        cs = cs.wrapping_add(count.wrapping_mul((delimiter as u32).wrapping_sub(1)));
+      }
+      previous = tag;
+    });
+  }
+  cs % 256
 }
 
-#[derive(PartialEq, Debug)]
+/// Returns the error of parsing a number with an invalid digit.
+fn invalid_digit() -> num::ParseIntError {
+  "+".parse::<u32>().unwrap_err()
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+  haystack.windows(needle.len()).position(|window| window == needle)
+}
+
+#[derive(Clone, PartialEq, Debug)]
 pub enum FIXChecksumValidatorError {
   InvalidEmptyMessage,
   ChecksumFieldNotFound,
   ChecksumFieldInvalidFormat(num::ParseIntError),
+  MessageTooLarge,
+  BodyLengthFieldNotFound,
+  BodyLengthInvalidFormat(num::ParseIntError),
+  BodyLengthMismatch,
+  ChecksumFieldNotLast,
+  FieldInvalidFormat,
+  BeginStringFieldNotFound,
+  RequiredFieldMissing(u32),
+  FieldValueInvalid(u32),
+  TagAlreadyDefined(u32),
+  ChecksumMismatch,
 }
 
-impl fmt::Display for FIXChecksumValidatorError {
-  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+impl FIXChecksumValidatorError {
+  /// Returns the stable numeric code of the error. Codes are never reused,
+  /// so they can be persisted or passed across FFI boundaries.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use fix_checksum::FIXChecksumValidatorError::ChecksumFieldNotFound;
+  /// assert_eq!(ChecksumFieldNotFound.error_code(), 2);
+  /// ```
+  pub fn error_code(&self) -> u32 {
     match *self {
-      ChecksumFieldInvalidFormat(ref err) => write!(f, "{}: {}", self.description(), err),
-      _ => write!(f, "{}", self.description()),
+      InvalidEmptyMessage => 1,
+      ChecksumFieldNotFound => 2,
+      ChecksumFieldInvalidFormat(..) => 3,
+      MessageTooLarge => 4,
+      BodyLengthFieldNotFound => 5,
+      BodyLengthInvalidFormat(..) => 6,
+      BodyLengthMismatch => 7,
+      ChecksumFieldNotLast => 8,
+      FieldInvalidFormat => 9,
+      BeginStringFieldNotFound => 10,
+      RequiredFieldMissing(..) => 11,
+      FieldValueInvalid(..) => 12,
+      TagAlreadyDefined(..) => 13,
+      ChecksumMismatch => 14,
     }
   }
-}
 
-impl Error for FIXChecksumValidatorError {
-  fn description(&self) -> &str {
+  /// Returns the error with the given code. Variants carrying a
+  /// `ParseIntError` get one for an invalid digit and variants carrying a tag
+  /// get tag 0, as the original details are not part of the code.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use fix_checksum::FIXChecksumValidatorError;
+  /// use fix_checksum::FIXChecksumValidatorError::ChecksumFieldNotFound;
+  /// assert_eq!(FIXChecksumValidatorError::from_code(2), Some(ChecksumFieldNotFound));
+  /// assert_eq!(FIXChecksumValidatorError::from_code(0), None);
+  /// ```
+  pub fn from_code(code: u32) -> Option<FIXChecksumValidatorError> {
+    match code {
+      1 => Some(InvalidEmptyMessage),
+      2 => Some(ChecksumFieldNotFound),
+      3 => Some(ChecksumFieldInvalidFormat(invalid_digit())),
+      4 => Some(MessageTooLarge),
+      5 => Some(BodyLengthFieldNotFound),
+      6 => Some(BodyLengthInvalidFormat(invalid_digit())),
+      7 => Some(BodyLengthMismatch),
+      8 => Some(ChecksumFieldNotLast),
+      9 => Some(FieldInvalidFormat),
+      10 => Some(BeginStringFieldNotFound),
+      11 => Some(RequiredFieldMissing(0)),
+      12 => Some(FieldValueInvalid(0)),
+      13 => Some(TagAlreadyDefined(0)),
+      14 => Some(ChecksumMismatch),
+      _ => None,
+    }
+  }
+
+  /// Returns the English text of the error, with `{tag}` standing for the
+  /// tag and `{detail}` for the parse error of the variants carrying them.
+  /// `Display` fills it in with `fill_template`; translations replace it,
+  /// while codes and kinds stay the same.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use fix_checksum::FIXChecksumValidatorError::RequiredFieldMissing;
+  /// assert_eq!(RequiredFieldMissing(35).message_template(), "Required field {tag} missing.");
+  /// assert_eq!(RequiredFieldMissing(35).fill_template("Feld {tag} fehlt."), "Feld 35 fehlt.");
+  /// ```
+  pub fn message_template(&self) -> &'static str {
     match *self {
       InvalidEmptyMessage => "Invalid empty message.",
       ChecksumFieldNotFound => "Checksum field not found.",
-      ChecksumFieldInvalidFormat(..) => "Checksum value invalid format",
+      ChecksumFieldInvalidFormat(..) => "Checksum value invalid format: {detail}",
+      MessageTooLarge => "Message exceeds maximum size.",
+      BodyLengthFieldNotFound => "Body length field not found.",
+      BodyLengthInvalidFormat(..) => "Body length value invalid format: {detail}",
+      BodyLengthMismatch => "Body length does not match message.",
+      ChecksumFieldNotLast => "Checksum field is not the last field.",
+      FieldInvalidFormat => "Field invalid format.",
+      BeginStringFieldNotFound => "Begin string field not found.",
+      RequiredFieldMissing(..) => "Required field {tag} missing.",
+      FieldValueInvalid(..) => "Field {tag} value invalid.",
+      TagAlreadyDefined(..) => "Tag {tag} is already defined.",
+      ChecksumMismatch => "Checksum does not match message.",
+    }
+  }
+
+  /// Returns `template` with the placeholders of `message_template` replaced
+  /// by the details of the error. The parse error detail is in English.
+  pub fn fill_template(&self, template: &str) -> String {
+    match *self {
+      ChecksumFieldInvalidFormat(ref err) | BodyLengthInvalidFormat(ref err) =>
+        template.replace("{detail}", &err.to_string()),
+      RequiredFieldMissing(tag) | FieldValueInvalid(tag) | TagAlreadyDefined(tag) =>
+        template.replace("{tag}", &tag.to_string()),
+      _ => template.to_string(),
+    }
+  }
+
+  /// Returns the name of the error variant.
+  pub fn kind(&self) -> &'static str {
+    match *self {
+      InvalidEmptyMessage => "InvalidEmptyMessage",
+      ChecksumFieldNotFound => "ChecksumFieldNotFound",
+      ChecksumFieldInvalidFormat(..) => "ChecksumFieldInvalidFormat",
+      MessageTooLarge => "MessageTooLarge",
+      BodyLengthFieldNotFound => "BodyLengthFieldNotFound",
+      BodyLengthInvalidFormat(..) => "BodyLengthInvalidFormat",
+      BodyLengthMismatch => "BodyLengthMismatch",
+      ChecksumFieldNotLast => "ChecksumFieldNotLast",
+      FieldInvalidFormat => "FieldInvalidFormat",
+      BeginStringFieldNotFound => "BeginStringFieldNotFound",
+      RequiredFieldMissing(..) => "RequiredFieldMissing",
+      FieldValueInvalid(..) => "FieldValueInvalid",
+      TagAlreadyDefined(..) => "TagAlreadyDefined",
+      ChecksumMismatch => "ChecksumMismatch",
+    }
+  }
+}
+
+impl fmt::Display for FIXChecksumValidatorError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    f.write_str(&self.fill_template(self.message_template()))
+  }
+}
+
+impl Error for FIXChecksumValidatorError {
+  fn source(&self) -> Option<&(dyn Error + 'static)> {
+    match *self {
+      ChecksumFieldInvalidFormat(ref err) | BodyLengthInvalidFormat(ref err) => Some(err),
+      _ => None,
     }
   }
 }
@@ -127,22 +376,382 @@ impl Error for FIXChecksumValidatorError {
 /// assert_eq!(fix_checksum::validate(&message).unwrap(), true);
 /// ```
 pub fn validate(inbound_message: &str) -> Result<bool, FIXChecksumValidatorError> {
-  if inbound_message.is_empty() { return Err(InvalidEmptyMessage); }
+  validate_with(inbound_message, &ValidationOptions::new())
+}
 
-  let tail_pattern = FIX_MESSAGE_DELIMITER.to_string() + FIX_CHECKSUM_FIELD;
-  let tail_start = inbound_message.find(&tail_pattern);
-  if tail_start.is_none() { return Err(ChecksumFieldNotFound); }
+/// This function validates FIX message checksum using the given options
+///
+/// # Examples
+///
+/// ```
+/// use fix_checksum::{validate_with, ValidationOptions};
+/// use fix_checksum::FIXChecksumValidatorError::{BodyLengthMismatch, MessageTooLarge};
+///
+/// let message = "8=FIX.4.2|9=70|35=0|49=BRKR|56=INVMGR|34=235|52=19980604-07:58:28|\
+///   112=19980604-07:58:28|10=233|";
+/// let options = ValidationOptions::new().delimiter('|');
+/// assert_eq!(validate_with(message, &options).unwrap(), true);
+///
+/// let options = options.check_body_length(true);
+/// assert_eq!(validate_with(message, &options).unwrap_err(), BodyLengthMismatch);
+///
+/// let options = options.max_message_size(16);
+/// assert_eq!(validate_with(message, &options).unwrap_err(), MessageTooLarge);
+/// ```
+pub fn validate_with(inbound_message: &str, options: &ValidationOptions)
+  -> Result<bool, FIXChecksumValidatorError> {
+  validate_bytes_with(inbound_message.as_bytes(), options)
+}
 
-  let split_index = tail_start.unwrap() + 1;
-  let (checksum_index_start, checksum_index_end) = (split_index + 3, split_index + 6);
+/// This function validates checksum of FIX message given as raw bytes
+///
+/// # Examples
+///
+/// ```
+/// use fix_checksum::{validate_bytes_with, ValidationOptions};
+///
+/// let message = b"8=FIX.4.2|9=5|35=0|10=161|";
+/// let options = ValidationOptions::new().delimiter('|');
+/// assert_eq!(validate_bytes_with(message, &options).unwrap(), true);
+/// ```
+pub fn validate_bytes_with(inbound_message: &[u8], options: &ValidationOptions)
+  -> Result<bool, FIXChecksumValidatorError> {
+  if inbound_message.is_empty() { return Err(InvalidEmptyMessage); }
+  if let Some(max_size) = options.max_message_size {
+    if inbound_message.len() > max_size { return Err(MessageTooLarge); }
+  }
 
-  let checksum_to_be = checksum(&inbound_message[..split_index]);
-  let checksum_as_is: u32 = try!(inbound_message[checksum_index_start..checksum_index_end]
-    .parse().map_err(FIXChecksumValidatorError::ChecksumFieldInvalidFormat));
+  let delimiter = options.delimiter;
+  let split_index = checksum_field_start(inbound_message, delimiter).ok_or(ChecksumFieldNotFound)?;
 
+  if options.check_body_length {
+    check_body_length(&inbound_message[..split_index], delimiter)?;
+  }
+
+  let checksum_as_is = checksum_field_value(&inbound_message[split_index..], options)?;
+  let checksum_to_be = checksum_bytes(&inbound_message[..split_index], delimiter);
   Ok(checksum_as_is == checksum_to_be)
 }
 
+/// Returns the value of `trailer`, the checksum field and what follows it
+/// up to the end of the message.
+fn checksum_field_value(trailer: &[u8], options: &ValidationOptions) -> Result<u32, FIXChecksumValidatorError> {
+  let checksum_index_end = FIX_CHECKSUM_FIELD.len() + 3;
+  // A checksum cut short by the end of the message is as invalid as one with a non-digit
+  let checksum_value = trailer.get(FIX_CHECKSUM_FIELD.len()..checksum_index_end)
+    .ok_or_else(|| ChecksumFieldInvalidFormat(invalid_digit()))?;
+  // `parse` accepts a leading '+', which is not a valid checksum in strict mode
+  if options.strict && !checksum_value.iter().all(|b| b.is_ascii_digit()) {
+    return Err(ChecksumFieldInvalidFormat(invalid_digit()));
+  }
+  let checksum_as_is: u32 = String::from_utf8_lossy(checksum_value)
+    .parse().map_err(ChecksumFieldInvalidFormat)?;
+
+  if options.strict && trailer[checksum_index_end..] != [options.delimiter] {
+    return Err(ChecksumFieldNotLast);
+  }
+  Ok(checksum_as_is)
+}
+
+/// Largest message `validate_untrusted` accepts when the options set no
+/// `max_message_size`.
+const UNTRUSTED_MAX_MESSAGE_SIZE: usize = 1 << 20;
+
+/// This function validates checksum of FIX message received from an
+/// untrusted source, such as a network peer or a fuzzer.
+///
+/// No input, however malformed, makes it panic: lengths and offsets read
+/// from the message are bounds checked and sums wrap instead of overflowing.
+/// It runs in time linear to the message and does not allocate. Unless
+/// `options` set a `max_message_size`, messages over 1 MiB fail with
+/// `MessageTooLarge`.
+///
+/// # Examples
+///
+/// ```
+/// use fix_checksum::{validate_untrusted, ValidationOptions};
+/// use fix_checksum::FIXChecksumValidatorError::ChecksumFieldInvalidFormat;
+///
+/// let options = ValidationOptions::new().delimiter('|');
+/// assert_eq!(validate_untrusted(b"8=FIX.4.2|9=5|35=0|10=161|", &options), Ok(true));
+/// assert!(matches!(validate_untrusted(b"8=FIX.4.2|9=5|35=0|10=1", &options), Err(ChecksumFieldInvalidFormat(_))));
+/// ```
+pub fn validate_untrusted(inbound_message: &[u8], options: &ValidationOptions)
+  -> Result<bool, FIXChecksumValidatorError> {
+  if options.max_message_size.is_none() && inbound_message.len() > UNTRUSTED_MAX_MESSAGE_SIZE {
+    return Err(MessageTooLarge);
+  }
+  validate_bytes_with(inbound_message, options)
+}
+
+/// This function validates checksum of FIX message scattered over several
+/// buffers, such as the segments filled by `readv` or the two halves of a
+/// wrapped ring buffer, as `validate_bytes_with` validates them joined.
+///
+/// The bytes before the checksum field are summed where they are. The
+/// segments are joined first only when BodyLength is checked, when the
+/// delimiter is not SOH, when the checksum field does not start in the last
+/// segment or when the message declares a data field, whose value may hold
+/// the delimiter.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::IoSlice;
+/// use fix_checksum::{validate_vectored, ValidationOptions};
+///
+/// let segments = [IoSlice::new(b"8=FIX.4.2\x019=5\x0135"), IoSlice::new(b"=0\x0110=161\x01")];
+/// assert_eq!(validate_vectored(&segments, &ValidationOptions::new()), Ok(true));
+/// ```
+pub fn validate_vectored(segments: &[IoSlice], options: &ValidationOptions)
+  -> Result<bool, FIXChecksumValidatorError> {
+  let length: usize = segments.iter().map(|segment| segment.len()).sum();
+  if length == 0 { return Err(InvalidEmptyMessage); }
+  if let Some(max_size) = options.max_message_size {
+    if length > max_size { return Err(MessageTooLarge); }
+  }
+
+  let joined = || validate_bytes_with(&segments.iter().flat_map(|segment| segment.iter().cloned()).collect::<Vec<_>>(),
+    options);
+  let delimiter = options.delimiter;
+  if delimiter != b'\x01' || options.check_body_length { return joined(); }
+
+  let last = segments.iter().rposition(|segment| !segment.is_empty()).expect("a segment is not empty");
+  let (head, tail) = (&segments[..last], &segments[last][..]);
+  let split_index = match vectored_checksum_field_start(head, tail, delimiter) {
+    Some(split_index) => split_index,
+    None => return joined(),
+  };
+
+  let mut scanner = DataFieldScanner::new();
+  let mut checksum_to_be = 0u8;
+  for bytes in head.iter().map(|segment| &segment[..]).chain(Some(&tail[..split_index])) {
+    scanner.update(bytes, delimiter);
+    checksum_to_be = bytes.iter().fold(checksum_to_be, |sum, b| sum.wrapping_add(*b));
+  }
+  if scanner.declares { return joined(); }
+
+  let checksum_as_is = checksum_field_value(&tail[split_index..], options)?;
+  Ok(checksum_as_is == checksum_to_be as u32)
+}
+
+/// Returns the offset in `tail`, the last segment of a message, of its last
+/// checksum field.
+fn vectored_checksum_field_start(head: &[IoSlice], tail: &[u8], delimiter: u8) -> Option<usize> {
+  let mut end = tail.len();
+  while let Some(separator) = tail[..end].iter().rposition(|b| *b == delimiter) {
+    if tail[separator + 1..].starts_with(FIX_CHECKSUM_FIELD.as_bytes()) { return Some(separator + 1); }
+    end = separator;
+  }
+  let after_delimiter = head.iter().rev().find(|segment| !segment.is_empty())
+    .is_some_and(|segment| segment.last() == Some(&delimiter));
+  if after_delimiter && tail.starts_with(FIX_CHECKSUM_FIELD.as_bytes()) { Some(0) } else { None }
+}
+
+/// Looks for the length of a data field in a message fed in pieces, as
+/// `message::declares_data_field` does in a whole message. Tags with a
+/// leading zero count too: the scanner may only err towards joining.
+struct DataFieldScanner {
+  // The tag read so far while at the start of a field
+  tag: Option<u32>,
+  declares: bool,
+}
+
+impl DataFieldScanner {
+  fn new() -> DataFieldScanner {
+    DataFieldScanner { tag: Some(0), declares: false }
+  }
+
+  fn update(&mut self, bytes: &[u8], delimiter: u8) {
+    for b in bytes {
+      self.tag = match self.tag {
+        _ if *b == delimiter => Some(0),
+        Some(tag) if b.is_ascii_digit() => Some(tag.saturating_mul(10).saturating_add((b - b'0') as u32)),
+        Some(tag) => {
+          if *b == b'=' && dictionary::data_field(tag).is_some() { self.declares = true; }
+          None
+        },
+        None => None,
+      };
+    }
+  }
+}
+
+/// Length of a checksum field written in full, `10=` three digits and the
+/// delimiter.
+const CHECKSUM_FIELD_LENGTH: usize = 7;
+
+/// This function validates checksum of FIX message already framed by the
+/// caller, skipping the checks that framing has made.
+///
+/// The checksum field is taken to be the last seven bytes of the message,
+/// as strict mode requires, and its digits are read without any UTF-8 or
+/// digit check. BodyLength, `max_message_size` and `strict` are not
+/// checked. Use it on the hot path of a session whose messages already went
+/// through `framing` or `stream::MessageReader`; any other message should
+/// go through `validate_bytes_with`, as the result for a message not ending
+/// with a full checksum field is meaningless.
+///
+/// # Safety
+///
+/// `inbound_message` must be at least seven bytes long: the checksum field
+/// is read without bounds checks.
+///
+/// # Examples
+///
+/// ```
+/// use fix_checksum::{validate_bytes_unchecked, ValidationOptions};
+///
+/// let options = ValidationOptions::new().delimiter('|');
+/// assert!(unsafe { validate_bytes_unchecked(b"8=FIX.4.2|9=5|35=0|10=161|", &options) });
+/// assert!(!unsafe { validate_bytes_unchecked(b"8=FIX.4.2|9=5|35=0|10=162|", &options) });
+/// ```
+pub unsafe fn validate_bytes_unchecked(inbound_message: &[u8], options: &ValidationOptions) -> bool {
+  let split_index = inbound_message.len() - CHECKSUM_FIELD_LENGTH;
+  let digits = inbound_message.get_unchecked(split_index + 3..split_index + 6);
+  let checksum_as_is = digits.iter().fold(0, |value, digit| value * 10 + digit.wrapping_sub(b'0') as u32);
+  checksum_as_is == checksum_bytes(inbound_message.get_unchecked(..split_index), options.delimiter)
+}
+
+/// What `validate_or_repair` corrected in a message.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Repairs {
+  /// BodyLength was rewritten, only checked with `check_body_length`.
+  pub body_length: bool,
+  /// CheckSum was rewritten.
+  pub checksum: bool,
+}
+
+impl Repairs {
+  /// Returns `true` if the message was valid as is.
+  pub fn is_empty(&self) -> bool {
+    !self.body_length && !self.checksum
+  }
+}
+
+/// This function returns a valid FIX message: the input itself when its
+/// checksum is correct, a copy with a corrected checksum otherwise.
+///
+/// # Examples
+///
+/// ```
+/// use std::borrow::Cow;
+///
+/// let message = "8=FIX.4.2\x019=5\x0135=0\x0110=163\x01";
+/// let (repaired, repairs) = fix_checksum::validate_or_repair(message).unwrap();
+/// assert_eq!(repaired, "8=FIX.4.2\x019=5\x0135=0\x0110=161\x01");
+/// assert!(repairs.checksum);
+///
+/// let (valid, repairs) = fix_checksum::validate_or_repair(&repaired).unwrap();
+/// assert!(matches!(valid, Cow::Borrowed(_)) && repairs.is_empty());
+/// ```
+pub fn validate_or_repair(inbound_message: &str)
+  -> Result<(Cow<'_, str>, Repairs), FIXChecksumValidatorError> {
+  validate_or_repair_with(inbound_message, &ValidationOptions::new())
+}
+
+/// This function returns a valid FIX message using the given options. With
+/// `check_body_length` a wrong BodyLength is corrected as well. Failures
+/// that cannot be repaired are returned as errors.
+///
+/// # Examples
+///
+/// ```
+/// use fix_checksum::{validate_or_repair_with, ValidationOptions};
+///
+/// let options = ValidationOptions::new().delimiter('|').check_body_length(true);
+/// let (repaired, repairs) = validate_or_repair_with("8=FIX.4.2|9=7|35=0|10=163|", &options).unwrap();
+/// assert_eq!(repaired, "8=FIX.4.2|9=5|35=0|10=161|");
+/// assert!(repairs.body_length && repairs.checksum);
+/// ```
+pub fn validate_or_repair_with<'a>(inbound_message: &'a str, options: &ValidationOptions)
+  -> Result<(Cow<'a, str>, Repairs), FIXChecksumValidatorError> {
+  let message = inbound_message.as_bytes();
+  let (repaired, body_length) = match validate_bytes_with(message, options) {
+    Ok(true) => return Ok((Cow::Borrowed(inbound_message), Repairs::default())),
+    Ok(false) => (stream::repair(message, options).ok_or(ChecksumFieldNotFound)?, false),
+    Err(BodyLengthMismatch) => {
+      let repaired = finalize(message, options)?;
+      if !validate_bytes_with(&repaired, options)? { return Err(BodyLengthMismatch); }
+      (repaired, true)
+    },
+    Err(err) => return Err(err),
+  };
+
+  let trailer = |message: &[u8]| checksum_field_start(message, options.delimiter)
+    .map(|start| message[start..].to_vec());
+  let repairs = Repairs { body_length, checksum: trailer(message) != trailer(&repaired) };
+  Ok((Cow::Owned(String::from_utf8_lossy(&repaired).into_owned()), repairs))
+}
+
+/// Returns the offset of the checksum field. The trailer ends the message,
+/// so the search goes back from the end, past `10=` in earlier values, and
+/// finds it after a few bytes whatever the size of the message. Only the
+/// values of data fields, such as Signature (89) after SignatureLength (93),
+/// are found by walking the fields forward, as they may hold the delimiter
+/// and even a whole `10=` field: messages declaring one are walked forward.
+fn checksum_field_start(message: &[u8], delimiter: u8) -> Option<usize> {
+  let mut end = message.len();
+  while let Some(separator) = message[..end].iter().rposition(|b| *b == delimiter) {
+    if message[separator + 1..].starts_with(FIX_CHECKSUM_FIELD.as_bytes()) {
+      if message::declares_data_field(&message[..separator], delimiter) {
+        return checksum_field_start_forward(message, delimiter);
+      }
+      return Some(separator + 1);
+    }
+    end = separator;
+  }
+  None
+}
+
+/// Returns the offset of the first checksum field after the start of the
+/// message, skipping the values of data fields by their declared length.
+fn checksum_field_start_forward(message: &[u8], delimiter: u8) -> Option<usize> {
+  let (mut start, mut data) = (0, None);
+  while start < message.len() {
+    if start > 0 && message[start..].starts_with(FIX_CHECKSUM_FIELD.as_bytes()) { return Some(start); }
+
+    let end = match message::field_tag(&message[start..], delimiter) {
+      Some((tag, value_offset)) => {
+        let value_start = start + value_offset;
+        let end = message::value_end(message, value_start, delimiter, data.take(), tag)?;
+        data = message::data_length(tag, &message[value_start..end]);
+        end
+      },
+      None => {
+        data = None;
+        start + message[start..].iter().position(|b| *b == delimiter)?
+      },
+    };
+    start = end + 1;
+  }
+  None
+}
+
+fn check_body_length(message: &[u8], delimiter: u8) -> Result<(), FIXChecksumValidatorError> {
+  let (declared, body_start) = declared_body_length(message, delimiter)?;
+  if declared != message.len() - body_start { return Err(BodyLengthMismatch); }
+  Ok(())
+}
+
+/// Returns the declared BodyLength and the offset of the first body byte.
+fn declared_body_length(message: &[u8], delimiter: u8)
+  -> Result<(usize, usize), FIXChecksumValidatorError> {
+  let begin_string_end = message.iter().position(|b| *b == delimiter).ok_or(BodyLengthFieldNotFound)?;
+  let body_length_start = begin_string_end + 1;
+  if !message[body_length_start..].starts_with(FIX_BODY_LENGTH_FIELD.as_bytes()) {
+    return Err(BodyLengthFieldNotFound);
+  }
+
+  let value_start = body_length_start + FIX_BODY_LENGTH_FIELD.len();
+  let value_length = message[value_start..].iter().position(|b| *b == delimiter)
+    .ok_or(BodyLengthFieldNotFound)?;
+  let body_start = value_start + value_length + 1;
+  let declared: usize = String::from_utf8_lossy(&message[value_start..body_start - 1])
+    .parse().map_err(BodyLengthInvalidFormat)?;
+  Ok((declared, body_start))
+}
+
 /// This function generates checksum of FIX message
 ///
 /// # Examples
@@ -156,7 +765,262 @@ pub fn validate(inbound_message: &str) -> Result<bool, FIXChecksumValidatorError
 /// assert_eq!("236", fix_checksum::generate(&message));
 /// ```
 pub fn generate(outbound_message: &str) -> String {
-  checksum(outbound_message).to_string()
+  let digits = checksum_digits(checksum(outbound_message));
+  let significant = digits.iter().take(2).take_while(|digit| **digit == b'0').count();
+  digits[significant..].iter().map(|digit| *digit as char).collect()
+}
+
+/// This function writes checksum of FIX message to `out` as the three
+/// zero-padded ASCII digits of a CheckSum (10) value, without allocating.
+///
+/// # Examples
+///
+/// ```
+/// let mut checksum = [0; 3];
+/// fix_checksum::generate_into(b"8=FIX.4.2\x019=5\x0135=0\x01", &mut checksum);
+/// assert_eq!(&checksum, b"161");
+/// fix_checksum::generate_into(b"8=FIX.4.2\x0135=0\x0158=10\x01", &mut checksum);
+/// assert_eq!(&checksum, b"001");
+/// ```
+pub fn generate_into(outbound_message: &[u8], out: &mut [u8; 3]) {
+  *out = *checksum_digits(checksum_bytes(outbound_message, b'\x01'));
+}
+
+/// This function computes checksum of SOH delimited FIX message scattered
+/// over several buffers, such as the segments passed to `writev`, without
+/// joining them. As with `generate`, the buffers hold the message up to the
+/// checksum field.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::IoSlice;
+///
+/// let segments = [IoSlice::new(b"8=FIX.4.2\x019=5\x01"), IoSlice::new(b"35=0\x01")];
+/// assert_eq!(fix_checksum::checksum_vectored(&segments), 161);
+/// ```
+pub fn checksum_vectored(segments: &[IoSlice]) -> u8 {
+  segments.iter().fold(0, |sum, segment| combine(sum, partial_checksum(segment)))
+}
+
+/// This function computes the partial checksum of a fragment of SOH
+/// delimited FIX message, the sum of its bytes modulo 256. The partial
+/// checksums of consecutive fragments `combine` into that of the fragments
+/// joined, so that a header summed once can be cached and the checksum of
+/// each message starting with it computed from the fresh body alone.
+///
+/// # Examples
+///
+/// ```
+/// use fix_checksum::{combine, partial_checksum};
+///
+/// let header = partial_checksum(b"8=FIX.4.2\x019=5\x01");
+/// let checksum = combine(header, partial_checksum(b"35=0\x01"));
+/// assert_eq!(checksum, partial_checksum(b"8=FIX.4.2\x019=5\x0135=0\x01"));
+/// assert_eq!(format!("10={:03}", checksum), "10=161");
+/// ```
+pub fn partial_checksum(fragment: &[u8]) -> u8 {
+  fragment.iter().fold(0, |sum, b| sum.wrapping_add(*b))
+}
+
+/// This function combines the partial checksums of two fragments into the
+/// partial checksum of the fragments joined, see `partial_checksum`. The
+/// order of the fragments does not matter and the empty fragment sums to 0.
+///
+/// # Examples
+///
+/// ```
+/// use fix_checksum::{combine, partial_checksum};
+///
+/// let fragments: [&[u8]; 3] = [b"8=FIX.4.2\x019=5\x01", b"35=0\x01", b""];
+/// let checksum = fragments.iter().map(|fragment| partial_checksum(fragment)).fold(0, combine);
+/// assert_eq!(checksum, 161);
+/// ```
+pub fn combine(partial_a: u8, partial_b: u8) -> u8 {
+  partial_a.wrapping_add(partial_b)
+}
+
+/// This function returns the checksum of a message once the bytes of
+/// `removed_field`, its delimiter included, are taken out of it, from the
+/// checksum it had before and without reading the rest of the message.
+///
+/// A removal also shortens the body: the digits of the BodyLength (9) value
+/// change the checksum the same way, removed ones taken out and new ones
+/// combined in.
+///
+/// # Examples
+///
+/// ```
+/// use fix_checksum::{combine, checksum_without_field, partial_checksum};
+///
+/// let message: &[u8] = b"8=FIX.4.2\x019=11\x0135=0\x01122=X\x01";
+/// let stripped = checksum_without_field(partial_checksum(message), b"122=X\x01");
+/// let stripped = combine(checksum_without_field(stripped, b"11"), partial_checksum(b"5"));
+/// assert_eq!(stripped, partial_checksum(b"8=FIX.4.2\x019=5\x0135=0\x01"));
+/// ```
+pub fn checksum_without_field(checksum: u8, removed_field: &[u8]) -> u8 {
+  checksum.wrapping_sub(partial_checksum(removed_field))
+}
+
+/// This function sets BodyLength and CheckSum of FIX message. Existing
+/// BodyLength and CheckSum fields are replaced, missing ones are added.
+///
+/// # Examples
+///
+/// ```
+/// use fix_checksum::{finalize, ValidationOptions};
+///
+/// let options = ValidationOptions::new().delimiter('|');
+/// assert_eq!(finalize(b"8=FIX.4.2|35=0|", &options).unwrap(), b"8=FIX.4.2|9=5|35=0|10=161|".to_vec());
+/// assert_eq!(finalize(b"8=FIX.4.2|9=1|35=0|10=000|", &options).unwrap(),
+///   b"8=FIX.4.2|9=5|35=0|10=161|".to_vec());
+/// ```
+pub fn finalize(outbound_message: &[u8], options: &ValidationOptions)
+  -> Result<Vec<u8>, FIXChecksumValidatorError> {
+  if outbound_message.is_empty() { return Err(InvalidEmptyMessage); }
+  let delimiter = options.delimiter;
+  if !outbound_message.starts_with(b"8=") { return Err(BeginStringFieldNotFound); }
+
+  let begin_string_end = outbound_message.iter().position(|b| *b == delimiter)
+    .ok_or(BeginStringFieldNotFound)? + 1;
+  let mut body = &outbound_message[begin_string_end..];
+  if body.starts_with(FIX_BODY_LENGTH_FIELD.as_bytes()) {
+    body = match body.iter().position(|b| *b == delimiter) {
+      Some(end) => &body[end + 1..],
+      None => &body[body.len()..],
+    };
+  }
+
+  if body.starts_with(FIX_CHECKSUM_FIELD.as_bytes()) {
+    body = &body[..0];
+  } else if let Some(split_index) = checksum_field_start(body, delimiter) {
+    body = &body[..split_index];
+  }
+
+  let mut message = outbound_message[..begin_string_end].to_vec();
+  message.extend_from_slice(FIX_BODY_LENGTH_FIELD.as_bytes());
+  let missing_delimiter = !body.is_empty() && body[body.len() - 1] != delimiter;
+  let body_length = body.len() + missing_delimiter as usize;
+  message.extend_from_slice(body_length.to_string().as_bytes());
+  message.push(delimiter);
+  message.extend_from_slice(body);
+  if missing_delimiter { message.push(delimiter); }
+
+  let checksum = checksum_bytes(&message, delimiter);
+  message.extend_from_slice(FIX_CHECKSUM_FIELD.as_bytes());
+  message.extend_from_slice(checksum_digits(checksum));
+  message.push(delimiter);
+  Ok(message)
+}
+
+/// Width of the BodyLength value reserved by `begin_message_into`, enough
+/// for messages of up to a million bytes.
+const RESERVED_BODY_LENGTH_WIDTH: usize = 6;
+
+/// This function starts a message in `buffer` with its BeginString and a
+/// BodyLength placeholder of fixed width, for the fields to be appended in
+/// place and the message completed by `finalize_into`.
+pub fn begin_message_into(buffer: &mut Vec<u8>, begin_string: &str, options: &ValidationOptions) {
+  buffer.extend_from_slice(b"8=");
+  buffer.extend_from_slice(begin_string.as_bytes());
+  buffer.push(options.delimiter);
+  buffer.extend_from_slice(FIX_BODY_LENGTH_FIELD.as_bytes());
+  buffer.extend_from_slice(&[b'0'; RESERVED_BODY_LENGTH_WIDTH]);
+  buffer.push(options.delimiter);
+}
+
+/// This function completes the message in `buffer` in place: it writes the
+/// BodyLength over the value already there, zero-padded to its width, and
+/// appends the CheckSum. A trailing CheckSum is replaced. BodyLength only
+/// moves the body when its value does not fit in the width reserved, so a
+/// message started by `begin_message_into` is built without copies.
+///
+/// # Examples
+///
+/// ```
+/// use fix_checksum::{begin_message_into, finalize_into, validate_bytes_with, ValidationOptions};
+///
+/// let options = ValidationOptions::new().delimiter('|').check_body_length(true);
+/// let mut buffer = Vec::with_capacity(256);
+/// begin_message_into(&mut buffer, "FIX.4.2", &options);
+/// buffer.extend_from_slice(b"35=0|");
+/// finalize_into(&mut buffer, &options).unwrap();
+/// assert_eq!(buffer, b"8=FIX.4.2|9=000005|35=0|10=145|".to_vec());
+/// assert!(validate_bytes_with(&buffer, &options).unwrap());
+/// ```
+pub fn finalize_into(buffer: &mut Vec<u8>, options: &ValidationOptions) -> Result<(), FIXChecksumValidatorError> {
+  if buffer.is_empty() { return Err(InvalidEmptyMessage); }
+  let delimiter = options.delimiter;
+  if !buffer.starts_with(b"8=") { return Err(BeginStringFieldNotFound); }
+  let begin_string_end = buffer.iter().position(|b| *b == delimiter).ok_or(BeginStringFieldNotFound)? + 1;
+  if !buffer[begin_string_end..].starts_with(FIX_BODY_LENGTH_FIELD.as_bytes()) { return Err(BodyLengthFieldNotFound); }
+
+  let value_start = begin_string_end + FIX_BODY_LENGTH_FIELD.len();
+  let mut width = buffer[value_start..].iter().position(|b| *b == delimiter).ok_or(BodyLengthFieldNotFound)?;
+  let body_start = value_start + width + 1;
+  if let Some(split_index) = checksum_field_start(&buffer[body_start..], delimiter) {
+    buffer.truncate(body_start + split_index);
+  }
+  if buffer.len() > body_start && buffer[buffer.len() - 1] != delimiter { buffer.push(delimiter); }
+
+  let body_length = buffer.len() - body_start;
+  let digits = body_length.to_string().len();
+  if digits > width {
+    buffer.splice(value_start..value_start, ::std::iter::repeat_n(b'0', digits - width));
+    width = digits;
+  }
+  let mut remaining = body_length;
+  for digit in buffer[value_start..value_start + width].iter_mut().rev() {
+    *digit = b'0' + (remaining % 10) as u8;
+    remaining /= 10;
+  }
+
+  let checksum = checksum_bytes(buffer, delimiter);
+  buffer.extend_from_slice(FIX_CHECKSUM_FIELD.as_bytes());
+  buffer.extend_from_slice(checksum_digits(checksum));
+  buffer.push(delimiter);
+  Ok(())
+}
+
+/// This function reorders the fields of a message into BeginString,
+/// MsgType, the other header fields, the body and the trailer, keeping the
+/// relative order within each part, and then finalizes it. User-defined
+/// tags stay with the body.
+///
+/// # Examples
+///
+/// ```
+/// use fix_checksum::{normalize, ValidationOptions};
+///
+/// let options = ValidationOptions::new().delimiter('|');
+/// assert_eq!(normalize(b"8=FIX.4.2|55=IBM|35=D|49=A|10=000|", &options).unwrap(),
+///   b"8=FIX.4.2|9=17|35=D|49=A|55=IBM|10=084|".to_vec());
+/// ```
+pub fn normalize(outbound_message: &[u8], options: &ValidationOptions)
+  -> Result<Vec<u8>, FIXChecksumValidatorError> {
+  if outbound_message.is_empty() { return Err(InvalidEmptyMessage); }
+  let delimiter = options.delimiter;
+  let view = message::MessageView::parse(outbound_message, delimiter as char)?;
+  if view.fields.first().map(|field| field.0) != Some(8) { return Err(BeginStringFieldNotFound); }
+
+  let rank = |tag: u32| match (tag, dictionary::classify(tag)) {
+    (8, _) => 0,
+    (35, _) => 1,
+    (_, dictionary::TagClass::Header) => 2,
+    (_, dictionary::TagClass::Trailer) => 4,
+    _ => 3,
+  };
+  let mut fields: Vec<_> = view.fields.iter().filter(|field| field.0 != 9 && field.0 != 10).collect();
+  fields.sort_by_key(|field| rank(field.0));
+
+  let mut message = Vec::with_capacity(outbound_message.len());
+  for &&(tag, value) in &fields {
+    message.extend_from_slice(tag.to_string().as_bytes());
+    message.push(b'=');
+    message.extend_from_slice(value);
+    message.push(delimiter);
+  }
+  finalize(&message, options)
 }
 
 #[test]
@@ -171,14 +1035,22 @@ fn it_should_calculate_fix_message_checksum() {
 
 #[cfg(test)]
 mod tests {
-  use super::{validate, generate};
+  use std::borrow::Cow;
+  use std::io::IoSlice;
+  use super::{validate, validate_with, validate_bytes_with, validate_untrusted, validate_bytes_unchecked, generate,
+    generate_into, checksum_vectored, partial_checksum, combine, checksum_without_field, validate_vectored, finalize,
+    finalize_into, begin_message_into, normalize, validate_or_repair, validate_or_repair_with, Repairs,
+    FIXChecksumValidatorError, ValidationOptions};
   use super::FIXChecksumValidatorError::{InvalidEmptyMessage, ChecksumFieldNotFound,
-    ChecksumFieldInvalidFormat};
+    ChecksumFieldInvalidFormat, BeginStringFieldNotFound, BodyLengthFieldNotFound, BodyLengthMismatch,
+    MessageTooLarge};
+  use super::message::MessageView;
+  use super::stream::StreamValidator;
 
   fn brew_message(message_parts: Vec<&str>, delimiter: &str) -> String {
-    return message_parts
+    message_parts
       .iter()
-      .fold(String::new(), |message, message_part| message.to_string() + message_part + delimiter);
+      .fold(String::new(), |message, message_part| message.to_string() + message_part + delimiter)
   }
 
   #[test]
@@ -200,12 +1072,113 @@ mod tests {
     message_parts = vec!["8=FIX.4.2", "9=73", "35=0", "49=BRKR", "56=INVMGR",
       "34=235", "52=19980604-07:58:28", "112=19980604-07:58:28", "10=231"];
     message = brew_message(message_parts, "\x01");
-    assert_eq!(validate(&message).unwrap(), false);
+    assert!(!validate(&message).unwrap());
 
     message_parts = vec!["8=FIX.4.2", "9=73", "35=0", "49=BRKR", "56=INVMGR",
       "34=235", "52=19980604-07:58:28", "112=19980604-07:58:28", "10=236"];
     message = brew_message(message_parts, "\x01");
-    assert_eq!(validate(&message).unwrap(), true);
+    assert!(validate(&message).unwrap());
+  }
+
+  #[test]
+  fn it_should_map_errors_to_stable_codes() {
+    for code in 1..15 {
+      let err = FIXChecksumValidatorError::from_code(code).unwrap();
+      assert_eq!(err.error_code(), code);
+    }
+    assert_eq!(FIXChecksumValidatorError::from_code(15), None);
+    assert_eq!(FIXChecksumValidatorError::from_code(3),
+      Some(ChecksumFieldInvalidFormat("2ZZ".parse::<u32>().unwrap_err())));
+  }
+
+  #[test]
+  fn it_should_validate_or_repair_fix_message() {
+    let valid = brew_message(vec!["8=FIX.4.2", "9=73", "35=0", "49=BRKR", "56=INVMGR", "34=235",
+      "52=19980604-07:58:28", "112=19980604-07:58:28", "10=236"], "\x01");
+    assert!(matches!(validate_or_repair(&valid).unwrap(), (Cow::Borrowed(_), repairs) if repairs.is_empty()));
+
+    let corrupted = valid.replace("10=236", "10=999");
+    let (repaired, repairs) = validate_or_repair(&corrupted).unwrap();
+    assert_eq!((repaired.as_ref(), repairs), (valid.as_str(), Repairs { body_length: false, checksum: true }));
+
+    let options = ValidationOptions::new().check_body_length(true);
+    let corrupted = valid.replace("9=73", "9=70");
+    let (repaired, repairs) = validate_or_repair_with(&corrupted, &options).unwrap();
+    assert_eq!((repaired.as_ref(), repairs), (valid.as_str(), Repairs { body_length: true, checksum: false }));
+
+    assert_eq!(validate_or_repair("8=FIX.4.2\x0135=0\x01").unwrap_err(), ChecksumFieldNotFound);
+    assert_eq!(validate_or_repair("").unwrap_err(), InvalidEmptyMessage);
+  }
+
+  #[test]
+  fn it_should_finalize_fix_message() {
+    let options = ValidationOptions::new();
+    let message = brew_message(vec!["8=FIX.4.2", "35=0", "49=BRKR", "56=INVMGR", "34=235",
+      "52=19980604-07:58:28", "112=19980604-07:58:28"], "\x01");
+    let finalized = brew_message(vec!["8=FIX.4.2", "9=73", "35=0", "49=BRKR", "56=INVMGR", "34=235",
+      "52=19980604-07:58:28", "112=19980604-07:58:28", "10=236"], "\x01");
+    assert_eq!(finalize(message.as_bytes(), &options).unwrap(), finalized.as_bytes());
+    assert_eq!(finalize(message.trim_end_matches('\x01').as_bytes(), &options).unwrap(), finalized.as_bytes());
+    assert_eq!(finalize(finalized.as_bytes(), &options).unwrap(), finalized.as_bytes());
+
+    assert_eq!(finalize(b"8=FIX.4.2\x01", &options).unwrap(), b"8=FIX.4.2\x019=0\x0110=198\x01".to_vec());
+    assert_eq!(finalize(b"", &options).unwrap_err(), InvalidEmptyMessage);
+    assert_eq!(finalize(b"35=0\x01", &options).unwrap_err(), BeginStringFieldNotFound);
+  }
+
+  #[test]
+  fn it_should_locate_checksum_from_the_end() {
+    let options = ValidationOptions::new().delimiter('|');
+    assert!(validate_with("8=FIX.4.2|58=10=1|10=000|35=0|10=158|", &options).unwrap());
+    assert_eq!(validate_with("8=FIX.4.2|58=10=1|", &options).unwrap_err(), ChecksumFieldNotFound);
+    assert_eq!(validate_with("10=000|", &options).unwrap_err(), ChecksumFieldNotFound);
+  }
+
+  #[test]
+  fn it_should_skip_signature_when_locating_checksum() {
+    let options = ValidationOptions::new().delimiter('|').check_body_length(true).strict(true);
+    let message = "8=FIX.4.2|9=22|35=0|93=8|89=ab|10=12|10=161|";
+    assert!(validate_with(message, &options).unwrap());
+    assert_eq!(finalize(message.as_bytes(), &options).unwrap(), message.as_bytes());
+
+    let unsigned = "8=FIX.4.2|9=22|35=0|93=9|89=ab|10=12|10=161|";
+    assert_eq!(validate_with(unsigned, &options).unwrap_err(), BodyLengthMismatch);
+  }
+
+  #[test]
+  fn it_should_normalize_field_order() {
+    let options = ValidationOptions::new();
+    let message = brew_message(vec!["8=FIX.4.2", "35=0", "112=19980604-07:58:28", "49=BRKR", "10=000",
+      "56=INVMGR", "9=1", "34=235", "52=19980604-07:58:28"], "\x01");
+    let normalized = brew_message(vec!["8=FIX.4.2", "9=73", "35=0", "49=BRKR", "56=INVMGR", "34=235",
+      "52=19980604-07:58:28", "112=19980604-07:58:28", "10=236"], "\x01");
+    assert_eq!(normalize(message.as_bytes(), &options).unwrap(), normalized.as_bytes());
+    assert_eq!(normalize(normalized.as_bytes(), &options).unwrap(), normalized.as_bytes());
+    assert_eq!(normalize(b"35=0\x018=FIX.4.2\x01", &options).unwrap_err(), BeginStringFieldNotFound);
+  }
+
+  #[test]
+  fn it_should_finalize_fix_message_in_place() {
+    let options = ValidationOptions::new().delimiter('|').check_body_length(true).strict(true);
+    let mut buffer = Vec::new();
+    begin_message_into(&mut buffer, "FIX.4.4", &options);
+    let body_start = buffer.len();
+    buffer.extend_from_slice(b"35=1|112=TEST");
+    finalize_into(&mut buffer, &options).unwrap();
+    assert_eq!(&buffer[..body_start], b"8=FIX.4.4|9=000014|");
+    assert!(validate_bytes_with(&buffer, &options).unwrap());
+
+    let finalized = buffer.clone();
+    finalize_into(&mut buffer, &options).unwrap();
+    assert_eq!(buffer, finalized);
+
+    let mut buffer = b"8=FIX.4.2|9=0|35=0|49=BRKR|10=000|".to_vec();
+    finalize_into(&mut buffer, &options).unwrap();
+    assert_eq!(buffer, b"8=FIX.4.2|9=13|35=0|49=BRKR|10=172|".to_vec());
+
+    assert_eq!(finalize_into(&mut Vec::new(), &options), Err(InvalidEmptyMessage));
+    assert_eq!(finalize_into(&mut b"35=0|".to_vec(), &options), Err(BeginStringFieldNotFound));
+    assert_eq!(finalize_into(&mut b"8=FIX.4.2|35=0|".to_vec(), &options), Err(BodyLengthFieldNotFound));
   }
 
   #[test]
@@ -214,5 +1187,90 @@ mod tests {
       "56=INVMGR", "34=235", "52=19980604-07:58:28", "112=19980604-07:58:28"];
     let message: String = brew_message(message_parts, "\x01");
     assert_eq!("236", generate(&message));
+
+    let mut checksum = [0; 3];
+    generate_into(message.as_bytes(), &mut checksum);
+    assert_eq!(&checksum, b"236");
+    generate_into(b"", &mut checksum);
+    assert_eq!(&checksum, b"000");
+    assert_eq!(generate(""), "0");
+    assert_eq!(generate("8=FIX.4.2\x0135=0\x0158=10\x01"), "1");
+    assert_eq!(generate("8=FIX.4.2\x0135=0\x0158=1\x01"), "209");
+  }
+
+  #[test]
+  fn it_should_validate_framed_messages_unchecked() {
+    let options = ValidationOptions::new().delimiter('|');
+    let messages: [&[u8]; 4] = [b"8=FIX.4.2|9=5|35=0|10=161|", b"8=FIX.4.2|9=5|35=0|10=016|",
+      b"8=FIX.4.2|9=17|35=0|93=3|89=a|b|10=159|", b"8=FIX.4.2|9=5|35=0|10=1X1|"];
+    for message in &messages {
+      let checked = validate_bytes_with(message, &options).unwrap_or(false);
+      assert_eq!(unsafe { validate_bytes_unchecked(message, &options) }, checked);
+    }
+    assert!(validate_bytes_with(messages[2], &options).unwrap());
+    let log = b"8=FIX.4.2\x019=5\x0135=0\x0110=161\x01\n";
+    let framed: Vec<_> = StreamValidator::new(&log[..], ValidationOptions::new())
+      .map(|result| result.unwrap().message).collect();
+    assert!(unsafe { validate_bytes_unchecked(&framed[0], &ValidationOptions::new()) });
+  }
+
+  #[test]
+  fn it_should_validate_scattered_messages_as_joined() {
+    let messages: [&[u8]; 5] = [b"8=FIX.4.2\x019=5\x0135=0\x0110=161\x01", b"8=FIX.4.2\x019=5\x0135=0\x0110=162\x01",
+      b"8=FIX.4.2\x0135=0\x0158=10=\x0110=001\x0110=x", b"8=FIX.4.2\x0135=0\x0193=5\x0189=\x0110=1\x0110=000\x01",
+      b"8=FIX.4.2\x0135=0\x0110=16"];
+    let options = [ValidationOptions::new(), ValidationOptions::new().strict(true),
+      ValidationOptions::new().check_body_length(true), ValidationOptions::new().max_message_size(20)];
+    for message in &messages {
+      for options in &options {
+        let expected = validate_bytes_with(message, options);
+        for split in 0..=message.len() {
+          let (first, second) = message.split_at(split);
+          let segments = [IoSlice::new(first), IoSlice::new(second), IoSlice::new(b"")];
+          assert_eq!(validate_vectored(&segments, options), expected, "{:?} split at {}", message, split);
+        }
+      }
+      let soh = message.iter().position(|b| *b == b'\x01').unwrap();
+      assert_eq!(checksum_vectored(&[IoSlice::new(&message[..soh]), IoSlice::new(&message[soh..])]) as u32,
+        super::checksum_bytes(message, b'\x01'));
+      for split in 0..=message.len() {
+        let (head, tail) = message.split_at(split);
+        assert_eq!(combine(partial_checksum(head), partial_checksum(tail)), partial_checksum(message));
+        assert_eq!(combine(partial_checksum(tail), partial_checksum(head)), partial_checksum(message));
+        assert_eq!(checksum_without_field(partial_checksum(message), tail), partial_checksum(head));
+      }
+    }
+    assert_eq!(validate_vectored(&[], &ValidationOptions::new()), Err(InvalidEmptyMessage));
+    let piped = ValidationOptions::new().delimiter('|');
+    assert_eq!(validate_vectored(&[IoSlice::new(b"8=FIX.4.2|9=5|3"), IoSlice::new(b"5=0|10=161|")], &piped), Ok(true));
+  }
+
+  #[test]
+  fn it_should_not_panic_on_hostile_input() {
+    let options = ValidationOptions::new().delimiter('|');
+    assert!(matches!(validate_untrusted(b"8=FIX.4.2|10=1", &options), Err(ChecksumFieldInvalidFormat(_))));
+    assert!(validate_untrusted(b"8=FIX.4.2|93=18446744073709551615|89=a|10=000|", &options).is_ok());
+    assert_eq!(validate_untrusted(&vec![b'8'; (1 << 20) + 1], &options), Err(MessageTooLarge));
+    assert!(validate_untrusted(&vec![b'8'; (1 << 20) + 1], &options.clone().max_message_size(usize::MAX)).is_err());
+
+    // A NUL delimiter within a data field takes a negative correction
+    let nul = ValidationOptions::new().delimiter('\0');
+    let signed = finalize(b"8=FIX.4.2\x0035=0\x0093=3\x0089=a\x00b\x00", &nul).unwrap();
+    assert_eq!(validate_bytes_with(&signed, &nul), Ok(true));
+
+    let atoms: [&[u8]; 12] = [b"8=", b"9=", b"10=", b"|", b"=", b"93=", b"89=", b"0", b"18446744073709551616",
+      b"FIX.4.2", b"\xff", b"\n"];
+    for first in &atoms {
+      for second in &atoms {
+        for third in &atoms {
+          let message = [*first, *second, *third, b"|"].concat();
+          let _ = validate_untrusted(&message, &options);
+          let _ = finalize(&message, &options);
+          let _ = normalize(&message, &options);
+          let _ = MessageView::parse(&message, '|');
+          let _ = StreamValidator::new(&message[..], options.clone()).count();
+        }
+      }
+    }
   }
 }
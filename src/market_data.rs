@@ -0,0 +1,302 @@
+//! Market data requests and the snapshots sent in response.
+
+use super::FIXChecksumValidatorError;
+use super::FIXChecksumValidatorError::{RequiredFieldMissing, FieldValueInvalid};
+use super::message::{GroupLayout, MessageView, optional_str, optional_decimal};
+use super::orders::{FieldWriter, Header};
+
+/// SubscriptionRequestType (263) of a market data request.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SubscriptionRequestType {
+  Snapshot,
+  SnapshotAndUpdates,
+  Unsubscribe,
+}
+
+impl SubscriptionRequestType {
+  /// Returns the wire value of the subscription request type.
+  pub fn value(&self) -> &'static str {
+    match *self {
+      SubscriptionRequestType::Snapshot => "0",
+      SubscriptionRequestType::SnapshotAndUpdates => "1",
+      SubscriptionRequestType::Unsubscribe => "2",
+    }
+  }
+}
+
+/// MDEntryType (269) of a market data entry.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MDEntryType {
+  Bid,
+  Offer,
+  Trade,
+  IndexValue,
+  OpeningPrice,
+  ClosingPrice,
+  SettlementPrice,
+  TradingSessionHighPrice,
+  TradingSessionLowPrice,
+  TradingSessionVWAPPrice,
+}
+
+impl MDEntryType {
+  /// Returns the wire value of the entry type.
+  pub fn value(&self) -> &'static str {
+    match *self {
+      MDEntryType::Bid => "0",
+      MDEntryType::Offer => "1",
+      MDEntryType::Trade => "2",
+      MDEntryType::IndexValue => "3",
+      MDEntryType::OpeningPrice => "4",
+      MDEntryType::ClosingPrice => "5",
+      MDEntryType::SettlementPrice => "6",
+      MDEntryType::TradingSessionHighPrice => "7",
+      MDEntryType::TradingSessionLowPrice => "8",
+      MDEntryType::TradingSessionVWAPPrice => "9",
+    }
+  }
+
+  /// Decodes the wire value of an entry type.
+  pub fn from_value(value: &[u8]) -> Option<MDEntryType> {
+    Some(match value {
+      b"0" => MDEntryType::Bid,
+      b"1" => MDEntryType::Offer,
+      b"2" => MDEntryType::Trade,
+      b"3" => MDEntryType::IndexValue,
+      b"4" => MDEntryType::OpeningPrice,
+      b"5" => MDEntryType::ClosingPrice,
+      b"6" => MDEntryType::SettlementPrice,
+      b"7" => MDEntryType::TradingSessionHighPrice,
+      b"8" => MDEntryType::TradingSessionLowPrice,
+      b"9" => MDEntryType::TradingSessionVWAPPrice,
+      _ => return None,
+    })
+  }
+}
+
+/// MDUpdateAction (279) of an incremental refresh entry.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MDUpdateAction {
+  New,
+  Change,
+  Delete,
+}
+
+impl MDUpdateAction {
+  /// Decodes the wire value of an update action.
+  pub fn from_value(value: &[u8]) -> Option<MDUpdateAction> {
+    Some(match value {
+      b"0" => MDUpdateAction::New,
+      b"1" => MDUpdateAction::Change,
+      b"2" => MDUpdateAction::Delete,
+      _ => return None,
+    })
+  }
+}
+
+/// Builder of MarketDataRequest (35=V) messages.
+///
+/// # Examples
+///
+/// ```
+/// use fix_checksum::market_data::{MarketDataRequest, MDEntryType, SubscriptionRequestType};
+/// use fix_checksum::orders::Header;
+///
+/// let header = Header::new("FIX.4.2", "CLIENT", "FEED", 1, "20240102-09:30:00");
+/// let message = MarketDataRequest::new("MD-1", SubscriptionRequestType::SnapshotAndUpdates)
+///   .entry_type(MDEntryType::Bid)
+///   .entry_type(MDEntryType::Offer)
+///   .symbol("IBM")
+///   .build(&header)
+///   .unwrap();
+/// let message = String::from_utf8(message).unwrap();
+/// assert!(message.contains("262=MD-1\x01263=1\x01264=0\x01267=2\x01269=0\x01269=1\x01146=1\x0155=IBM\x01"));
+/// assert!(fix_checksum::validate(&message).unwrap());
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct MarketDataRequest {
+  md_req_id: String,
+  subscription_request_type: SubscriptionRequestType,
+  market_depth: u32,
+  entry_types: Vec<MDEntryType>,
+  symbols: Vec<String>,
+}
+
+impl MarketDataRequest {
+  /// Creates a builder of a full book request without entry types or symbols.
+  pub fn new(md_req_id: &str, subscription_request_type: SubscriptionRequestType) -> MarketDataRequest {
+    MarketDataRequest {
+      md_req_id: md_req_id.to_string(),
+      subscription_request_type,
+      market_depth: 0,
+      entry_types: Vec::new(),
+      symbols: Vec::new(),
+    }
+  }
+
+  /// Sets MarketDepth (264), 0 requests the full book and 1 the top of book.
+  pub fn market_depth(mut self, market_depth: u32) -> MarketDataRequest {
+    self.market_depth = market_depth;
+    self
+  }
+
+  /// Adds an entry of the NoMDEntryTypes (267) group.
+  pub fn entry_type(mut self, entry_type: MDEntryType) -> MarketDataRequest {
+    self.entry_types.push(entry_type);
+    self
+  }
+
+  /// Adds an entry of the NoRelatedSym (146) group.
+  pub fn symbol(mut self, symbol: &str) -> MarketDataRequest {
+    self.symbols.push(symbol.to_string());
+    self
+  }
+
+  /// Checks the fields and returns the finalized message. Both groups need
+  /// at least one entry.
+  pub fn build(&self, header: &Header) -> Result<Vec<u8>, FIXChecksumValidatorError> {
+    if self.entry_types.is_empty() { return Err(RequiredFieldMissing(267)); }
+    if self.symbols.is_empty() { return Err(RequiredFieldMissing(146)); }
+
+    let mut message = FieldWriter::new(header, "V")?;
+    message.text(262, &self.md_req_id)?;
+    message.field(263, self.subscription_request_type.value());
+    message.field(264, self.market_depth);
+    message.field(267, self.entry_types.len());
+    for entry_type in &self.entry_types { message.field(269, entry_type.value()); }
+    message.field(146, self.symbols.len());
+    for symbol in &self.symbols { message.text(55, symbol)?; }
+    message.finish()
+  }
+}
+
+/// An entry of the MDEntries (268) group.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MDEntry<'a> {
+  /// Only set by incremental refreshes.
+  pub update_action: Option<MDUpdateAction>,
+  /// Deletions of an incremental refresh may omit the entry type.
+  pub entry_type: Option<MDEntryType>,
+  pub entry_id: Option<&'a str>,
+  pub symbol: Option<&'a str>,
+  pub price: Option<f64>,
+  pub size: Option<f64>,
+}
+
+/// Decoded MarketDataSnapshotFullRefresh (35=W) or
+/// MarketDataIncrementalRefresh (35=X) message.
+///
+/// # Examples
+///
+/// ```
+/// use fix_checksum::market_data::{MarketDataSnapshot, MDEntryType};
+///
+/// let message = b"8=FIX.4.2|9=76|35=W|262=MD-1|55=IBM|268=2|269=0|270=125.25|271=300|\
+///   269=1|270=125.5|271=200|10=033|";
+/// let snapshot = MarketDataSnapshot::parse(message, '|').unwrap();
+/// assert_eq!(snapshot.symbol, Some("IBM"));
+/// assert_eq!(snapshot.entries.len(), 2);
+/// assert_eq!(snapshot.entries[1].entry_type, Some(MDEntryType::Offer));
+/// assert_eq!((snapshot.entries[1].price, snapshot.entries[1].size), (Some(125.5), Some(200.0)));
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct MarketDataSnapshot<'a> {
+  /// `true` for an incremental refresh.
+  pub incremental: bool,
+  pub md_req_id: Option<&'a str>,
+  /// Symbol of a full refresh, incremental entries carry their own.
+  pub symbol: Option<&'a str>,
+  pub entries: Vec<MDEntry<'a>>,
+}
+
+impl<'a> MarketDataSnapshot<'a> {
+  /// Parses `message` and decodes it as a full or incremental refresh.
+  pub fn parse(message: &'a [u8], delimiter: char) -> Result<MarketDataSnapshot<'a>, FIXChecksumValidatorError> {
+    MarketDataSnapshot::from_view(&MessageView::parse(message, delimiter)?)
+  }
+
+  /// Decodes a refresh from the fields of `view`. Fails with
+  /// `FieldValueInvalid(35)` for other message types.
+  pub fn from_view(view: &MessageView<'a>) -> Result<MarketDataSnapshot<'a>, FIXChecksumValidatorError> {
+    let incremental = match view.get(35).ok_or(RequiredFieldMissing(35))? {
+      b"W" => false,
+      b"X" => true,
+      _ => return Err(FieldValueInvalid(35)),
+    };
+    if view.get(268).is_none() { return Err(RequiredFieldMissing(268)); }
+
+    let layout = if incremental { GroupLayout::MD_INCREMENTAL_ENTRIES } else { GroupLayout::MD_ENTRIES };
+    let entries = view.groups(&layout)?.map(|entry| decode_entry(entry.as_view())).collect::<Result<_, _>>()?;
+    Ok(MarketDataSnapshot {
+      incremental,
+      md_req_id: optional_str(view, 262)?,
+      symbol: if incremental { None } else { optional_str(view, 55)? },
+      entries,
+    })
+  }
+}
+
+fn decode_entry<'a>(entry: &MessageView<'a>) -> Result<MDEntry<'a>, FIXChecksumValidatorError> {
+  let update_action = match entry.get(279) {
+    Some(value) => Some(MDUpdateAction::from_value(value).ok_or(FieldValueInvalid(279))?),
+    None => None,
+  };
+  let entry_type = match entry.get(269) {
+    Some(value) => Some(MDEntryType::from_value(value).ok_or(FieldValueInvalid(269))?),
+    None => None,
+  };
+  Ok(MDEntry {
+    update_action,
+    entry_type,
+    entry_id: optional_str(entry, 278)?,
+    symbol: optional_str(entry, 55)?,
+    price: optional_decimal(entry, 270)?,
+    size: optional_decimal(entry, 271)?,
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{MarketDataRequest, MarketDataSnapshot, MDEntryType, MDUpdateAction, SubscriptionRequestType};
+  use super::super::{ValidationOptions, validate_bytes_with};
+  use super::super::FIXChecksumValidatorError::{RequiredFieldMissing, FieldValueInvalid};
+  use super::super::orders::Header;
+
+  #[test]
+  fn it_should_build_market_data_request() {
+    let header = Header::new("FIX.4.4", "CLIENT", "FEED", 4, "20240102-09:30:00");
+    let request = MarketDataRequest::new("MD-2", SubscriptionRequestType::Snapshot).market_depth(1);
+    assert_eq!(request.build(&header).unwrap_err(), RequiredFieldMissing(267));
+    let request = request.entry_type(MDEntryType::Trade);
+    assert_eq!(request.build(&header).unwrap_err(), RequiredFieldMissing(146));
+
+    let message = request.symbol("IBM").symbol("MSFT").build(&header).unwrap();
+    let options = ValidationOptions::new().strict(true).check_body_length(true);
+    assert!(validate_bytes_with(&message, &options).unwrap());
+    assert!(String::from_utf8(message).unwrap()
+      .contains("262=MD-2\x01263=0\x01264=1\x01267=1\x01269=2\x01146=2\x0155=IBM\x0155=MSFT\x0110="));
+  }
+
+  #[test]
+  fn it_should_decode_incremental_refresh() {
+    let message = b"8=FIX.4.2|35=X|262=MD-1|268=2|279=0|269=2|55=IBM|270=10|271=5|279=2|278=E-1|\
+      55=MSFT|10=000|";
+    let refresh = MarketDataSnapshot::parse(message, '|').unwrap();
+    assert!(refresh.incremental);
+    assert_eq!((refresh.md_req_id, refresh.symbol), (Some("MD-1"), None));
+    assert_eq!(refresh.entries[0].update_action, Some(MDUpdateAction::New));
+    assert_eq!((refresh.entries[0].symbol, refresh.entries[0].price), (Some("IBM"), Some(10.0)));
+    assert_eq!(refresh.entries[1].update_action, Some(MDUpdateAction::Delete));
+    assert_eq!((refresh.entries[1].entry_type, refresh.entries[1].entry_id), (None, Some("E-1")));
+  }
+
+  #[test]
+  fn it_should_reject_invalid_snapshot() {
+    let parse = |message: &[u8]| MarketDataSnapshot::parse(message, '|').unwrap_err();
+    assert_eq!(parse(b"8=FIX.4.2|35=V|268=0|"), FieldValueInvalid(35));
+    assert_eq!(parse(b"8=FIX.4.2|35=W|55=IBM|"), RequiredFieldMissing(268));
+    assert_eq!(parse(b"8=FIX.4.2|35=W|268=2|269=0|270=1|"), FieldValueInvalid(268));
+    assert_eq!(parse(b"8=FIX.4.2|35=W|268=1|269=Z|"), FieldValueInvalid(269));
+    assert_eq!(parse(b"8=FIX.4.2|35=W|268=1|269=0|270=1,5|"), FieldValueInvalid(270));
+  }
+}
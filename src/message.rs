@@ -0,0 +1,1169 @@
+//! Access to the fields of a FIX message, borrowed or owned.
+
+use std::convert::TryFrom;
+use std::fmt;
+use std::ops::Index;
+use std::slice;
+use std::vec;
+use std::str;
+use std::str::FromStr;
+
+use super::{FIXChecksumValidatorError, ValidationOptions, checksum_bytes, checksum_digits, checksum_without_field,
+  combine, partial_checksum, validate_bytes_with};
+use super::dictionary::data_field;
+use super::inline::InlineVec;
+use super::FIXChecksumValidatorError::{FieldInvalidFormat, RequiredFieldMissing, FieldValueInvalid,
+  ChecksumMismatch, ChecksumFieldNotFound, ChecksumFieldInvalidFormat, BodyLengthFieldNotFound, BodyLengthInvalidFormat,
+  BodyLengthMismatch};
+
+/// Fields of a message borrowed from the buffer it was parsed from.
+///
+/// # Examples
+///
+/// ```
+/// use fix_checksum::message::MessageView;
+///
+/// let message = MessageView::parse(b"8=FIX.4.2|9=5|35=0|10=161|", '|').unwrap();
+/// assert_eq!(message.get(35), Some(&b"0"[..]));
+/// assert_eq!(message.get_str(8), Some("FIX.4.2"));
+/// assert_eq!(message.get(49), None);
+/// assert_eq!(format!("{:?}", message),
+///   r#"MessageView { delimiter: '|', fields: [8="FIX.4.2", 9="5", 35="0", 10="161"] }"#);
+/// ```
+#[derive(Clone, PartialEq)]
+pub struct MessageView<'a> {
+  pub(crate) fields: FieldIndex<'a>,
+  delimiter: u8,
+}
+
+/// Fields kept inline before allocating, more than typical admin and order
+/// messages have.
+const INLINE_FIELDS: usize = 64;
+
+/// The fields of a `MessageView`, inline up to `INLINE_FIELDS`.
+pub(crate) type FieldIndex<'a> = InlineVec<(u32, &'a [u8]), INLINE_FIELDS>;
+
+impl<'a> MessageView<'a> {
+  /// Splits `message` into fields. Every field must be a numeric tag, `=`
+  /// and a value; the delimiter after the last field may be missing.
+  /// Panics if `delimiter` is not an ASCII character.
+  pub fn parse(message: &'a [u8], delimiter: char) -> Result<MessageView<'a>, FIXChecksumValidatorError> {
+    assert!(delimiter.is_ascii(), "FIX delimiter must be an ASCII character");
+    let mut fields = FieldIndex::new();
+    split_fields(message, delimiter as u8, |tag, start, end| fields.push((tag, &message[start..end])))?;
+    Ok(MessageView { fields, delimiter: delimiter as u8 })
+  }
+
+  /// Returns the value of the first occurrence of `tag`.
+  pub fn get(&self, tag: u32) -> Option<&'a [u8]> {
+    self.fields.iter().find(|field| field.0 == tag).map(|field| field.1)
+  }
+
+  /// Returns the value of the first occurrence of `tag` if it is valid UTF-8.
+  pub fn get_str(&self, tag: u32) -> Option<&'a str> {
+    self.get(tag).and_then(|value| ::std::str::from_utf8(value).ok())
+  }
+
+  /// Returns the Signature (89) of the trailer for the application to
+  /// verify. Being a data field it may contain the delimiter.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use fix_checksum::message::MessageView;
+  ///
+  /// let message = MessageView::parse(b"8=FIX.4.2|9=18|35=0|93=4|89=a|b=|10=222|", '|').unwrap();
+  /// assert_eq!(message.signature(), Some(&b"a|b="[..]));
+  /// assert_eq!(message.get_str(10), Some("222"));
+  /// ```
+  pub fn signature(&self) -> Option<&'a [u8]> {
+    self.get(89)
+  }
+
+  /// Returns the SecureData (91) of the header, untouched. Being a data
+  /// field it may contain the delimiter.
+  pub fn secure_data(&self) -> Option<&'a [u8]> {
+    self.get(91)
+  }
+
+  /// Returns the XmlData (213) payload of the header, untouched.
+  pub fn xml_data(&self) -> Option<&'a [u8]> {
+    self.get(213)
+  }
+
+  /// Returns the XmlData (213) payload if it is valid UTF-8, for
+  /// inspection.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use fix_checksum::message::MessageView;
+  ///
+  /// let message = MessageView::parse(b"35=n\x01212=10\x01213=<a>\x01bc</a>\x01", '\x01').unwrap();
+  /// assert_eq!(message.xml_str(), Some("<a>\x01bc</a>"));
+  /// ```
+  pub fn xml_str(&self) -> Option<&'a str> {
+    self.get_str(213)
+  }
+
+  /// Returns the number of fields.
+  pub fn len(&self) -> usize {
+    self.fields.len()
+  }
+
+  /// Returns `true` if the message has no fields.
+  pub fn is_empty(&self) -> bool {
+    self.fields.is_empty()
+  }
+
+  /// Returns the fields in wire order.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use fix_checksum::message::MessageView;
+  ///
+  /// let message = MessageView::parse(b"8=FIX.4.2|35=0|", '|').unwrap();
+  /// let tags: Vec<u32> = message.fields().map(|(tag, _)| tag).collect();
+  /// assert_eq!(tags, vec![8, 35]);
+  /// ```
+  pub fn fields(&self) -> Fields<'_> {
+    Fields { inner: FieldsInner::Borrowed(self.fields.iter()) }
+  }
+
+  /// Copies the fields into a message that does not borrow the buffer it
+  /// was parsed from.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use fix_checksum::message::{FixMessage, MessageView};
+  ///
+  /// let owned: FixMessage = {
+  ///   let buffer = b"8=FIX.4.2|35=0|".to_vec();
+  ///   MessageView::parse(&buffer, '|').unwrap().to_owned()
+  /// };
+  /// assert_eq!(owned.as_view().get_str(35), Some("0"));
+  /// ```
+  pub fn to_owned(&self) -> FixMessage {
+    let mut owned = FixMessage::new(self.delimiter as char);
+    owned.fields = self.fields.iter().map(|&(tag, value)| (tag, value.to_vec())).collect();
+    owned.length = owned.fields.iter().map(|&(tag, ref value)| counted_length(tag, value)).sum();
+    owned
+  }
+
+  /// Returns the entries of the repeating group counted by `count_tag`.
+  /// Every entry starts with `members[0]` and ends before the next
+  /// occurrence of it or before the first tag which is not in `members`.
+  /// A missing count field is an empty group; a count that does not match
+  /// the entries is reported as `FieldValueInvalid(count_tag)`.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use fix_checksum::message::MessageView;
+  ///
+  /// let message = MessageView::parse(b"35=V|267=2|269=0|269=1|55=IBM|", '|').unwrap();
+  /// let entries = message.group(267, &[269]).unwrap();
+  /// assert_eq!(entries.len(), 2);
+  /// assert_eq!(entries[1].get(269), Some(&b"1"[..]));
+  /// ```
+  pub fn group(&self, count_tag: u32, members: &[u32]) -> Result<Vec<MessageView<'a>>, FIXChecksumValidatorError> {
+    let (count, entries) = match self.group_entries(count_tag, members) {
+      Some(group) => group,
+      None => return Ok(Vec::new()),
+    };
+    match count {
+      Some(count) if count == entries.len() => Ok(entries),
+      _ => Err(FieldValueInvalid(count_tag)),
+    }
+  }
+
+  /// Returns the count declared by the first occurrence of `count_tag`, if
+  /// it is a number, and the entries following it, none without the field.
+  pub(crate) fn group_entries(&self, count_tag: u32, members: &[u32])
+    -> Option<(Option<usize>, Vec<MessageView<'a>>)> {
+    let start = self.fields.iter().position(|field| field.0 == count_tag)?;
+    let count = parse_tag(self.fields[start].1).map(|count| count as usize)
+      .or_else(|| if self.fields[start].1 == b"0" { Some(0) } else { None });
+
+    let mut entries: Vec<MessageView<'a>> = Vec::new();
+    for field in &self.fields[start + 1..] {
+      if Some(&field.0) == members.first() {
+        entries.push(MessageView { fields: FieldIndex::new(), delimiter: self.delimiter });
+      } else if entries.is_empty() || !members.contains(&field.0) {
+        break;
+      }
+      if let Some(entry) = entries.last_mut() { entry.fields.push(*field); }
+    }
+    Some((count, entries))
+  }
+
+  /// Returns the entries of the repeating group described by `layout`,
+  /// checked as by `group`.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use fix_checksum::message::{GroupLayout, MessageView};
+  ///
+  /// let message = MessageView::parse(b"35=D|453=2|448=BRKR|452=1|448=DESK|452=24|55=IBM|", '|').unwrap();
+  /// let roles: Vec<&str> = message.groups(&GroupLayout::PARTIES).unwrap()
+  ///   .filter_map(|party| party.get_str(452))
+  ///   .collect();
+  /// assert_eq!(roles, vec!["1", "24"]);
+  /// ```
+  pub fn groups(&self, layout: &GroupLayout) -> Result<Groups<'a>, FIXChecksumValidatorError> {
+    Ok(Groups { entries: self.group(layout.count_tag, layout.members)?.into_iter() })
+  }
+}
+
+/// Reusable storage for the field index of parsed messages.
+///
+/// `MessageView::parse` allocates the index of every message it parses. A
+/// decoding loop parsing into an arena reuses one allocation instead, which
+/// only grows to fit the largest message seen.
+///
+/// # Examples
+///
+/// ```
+/// use fix_checksum::message::FieldArena;
+///
+/// let mut arena = FieldArena::with_capacity(32);
+/// for wire in &[&b"8=FIX.4.2|35=0|"[..], &b"8=FIX.4.2|35=1|112=X|"[..]] {
+///   let message = arena.parse(wire, '|').unwrap();
+///   assert_eq!(message.get_str(8), Some("FIX.4.2"));
+/// }
+/// assert_eq!(arena.capacity(), 32);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct FieldArena {
+  index: Vec<(u32, usize, usize)>,
+}
+
+impl FieldArena {
+  /// Creates an arena without storage, which is allocated by the first
+  /// parse.
+  pub fn new() -> FieldArena {
+    FieldArena { index: Vec::new() }
+  }
+
+  /// Creates an arena with storage for `fields` fields.
+  pub fn with_capacity(fields: usize) -> FieldArena {
+    FieldArena { index: Vec::with_capacity(fields) }
+  }
+
+  /// Returns how many fields fit without allocating.
+  pub fn capacity(&self) -> usize {
+    self.index.capacity()
+  }
+
+  /// Splits `message` into fields as `MessageView::parse` does, replacing
+  /// the fields of the previous message.
+  pub fn parse<'a>(&'a mut self, message: &'a [u8], delimiter: char)
+    -> Result<ArenaMessage<'a>, FIXChecksumValidatorError> {
+    assert!(delimiter.is_ascii(), "FIX delimiter must be an ASCII character");
+    self.index.clear();
+    let index = &mut self.index;
+    split_fields(message, delimiter as u8, |tag, start, end| index.push((tag, start, end)))?;
+    Ok(ArenaMessage { message, index: &self.index, delimiter: delimiter as u8 })
+  }
+}
+
+/// Fields of a message indexed in a `FieldArena`.
+#[derive(Clone, Copy, Debug)]
+pub struct ArenaMessage<'a> {
+  pub(crate) message: &'a [u8],
+  pub(crate) index: &'a [(u32, usize, usize)],
+  pub(crate) delimiter: u8,
+}
+
+impl<'a> ArenaMessage<'a> {
+  /// Returns the value of the first occurrence of `tag`.
+  pub fn get(&self, tag: u32) -> Option<&'a [u8]> {
+    self.index.iter().find(|field| field.0 == tag).map(|field| &self.message[field.1..field.2])
+  }
+
+  /// Returns the value of the first occurrence of `tag` if it is valid UTF-8.
+  pub fn get_str(&self, tag: u32) -> Option<&'a str> {
+    self.get(tag).and_then(|value| ::std::str::from_utf8(value).ok())
+  }
+
+  /// Returns the number of fields.
+  pub fn len(&self) -> usize {
+    self.index.len()
+  }
+
+  /// Returns `true` if the message has no fields.
+  pub fn is_empty(&self) -> bool {
+    self.index.is_empty()
+  }
+
+  /// Returns the fields in wire order.
+  pub fn fields(&self) -> Fields<'a> {
+    Fields { inner: FieldsInner::Indexed(self.message, self.index.iter()) }
+  }
+
+  /// Builds a message view of the fields, which allocates, for decoders
+  /// taking one.
+  pub fn to_view(&self) -> MessageView<'a> {
+    MessageView { fields: self.fields().collect(), delimiter: self.delimiter }
+  }
+}
+
+/// Layout of a repeating group: the NumInGroup tag counting the entries
+/// and the tags of an entry, the first of which starts every entry.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GroupLayout {
+  pub count_tag: u32,
+  pub members: &'static [u32],
+}
+
+impl GroupLayout {
+  /// NoPartyIDs (453) of the Parties component.
+  pub const PARTIES: GroupLayout = GroupLayout { count_tag: 453, members: &[448, 447, 452] };
+  /// NoRelatedSym (146) of a market data request.
+  pub const RELATED_SYMBOLS: GroupLayout = GroupLayout { count_tag: 146, members: &[55, 65, 48, 22, 207] };
+  /// NoMDEntryTypes (267) of a market data request.
+  pub const MD_ENTRY_TYPES: GroupLayout = GroupLayout { count_tag: 267, members: &[269] };
+  /// NoMDEntries (268) of a market data snapshot.
+  pub const MD_ENTRIES: GroupLayout = GroupLayout { count_tag: 268, members: &[269, 270, 271, 272, 273, 278, 290] };
+  /// NoMDEntries (268) of an incremental refresh.
+  pub const MD_INCREMENTAL_ENTRIES: GroupLayout =
+    GroupLayout { count_tag: 268, members: &[279, 269, 278, 55, 270, 271, 272, 273, 290] };
+}
+
+/// One entry of a repeating group. Only the member tags of its layout are
+/// visible.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GroupView<'a> {
+  entry: MessageView<'a>,
+}
+
+impl<'a> GroupView<'a> {
+  /// Returns the value of `tag` in the entry.
+  pub fn get(&self, tag: u32) -> Option<&'a [u8]> {
+    self.entry.get(tag)
+  }
+
+  /// Returns the value of `tag` in the entry if it is valid UTF-8.
+  pub fn get_str(&self, tag: u32) -> Option<&'a str> {
+    self.entry.get_str(tag)
+  }
+
+  /// Returns the fields of the entry in wire order.
+  pub fn fields(&self) -> Fields<'_> {
+    self.entry.fields()
+  }
+
+  /// Returns the fields of the entry as a message view, for decoders
+  /// taking one.
+  pub fn as_view(&self) -> &MessageView<'a> {
+    &self.entry
+  }
+}
+
+/// Iterator over the entries of a repeating group, see `groups`.
+#[derive(Clone, Debug)]
+pub struct Groups<'a> {
+  entries: vec::IntoIter<MessageView<'a>>,
+}
+
+impl<'a> Iterator for Groups<'a> {
+  type Item = GroupView<'a>;
+
+  fn next(&mut self) -> Option<GroupView<'a>> {
+    self.entries.next().map(|entry| GroupView { entry })
+  }
+
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    self.entries.size_hint()
+  }
+}
+
+impl<'a> ExactSizeIterator for Groups<'a> {}
+
+/// Fields of a message owned by the message, which can be modified.
+///
+/// # Examples
+///
+/// ```
+/// use fix_checksum::message::FixMessage;
+///
+/// let mut message = FixMessage::parse(b"8=FIX.4.2|9=5|35=0|10=161|", '|').unwrap();
+/// message.set(35, b"1");
+/// assert_eq!(message.get(35), Some(&b"1"[..]));
+/// assert_eq!(message.serialize(), b"8=FIX.4.2|9=5|35=1|10=161|".to_vec());
+/// ```
+#[derive(Clone, PartialEq)]
+pub struct FixMessage {
+  pub(crate) fields: Vec<(u32, Vec<u8>)>,
+  delimiter: u8,
+  /// The parsed bytes, kept by `parse_exact` until a field is modified.
+  original: Option<Vec<u8>>,
+  /// The serialized length of the fields but BodyLength (9) and CheckSum
+  /// (10), kept up to date by every change of the fields.
+  length: usize,
+}
+
+impl FixMessage {
+  /// Creates a message without fields.
+  pub fn new(delimiter: char) -> FixMessage {
+    assert!(delimiter.is_ascii(), "FIX delimiter must be an ASCII character");
+    FixMessage { fields: Vec::new(), delimiter: delimiter as u8, original: None, length: 0 }
+  }
+
+  /// Splits `message` into fields as `MessageView::parse` does and copies
+  /// them.
+  pub fn parse(message: &[u8], delimiter: char) -> Result<FixMessage, FIXChecksumValidatorError> {
+    Ok(MessageView::parse(message, delimiter)?.to_owned())
+  }
+
+  /// Parses `message` and keeps its bytes, so that `serialize` returns
+  /// them unchanged, including a missing last delimiter, until the message
+  /// is modified. Audit trails need the exact bytes that were received.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use fix_checksum::message::FixMessage;
+  ///
+  /// let mut message = FixMessage::parse_exact(b"8=FIX.4.2|9=5|35=0|10=161", '|').unwrap();
+  /// assert_eq!(message.serialize(), b"8=FIX.4.2|9=5|35=0|10=161".to_vec());
+  /// assert!(message.is_exact());
+  /// message.remove(10);
+  /// assert_eq!(message.serialize(), b"8=FIX.4.2|9=5|35=0|".to_vec());
+  /// ```
+  pub fn parse_exact(message: &[u8], delimiter: char) -> Result<FixMessage, FIXChecksumValidatorError> {
+    let mut owned = FixMessage::parse(message, delimiter)?;
+    owned.original = Some(message.to_vec());
+    Ok(owned)
+  }
+
+  /// Returns `true` if `serialize` reproduces the parsed bytes.
+  pub fn is_exact(&self) -> bool {
+    self.original.is_some()
+  }
+
+  /// Returns the field delimiter.
+  pub fn delimiter(&self) -> char {
+    self.delimiter as char
+  }
+
+  /// Returns the value of the first occurrence of `tag`.
+  pub fn get(&self, tag: u32) -> Option<&[u8]> {
+    self.fields.iter().find(|field| field.0 == tag).map(|field| &field.1[..])
+  }
+
+  /// Returns the value of the first occurrence of `tag` if it is valid UTF-8.
+  pub fn get_str(&self, tag: u32) -> Option<&str> {
+    self.get(tag).and_then(|value| ::std::str::from_utf8(value).ok())
+  }
+
+  /// Returns the number of fields.
+  pub fn len(&self) -> usize {
+    self.fields.len()
+  }
+
+  /// Returns `true` if the message has no fields.
+  pub fn is_empty(&self) -> bool {
+    self.fields.is_empty()
+  }
+
+  /// Returns the fields in wire order.
+  pub fn fields(&self) -> Fields<'_> {
+    Fields { inner: FieldsInner::Owned(self.fields.iter()) }
+  }
+
+  /// Borrows the fields as a message view, for code written against
+  /// `MessageView` such as the decoders and validation rules.
+  pub fn as_view(&self) -> MessageView<'_> {
+    MessageView { fields: self.fields.iter().map(|&(tag, ref value)| (tag, &value[..])).collect(),
+      delimiter: self.delimiter }
+  }
+
+  /// Replaces the value of the first occurrence of `tag`, or appends the
+  /// field when the message does not have it.
+  pub fn set(&mut self, tag: u32, value: &[u8]) {
+    self.original = None;
+    match self.fields.iter_mut().find(|field| field.0 == tag) {
+      Some(field) => {
+        self.length = self.length + counted_length(tag, value) - counted_length(tag, &field.1);
+        field.1 = value.to_vec();
+      },
+      None => self.push(tag, value),
+    }
+  }
+
+  /// Replaces the value of the first occurrence of `tag` as `set` does, or
+  /// adds the field before the CheckSum (10), keeping the BodyLength (9)
+  /// and CheckSum of a finalized message right. Both are updated from the
+  /// bytes of the old and new values alone, without summing the message
+  /// again, which makes edits of large messages cheap.
+  ///
+  /// Fails with the error `validate` gives when the message has no
+  /// BodyLength or CheckSum to update, and with `FieldValueInvalid` for
+  /// tags 8, 9 and 10, which are set with `set`. The message is unchanged
+  /// on failure.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use fix_checksum::message::FixMessage;
+  ///
+  /// let mut message = FixMessage::parse(b"8=FIX.4.2|9=5|35=0|10=161|", '|').unwrap();
+  /// message.set_field(35, b"1").unwrap();
+  /// message.set_field(112, b"TEST").unwrap();
+  /// assert_eq!(message.serialize(), b"8=FIX.4.2|9=14|35=1|112=TEST|10=228|".to_vec());
+  /// assert_eq!(message.serialize(), message.to_string().into_bytes());
+  /// ```
+  pub fn set_field(&mut self, tag: u32, value: &[u8]) -> Result<(), FIXChecksumValidatorError> {
+    if tag == 8 || tag == 9 || tag == 10 { return Err(FieldValueInvalid(tag)); }
+    let checksum_index = self.fields.iter().position(|field| field.0 == 10).ok_or(ChecksumFieldNotFound)?;
+    let length_index = self.fields.iter().position(|field| field.0 == 9).ok_or(BodyLengthFieldNotFound)?;
+    // Values which are not UTF-8 fail to parse as `+` does
+    let number = |index: usize| str::from_utf8(&self.fields[index].1).unwrap_or("+");
+    let checksum = number(checksum_index).parse::<u8>().map_err(ChecksumFieldInvalidFormat)?;
+    let length = number(length_index).parse::<usize>().map_err(BodyLengthInvalidFormat)?;
+
+    let existing = self.fields.iter().position(|field| field.0 == tag);
+    let (removed, added, added_sum) = match existing {
+      Some(index) => (&self.fields[index].1[..], value.len(), partial_checksum(value)),
+      None => {
+        // The delimiter counts as SOH in the checksum, value bytes as they are.
+        let field = [format!("{}=", tag).as_bytes(), value, b"\x01"].concat();
+        (&[][..], field.len(), partial_checksum(&field))
+      },
+    };
+    let new_length = (length + added).checked_sub(removed.len()).ok_or(BodyLengthMismatch)?.to_string().into_bytes();
+    let checksum = checksum_without_field(checksum, &self.fields[length_index].1);
+    let checksum = checksum_without_field(combine(checksum, partial_checksum(&new_length)), removed);
+    let checksum = combine(checksum, added_sum);
+
+    self.original = None;
+    self.length = self.length + counted_length(tag, value) - existing.map_or(0, |_| counted_length(tag, removed));
+    match existing {
+      Some(index) => self.fields[index].1 = value.to_vec(),
+      None => self.fields.insert(checksum_index, (tag, value.to_vec())),
+    }
+    let checksum_index = checksum_index + existing.is_none() as usize;
+    self.fields[length_index].1 = new_length;
+    self.fields[checksum_index].1 = checksum_digits(u32::from(checksum)).to_vec();
+    Ok(())
+  }
+
+  /// Appends a field, also when the message has the tag already as the
+  /// entries of repeating groups do.
+  pub fn push(&mut self, tag: u32, value: &[u8]) {
+    let index = self.fields.len();
+    self.insert(index, tag, value);
+  }
+
+  /// Inserts a field at `index` of the wire order.
+  pub(crate) fn insert(&mut self, index: usize, tag: u32, value: &[u8]) {
+    self.original = None;
+    self.length += counted_length(tag, value);
+    self.fields.insert(index, (tag, value.to_vec()));
+  }
+
+  /// Removes the first occurrence of `tag` and returns its value.
+  pub fn remove(&mut self, tag: u32) -> Option<Vec<u8>> {
+    let index = self.fields.iter().position(|field| field.0 == tag)?;
+    self.original = None;
+    let (_, value) = self.fields.remove(index);
+    self.length -= counted_length(tag, &value);
+    Some(value)
+  }
+
+  /// Removes every field. The storage of the fields is kept for reparsing.
+  pub fn clear(&mut self) {
+    self.original = None;
+    self.fields.clear();
+    self.length = 0;
+  }
+
+  /// Returns the BodyLength (9) of the message, the length of the fields
+  /// after BeginString (8) but BodyLength and CheckSum (10), as `Display`
+  /// writes it. It is kept up to date as fields change, so that writing
+  /// the message takes a single pass over them.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use fix_checksum::message::FixMessage;
+  ///
+  /// let mut message = FixMessage::parse(b"8=FIX.4.2|9=99|35=0|10=000|", '|').unwrap();
+  /// assert_eq!(message.body_length(), 5);
+  /// message.push(112, b"TEST");
+  /// assert_eq!(message.body_length(), 14);
+  /// ```
+  pub fn body_length(&self) -> usize {
+    match self.fields.iter().find(|field| field.0 != 9 && field.0 != 10) {
+      Some(&(8, ref begin_string)) => self.length - counted_length(8, begin_string),
+      _ => self.length,
+    }
+  }
+
+  /// Replaces the fields with those of `message`, reusing the storage of
+  /// the current field values.
+  pub(crate) fn reparse(&mut self, message: &[u8]) -> Result<(), FIXChecksumValidatorError> {
+    self.original = None;
+    let (fields, mut count, mut length) = (&mut self.fields, 0, 0);
+    let result = split_fields(message, self.delimiter, |tag, start, end| {
+      length += counted_length(tag, &message[start..end]);
+      match fields.get_mut(count) {
+        Some(field) => {
+          field.0 = tag;
+          field.1.clear();
+          field.1.extend_from_slice(&message[start..end]);
+        },
+        None => fields.push((tag, message[start..end].to_vec())),
+      }
+      count += 1;
+    });
+    self.fields.truncate(if result.is_ok() { count } else { 0 });
+    self.length = if result.is_ok() { length } else { 0 };
+    result
+  }
+
+  /// Returns the message as it goes on the wire. BodyLength and CheckSum
+  /// are written as they are, see `Display` for a message with a fresh
+  /// trailer.
+  pub fn serialize(&self) -> Vec<u8> {
+    if let Some(ref original) = self.original { return original.clone(); }
+
+    let mut message = Vec::new();
+    for &(tag, ref value) in &self.fields { self.write_field(&mut message, tag, value); }
+    message
+  }
+
+  /// Returns the message with BodyLength (9) and CheckSum (10) computed
+  /// from its fields, written in one pass. Wherever the message had them,
+  /// BodyLength follows BeginString (8) and CheckSum ends the message.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use fix_checksum::message::FixMessage;
+  ///
+  /// let message = FixMessage::parse(b"8=FIX.4.2|35=0|10=000|", '|').unwrap();
+  /// assert_eq!(message.serialize_finalized(), b"8=FIX.4.2|9=5|35=0|10=161|".to_vec());
+  /// ```
+  pub fn serialize_finalized(&self) -> Vec<u8> {
+    let body_length = self.body_length().to_string();
+    let mut message = Vec::with_capacity(self.length + body_length.len() + 10);
+    let mut fields = self.fields.iter().filter(|field| field.0 != 9 && field.0 != 10).peekable();
+    if let Some(&&(8, ref begin_string)) = fields.peek() {
+      self.write_field(&mut message, 8, begin_string);
+      fields.next();
+    }
+    self.write_field(&mut message, 9, body_length.as_bytes());
+    for &(tag, ref value) in fields { self.write_field(&mut message, tag, value); }
+    let checksum = checksum_digits(checksum_bytes(&message, self.delimiter));
+    self.write_field(&mut message, 10, checksum);
+    message
+  }
+
+  fn write_field(&self, message: &mut Vec<u8>, tag: u32, value: &[u8]) {
+    message.extend_from_slice(tag.to_string().as_bytes());
+    message.push(b'=');
+    message.extend_from_slice(value);
+    message.push(self.delimiter);
+  }
+}
+
+/// Returns the serialized length of a field, none for BodyLength (9) and
+/// CheckSum (10) which are not part of the body.
+fn counted_length(tag: u32, value: &[u8]) -> usize {
+  if tag == 9 || tag == 10 { return 0; }
+  let mut digits = 1;
+  while digits < 10 && tag >= 10u32.pow(digits) { digits += 1; }
+  digits as usize + value.len() + 2
+}
+
+/// Parses one SOH delimited wire message after checking that its checksum
+/// matches and is the last field. Surrounding line breaks are ignored.
+///
+/// # Examples
+///
+/// ```
+/// use fix_checksum::FIXChecksumValidatorError;
+/// use fix_checksum::message::FixMessage;
+///
+/// let message: FixMessage = "8=FIX.4.2\x019=5\x0135=0\x0110=161\x01\n".parse().unwrap();
+/// assert_eq!(message.get_str(35), Some("0"));
+/// assert_eq!("8=FIX.4.2\x019=5\x0135=0\x0110=162\x01".parse::<FixMessage>(),
+///   Err(FIXChecksumValidatorError::ChecksumMismatch));
+/// ```
+impl FromStr for FixMessage {
+  type Err = FIXChecksumValidatorError;
+
+  fn from_str(message: &str) -> Result<FixMessage, FIXChecksumValidatorError> {
+    FixMessage::try_from(message.as_bytes())
+  }
+}
+
+impl<'a> TryFrom<&'a [u8]> for FixMessage {
+  type Error = FIXChecksumValidatorError;
+
+  fn try_from(message: &'a [u8]) -> Result<FixMessage, FIXChecksumValidatorError> {
+    let start = message.iter().position(|b| !b.is_ascii_whitespace()).unwrap_or(message.len());
+    let end = message.iter().rposition(|b| *b != b'\r' && *b != b'\n').map_or(start, |end| end + 1);
+    let message = &message[start..end.max(start)];
+    if !validate_bytes_with(message, &ValidationOptions::new().strict(true))? { return Err(ChecksumMismatch); }
+    FixMessage::parse(message, '\x01')
+  }
+}
+
+/// Writes the message with BodyLength following BeginString and CheckSum
+/// last, both recomputed. Values which are not UTF-8 are written lossily.
+///
+/// # Examples
+///
+/// ```
+/// use fix_checksum::message::FixMessage;
+///
+/// let mut message = FixMessage::parse(b"8=FIX.4.2|35=0|10=000|", '|').unwrap();
+/// assert_eq!(message.to_string(), "8=FIX.4.2|9=5|35=0|10=161|");
+/// message.set(112, b"TEST");
+/// assert_eq!(message.to_string(), "8=FIX.4.2|9=14|35=0|112=TEST|10=227|");
+/// ```
+impl fmt::Display for FixMessage {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    f.write_str(&String::from_utf8_lossy(&self.serialize_finalized()))
+  }
+}
+
+impl fmt::Debug for FixMessage {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    f.debug_struct("FixMessage").field("delimiter", &(self.delimiter as char))
+      .field("fields", &DebugFields(self.fields())).field("exact", &self.is_exact()).finish()
+  }
+}
+
+impl<'a> fmt::Debug for MessageView<'a> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    f.debug_struct("MessageView").field("delimiter", &(self.delimiter as char))
+      .field("fields", &DebugFields(self.fields())).finish()
+  }
+}
+
+/// Renders fields as `tag="value"`, with SOH and other control and non-ASCII
+/// bytes of the values escaped, so that raw buffers print readably.
+struct DebugFields<'a>(Fields<'a>);
+
+impl<'a> fmt::Debug for DebugFields<'a> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    f.debug_list().entries(self.0.clone().map(|(tag, value)| DebugField(tag, value))).finish()
+  }
+}
+
+struct DebugField<'a>(u32, &'a [u8]);
+
+impl<'a> fmt::Debug for DebugField<'a> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{}=\"{}\"", self.0, self.1.escape_ascii())
+  }
+}
+
+/// Iterator over the `(tag, value)` pairs of a message, see `fields`.
+#[derive(Clone, Debug)]
+pub struct Fields<'a> {
+  inner: FieldsInner<'a>,
+}
+
+#[derive(Clone, Debug)]
+enum FieldsInner<'a> {
+  Borrowed(slice::Iter<'a, (u32, &'a [u8])>),
+  Owned(slice::Iter<'a, (u32, Vec<u8>)>),
+  Indexed(&'a [u8], slice::Iter<'a, (u32, usize, usize)>),
+}
+
+impl<'a> Iterator for Fields<'a> {
+  type Item = (u32, &'a [u8]);
+
+  fn next(&mut self) -> Option<(u32, &'a [u8])> {
+    match self.inner {
+      FieldsInner::Borrowed(ref mut fields) => fields.next().map(|&(tag, value)| (tag, value)),
+      FieldsInner::Owned(ref mut fields) => fields.next().map(|&(tag, ref value)| (tag, &value[..])),
+      FieldsInner::Indexed(message, ref mut fields) =>
+        fields.next().map(|&(tag, start, end)| (tag, &message[start..end])),
+    }
+  }
+
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    match self.inner {
+      FieldsInner::Borrowed(ref fields) => fields.size_hint(),
+      FieldsInner::Owned(ref fields) => fields.size_hint(),
+      FieldsInner::Indexed(_, ref fields) => fields.size_hint(),
+    }
+  }
+}
+
+impl<'a> ExactSizeIterator for Fields<'a> {}
+
+/// Returns the value of the first occurrence of a tag.
+///
+/// # Panics
+///
+/// Panics if the message does not have the tag, see `get` for a checked
+/// access.
+///
+/// # Examples
+///
+/// ```
+/// use fix_checksum::message::{FixMessage, MessageView};
+///
+/// let wire = b"8=FIX.4.2|9=5|35=0|10=161|";
+/// assert_eq!(&MessageView::parse(wire, '|').unwrap()[35], b"0");
+/// assert_eq!(&FixMessage::parse(wire, '|').unwrap()[8], b"FIX.4.2");
+/// ```
+impl<'a> Index<u32> for MessageView<'a> {
+  type Output = [u8];
+
+  fn index(&self, tag: u32) -> &[u8] {
+    self.get(tag).unwrap_or_else(|| panic!("tag {} not found in message", tag))
+  }
+}
+
+impl Index<u32> for FixMessage {
+  type Output = [u8];
+
+  fn index(&self, tag: u32) -> &[u8] {
+    self.get(tag).unwrap_or_else(|| panic!("tag {} not found in message", tag))
+  }
+}
+
+/// Returns the value of `tag` as text, failing on invalid UTF-8.
+pub(crate) fn optional_str<'a>(view: &MessageView<'a>, tag: u32) -> Result<Option<&'a str>, FIXChecksumValidatorError> {
+  match view.get(tag) {
+    Some(_) => view.get_str(tag).map(Some).ok_or(FieldValueInvalid(tag)),
+    None => Ok(None),
+  }
+}
+
+/// Returns the value of `tag` as text, failing when it is missing.
+pub(crate) fn required_str<'a>(view: &MessageView<'a>, tag: u32) -> Result<&'a str, FIXChecksumValidatorError> {
+  optional_str(view, tag)?.ok_or(RequiredFieldMissing(tag))
+}
+
+/// Returns the value of `tag` as a number, failing when it is not a decimal.
+pub(crate) fn optional_decimal(view: &MessageView, tag: u32) -> Result<Option<f64>, FIXChecksumValidatorError> {
+  match view.get(tag) {
+    Some(value) => decimal_value(tag, value).map(Some),
+    None => Ok(None),
+  }
+}
+
+fn decimal_value(tag: u32, value: &[u8]) -> Result<f64, FIXChecksumValidatorError> {
+  if !value.iter().all(|b| b.is_ascii_digit() || *b == b'.' || *b == b'-') { return Err(FieldValueInvalid(tag)); }
+  ::std::str::from_utf8(value).ok().and_then(|value| value.parse().ok()).ok_or(FieldValueInvalid(tag))
+}
+
+/// Calls `field` with the tag and the value range of every field of
+/// `message`.
+pub(crate) fn split_fields<F: FnMut(u32, usize, usize)>(message: &[u8], delimiter: u8, mut field: F)
+  -> Result<(), FIXChecksumValidatorError> {
+  let (mut start, mut data) = (0, None);
+  while start < message.len() {
+    let (tag, value_offset) = field_tag(&message[start..], delimiter).ok_or(FieldInvalidFormat)?;
+    let value_start = start + value_offset;
+    let end = value_end(message, value_start, delimiter, data.take(), tag).unwrap_or(message.len());
+    field(tag, value_start, end);
+    data = data_length(tag, &message[value_start..end]);
+    start = end + 1;
+  }
+  Ok(())
+}
+
+/// Returns the tag of the field at the start of `field` and the offset of
+/// its value.
+pub(crate) fn field_tag(field: &[u8], delimiter: u8) -> Option<(u32, usize)> {
+  let separator = field.iter().position(|b| *b == b'=' || *b == delimiter).filter(|end| field[*end] == b'=')?;
+  parse_tag(&field[..separator]).map(|tag| (tag, separator + 1))
+}
+
+/// Returns the end of the value of `tag` starting at `value_start`. That is
+/// the next delimiter, or for the data field announced by `data` the end of
+/// its declared length if a delimiter follows there.
+pub(crate) fn value_end(message: &[u8], value_start: usize, delimiter: u8, data: Option<(u32, usize)>, tag: u32)
+  -> Option<usize> {
+  if let Some((_, length)) = data.filter(|data| data.0 == tag) {
+    let end = value_start.saturating_add(length);
+    if message.len() >= end && message.get(end).is_none_or(|b| *b == delimiter) { return Some(end); }
+  }
+  message[value_start..].iter().position(|b| *b == delimiter).map(|end| value_start + end)
+}
+
+/// Returns `true` if a field of `message` is the length of a data field.
+pub(crate) fn declares_data_field(message: &[u8], delimiter: u8) -> bool {
+  message.split(|b| *b == delimiter)
+    .any(|field| field_tag(field, delimiter).is_some_and(|(tag, _)| data_field(tag).is_some()))
+}
+
+/// Returns the data field announced by a length field and its length.
+pub(crate) fn data_length(tag: u32, value: &[u8]) -> Option<(u32, usize)> {
+  let data_tag = data_field(tag)?;
+  ::std::str::from_utf8(value).ok()?.parse().ok().map(|length| (data_tag, length))
+}
+
+fn parse_tag(tag: &[u8]) -> Option<u32> {
+  if tag.is_empty() || tag[0] == b'0' || !tag.iter().all(|b| b.is_ascii_digit()) { return None; }
+  ::std::str::from_utf8(tag).ok().and_then(|tag| tag.parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+  use std::convert::TryFrom;
+  use super::{MessageView, FixMessage, GroupLayout, FieldArena};
+  use super::super::{ValidationOptions, validate, validate_with};
+  use super::super::FIXChecksumValidatorError::{InvalidEmptyMessage, ChecksumFieldNotLast, ChecksumMismatch};
+  use super::super::FIXChecksumValidatorError::{FieldInvalidFormat, FieldValueInvalid, ChecksumFieldInvalidFormat,
+    BodyLengthFieldNotFound};
+
+  #[test]
+  fn it_should_parse_message_fields() {
+    let message = MessageView::parse(b"8=FIX.4.2\x019=5\x0135=0\x0110=161\x01", '\x01').unwrap();
+    assert_eq!(message.len(), 4);
+    assert_eq!(message.get(9), Some(&b"5"[..]));
+    assert_eq!(message.get_str(10), Some("161"));
+
+    let message = MessageView::parse(b"8=FIX.4.2|58=a=b|10=", '|').unwrap();
+    assert_eq!(message.get(58), Some(&b"a=b"[..]));
+    assert_eq!(message.get(10), Some(&b""[..]));
+    assert!(message.fields.is_inline());
+
+    let many: Vec<u8> = (0..100).flat_map(|i| format!("{}=x|", 5000 + i).into_bytes()).collect();
+    let message = MessageView::parse(&many, '|').unwrap();
+    assert!(!message.fields.is_inline());
+    assert_eq!((message.len(), message.get(5099)), (100, Some(&b"x"[..])));
+  }
+
+  #[test]
+  fn it_should_reject_malformed_fields() {
+    assert_eq!(MessageView::parse(b"8=FIX.4.2||10=161|", '|').unwrap_err(), FieldInvalidFormat);
+    assert_eq!(MessageView::parse(b"8=FIX.4.2|X=1|", '|').unwrap_err(), FieldInvalidFormat);
+    assert_eq!(MessageView::parse(b"8=FIX.4.2|035=1|", '|').unwrap_err(), FieldInvalidFormat);
+  }
+
+  #[test]
+  fn it_should_split_repeating_groups() {
+    let message = MessageView::parse(b"35=W|55=IBM|268=2|269=0|270=10.5|271=100|269=1|270=10.6|58=x|", '|').unwrap();
+    let entries = message.group(268, &[269, 270, 271]).unwrap();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].len(), 3);
+    assert_eq!((entries[1].get_str(270), entries[1].get(271), entries[1].get(58)), (Some("10.6"), None, None));
+
+    assert!(message.group(146, &[55]).unwrap().is_empty());
+    let message = MessageView::parse(b"35=W|268=3|269=0|269=1|", '|').unwrap();
+    assert_eq!(message.group(268, &[269]).unwrap_err(), FieldValueInvalid(268));
+    let message = MessageView::parse(b"35=W|268=A|269=0|", '|').unwrap();
+    assert_eq!(message.group(268, &[269]).unwrap_err(), FieldValueInvalid(268));
+  }
+
+  #[test]
+  fn it_should_roundtrip_exact_bytes() {
+    let wire = b"8=FIX.4.2|9=21|35=0|58=a=b|112=|10=033";
+    let message = FixMessage::parse_exact(wire, '|').unwrap();
+    assert_eq!(message.serialize(), wire.to_vec());
+    assert_eq!(FixMessage::parse(wire, '|').unwrap().serialize(), [&wire[..], b"|"].concat());
+
+    let mut modified = message.clone();
+    modified.set(58, b"a=b");
+    assert!(!modified.is_exact());
+    modified.set(49, b"BRKR");
+    assert_eq!(modified.serialize(), b"8=FIX.4.2|9=21|35=0|58=a=b|112=|10=033|49=BRKR|".to_vec());
+
+    let mut unmodified = message;
+    assert_eq!(unmodified.remove(57), None);
+    assert!(unmodified.is_exact());
+  }
+
+  #[test]
+  fn it_should_display_message_with_fresh_trailer() {
+    let message = FixMessage::parse(b"8=FIX.4.2\x0110=1\x0135=0\x019=99\x0149=BRKR\x01", '\x01').unwrap();
+    let displayed = message.to_string();
+    assert_eq!(displayed, "8=FIX.4.2\x019=13\x0135=0\x0149=BRKR\x0110=172\x01");
+    assert!(validate(&displayed).unwrap());
+
+    let mut message = FixMessage::new('|');
+    message.set(35, b"0");
+    assert_eq!(message.to_string(), "9=5|35=0|10=130|");
+  }
+
+  #[test]
+  fn it_should_keep_trailer_right_on_field_edits() {
+    for &delimiter in &['\x01', '|'] {
+      let wire = format!("8=FIX.4.2|9=0|35=D|49=BRKR|56=EXCH|34=7|93=3|89=a{}b|10=000|", delimiter)
+        .replace('|', &delimiter.to_string());
+      let mut message = FixMessage::parse(wire.as_bytes(), delimiter).unwrap();
+      message = FixMessage::parse(message.to_string().as_bytes(), delimiter).unwrap();
+      let edits: [(u32, &[u8]); 6] = [(35, b"F"), (49, b"BROKER-1"), (56, b"X"), (11, b"ORD-1"), (58, b""),
+        (34, b"1234567890")];
+      for &(tag, value) in &edits {
+        message.set_field(tag, value).unwrap();
+        assert_eq!(message.get(tag), Some(value));
+        assert_eq!(String::from_utf8(message.serialize()).unwrap(), message.to_string(), "{}", tag);
+      }
+    }
+
+    let mut message = FixMessage::parse(b"8=FIX.4.2|9=5|35=0|10=161|", '|').unwrap();
+    assert_eq!(message.set_field(9, b"7"), Err(FieldValueInvalid(9)));
+    message.set(10, b"1X1");
+    assert!(matches!(message.set_field(35, b"1"), Err(ChecksumFieldInvalidFormat(_))));
+    message.set(10, b"161");
+    message.remove(9);
+    assert_eq!(message.set_field(35, b"1"), Err(BodyLengthFieldNotFound));
+    assert_eq!(message.get(35), Some(&b"0"[..]));
+  }
+
+  #[test]
+  fn it_should_track_body_length_through_edits() {
+    let measured = |message: &FixMessage| {
+      let finalized = message.serialize_finalized();
+      let view = MessageView::parse(&finalized, '|').unwrap();
+      assert_eq!(view.get_str(9), Some(message.body_length().to_string().as_str()));
+      let options = ValidationOptions::new().delimiter('|').check_body_length(true);
+      assert!(validate_with(&String::from_utf8(finalized).unwrap(), &options).unwrap());
+    };
+    let mut message = FixMessage::parse(b"8=FIX.4.2|9=1|35=0|10=000|", '|').unwrap();
+    measured(&message);
+    message.set(35, b"D");
+    message.push(1234567, b"value");
+    message.set(58, b"");
+    message.set(9, b"123456");
+    measured(&message);
+    assert_eq!(message.remove(35), Some(b"D".to_vec()));
+    message.set_field(11, b"ORD-1").unwrap();
+    measured(&message);
+
+    let mut reparsed = message.clone();
+    reparsed.reparse(b"9=5|8=FIX.4.4|49=B|").unwrap();
+    assert_eq!(reparsed.body_length(), 5);
+    assert_eq!(reparsed.serialize_finalized(), b"8=FIX.4.4|9=5|49=B|10=186|".to_vec());
+    assert!(reparsed.reparse(b"8=FIX.4.2|=|").is_err());
+    assert_eq!(reparsed.body_length(), 0);
+    message.clear();
+    assert_eq!((message.body_length(), message.to_string()), (0, "9=0|10=167|".to_string()));
+
+    let mut message = FixMessage::parse(b"8=FIX.4.2|1000000000=x|", '|').unwrap();
+    assert_eq!(message.body_length(), 13);
+    message.push(u32::MAX, b"y");
+    assert_eq!(message.body_length(), 26);
+    measured(&message);
+  }
+
+  #[test]
+  fn it_should_convert_validated_wire_messages() {
+    let wire = b"8=FIX.4.2\x019=5\x0135=0\x0110=161\x01";
+    let message = FixMessage::try_from(&wire[..]).unwrap();
+    assert_eq!(message.serialize(), wire.to_vec());
+    assert_eq!("\r\n8=FIX.4.2\x019=5\x0135=0\x0110=161\x01\r\n".parse(), Ok(message));
+
+    assert_eq!(" \n".parse::<FixMessage>(), Err(InvalidEmptyMessage));
+    assert_eq!("8=FIX.4.2\x019=5\x0135=0\x0110=161\x0158=x\x01".parse::<FixMessage>(), Err(ChecksumFieldNotLast));
+    assert_eq!(FixMessage::try_from(&b"8=FIX.4.2\x019=5\x0135=1\x0110=161\x01"[..]), Err(ChecksumMismatch));
+  }
+
+  #[test]
+  fn it_should_index_fields_by_tag() {
+    let wire = b"8=FIX.4.2|35=D|55=IBM|55=MSFT|";
+    assert_eq!(&FixMessage::parse(wire, '|').unwrap()[55], b"IBM");
+    assert_eq!(&MessageView::parse(wire, '|').unwrap()[55], b"IBM");
+  }
+
+  #[test]
+  #[should_panic(expected = "tag 49 not found in message")]
+  fn it_should_panic_on_missing_index() {
+    let _ = &MessageView::parse(b"8=FIX.4.2|35=D|", '|').unwrap()[49];
+  }
+
+  #[test]
+  #[should_panic(expected = "FIX delimiter must be an ASCII character")]
+  fn it_should_reject_non_ascii_view_delimiter() {
+    let _ = MessageView::parse(b"8=FIX.4.2\x0135=D\x01", '\u{101}');
+  }
+
+  #[test]
+  #[should_panic(expected = "FIX delimiter must be an ASCII character")]
+  fn it_should_reject_non_ascii_arena_delimiter() {
+    let _ = FieldArena::new().parse(b"8=FIX.4.2\x0135=D\x01", '\u{101}');
+  }
+
+  #[test]
+  fn it_should_iterate_fields_in_wire_order() {
+    let wire = b"8=FIX.4.2|35=D|55=IBM|55=MSFT|";
+    let view = MessageView::parse(wire, '|').unwrap();
+    let mut owned = FixMessage::parse(wire, '|').unwrap();
+    assert!(view.fields().eq(owned.fields()));
+    assert_eq!(owned.fields().len(), 4);
+
+    owned.set(55, b"AAPL");
+    let fields: Vec<(u32, &[u8])> = owned.fields().skip(2).collect();
+    assert_eq!(fields, vec![(55, &b"AAPL"[..]), (55, &b"MSFT"[..])]);
+  }
+
+  #[test]
+  fn it_should_iterate_groups_by_layout() {
+    let message = MessageView::parse(b"35=X|268=2|279=0|269=0|55=IBM|270=10|279=2|269=1|55=MSFT|58=x|", '|')
+      .unwrap();
+    let entries: Vec<_> = message.groups(&GroupLayout::MD_INCREMENTAL_ENTRIES).unwrap().collect();
+    assert_eq!(entries.len(), 2);
+    assert_eq!((entries[0].get_str(55), entries[0].get(270)), (Some("IBM"), Some(&b"10"[..])));
+    assert_eq!((entries[1].get_str(55), entries[1].get(58)), (Some("MSFT"), None));
+    assert_eq!(entries[1].fields().count(), 3);
+
+    assert_eq!(message.groups(&GroupLayout::PARTIES).unwrap().len(), 0);
+    assert_eq!(message.groups(&GroupLayout::MD_ENTRIES).unwrap_err(), FieldValueInvalid(268));
+  }
+
+  #[test]
+  fn it_should_convert_between_owned_and_borrowed_messages() {
+    let wire = b"8=FIX.4.2|35=D|453=1|448=BRKR|";
+    let view = MessageView::parse(wire, '|').unwrap();
+    let owned = view.to_owned();
+    assert_eq!(owned, FixMessage::parse(wire, '|').unwrap());
+    assert_eq!(owned.as_view(), view);
+    assert_eq!(owned.delimiter(), '|');
+    assert_eq!(owned.as_view().groups(&GroupLayout::PARTIES).unwrap().len(), 1);
+  }
+
+  #[test]
+  fn it_should_reuse_arena_storage() {
+    let mut arena = FieldArena::new();
+    let wire = b"8=FIX.4.2|9=5|35=0|10=161|";
+    let view = arena.parse(wire, '|').unwrap().to_view();
+    assert_eq!(view, MessageView::parse(wire, '|').unwrap());
+    let capacity = arena.capacity();
+
+    let message = arena.parse(b"8=FIX.4.2|58=a=b|10=", '|').unwrap();
+    assert_eq!((message.len(), message.get(58), message.get(10)), (3, Some(&b"a=b"[..]), Some(&b""[..])));
+    assert_eq!(arena.capacity(), capacity);
+    assert_eq!(arena.parse(b"8=FIX.4.2||10=161|", '|').unwrap_err(), FieldInvalidFormat);
+    assert!(arena.parse(b"", '|').unwrap().is_empty());
+  }
+
+  #[test]
+  fn it_should_parse_data_fields_by_length() {
+    let message = MessageView::parse(b"8=FIX.4.2|9=30|35=0|90=10|91=a|10=1\n8=Z|49=A|10=240|", '|').unwrap();
+    assert_eq!(message.secure_data(), Some(&b"a|10=1\n8=Z"[..]));
+    assert_eq!((message.len(), message.get_str(49), message.get_str(10)), (7, Some("A"), Some("240")));
+
+    let message = MessageView::parse(b"35=0|90=99|91=a|49=A|", '|').unwrap();
+    assert_eq!((message.secure_data(), message.get_str(49)), (Some(&b"a"[..]), Some("A")));
+
+    let message = MessageView::parse(b"35=n|212=8|213=<x>|</x>|10=000|", '|').unwrap();
+    assert_eq!((message.xml_str(), message.get_str(10)), (Some("<x>|</x>"), Some("000")));
+    assert_eq!(MessageView::parse(b"212=1|213=\xff|", '|').unwrap().xml_str(), None);
+  }
+
+  #[test]
+  fn it_should_debug_print_control_bytes() {
+    let mut message = FixMessage::parse_exact(b"8=FIX.4.2\x0195=2\x0196=\x01\xff\x0110=000\x01", '\x01').unwrap();
+    assert_eq!(format!("{:?}", message),
+      r#"FixMessage { delimiter: '\u{1}', fields: [8="FIX.4.2", 95="2", 96="\x01\xff", 10="000"], exact: true }"#);
+    message.set(58, b"a\"b");
+    assert!(format!("{:?}", message).ends_with(r#"58="a\"b"], exact: false }"#));
+    assert!(format!("{:#?}", message.as_view()).contains("    fields: [\n        8=\"FIX.4.2\",\n"));
+  }
+}
@@ -0,0 +1,266 @@
+//! Throughput and failure rates of message streams.
+
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use super::clock::{format_utc_timestamp, parse_utc_timestamp};
+use super::message::MessageView;
+use super::session::SessionId;
+use super::stream::StreamResult;
+use super::ValidationOptions;
+
+/// Messages and bytes per second over the window of a `RateMeter`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Rate {
+  pub messages_per_sec: f64,
+  pub bytes_per_sec: f64,
+}
+
+impl fmt::Display for Rate {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{:.1} msg/s, {:.1} B/s", self.messages_per_sec, self.bytes_per_sec)
+  }
+}
+
+/// Arrivals of messages within a window, oldest first.
+#[derive(Clone, Debug, Default, PartialEq)]
+struct Samples {
+  arrivals: VecDeque<(SystemTime, usize)>,
+}
+
+impl Samples {
+  fn add(&mut self, time: SystemTime, bytes: usize, window: Duration) {
+    self.arrivals.push_back((time, bytes));
+    while self.arrivals.front().is_some_and(|&(arrived, _)| !within(arrived, time, window)) {
+      self.arrivals.pop_front();
+    }
+  }
+
+  fn rate(&self, now: SystemTime, window: Duration) -> Rate {
+    let (messages, bytes) = self.arrivals.iter().filter(|&&(arrived, _)| within(arrived, now, window))
+      .fold((0, 0), |(messages, bytes), &(_, size)| (messages + 1, bytes + size));
+    let seconds = window.as_secs_f64();
+    Rate { messages_per_sec: messages as f64 / seconds, bytes_per_sec: bytes as f64 / seconds }
+  }
+}
+
+/// Whether `arrived` lies in the window of length `window` ending at `now`.
+fn within(arrived: SystemTime, now: SystemTime, window: Duration) -> bool {
+  match now.duration_since(arrived) {
+    Ok(age) => age < window,
+    Err(_) => false,
+  }
+}
+
+/// Measures rolling message and byte rates, overall and per session.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::{Duration, UNIX_EPOCH};
+/// use fix_checksum::ValidationOptions;
+/// use fix_checksum::metrics::RateMeter;
+/// use fix_checksum::session::SessionId;
+/// use fix_checksum::stream::StreamValidator;
+///
+/// let log = "8=FIX.4.2|9=23|35=0|49=BRKR|56=INVMGR|10=041|\n8=FIX.4.2|9=5|35=0|10=161|\n";
+/// let options = ValidationOptions::new().delimiter('|');
+/// let mut meter = RateMeter::new(Duration::from_secs(1), &options);
+/// for (index, result) in StreamValidator::new(log.as_bytes(), options).enumerate() {
+///   meter.record(&result.unwrap(), UNIX_EPOCH + Duration::from_millis(100 * index as u64));
+/// }
+/// let now = UNIX_EPOCH + Duration::from_millis(500);
+/// assert_eq!(meter.rate(now).messages_per_sec, 2.0);
+/// assert_eq!(meter.session_rate(&SessionId::new("BRKR", "INVMGR"), now).bytes_per_sec, 45.0);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct RateMeter {
+  window: Duration,
+  total: Samples,
+  sessions: BTreeMap<SessionId, Samples>,
+  delimiter: char,
+}
+
+impl RateMeter {
+  /// Creates a meter averaging over the last `window` for messages
+  /// validated with `options`.
+  pub fn new(window: Duration, options: &ValidationOptions) -> RateMeter {
+    RateMeter {
+      window: window.max(Duration::from_millis(1)),
+      total: Samples::default(),
+      sessions: BTreeMap::new(),
+      delimiter: options.delimiter as char,
+    }
+  }
+
+  /// Records the message of a stream result as arrived at `time`, counted
+  /// for its session if it carries SenderCompID and TargetCompID. Times
+  /// are expected in order.
+  pub fn record(&mut self, result: &StreamResult, time: SystemTime) {
+    let session = MessageView::parse(&result.message, self.delimiter).ok()
+      .and_then(|message| SessionId::from_message(&message));
+    self.record_bytes(session, result.message.len(), time);
+  }
+
+  /// Records a message of `bytes` bytes arrived at `time`.
+  pub fn record_bytes(&mut self, session: Option<SessionId>, bytes: usize, time: SystemTime) {
+    self.total.add(time, bytes, self.window);
+    if let Some(session) = session { self.sessions.entry(session).or_default().add(time, bytes, self.window); }
+  }
+
+  /// Returns the rate of all messages over the window ending at `now`.
+  pub fn rate(&self, now: SystemTime) -> Rate {
+    self.total.rate(now, self.window)
+  }
+
+  /// Returns the rate of the messages of `session`.
+  pub fn session_rate(&self, session: &SessionId, now: SystemTime) -> Rate {
+    self.sessions.get(session).map(|samples| samples.rate(now, self.window)).unwrap_or_default()
+  }
+
+  /// Returns the rates of every session seen, sorted by session.
+  pub fn session_rates(&self, now: SystemTime) -> Vec<(SessionId, Rate)> {
+    self.sessions.iter().map(|(session, samples)| (session.clone(), samples.rate(now, self.window))).collect()
+  }
+}
+
+/// Failures of one time window of a `FailureHistogram`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FailureBucket {
+  /// Start of the window.
+  pub start: SystemTime,
+  /// Number of failed messages by kind of failure.
+  pub failures: BTreeMap<&'static str, usize>,
+}
+
+impl FailureBucket {
+  /// Returns the number of failed messages of the window.
+  pub fn total(&self) -> usize {
+    self.failures.values().sum()
+  }
+}
+
+/// Counts failed messages per time window, to show when corruption
+/// started. A failure is placed by the time it was captured at when known,
+/// else by its SendingTime (52).
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+/// use fix_checksum::{ValidationOptions, ErrorPolicy};
+/// use fix_checksum::clock::parse_utc_timestamp;
+/// use fix_checksum::metrics::FailureHistogram;
+/// use fix_checksum::stream::StreamValidator;
+///
+/// let log = "8=FIX.4.2|9=26|35=0|52=20240102-09:31:15|10=000|\n8=FIX.4.2|9=5|35=0|10=161|\n";
+/// let options = ValidationOptions::new().delimiter('|').error_policy(ErrorPolicy::Skip);
+/// let mut histogram = FailureHistogram::new(Duration::from_secs(60), &options);
+/// for result in StreamValidator::new(log.as_bytes(), options) {
+///   histogram.record(&result.unwrap(), None);
+/// }
+/// let buckets = histogram.buckets();
+/// assert_eq!(buckets.len(), 1);
+/// assert_eq!(buckets[0].start, parse_utc_timestamp("20240102-09:31:00").unwrap());
+/// assert_eq!(buckets[0].failures.get("ChecksumMismatch"), Some(&1));
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct FailureHistogram {
+  width: u64,
+  buckets: BTreeMap<u64, BTreeMap<&'static str, usize>>,
+  /// Failed messages without a capture time or a valid SendingTime.
+  pub untimed: usize,
+  delimiter: char,
+}
+
+impl FailureHistogram {
+  /// Creates a histogram of windows `width` wide for messages validated
+  /// with `options`.
+  pub fn new(width: Duration, options: &ValidationOptions) -> FailureHistogram {
+    FailureHistogram {
+      width: (width.as_millis() as u64).max(1),
+      buckets: BTreeMap::new(),
+      untimed: 0,
+      delimiter: options.delimiter as char,
+    }
+  }
+
+  /// Adds a stream result captured at `captured`, if known. Valid
+  /// messages are not counted.
+  pub fn record(&mut self, result: &StreamResult, captured: Option<SystemTime>) {
+    if !result.outcome.is_failure() { return; }
+    let time = captured.or_else(|| MessageView::parse(&result.message, self.delimiter).ok()
+      .and_then(|message| message.get_str(52).and_then(parse_utc_timestamp)));
+    let millis = match time.and_then(|time| time.duration_since(UNIX_EPOCH).ok()) {
+      Some(since) => since.as_millis() as u64,
+      None => { self.untimed += 1; return; },
+    };
+    let start = millis - millis % self.width;
+    *self.buckets.entry(start).or_default().entry(result.outcome.kind()).or_insert(0) += 1;
+  }
+
+  /// Returns the windows with failures, oldest first.
+  pub fn buckets(&self) -> Vec<FailureBucket> {
+    self.buckets.iter().map(|(&start, failures)| FailureBucket {
+      start: UNIX_EPOCH + Duration::from_millis(start),
+      failures: failures.clone(),
+    }).collect()
+  }
+}
+
+impl fmt::Display for FailureHistogram {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    for bucket in self.buckets() {
+      write!(f, "{} {}", format_utc_timestamp(bucket.start), bucket.total())?;
+      for (kind, count) in &bucket.failures { write!(f, " {}={}", kind, count)?; }
+      writeln!(f)?;
+    }
+    if self.untimed > 0 { writeln!(f, "untimed {}", self.untimed)?; }
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::time::{Duration, UNIX_EPOCH};
+  use super::{FailureHistogram, Rate, RateMeter};
+  use super::super::{ErrorPolicy, ValidationOptions};
+  use super::super::clock::parse_utc_timestamp;
+  use super::super::stream::StreamValidator;
+  use super::super::session::SessionId;
+
+  #[test]
+  fn it_should_roll_the_window() {
+    let mut meter = RateMeter::new(Duration::from_secs(2), &ValidationOptions::new());
+    let at = |millis| UNIX_EPOCH + Duration::from_millis(millis);
+    let session = SessionId::new("A", "B");
+    for millis in &[0, 500, 1_000, 1_500] { meter.record_bytes(Some(session.clone()), 100, at(*millis)); }
+    meter.record_bytes(None, 50, at(1_900));
+
+    assert_eq!(meter.rate(at(1_900)), Rate { messages_per_sec: 2.5, bytes_per_sec: 225.0 });
+    assert_eq!(meter.session_rate(&session, at(2_600)).messages_per_sec, 1.0);
+    assert_eq!(meter.rate(at(10_000)), Rate::default());
+    assert_eq!(meter.session_rates(at(1_900)).len(), 1);
+    assert_eq!(Rate { messages_per_sec: 2.5, bytes_per_sec: 225.0 }.to_string(), "2.5 msg/s, 225.0 B/s");
+  }
+
+  #[test]
+  fn it_should_bucket_failures_by_time() {
+    let log = "8=FIX.4.2|9=26|35=0|52=20240102-09:30:59|10=000|\n\
+      8=FIX.4.2|9=26|35=0|52=20240102-09:32:00|10=000|\n\
+      8=FIX.4.2|9=26|35=0|52=20240102-09:32:30|10=00|\n\
+      8=FIX.4.2|9=5|35=0|10=000|\n";
+    let options = ValidationOptions::new().delimiter('|').error_policy(ErrorPolicy::Skip);
+    let mut histogram = FailureHistogram::new(Duration::from_secs(60), &options);
+    let results: Vec<_> = StreamValidator::new(log.as_bytes(), options).map(|result| result.unwrap()).collect();
+    for result in &results { histogram.record(result, None); }
+    histogram.record(&results[0], parse_utc_timestamp("20240102-09:33:00.500"));
+
+    assert_eq!(histogram.buckets().iter().map(|bucket| bucket.total()).collect::<Vec<_>>(), vec![1, 2, 1]);
+    assert_eq!(histogram.to_string(), "20240102-09:30:00.000 1 ChecksumMismatch=1\n\
+      20240102-09:32:00.000 2 ChecksumFieldInvalidFormat=1 ChecksumMismatch=1\n\
+      20240102-09:33:00.000 1 ChecksumMismatch=1\n\
+      untimed 1\n");
+  }
+}
@@ -0,0 +1,432 @@
+//! An in-process FIX counterparty for integration tests, enabled by the
+//! `mock` feature.
+//!
+//! A `MockCounterparty` is the stream an engine under test reads from and
+//! writes to: every message written to it is validated and answered at
+//! once, and the answers are what the next reads return. It logs on, answers
+//! TestRequests (1), ResendRequests (2) and Logouts (5) as a counterparty
+//! would, and sends the replies scripted with `on` for other messages. A
+//! `Fault` set for a sequence number makes the message sent with it break
+//! the session rules: a wrong CheckSum (10), a gap before it or a second
+//! copy, so that recovery can be tested without real connectivity.
+//!
+//! Reads with nothing to return fail with `WouldBlock`, as those of a
+//! socket with a read timeout do, and return the end of the stream once the
+//! counterparty sent its Logout or after `idle_limit` such reads in a row.
+//!
+//! # Examples
+//!
+//! ```
+//! use fix_checksum::connection::Event;
+//! use fix_checksum::initiator::Initiator;
+//! use fix_checksum::mock::{Fault, MockCounterparty, Reply};
+//! use fix_checksum::orders::{NewOrderSingle, OrdType, Side};
+//! use fix_checksum::session::SessionId;
+//! use fix_checksum::store::MemoryStore;
+//!
+//! let exchange = MockCounterparty::new(SessionId::new("EXCH", "BRKR"))
+//!   .on("D", |order| vec![Reply::new("8").field(11, order.get_str(11).unwrap_or("NONE")).field(39, "0")])
+//!   .fault(2, Fault::CorruptChecksum);
+//! let mut connection = Initiator::new(exchange, SessionId::new("BRKR", "EXCH"), MemoryStore::new()).logon().unwrap();
+//! connection.send(|header| NewOrderSingle::new("ORD-1", "IBM", Side::Buy, "100", OrdType::Market).build(header))
+//!   .unwrap();
+//! connection.logout().unwrap();
+//! assert_eq!(connection.next_event().unwrap(), Event::LoggedOut);
+//! assert_eq!(connection.get_ref().garbled(), 0);
+//! assert_eq!(connection.checkpoint().resend_ranges, vec![(2, 2)]);
+//! ```
+
+use std::collections::BTreeMap;
+use std::io;
+use std::io::{Read, Write};
+
+use super::{FIXChecksumValidatorError, ValidationOptions};
+use super::clock::{Clock, SystemClock, format_utc_timestamp};
+use super::connection::{ADMIN_MSG_TYPES, gap_fill, possible_duplicate};
+use super::framing::{Decoded, decode};
+use super::message::{FixMessage, MessageView};
+use super::orders::{FieldWriter, Header};
+use super::session::SessionId;
+use super::stream::Outcome;
+
+/// Reads failing with `WouldBlock` in a row before the end of the stream.
+const IDLE_LIMIT: usize = 16;
+
+/// Replies to the messages of one type, see `MockCounterparty::on`.
+type Handler = Box<dyn FnMut(&MessageView) -> Vec<Reply>>;
+
+/// A message for the counterparty to send, its header aside.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Reply {
+  pub msg_type: String,
+  pub fields: Vec<(u32, String)>,
+}
+
+impl Reply {
+  /// Creates a message of type `msg_type` without fields.
+  pub fn new(msg_type: &str) -> Reply {
+    Reply { msg_type: msg_type.to_string(), fields: Vec::new() }
+  }
+
+  /// Appends a field.
+  pub fn field(mut self, tag: u32, value: &str) -> Reply {
+    self.fields.push((tag, value.to_string()));
+    self
+  }
+}
+
+/// A breach of the session rules by the message sent with a given
+/// MsgSeqNum (34).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Fault {
+  /// The message goes out with a wrong CheckSum (10). It is stored right,
+  /// so that it is correct when sent again on a ResendRequest.
+  CorruptChecksum,
+  /// The given number of sequence numbers is skipped before the message,
+  /// leaving a gap filled with a SequenceReset-GapFill (4) on request.
+  SkipSeqNums(u64),
+  /// The message goes out twice, the second time without PossDupFlag (43).
+  Duplicate,
+}
+
+/// A counterparty answering from memory, see the module documentation.
+pub struct MockCounterparty<C = SystemClock> {
+  session: SessionId,
+  begin_string: String,
+  clock: C,
+  options: ValidationOptions,
+  handlers: Vec<(String, Handler)>,
+  faults: BTreeMap<u64, Fault>,
+  next_seq_num: u64,
+  sent: BTreeMap<u64, Vec<u8>>,
+  received: Vec<Vec<u8>>,
+  garbled: usize,
+  input: Vec<u8>,
+  output: Vec<u8>,
+  idle_reads: usize,
+  idle_limit: usize,
+  logged_out: bool,
+}
+
+impl MockCounterparty {
+  /// Creates the counterparty of `session`, SenderCompID (49) being the
+  /// counterparty itself, with FIX.4.2.
+  pub fn new(session: SessionId) -> MockCounterparty {
+    MockCounterparty {
+      session,
+      begin_string: "FIX.4.2".to_string(),
+      clock: SystemClock,
+      options: ValidationOptions::new().strict(true).check_body_length(true),
+      handlers: Vec::new(),
+      faults: BTreeMap::new(),
+      next_seq_num: 1,
+      sent: BTreeMap::new(),
+      received: Vec::new(),
+      garbled: 0,
+      input: Vec::new(),
+      output: Vec::new(),
+      idle_reads: 0,
+      idle_limit: IDLE_LIMIT,
+      logged_out: false,
+    }
+  }
+}
+
+impl<C: Clock> MockCounterparty<C> {
+  /// Sets the BeginString (8) of the messages sent.
+  pub fn begin_string(mut self, begin_string: &str) -> MockCounterparty<C> {
+    self.begin_string = begin_string.to_string();
+    self
+  }
+
+  /// Takes the SendingTime (52) of messages from `clock`.
+  pub fn clock<D: Clock>(self, clock: D) -> MockCounterparty<D> {
+    MockCounterparty {
+      session: self.session,
+      begin_string: self.begin_string,
+      clock,
+      options: self.options,
+      handlers: self.handlers,
+      faults: self.faults,
+      next_seq_num: self.next_seq_num,
+      sent: self.sent,
+      received: self.received,
+      garbled: self.garbled,
+      input: self.input,
+      output: self.output,
+      idle_reads: self.idle_reads,
+      idle_limit: self.idle_limit,
+      logged_out: self.logged_out,
+    }
+  }
+
+  /// Answers the messages of type `msg_type` with the replies `respond`
+  /// returns, instead of the answer of the counterparty to administrative
+  /// messages. A Logon (A) can so be rejected with a Logout (5).
+  pub fn on<F: FnMut(&MessageView) -> Vec<Reply> + 'static>(mut self, msg_type: &str, respond: F)
+    -> MockCounterparty<C> {
+    self.handlers.push((msg_type.to_string(), Box::new(respond)));
+    self
+  }
+
+  /// Makes the message sent with `seq_num` break the session rules.
+  pub fn fault(mut self, seq_num: u64, fault: Fault) -> MockCounterparty<C> {
+    self.faults.insert(seq_num, fault);
+    self
+  }
+
+  /// Sets the number of reads failing with `WouldBlock` in a row before
+  /// the end of the stream, 16 by default.
+  pub fn idle_limit(mut self, reads: usize) -> MockCounterparty<C> {
+    self.idle_limit = reads;
+    self
+  }
+
+  /// Sends `reply` with the next header, as an unsolicited message, and
+  /// returns its MsgSeqNum (34).
+  pub fn send(&mut self, reply: &Reply) -> Result<u64, FIXChecksumValidatorError> {
+    let fault = self.faults.remove(&self.next_seq_num);
+    if let Some(Fault::SkipSeqNums(count)) = fault { self.next_seq_num += count; }
+    let seq_num = self.next_seq_num;
+    let mut writer = FieldWriter::new(&self.header(seq_num), &reply.msg_type)?;
+    for &(tag, ref value) in &reply.fields { writer.text(tag, value)?; }
+    let message = writer.finish()?;
+
+    match fault {
+      Some(Fault::CorruptChecksum) => self.output.extend_from_slice(&corrupt_checksum(&message)),
+      Some(Fault::Duplicate) => {
+        self.output.extend_from_slice(&message);
+        self.output.extend_from_slice(&message);
+      },
+      _ => self.output.extend_from_slice(&message),
+    }
+    self.sent.insert(seq_num, message);
+    self.next_seq_num += 1;
+    if reply.msg_type == "5" { self.logged_out = true; }
+    Ok(seq_num)
+  }
+
+  /// Returns the valid messages written by the engine, in order.
+  pub fn received(&self) -> &[Vec<u8>] {
+    &self.received
+  }
+
+  /// Returns the number of garbled messages written by the engine.
+  pub fn garbled(&self) -> usize {
+    self.garbled
+  }
+
+  /// Returns the MsgSeqNum (34) of the next message sent.
+  pub fn next_seq_num(&self) -> u64 {
+    self.next_seq_num
+  }
+
+  fn header(&self, seq_num: u64) -> Header {
+    Header::new(&self.begin_string, &self.session.sender_comp_id, &self.session.target_comp_id, seq_num,
+      &format_utc_timestamp(self.clock.now()))
+  }
+
+  /// Validates and answers the whole messages written so far.
+  fn receive(&mut self) -> Result<(), FIXChecksumValidatorError> {
+    loop {
+      let (message, consumed) = match decode(&self.input, &self.options) {
+        Ok(Decoded::Message { bytes, outcome: Outcome::Valid, consumed }) => (Some(bytes.to_vec()), consumed),
+        Ok(Decoded::Message { consumed, .. }) => (None, consumed),
+        Ok(Decoded::NeedMoreBytes { .. }) => return Ok(()),
+        Err(_) => (None, self.input.len()),
+      };
+      self.input.drain(..consumed);
+      match message {
+        Some(message) => {
+          self.respond(&MessageView::parse(&message, '\x01')?)?;
+          self.received.push(message);
+        },
+        None => self.garbled += 1,
+      }
+    }
+  }
+
+  fn respond(&mut self, view: &MessageView) -> Result<(), FIXChecksumValidatorError> {
+    let msg_type = view.get_str(35).unwrap_or("");
+    let mut replies = Vec::new();
+    let mut handled = false;
+    for &mut (ref on, ref mut respond) in &mut self.handlers {
+      if on == msg_type {
+        handled = true;
+        replies.extend(respond(view));
+      }
+    }
+    if !handled {
+      match msg_type {
+        "A" => replies.push(Reply::new("A").field(98, "0").field(108, view.get_str(108).unwrap_or("30"))),
+        "1" => replies.push(Reply::new("0").field(112, view.get_str(112).unwrap_or("TEST"))),
+        "2" => self.resend(view)?,
+        "5" if !self.logged_out => replies.push(Reply::new("5")),
+        _ => {},
+      }
+    }
+    replies.iter().try_for_each(|reply| self.send(reply).map(|_| ()))
+  }
+
+  /// Sends the messages BeginSeqNo (7) to EndSeqNo (16) again as
+  /// `Connection` does.
+  fn resend(&mut self, view: &MessageView) -> Result<(), FIXChecksumValidatorError> {
+    let last_sent = self.next_seq_num - 1;
+    let seq_num_field = |tag| view.get_str(tag).and_then(|value| value.parse::<u64>().ok())
+      .ok_or(FIXChecksumValidatorError::RequiredFieldMissing(tag));
+    let begin = seq_num_field(7)?.max(1);
+    let end = match seq_num_field(16)? {
+      0 => last_sent,
+      end => end.min(last_sent),
+    };
+    if begin > end { return Ok(()); }
+    let mut next = begin;
+    let stored: Vec<_> = self.sent.range(begin..=end).map(|(&seq_num, message)| (seq_num, message.clone())).collect();
+    for (seq_num, message) in stored {
+      let message = FixMessage::parse(&message, '\x01')?;
+      if message.get(35).is_none_or(|msg_type| ADMIN_MSG_TYPES.contains(&msg_type)) { continue; }
+      if seq_num > next { self.gap_fill(next, seq_num)?; }
+      let sending_time = format_utc_timestamp(self.clock.now());
+      self.output.extend_from_slice(&possible_duplicate(message, &sending_time)?);
+      next = seq_num + 1;
+    }
+    if next <= end { self.gap_fill(next, end + 1)?; }
+    Ok(())
+  }
+
+  fn gap_fill(&mut self, seq_num: u64, new_seq_num: u64) -> Result<(), FIXChecksumValidatorError> {
+    let message = gap_fill(&self.header(seq_num), new_seq_num)?;
+    self.output.extend_from_slice(&message);
+    Ok(())
+  }
+}
+
+/// Returns `message` with the value of its CheckSum (10) off by one.
+fn corrupt_checksum(message: &[u8]) -> Vec<u8> {
+  let mut corrupted = message.to_vec();
+  let digits = corrupted.len() - 4..corrupted.len() - 1;
+  let checksum = ::std::str::from_utf8(&corrupted[digits.clone()]).ok().and_then(|value| value.parse::<u32>().ok())
+    .unwrap_or(0);
+  corrupted.splice(digits, format!("{:03}", (checksum + 1) % 256).into_bytes());
+  corrupted
+}
+
+impl<C: Clock> Read for MockCounterparty<C> {
+  fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+    if self.output.is_empty() {
+      if self.logged_out || self.idle_reads >= self.idle_limit { return Ok(0); }
+      self.idle_reads += 1;
+      return Err(io::Error::new(io::ErrorKind::WouldBlock, "nothing to read"));
+    }
+    self.idle_reads = 0;
+    let count = buf.len().min(self.output.len());
+    buf[..count].copy_from_slice(&self.output[..count]);
+    self.output.drain(..count);
+    Ok(count)
+  }
+}
+
+/// Writes are the messages of the engine, answered as soon as they are
+/// whole.
+impl<C: Clock> Write for MockCounterparty<C> {
+  fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+    self.input.extend_from_slice(buf);
+    self.receive().map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+    Ok(buf.len())
+  }
+
+  fn flush(&mut self) -> io::Result<()> {
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::io;
+  use super::{Fault, MockCounterparty, Reply};
+  use super::super::connection::{Connection, Event};
+  use super::super::initiator::Initiator;
+  use super::super::message::MessageView;
+  use super::super::orders::{NewOrderSingle, OrdType, Side};
+  use super::super::session::SessionId;
+  use super::super::store::MemoryStore;
+
+  fn exchange() -> MockCounterparty {
+    MockCounterparty::new(SessionId::new("EXCH", "BRKR"))
+      .on("D", |order| vec![Reply::new("8").field(11, order.get_str(11).unwrap_or("NONE"))])
+  }
+
+  fn logon(exchange: MockCounterparty) -> Connection<MockCounterparty, MemoryStore> {
+    let mut connection = Initiator::new(exchange, SessionId::new("BRKR", "EXCH"), MemoryStore::new()).logon().unwrap();
+    connection.send(|header| NewOrderSingle::new("ORD-1", "IBM", Side::Buy, "100", OrdType::Market).build(header))
+      .unwrap();
+    connection
+  }
+
+  fn msg_types(messages: &[Vec<u8>]) -> Vec<String> {
+    messages.iter().map(|message| MessageView::parse(message, '\x01').unwrap().get_str(35).unwrap().to_string())
+      .collect()
+  }
+
+  #[test]
+  fn it_should_answer_as_counterparty() {
+    let mut connection = logon(exchange());
+    match connection.next_event().unwrap() {
+      Event::Application(message) => assert!(message.windows(8).any(|window| window == b"11=ORD-1")),
+      event => panic!("unexpected {:?}", event),
+    }
+    connection.logout().unwrap();
+    assert_eq!(connection.next_event().unwrap(), Event::LoggedOut);
+    assert_eq!(msg_types(connection.get_ref().received()), vec!["A", "D", "5"]);
+    assert_eq!(connection.get_ref().next_seq_num(), 4);
+
+    let rejecting = MockCounterparty::new(SessionId::new("EXCH", "BRKR"))
+      .on("A", |_| vec![Reply::new("5").field(58, "unknown session")]);
+    let err = Initiator::new(rejecting, SessionId::new("BRKR", "EXCH"), MemoryStore::new()).logon().err().unwrap();
+    assert_eq!((err.kind(), err.to_string()), (io::ErrorKind::PermissionDenied, "unknown session".to_string()));
+  }
+
+  #[test]
+  fn it_should_resend_corrupted_messages() {
+    let exchange = exchange().on("D", |_| vec![Reply::new("8").field(11, "ORD-2")]).fault(2, Fault::CorruptChecksum);
+    let mut connection = logon(exchange);
+    assert!(matches!(connection.next_event().unwrap(), Event::Application(_)));
+    assert_eq!(connection.checkpoint().resend_ranges, vec![(2, 2)]);
+    match connection.next_event().unwrap() {
+      Event::Application(message) => {
+        let view = MessageView::parse(&message, '\x01').unwrap();
+        assert_eq!((view.get_str(34), view.get_str(43), view.get_str(11)), (Some("2"), Some("Y"), Some("ORD-1")));
+      },
+      event => panic!("unexpected {:?}", event),
+    }
+    assert!(connection.checkpoint().resend_ranges.is_empty());
+    assert_eq!(msg_types(connection.get_ref().received()), vec!["A", "D", "2"]);
+  }
+
+  #[test]
+  fn it_should_play_seq_num_games() {
+    let mut connection = logon(exchange().fault(2, Fault::SkipSeqNums(2)).idle_limit(0));
+    assert!(matches!(connection.next_event().unwrap(), Event::Application(_)));
+    assert_eq!(connection.checkpoint().resend_ranges, vec![(2, 3)]);
+    assert_eq!(connection.next_event().unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+    assert!(connection.checkpoint().resend_ranges.is_empty());
+    assert_eq!(connection.checkpoint().last_received, 4);
+
+    let mut connection = logon(exchange().fault(2, Fault::Duplicate));
+    assert!(matches!(connection.next_event().unwrap(), Event::Application(_)));
+    assert_eq!(connection.next_event().unwrap_err().kind(), io::ErrorKind::InvalidData);
+  }
+
+  #[test]
+  fn it_should_count_garbled_messages() {
+    let mut exchange = MockCounterparty::new(SessionId::new("EXCH", "BRKR"));
+    io::Write::write_all(&mut exchange, b"8=FIX.4.2\x019=5\x0135=0\x0110=000\x01").unwrap();
+    io::Write::write_all(&mut exchange, b"garbage").unwrap();
+    assert_eq!(exchange.garbled(), 2);
+    assert!(exchange.received().is_empty());
+    assert_eq!(exchange.send(&Reply::new("B").field(148, "Headline")).unwrap(), 1);
+    let mut buf = [0; 256];
+    assert!(io::Read::read(&mut exchange, &mut buf).unwrap() > 0);
+    assert_eq!(io::Read::read(&mut exchange, &mut buf).unwrap_err().kind(), io::ErrorKind::WouldBlock);
+  }
+}
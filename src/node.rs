@@ -0,0 +1,227 @@
+//! Node.js addon, enabled by the `node` feature.
+//!
+//! The addon talks to Node through the stable N-API C interface, whose
+//! symbols are resolved by Node when it loads the library. The few N-API
+//! functions it calls are declared by hand instead of through `napi-rs`,
+//! keeping the crate on std alone. Test builds replace them with stubs
+//! failing every call, so that the module is type checked and linked
+//! without Node; calls into a real Node are not tested. Copy the built
+//! `libfix_checksum.so` to `fix_checksum.node` and `require` it:
+//!
+//! ```js
+//! const fix = require('./fix_checksum.node');
+//! fix.validate('8=FIX.4.2|9=5|35=0|10=161|', '|');   // true
+//! fix.frame(log, '|');                               // array of messages
+//! fix.prettyPrint('8=FIX.4.2|9=5|35=0|10=161|', '|'); // one field per line
+//! ```
+//!
+//! Validation failures other than a checksum mismatch are thrown as errors
+//! whose `code` is the error kind, e.g. `ChecksumFieldNotFound`.
+
+use std::ffi::CString;
+use std::os::raw::{c_char, c_void};
+use std::ptr;
+
+use super::{ValidationOptions, validate_bytes_with};
+use super::pretty::pretty_print;
+use super::stream::MessageReader;
+
+#[allow(non_camel_case_types)]
+type napi_env = *mut c_void;
+#[allow(non_camel_case_types)]
+type napi_value = *mut c_void;
+#[allow(non_camel_case_types)]
+type napi_callback_info = *mut c_void;
+#[allow(non_camel_case_types)]
+type napi_callback = unsafe extern "C" fn(napi_env, napi_callback_info) -> napi_value;
+
+const NAPI_OK: i32 = 0;
+
+#[cfg(not(test))]
+extern "C" {
+  fn napi_create_function(env: napi_env, name: *const c_char, length: usize, cb: napi_callback,
+    data: *mut c_void, result: *mut napi_value) -> i32;
+  fn napi_set_named_property(env: napi_env, object: napi_value, name: *const c_char,
+    value: napi_value) -> i32;
+  fn napi_get_cb_info(env: napi_env, info: napi_callback_info, argc: *mut usize, argv: *mut napi_value,
+    this_arg: *mut napi_value, data: *mut *mut c_void) -> i32;
+  fn napi_get_value_string_utf8(env: napi_env, value: napi_value, buf: *mut c_char, size: usize,
+    result: *mut usize) -> i32;
+  fn napi_get_boolean(env: napi_env, value: bool, result: *mut napi_value) -> i32;
+  fn napi_create_string_utf8(env: napi_env, s: *const c_char, length: usize, result: *mut napi_value) -> i32;
+  fn napi_create_array_with_length(env: napi_env, length: usize, result: *mut napi_value) -> i32;
+  fn napi_set_element(env: napi_env, object: napi_value, index: u32, value: napi_value) -> i32;
+  fn napi_throw_error(env: napi_env, code: *const c_char, msg: *const c_char) -> i32;
+}
+
+#[cfg(test)]
+use self::stubs::*;
+
+/// N-API functions of test builds, failing with `napi_generic_failure`.
+#[cfg(test)]
+mod stubs {
+  use std::os::raw::{c_char, c_void};
+  use super::{napi_callback, napi_callback_info, napi_env, napi_value};
+
+  const NAPI_GENERIC_FAILURE: i32 = 9;
+
+  pub unsafe fn napi_create_function(_: napi_env, _: *const c_char, _: usize, _: napi_callback, _: *mut c_void,
+    _: *mut napi_value) -> i32 { NAPI_GENERIC_FAILURE }
+  pub unsafe fn napi_set_named_property(_: napi_env, _: napi_value, _: *const c_char, _: napi_value) -> i32 {
+    NAPI_GENERIC_FAILURE
+  }
+  pub unsafe fn napi_get_cb_info(_: napi_env, _: napi_callback_info, _: *mut usize, _: *mut napi_value,
+    _: *mut napi_value, _: *mut *mut c_void) -> i32 { NAPI_GENERIC_FAILURE }
+  pub unsafe fn napi_get_value_string_utf8(_: napi_env, _: napi_value, _: *mut c_char, _: usize, _: *mut usize)
+    -> i32 { NAPI_GENERIC_FAILURE }
+  pub unsafe fn napi_get_boolean(_: napi_env, _: bool, _: *mut napi_value) -> i32 { NAPI_GENERIC_FAILURE }
+  pub unsafe fn napi_create_string_utf8(_: napi_env, _: *const c_char, _: usize, _: *mut napi_value) -> i32 {
+    NAPI_GENERIC_FAILURE
+  }
+  pub unsafe fn napi_create_array_with_length(_: napi_env, _: usize, _: *mut napi_value) -> i32 {
+    NAPI_GENERIC_FAILURE
+  }
+  pub unsafe fn napi_set_element(_: napi_env, _: napi_value, _: u32, _: napi_value) -> i32 { NAPI_GENERIC_FAILURE }
+  pub unsafe fn napi_throw_error(_: napi_env, _: *const c_char, _: *const c_char) -> i32 { NAPI_GENERIC_FAILURE }
+}
+
+/// Entry point looked up by Node when the addon is loaded.
+///
+/// # Safety
+///
+/// Must only be called by Node with a valid environment.
+#[no_mangle]
+pub unsafe extern "C" fn napi_register_module_v1(env: napi_env, exports: napi_value) -> napi_value {
+  export(env, exports, "validate\0", validate);
+  export(env, exports, "frame\0", frame);
+  export(env, exports, "prettyPrint\0", pretty);
+  exports
+}
+
+unsafe fn export(env: napi_env, exports: napi_value, name: &str, cb: napi_callback) {
+  let mut function = ptr::null_mut();
+  if napi_create_function(env, name.as_ptr() as *const c_char, name.len() - 1, cb, ptr::null_mut(),
+    &mut function) == NAPI_OK {
+    napi_set_named_property(env, exports, name.as_ptr() as *const c_char, function);
+  }
+}
+
+/// Reads the message and the optional delimiter arguments of a call.
+unsafe fn arguments(env: napi_env, info: napi_callback_info) -> Option<(Vec<u8>, ValidationOptions)> {
+  let mut argc = 2;
+  let mut argv = [ptr::null_mut(); 2];
+  if napi_get_cb_info(env, info, &mut argc, argv.as_mut_ptr(), ptr::null_mut(), ptr::null_mut()) != NAPI_OK
+    || argc == 0 {
+    throw(env, "InvalidArgument", "expected a message");
+    return None;
+  }
+
+  let message = match string(env, argv[0]) {
+    Some(message) => message,
+    None => {
+      throw(env, "InvalidArgument", "message must be a string");
+      return None;
+    },
+  };
+  let mut options = ValidationOptions::new();
+  if argc > 1 {
+    match string(env, argv[1]) {
+      Some(ref delimiter) if delimiter.len() == 1 && delimiter[0].is_ascii() => {
+        options = options.delimiter(delimiter[0] as char);
+      },
+      _ => {
+        throw(env, "InvalidArgument", "delimiter must be a single ASCII character");
+        return None;
+      },
+    }
+  }
+  Some((message, options))
+}
+
+unsafe fn string(env: napi_env, value: napi_value) -> Option<Vec<u8>> {
+  let mut length = 0;
+  if napi_get_value_string_utf8(env, value, ptr::null_mut(), 0, &mut length) != NAPI_OK { return None; }
+
+  let mut buffer = vec![0u8; length + 1];
+  if napi_get_value_string_utf8(env, value, buffer.as_mut_ptr() as *mut c_char, buffer.len(),
+    &mut length) != NAPI_OK {
+    return None;
+  }
+  buffer.truncate(length);
+  Some(buffer)
+}
+
+unsafe fn to_js_string(env: napi_env, value: &[u8]) -> napi_value {
+  let value = String::from_utf8_lossy(value);
+  let mut result = ptr::null_mut();
+  napi_create_string_utf8(env, value.as_ptr() as *const c_char, value.len(), &mut result);
+  result
+}
+
+unsafe fn throw(env: napi_env, code: &str, message: &str) -> napi_value {
+  let code = CString::new(code).unwrap_or_default();
+  let message = CString::new(message).unwrap_or_default();
+  napi_throw_error(env, code.as_ptr(), message.as_ptr());
+  ptr::null_mut()
+}
+
+unsafe extern "C" fn validate(env: napi_env, info: napi_callback_info) -> napi_value {
+  let (message, options) = match arguments(env, info) {
+    Some(arguments) => arguments,
+    None => return ptr::null_mut(),
+  };
+  match validate_bytes_with(&message, &options) {
+    Ok(valid) => {
+      let mut result = ptr::null_mut();
+      napi_get_boolean(env, valid, &mut result);
+      result
+    },
+    Err(err) => throw(env, err.kind(), &err.to_string()),
+  }
+}
+
+unsafe extern "C" fn frame(env: napi_env, info: napi_callback_info) -> napi_value {
+  let (input, options) = match arguments(env, info) {
+    Some(arguments) => arguments,
+    None => return ptr::null_mut(),
+  };
+  let frames: Vec<_> = MessageReader::new(&input[..], options.delimiter as char)
+    .filter_map(|frame| frame.ok())
+    .collect();
+
+  let mut result = ptr::null_mut();
+  napi_create_array_with_length(env, frames.len(), &mut result);
+  for (index, frame) in frames.iter().enumerate() {
+    napi_set_element(env, result, index as u32, to_js_string(env, &frame.bytes));
+  }
+  result
+}
+
+unsafe extern "C" fn pretty(env: napi_env, info: napi_callback_info) -> napi_value {
+  let (message, options) = match arguments(env, info) {
+    Some(arguments) => arguments,
+    None => return ptr::null_mut(),
+  };
+  match pretty_print(&message, options.delimiter as char) {
+    Ok(text) => to_js_string(env, text.as_bytes()),
+    Err(err) => throw(env, err.kind(), &err.to_string()),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::ptr;
+
+  use super::{frame, napi_register_module_v1, pretty, validate};
+
+  #[test]
+  fn it_should_fail_calls_without_node() {
+    unsafe {
+      let exports = ptr::dangling_mut();
+      assert_eq!(napi_register_module_v1(ptr::null_mut(), exports), exports);
+      for cb in &[validate, frame, pretty] {
+        assert!(cb(ptr::null_mut(), ptr::null_mut()).is_null());
+      }
+    }
+  }
+}
@@ -0,0 +1,194 @@
+//! Validation configuration shared by every entry point of the crate.
+
+const FIX_MESSAGE_DELIMITER: char = '\x01';
+
+/// What a stream validator does when a message fails validation.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ErrorPolicy {
+  /// Report the first failure and end the stream.
+  Stop,
+  /// Report every failure and keep going.
+  Skip,
+  /// Rewrite the checksum of messages that have one and keep going.
+  /// Failures that cannot be repaired are reported as with `Skip`.
+  Repair,
+}
+
+/// Text an engine writes before the message on every line of its log, such
+/// as the capture time in `20240604-07:58:28.123 : 8=FIX.4.2...`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum LinePrefix {
+  /// A prefix of this many bytes.
+  FixedWidth(usize),
+  /// A prefix ending with the first occurrence of this separator on the
+  /// line.
+  Separator(String),
+}
+
+impl LinePrefix {
+  /// Returns the length of the prefix of `line`, if `line` has one.
+  pub(crate) fn length(&self, line: &[u8]) -> Option<usize> {
+    match *self {
+      LinePrefix::FixedWidth(width) => if line.len() >= width { Some(width) } else { None },
+      LinePrefix::Separator(ref separator) if !separator.is_empty() => line.windows(separator.len())
+        .position(|window| window == separator.as_bytes()).map(|start| start + separator.len()),
+      LinePrefix::Separator(_) => Some(0),
+    }
+  }
+}
+
+/// Options controlling how a FIX message is validated.
+///
+/// The default options reproduce the behaviour of `validate`: SOH delimited
+/// messages, no size limit and only the checksum itself is verified.
+///
+/// # Examples
+///
+/// ```
+/// use fix_checksum::{validate_with, ValidationOptions};
+///
+/// let options = ValidationOptions::new()
+///   .delimiter('|')
+///   .max_message_size(4096)
+///   .check_body_length(true)
+///   .strict(true);
+///
+/// let message = "8=FIX.4.2|9=73|35=0|49=BRKR|56=INVMGR|34=235|52=19980604-07:58:28|\
+///   112=19980604-07:58:28|10=236|";
+/// assert!(validate_with(message, &options).unwrap());
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct ValidationOptions {
+  pub(crate) delimiter: u8,
+  pub(crate) strict: bool,
+  pub(crate) max_message_size: Option<usize>,
+  pub(crate) check_body_length: bool,
+  pub(crate) error_policy: ErrorPolicy,
+  pub(crate) diagnose_corruption: bool,
+  pub(crate) line_prefix: Option<LinePrefix>,
+  pub(crate) cache_size: usize,
+}
+
+impl ValidationOptions {
+  /// Creates options with the default behaviour.
+  pub fn new() -> ValidationOptions {
+    ValidationOptions {
+      delimiter: FIX_MESSAGE_DELIMITER as u8,
+      strict: false,
+      max_message_size: None,
+      check_body_length: false,
+      error_policy: ErrorPolicy::Stop,
+      diagnose_corruption: false,
+      line_prefix: None,
+      cache_size: 0,
+    }
+  }
+
+  /// Sets the field delimiter. Delimiters other than SOH are counted as SOH
+  /// when the checksum is computed, so pipe delimited logs validate against
+  /// the checksum of the original wire message.
+  ///
+  /// # Panics
+  ///
+  /// Panics if `delimiter` is not an ASCII character.
+  pub fn delimiter(mut self, delimiter: char) -> ValidationOptions {
+    assert!(delimiter.is_ascii(), "FIX delimiter must be an ASCII character");
+    self.delimiter = delimiter as u8;
+    self
+  }
+
+  /// Requires the checksum field to be exactly three digits, terminated by
+  /// the delimiter and to be the last field of the message.
+  pub fn strict(mut self, strict: bool) -> ValidationOptions {
+    self.strict = strict;
+    self
+  }
+
+  /// Rejects messages longer than `size` bytes before any other check.
+  pub fn max_message_size(mut self, size: usize) -> ValidationOptions {
+    self.max_message_size = Some(size);
+    self
+  }
+
+  /// Enables verification of the BodyLength (9) field.
+  pub fn check_body_length(mut self, check: bool) -> ValidationOptions {
+    self.check_body_length = check;
+    self
+  }
+
+  /// Sets the policy applied by stream validation on failures.
+  pub fn error_policy(mut self, policy: ErrorPolicy) -> ValidationOptions {
+    self.error_policy = policy;
+    self
+  }
+
+  /// Makes stream validation look for a likely cause of checksum
+  /// mismatches, see `diagnose::diagnose`.
+  pub fn diagnose_corruption(mut self, diagnose: bool) -> ValidationOptions {
+    self.diagnose_corruption = diagnose;
+    self
+  }
+
+  /// Makes stream validation skip `prefix` before the messages starting a
+  /// line. Lines starting right with a message are still read.
+  pub fn line_prefix(mut self, prefix: LinePrefix) -> ValidationOptions {
+    self.line_prefix = Some(prefix);
+    self
+  }
+
+  /// Makes stream validation remember the result of the last `size`
+  /// distinct messages, see `cache::ValidationCache`. Disabled by default.
+  pub fn cache_size(mut self, size: usize) -> ValidationOptions {
+    self.cache_size = size;
+    self
+  }
+}
+
+impl Default for ValidationOptions {
+  fn default() -> ValidationOptions {
+    ValidationOptions::new()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{ValidationOptions, ErrorPolicy, LinePrefix};
+
+  #[test]
+  fn it_should_build_validation_options() {
+    let options = ValidationOptions::new();
+    assert_eq!(options.delimiter, b'\x01');
+    assert!(!options.strict);
+    assert!(!options.check_body_length);
+    assert_eq!(options.max_message_size, None);
+    assert_eq!(options.error_policy, ErrorPolicy::Stop);
+    assert!(!options.diagnose_corruption);
+    assert_eq!(options.line_prefix, None);
+    assert_eq!(options.cache_size, 0);
+
+    let options = options.delimiter('|').strict(true).check_body_length(true).max_message_size(10)
+      .error_policy(ErrorPolicy::Repair).diagnose_corruption(true).cache_size(8);
+    assert_eq!(options.delimiter, b'|');
+    assert!(options.strict);
+    assert!(options.check_body_length);
+    assert_eq!(options.max_message_size, Some(10));
+    assert_eq!(options.error_policy, ErrorPolicy::Repair);
+    assert!(options.diagnose_corruption);
+    assert_eq!(options.cache_size, 8);
+  }
+
+  #[test]
+  fn it_should_measure_line_prefixes() {
+    let line = b"20240604-07:58:28.123 : 8=FIX.4.2";
+    assert_eq!(LinePrefix::FixedWidth(24).length(line), Some(24));
+    assert_eq!(LinePrefix::FixedWidth(40).length(line), None);
+    assert_eq!(LinePrefix::Separator(" : ".to_string()).length(line), Some(24));
+    assert_eq!(LinePrefix::Separator(" | ".to_string()).length(line), None);
+  }
+
+  #[test]
+  #[should_panic]
+  fn it_should_reject_non_ascii_delimiter() {
+    ValidationOptions::new().delimiter('ж');
+  }
+}
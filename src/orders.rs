@@ -0,0 +1,414 @@
+//! Typed builders of order messages.
+//!
+//! The builders check the values of their fields when the message is built
+//! and emit SOH delimited messages with BodyLength and CheckSum already set.
+
+use std::fmt;
+
+use super::{FIXChecksumValidatorError, ValidationOptions, finalize};
+use super::dictionary::{is_decimal, is_utc_timestamp};
+use super::FIXChecksumValidatorError::{RequiredFieldMissing, FieldValueInvalid};
+
+/// Side (54) of an order.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Side {
+  Buy,
+  Sell,
+  BuyMinus,
+  SellPlus,
+  SellShort,
+  SellShortExempt,
+}
+
+impl Side {
+  /// Returns the wire value of the side.
+  pub fn value(&self) -> &'static str {
+    match *self {
+      Side::Buy => "1",
+      Side::Sell => "2",
+      Side::BuyMinus => "3",
+      Side::SellPlus => "4",
+      Side::SellShort => "5",
+      Side::SellShortExempt => "6",
+    }
+  }
+}
+
+/// OrdType (40) of an order.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OrdType {
+  Market,
+  Limit,
+  Stop,
+  StopLimit,
+}
+
+impl OrdType {
+  /// Returns the wire value of the order type.
+  pub fn value(&self) -> &'static str {
+    match *self {
+      OrdType::Market => "1",
+      OrdType::Limit => "2",
+      OrdType::Stop => "3",
+      OrdType::StopLimit => "4",
+    }
+  }
+
+  fn requires_price(&self) -> bool {
+    *self == OrdType::Limit || *self == OrdType::StopLimit
+  }
+}
+
+/// TimeInForce (59) of an order.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TimeInForce {
+  Day,
+  GoodTillCancel,
+  AtTheOpening,
+  ImmediateOrCancel,
+  FillOrKill,
+  GoodTillCrossing,
+  GoodTillDate,
+}
+
+impl TimeInForce {
+  /// Returns the wire value of the time in force.
+  pub fn value(&self) -> &'static str {
+    match *self {
+      TimeInForce::Day => "0",
+      TimeInForce::GoodTillCancel => "1",
+      TimeInForce::AtTheOpening => "2",
+      TimeInForce::ImmediateOrCancel => "3",
+      TimeInForce::FillOrKill => "4",
+      TimeInForce::GoodTillCrossing => "5",
+      TimeInForce::GoodTillDate => "6",
+    }
+  }
+}
+
+/// Standard header fields shared by the messages of a session.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Header {
+  pub begin_string: String,
+  pub sender_comp_id: String,
+  pub target_comp_id: String,
+  pub msg_seq_num: u64,
+  /// SendingTime (52) as `YYYYMMDD-HH:MM:SS` with optional milliseconds.
+  pub sending_time: String,
+}
+
+impl Header {
+  /// Creates a header.
+  pub fn new(begin_string: &str, sender_comp_id: &str, target_comp_id: &str, msg_seq_num: u64,
+    sending_time: &str) -> Header {
+    Header {
+      begin_string: begin_string.to_string(),
+      sender_comp_id: sender_comp_id.to_string(),
+      target_comp_id: target_comp_id.to_string(),
+      msg_seq_num,
+      sending_time: sending_time.to_string(),
+    }
+  }
+}
+
+/// Builder of NewOrderSingle (35=D) messages.
+///
+/// # Examples
+///
+/// ```
+/// use fix_checksum::validate_bytes_with;
+/// use fix_checksum::ValidationOptions;
+/// use fix_checksum::orders::{Header, NewOrderSingle, OrdType, Side, TimeInForce};
+///
+/// let header = Header::new("FIX.4.2", "BUYER", "SELLER", 1, "20240102-09:30:00");
+/// let message = NewOrderSingle::new("ORD-1", "IBM", Side::Buy, "100", OrdType::Limit)
+///   .price("125.50")
+///   .time_in_force(TimeInForce::Day)
+///   .build(&header)
+///   .unwrap();
+/// assert!(validate_bytes_with(&message, &ValidationOptions::new().strict(true).check_body_length(true)).unwrap());
+///
+/// let error = NewOrderSingle::new("ORD-2", "IBM", Side::Sell, "100", OrdType::Limit).build(&header);
+/// assert_eq!(error.unwrap_err(), fix_checksum::FIXChecksumValidatorError::RequiredFieldMissing(44));
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct NewOrderSingle {
+  cl_ord_id: String,
+  symbol: String,
+  side: Side,
+  order_qty: String,
+  ord_type: OrdType,
+  price: Option<String>,
+  time_in_force: Option<TimeInForce>,
+  transact_time: Option<String>,
+}
+
+impl NewOrderSingle {
+  /// Creates a builder with the fields every order has.
+  pub fn new(cl_ord_id: &str, symbol: &str, side: Side, order_qty: &str, ord_type: OrdType) -> NewOrderSingle {
+    NewOrderSingle {
+      cl_ord_id: cl_ord_id.to_string(),
+      symbol: symbol.to_string(),
+      side,
+      order_qty: order_qty.to_string(),
+      ord_type,
+      price: None,
+      time_in_force: None,
+      transact_time: None,
+    }
+  }
+
+  /// Sets Price (44), required by limit and stop limit orders.
+  pub fn price(mut self, price: &str) -> NewOrderSingle {
+    self.price = Some(price.to_string());
+    self
+  }
+
+  /// Sets TimeInForce (59). Without it the counterparty assumes Day.
+  pub fn time_in_force(mut self, time_in_force: TimeInForce) -> NewOrderSingle {
+    self.time_in_force = Some(time_in_force);
+    self
+  }
+
+  /// Sets TransactTime (60). It defaults to the SendingTime of the header.
+  pub fn transact_time(mut self, transact_time: &str) -> NewOrderSingle {
+    self.transact_time = Some(transact_time.to_string());
+    self
+  }
+
+  /// Checks the fields and returns the finalized message.
+  pub fn build(&self, header: &Header) -> Result<Vec<u8>, FIXChecksumValidatorError> {
+    let mut message = FieldWriter::new(header, "D")?;
+    self.write_fields(&mut message, header)?;
+    message.finish()
+  }
+
+  fn write_fields(&self, message: &mut FieldWriter, header: &Header) -> Result<(), FIXChecksumValidatorError> {
+    message.text(11, &self.cl_ord_id)?;
+    message.text(55, &self.symbol)?;
+    message.field(54, self.side.value());
+    message.quantity(38, &self.order_qty)?;
+    message.field(40, self.ord_type.value());
+    match self.price {
+      Some(ref price) => message.price(44, price)?,
+      None if self.ord_type.requires_price() => return Err(RequiredFieldMissing(44)),
+      None => {},
+    }
+    if let Some(time_in_force) = self.time_in_force { message.field(59, time_in_force.value()); }
+    message.timestamp(60, self.transact_time.as_ref().unwrap_or(&header.sending_time))
+  }
+}
+
+/// Builder of OrderCancelRequest (35=F) messages.
+///
+/// # Examples
+///
+/// ```
+/// use fix_checksum::orders::{Header, OrderCancelRequest, Side};
+///
+/// let header = Header::new("FIX.4.2", "BUYER", "SELLER", 2, "20240102-09:31:00");
+/// let message = OrderCancelRequest::new("ORD-1", "ORD-2", "IBM", Side::Buy, "100").build(&header).unwrap();
+/// assert!(fix_checksum::validate(&String::from_utf8(message).unwrap()).unwrap());
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct OrderCancelRequest {
+  orig_cl_ord_id: String,
+  cl_ord_id: String,
+  symbol: String,
+  side: Side,
+  order_qty: String,
+  transact_time: Option<String>,
+}
+
+impl OrderCancelRequest {
+  /// Creates a builder cancelling the order `orig_cl_ord_id`.
+  pub fn new(orig_cl_ord_id: &str, cl_ord_id: &str, symbol: &str, side: Side, order_qty: &str)
+    -> OrderCancelRequest {
+    OrderCancelRequest {
+      orig_cl_ord_id: orig_cl_ord_id.to_string(),
+      cl_ord_id: cl_ord_id.to_string(),
+      symbol: symbol.to_string(),
+      side,
+      order_qty: order_qty.to_string(),
+      transact_time: None,
+    }
+  }
+
+  /// Sets TransactTime (60). It defaults to the SendingTime of the header.
+  pub fn transact_time(mut self, transact_time: &str) -> OrderCancelRequest {
+    self.transact_time = Some(transact_time.to_string());
+    self
+  }
+
+  /// Checks the fields and returns the finalized message.
+  pub fn build(&self, header: &Header) -> Result<Vec<u8>, FIXChecksumValidatorError> {
+    let mut message = FieldWriter::new(header, "F")?;
+    message.text(41, &self.orig_cl_ord_id)?;
+    message.text(11, &self.cl_ord_id)?;
+    message.text(55, &self.symbol)?;
+    message.field(54, self.side.value());
+    message.quantity(38, &self.order_qty)?;
+    message.timestamp(60, self.transact_time.as_ref().unwrap_or(&header.sending_time))?;
+    message.finish()
+  }
+}
+
+/// Builder of OrderCancelReplaceRequest (35=G) messages. The replacement
+/// order is described like a `NewOrderSingle`.
+///
+/// # Examples
+///
+/// ```
+/// use fix_checksum::orders::{Header, NewOrderSingle, OrderCancelReplaceRequest, OrdType, Side};
+///
+/// let header = Header::new("FIX.4.2", "BUYER", "SELLER", 3, "20240102-09:32:00");
+/// let order = NewOrderSingle::new("ORD-3", "IBM", Side::Buy, "150", OrdType::Limit).price("125.25");
+/// let message = OrderCancelReplaceRequest::new("ORD-1", order).build(&header).unwrap();
+/// assert!(fix_checksum::validate(&String::from_utf8(message).unwrap()).unwrap());
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct OrderCancelReplaceRequest {
+  orig_cl_ord_id: String,
+  order: NewOrderSingle,
+}
+
+impl OrderCancelReplaceRequest {
+  /// Creates a builder replacing the order `orig_cl_ord_id` with `order`.
+  pub fn new(orig_cl_ord_id: &str, order: NewOrderSingle) -> OrderCancelReplaceRequest {
+    OrderCancelReplaceRequest { orig_cl_ord_id: orig_cl_ord_id.to_string(), order }
+  }
+
+  /// Checks the fields and returns the finalized message.
+  pub fn build(&self, header: &Header) -> Result<Vec<u8>, FIXChecksumValidatorError> {
+    let mut message = FieldWriter::new(header, "G")?;
+    message.text(41, &self.orig_cl_ord_id)?;
+    self.order.write_fields(&mut message, header)?;
+    message.finish()
+  }
+}
+
+/// Serializes fields of a message under construction, checking their values.
+pub(crate) struct FieldWriter {
+  buffer: Vec<u8>,
+}
+
+impl FieldWriter {
+  /// Starts a message of type `msg_type` with the fields of `header`.
+  pub(crate) fn new(header: &Header, msg_type: &str) -> Result<FieldWriter, FIXChecksumValidatorError> {
+    let mut writer = FieldWriter { buffer: Vec::new() };
+    writer.text(8, &header.begin_string)?;
+    writer.field(35, msg_type);
+    writer.text(49, &header.sender_comp_id)?;
+    writer.text(56, &header.target_comp_id)?;
+    writer.field(34, header.msg_seq_num);
+    writer.timestamp(52, &header.sending_time)?;
+    Ok(writer)
+  }
+
+  /// Appends a field whose value is known to be valid.
+  pub(crate) fn field<T: fmt::Display>(&mut self, tag: u32, value: T) {
+    self.buffer.extend(format!("{}={}\x01", tag, value).into_bytes());
+  }
+
+  /// Appends a required free text field.
+  pub(crate) fn text(&mut self, tag: u32, value: &str) -> Result<(), FIXChecksumValidatorError> {
+    if value.is_empty() { return Err(RequiredFieldMissing(tag)); }
+    if value.contains('\x01') { return Err(FieldValueInvalid(tag)); }
+    self.field(tag, value);
+    Ok(())
+  }
+
+  /// Appends a strictly positive decimal field.
+  pub(crate) fn quantity(&mut self, tag: u32, value: &str) -> Result<(), FIXChecksumValidatorError> {
+    if !is_decimal(value) || value.starts_with('-') || !value.bytes().any(|b| b > b'0' && b <= b'9') {
+      return Err(FieldValueInvalid(tag));
+    }
+    self.field(tag, value);
+    Ok(())
+  }
+
+  /// Appends a decimal field, prices may be negative.
+  pub(crate) fn price(&mut self, tag: u32, value: &str) -> Result<(), FIXChecksumValidatorError> {
+    if !is_decimal(value) { return Err(FieldValueInvalid(tag)); }
+    self.field(tag, value);
+    Ok(())
+  }
+
+  /// Appends a UTC timestamp field.
+  pub(crate) fn timestamp(&mut self, tag: u32, value: &str) -> Result<(), FIXChecksumValidatorError> {
+    if !is_utc_timestamp(value) { return Err(FieldValueInvalid(tag)); }
+    self.field(tag, value);
+    Ok(())
+  }
+
+  /// Sets BodyLength and CheckSum and returns the message.
+  pub(crate) fn finish(self) -> Result<Vec<u8>, FIXChecksumValidatorError> {
+    finalize(&self.buffer, &ValidationOptions::new())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{Header, NewOrderSingle, OrderCancelRequest, OrderCancelReplaceRequest, OrdType, Side, TimeInForce};
+  use super::super::{ValidationOptions, validate_bytes_with};
+  use super::super::FIXChecksumValidatorError::{RequiredFieldMissing, FieldValueInvalid};
+
+  fn header() -> Header {
+    Header::new("FIX.4.2", "BUYER", "SELLER", 7, "20240102-09:30:00.125")
+  }
+
+  #[test]
+  fn it_should_build_new_order_single() {
+    let message = NewOrderSingle::new("ORD-1", "IBM", Side::Sell, "250", OrdType::Market)
+      .time_in_force(TimeInForce::ImmediateOrCancel)
+      .build(&header())
+      .unwrap();
+    let expected = "8=FIX.4.2\x019=117\x0135=D\x0149=BUYER\x0156=SELLER\x0134=7\x0152=20240102-09:30:00.125\x01\
+      11=ORD-1\x0155=IBM\x0154=2\x0138=250\x0140=1\x0159=3\x0160=20240102-09:30:00.125\x0110=";
+    assert!(String::from_utf8(message.clone()).unwrap().starts_with(expected));
+    let options = ValidationOptions::new().strict(true).check_body_length(true);
+    assert!(validate_bytes_with(&message, &options).unwrap());
+  }
+
+  #[test]
+  fn it_should_reject_invalid_fields() {
+    let order = NewOrderSingle::new("ORD-1", "IBM", Side::Buy, "100", OrdType::StopLimit);
+    assert_eq!(order.build(&header()).unwrap_err(), RequiredFieldMissing(44));
+    assert_eq!(order.clone().price("1,5").build(&header()).unwrap_err(), FieldValueInvalid(44));
+    assert_eq!(order.clone().price("-0.5").transact_time("2024-01-02").build(&header()).unwrap_err(),
+      FieldValueInvalid(60));
+    assert!(order.clone().price("-0.5").build(&header()).is_ok());
+
+    let order = NewOrderSingle::new("", "IBM", Side::Buy, "100", OrdType::Market);
+    assert_eq!(order.build(&header()).unwrap_err(), RequiredFieldMissing(11));
+    let order = NewOrderSingle::new("ORD-1", "IBM\x01", Side::Buy, "100", OrdType::Market);
+    assert_eq!(order.build(&header()).unwrap_err(), FieldValueInvalid(55));
+    let order = NewOrderSingle::new("ORD-1", "IBM", Side::Buy, "0.00", OrdType::Market);
+    assert_eq!(order.build(&header()).unwrap_err(), FieldValueInvalid(38));
+
+    let header = Header::new("FIX.4.2", "BUYER", "SELLER", 7, "20240102 09:30:00");
+    let order = NewOrderSingle::new("ORD-1", "IBM", Side::Buy, "100", OrdType::Market);
+    assert_eq!(order.build(&header).unwrap_err(), FieldValueInvalid(52));
+  }
+
+  #[test]
+  fn it_should_build_cancel_and_replace_requests() {
+    let options = ValidationOptions::new().strict(true).check_body_length(true);
+    let message = OrderCancelRequest::new("ORD-1", "ORD-2", "IBM", Side::Buy, "100").build(&header()).unwrap();
+    let expected = "35=F\x0149=BUYER\x0156=SELLER\x0134=7\x0152=20240102-09:30:00.125\x01\
+      41=ORD-1\x0111=ORD-2\x0155=IBM\x0154=1\x0138=100\x0160=20240102-09:30:00.125\x0110=";
+    assert!(String::from_utf8(message.clone()).unwrap().contains(expected));
+    assert!(validate_bytes_with(&message, &options).unwrap());
+    let cancel = OrderCancelRequest::new("", "ORD-2", "IBM", Side::Buy, "100");
+    assert_eq!(cancel.build(&header()).unwrap_err(), RequiredFieldMissing(41));
+
+    let order = NewOrderSingle::new("ORD-3", "IBM", Side::Buy, "150", OrdType::Limit);
+    let replace = OrderCancelReplaceRequest::new("ORD-1", order.clone());
+    assert_eq!(replace.build(&header()).unwrap_err(), RequiredFieldMissing(44));
+    let message = OrderCancelReplaceRequest::new("ORD-1", order.price("99.5")).build(&header()).unwrap();
+    let expected = "35=G\x0149=BUYER\x0156=SELLER\x0134=7\x0152=20240102-09:30:00.125\x01\
+      41=ORD-1\x0111=ORD-3\x0155=IBM\x0154=1\x0138=150\x0140=2\x0144=99.5\x0160=20240102-09:30:00.125\x0110=";
+    assert!(String::from_utf8(message.clone()).unwrap().contains(expected));
+    assert!(validate_bytes_with(&message, &options).unwrap());
+  }
+}
@@ -0,0 +1,119 @@
+//! Recycling of owned messages for sustained high message rates.
+
+use super::FIXChecksumValidatorError;
+use super::message::FixMessage;
+
+/// A bounded stack of released messages handed out again by `acquire` and
+/// `decode`, so that the storage of their fields is allocated once.
+///
+/// # Examples
+///
+/// ```
+/// use fix_checksum::pool::MessagePool;
+///
+/// let mut pool = MessagePool::new(2).delimiter('|');
+/// let message = pool.decode(b"8=FIX.4.2|35=0|").unwrap();
+/// assert_eq!(message.get_str(35), Some("0"));
+/// pool.release(message);
+/// assert_eq!(pool.len(), 1);
+///
+/// let message = pool.decode(b"8=FIX.4.2|35=1|").unwrap();
+/// assert_eq!(message.get_str(35), Some("1"));
+/// assert!(pool.is_empty());
+/// ```
+#[derive(Clone, Debug)]
+pub struct MessagePool {
+  free: Vec<FixMessage>,
+  capacity: usize,
+  delimiter: char,
+}
+
+impl MessagePool {
+  /// Creates a pool keeping at most `capacity` released SOH delimited
+  /// messages.
+  pub fn new(capacity: usize) -> MessagePool {
+    MessagePool { free: Vec::with_capacity(capacity), capacity, delimiter: '\x01' }
+  }
+
+  /// Sets the delimiter of the messages handed out by the pool.
+  pub fn delimiter(mut self, delimiter: char) -> MessagePool {
+    assert!(delimiter.is_ascii(), "FIX delimiter must be an ASCII character");
+    self.delimiter = delimiter;
+    self.free.clear();
+    self
+  }
+
+  /// Returns the maximum number of messages kept.
+  pub fn capacity(&self) -> usize {
+    self.capacity
+  }
+
+  /// Returns the number of messages ready to be handed out.
+  pub fn len(&self) -> usize {
+    self.free.len()
+  }
+
+  /// Returns `true` if the next message handed out will be allocated.
+  pub fn is_empty(&self) -> bool {
+    self.free.is_empty()
+  }
+
+  /// Returns an empty message, recycled if one is available.
+  pub fn acquire(&mut self) -> FixMessage {
+    match self.free.pop() {
+      Some(mut message) => { message.clear(); message },
+      None => FixMessage::new(self.delimiter),
+    }
+  }
+
+  /// Parses `message` into a recycled message, reusing the storage of its
+  /// field values. On failure the recycled message goes back to the pool.
+  pub fn decode(&mut self, message: &[u8]) -> Result<FixMessage, FIXChecksumValidatorError> {
+    let mut decoded = self.free.pop().unwrap_or_else(|| FixMessage::new(self.delimiter));
+    match decoded.reparse(message) {
+      Ok(()) => Ok(decoded),
+      Err(err) => { self.release(decoded); Err(err) },
+    }
+  }
+
+  /// Returns a message to the pool. Messages over capacity or with another
+  /// delimiter are dropped.
+  pub fn release(&mut self, message: FixMessage) {
+    if self.free.len() < self.capacity && message.delimiter() == self.delimiter { self.free.push(message); }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::MessagePool;
+  use super::super::message::FixMessage;
+  use super::super::FIXChecksumValidatorError::FieldInvalidFormat;
+
+  #[test]
+  fn it_should_recycle_messages_up_to_capacity() {
+    let mut pool = MessagePool::new(1);
+    let (first, second) = (pool.acquire(), pool.acquire());
+    pool.release(first);
+    pool.release(second);
+    pool.release(FixMessage::new('|'));
+    assert_eq!((pool.len(), pool.capacity()), (1, 1));
+
+    let mut message = pool.acquire();
+    assert!(message.is_empty() && pool.is_empty());
+    message.set(35, b"0");
+    pool.release(message);
+    assert!(pool.acquire().is_empty());
+  }
+
+  #[test]
+  fn it_should_decode_into_recycled_messages() {
+    let mut pool = MessagePool::new(4).delimiter('|');
+    pool.release(FixMessage::parse(b"8=FIX.4.2|35=D|55=IBM|54=1|38=100|", '|').unwrap());
+    let message = pool.decode(b"8=FIX.4.4|35=0|").unwrap();
+    assert_eq!(message, FixMessage::parse(b"8=FIX.4.4|35=0|", '|').unwrap());
+
+    pool.release(message);
+    assert_eq!(pool.decode(b"8=FIX.4.2||").unwrap_err(), FieldInvalidFormat);
+    assert_eq!(pool.len(), 1);
+  }
+}
@@ -0,0 +1,168 @@
+//! Human readable rendering of FIX messages.
+
+use std::fmt;
+use std::fmt::Write;
+
+use super::FIXChecksumValidatorError;
+use super::dictionary::Dictionary;
+use super::message::MessageView;
+
+/// This function renders one field per line, naming standard tags.
+///
+/// # Examples
+///
+/// ```
+/// let message = b"8=FIX.4.2|9=5|35=0|10=161|";
+/// assert_eq!(fix_checksum::pretty::pretty_print(message, '|').unwrap(),
+///   "BeginString (8) = FIX.4.2\nBodyLength (9) = 5\nMsgType (35) = 0\nCheckSum (10) = 161\n");
+/// ```
+pub fn pretty_print(message: &[u8], delimiter: char) -> Result<String, FIXChecksumValidatorError> {
+  pretty_print_with(message, delimiter, &Dictionary::new())
+}
+
+/// This function renders one field per line, naming standard tags and the
+/// user-defined tags registered in `dictionary`.
+///
+/// # Examples
+///
+/// ```
+/// use fix_checksum::dictionary::{Dictionary, FieldType};
+/// use fix_checksum::pretty::pretty_print_with;
+///
+/// let mut dictionary = Dictionary::new();
+/// dictionary.register(5001, "StrategyID", FieldType::Int).unwrap();
+/// assert_eq!(pretty_print_with(b"35=D|5001=7|", '|', &dictionary).unwrap(),
+///   "MsgType (35) = D\nStrategyID (5001) = 7\n");
+/// ```
+pub fn pretty_print_with(message: &[u8], delimiter: char, dictionary: &Dictionary)
+  -> Result<String, FIXChecksumValidatorError> {
+  let message = MessageView::parse(message, delimiter)?;
+  let mut output = String::new();
+  for &(tag, value) in &message.fields {
+    let value = String::from_utf8_lossy(value);
+    match dictionary.tag_name(tag) {
+      Some(name) => output.push_str(&format!("{} ({}) = {}\n", name, tag, value)),
+      None => output.push_str(&format!("{} = {}\n", tag, value)),
+    }
+  }
+  Ok(output)
+}
+
+/// Display adapter rendering bytes as a hex dump, 16 per line or as many as
+/// the width of the format, with their text beside them and dots for
+/// control and non-ASCII bytes. `Debug` renders the same, for `dbg!`.
+///
+/// # Examples
+///
+/// ```
+/// use fix_checksum::pretty::HexDump;
+///
+/// let message = b"8=FIX.4.2\x019=5\x0135=0\x0110=161\x01";
+/// assert_eq!(HexDump(message).to_string(), "\
+///   00000000  38 3d 46 49 58 2e 34 2e 32 01 39 3d 35 01 33 35  |8=FIX.4.2.9=5.35|\n\
+///   00000010  3d 30 01 31 30 3d 31 36 31 01                    |=0.10=161.|\n");
+/// assert_eq!(format!("{:8}", HexDump(&message[..10])), "\
+///   00000000  38 3d 46 49 58 2e 34 2e  |8=FIX.4.|\n\
+///   00000008  32 01                    |2.|\n");
+/// ```
+#[derive(Clone, Copy, PartialEq)]
+pub struct HexDump<'a>(pub &'a [u8]);
+
+impl<'a> fmt::Display for HexDump<'a> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let width = f.width().unwrap_or(16).max(1);
+    for (index, line) in self.0.chunks(width).enumerate() {
+      write_hex_line(f, index * width, line, width)?;
+    }
+    Ok(())
+  }
+}
+
+impl<'a> fmt::Debug for HexDump<'a> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    fmt::Display::fmt(self, f)
+  }
+}
+
+/// This function renders the bytes of `message` around `offset` as a hex
+/// dump of `width` bytes per line, with the text of every line beside it and
+/// dots for control and non-ASCII bytes: the line holding `offset`, the
+/// lines before and after it, and a caret under the byte at `offset`.
+///
+/// # Examples
+///
+/// ```
+/// let dump = fix_checksum::pretty::hex_dump_around(b"8=FIX.4.2\x019=5\x0135=0\x0110=1\x0061\x01", 23, 8);
+/// assert_eq!(dump, "\
+///   00000008  32 01 39 3d 35 01 33 35  |2.9=5.35|\n\
+///   00000010  3d 30 01 31 30 3d 31 00  |=0.10=1.|\n\
+///   \x20                              ^^\n\
+///   00000018  36 31 01                 |61.|\n");
+/// ```
+pub fn hex_dump_around(message: &[u8], offset: usize, width: usize) -> String {
+  let width = width.max(1);
+  let mut output = String::new();
+  if message.is_empty() { return output; }
+  let line = offset.min(message.len() - 1) / width;
+  for index in line.saturating_sub(1)..line + 2 {
+    let start = index * width;
+    if start >= message.len() { break; }
+    let _ = write_hex_line(&mut output, start, &message[start..message.len().min(start + width)], width);
+    if index == line {
+      let column = offset.min(message.len() - 1) - start;
+      let _ = writeln!(output, "{:1$}^^", "", 10 + 3 * column);
+    }
+  }
+  output
+}
+
+/// Writes the hex dump line of `bytes`, found at `offset`, padded to `width`
+/// bytes.
+fn write_hex_line<W: Write>(output: &mut W, offset: usize, bytes: &[u8], width: usize) -> fmt::Result {
+  write!(output, "{:08x} ", offset)?;
+  for byte in bytes {
+    write!(output, " {:02x}", byte)?;
+  }
+  write!(output, "{:1$}  |", "", 3 * (width - bytes.len()))?;
+  for &byte in bytes {
+    output.write_char(if byte.is_ascii_graphic() || byte == b' ' { byte as char } else { '.' })?;
+  }
+  writeln!(output, "|")
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{HexDump, hex_dump_around, pretty_print};
+  use super::super::FIXChecksumValidatorError::FieldInvalidFormat;
+
+  #[test]
+  fn it_should_pretty_print_message() {
+    assert_eq!(pretty_print(b"8=FIX.4.4\x015001=x\x01", '\x01').unwrap(),
+      "BeginString (8) = FIX.4.4\n5001 = x\n");
+    assert_eq!(pretty_print(b"8=FIX.4.4\x01garbage", '\x01').unwrap_err(), FieldInvalidFormat);
+  }
+
+  #[test]
+  fn it_should_dump_bytes_around_offset() {
+    let message = b"8=FIX.4.2\x019=5\x0135=0\x0110=161\x01";
+    assert_eq!(hex_dump_around(message, 0, 16), "\
+      00000000  38 3d 46 49 58 2e 34 2e 32 01 39 3d 35 01 33 35  |8=FIX.4.2.9=5.35|\n\
+      \x20         ^^\n\
+      00000010  3d 30 01 31 30 3d 31 36 31 01                    |=0.10=161.|\n");
+    let dump = hex_dump_around(message, 9, 4);
+    assert_eq!(dump.lines().map(|line| &line[..8]).collect::<Vec<_>>(), vec!["00000004", "00000008", "        ",
+      "0000000c"]);
+    assert!(dump.contains("00000008  32 01 39 3d  |2.9=|\n             ^^\n"), "{}", dump);
+    assert_eq!(hex_dump_around(b"8=\xff", 99, 0), "00000001  3d  |=|\n00000002  ff  |.|\n          ^^\n");
+    assert_eq!(hex_dump_around(b"", 0, 16), "");
+  }
+
+  #[test]
+  fn it_should_dump_whole_buffers() {
+    assert_eq!(HexDump(b"").to_string(), "");
+    assert_eq!(format!("{:?}", HexDump(b"10=\x7f\xe2")),
+      "00000000  31 30 3d 7f e2                                   |10=..|\n");
+    assert_eq!(format!("{:1}", HexDump(b"8=")), "00000000  38  |8|\n00000001  3d  |=|\n");
+    assert_eq!(format!("{:2}", HexDump(b"8=F")).lines().last(), Some("00000002  46     |F|"));
+  }
+}
@@ -0,0 +1,101 @@
+//! Bundles of options and rules for common counterparties.
+
+use super::ValidationOptions;
+use super::rules::{BeginString, FieldOrder, HeaderOrder, RequiredFields, SessionFieldFormats};
+use super::validator::Validator;
+
+/// Header fields every application and session message carries.
+const STANDARD_HEADER: &[u32] = &[8, 9, 35, 49, 56, 34, 52];
+
+/// A selectable set of structural checks.
+///
+/// # Examples
+///
+/// ```
+/// use fix_checksum::profile::Profile;
+///
+/// let validator = Profile::Fix42.validator();
+/// let report = validator.validate(b"8=FIX.4.4\x019=5\x0135=0\x0110=163\x01");
+/// assert!(!report.is_valid());
+/// assert_eq!(Profile::from_name("strict-session"), Some(Profile::StrictSessionLayer));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Profile {
+  /// FIX 4.2 messages with a correct BodyLength and the standard header.
+  Fix42,
+  /// FIX 4.4 messages with a correct BodyLength and the standard header.
+  Fix44,
+  /// Any FIX 4.x or FIXT 1.1 message with strict trailer, header order and
+  /// well-formed session fields.
+  StrictSessionLayer,
+}
+
+impl Profile {
+  /// Every profile, for listing in user interfaces.
+  pub const ALL: &'static [Profile] = &[Profile::Fix42, Profile::Fix44, Profile::StrictSessionLayer];
+
+  /// Returns the name used by `from_name`.
+  pub fn name(&self) -> &'static str {
+    match *self {
+      Profile::Fix42 => "fix42",
+      Profile::Fix44 => "fix44",
+      Profile::StrictSessionLayer => "strict-session",
+    }
+  }
+
+  /// Returns the profile with the given name.
+  pub fn from_name(name: &str) -> Option<Profile> {
+    Profile::ALL.iter().find(|profile| profile.name() == name).cloned()
+  }
+
+  /// Returns the checksum validation options of the profile.
+  pub fn options(&self) -> ValidationOptions {
+    match *self {
+      Profile::Fix42 | Profile::Fix44 => ValidationOptions::new().check_body_length(true),
+      Profile::StrictSessionLayer => ValidationOptions::new().check_body_length(true).strict(true),
+    }
+  }
+
+  /// Returns a validator enforcing the profile. More rules can be
+  /// registered on it.
+  pub fn validator(&self) -> Validator {
+    let validator = Validator::new(self.options()).rule(RequiredFields(STANDARD_HEADER));
+    match *self {
+      Profile::Fix42 => validator.rule(BeginString(&["FIX.4.2"])),
+      Profile::Fix44 => validator.rule(BeginString(&["FIX.4.4"])),
+      Profile::StrictSessionLayer => validator
+        .rule(BeginString(&["FIX.4.0", "FIX.4.1", "FIX.4.2", "FIX.4.3", "FIX.4.4", "FIXT.1.1"]))
+        .rule(HeaderOrder)
+        .rule(FieldOrder)
+        .rule(SessionFieldFormats),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::Profile;
+
+  const HEARTBEAT: &[u8] = b"8=FIX.4.2\x019=46\x0135=0\x0149=A\x0156=B\x0134=1\x0152=20240102-09:30:00\x01\
+    43=N\x0110=115\x01";
+
+  #[test]
+  fn it_should_apply_profiles() {
+    assert!(Profile::Fix42.validator().validate(HEARTBEAT).is_valid());
+    assert!(Profile::StrictSessionLayer.validator().validate(HEARTBEAT).is_valid());
+    let issues = Profile::Fix44.validator().validate(HEARTBEAT).issues().to_vec();
+    assert_eq!(issues.len(), 1);
+    assert_eq!(issues[0].tag, Some(8));
+
+    let message = b"8=FIX.4.2\x019=46\x0135=0\x0149=A\x0156=B\x0134=1\x0152=20240102-09:30:00\x01\
+      43=X\x0110=125\x01";
+    assert!(Profile::Fix42.validator().validate(message).is_valid());
+    assert!(!Profile::StrictSessionLayer.validator().validate(message).is_valid());
+  }
+
+  #[test]
+  fn it_should_name_profiles() {
+    for profile in Profile::ALL { assert_eq!(Profile::from_name(profile.name()), Some(*profile)); }
+    assert_eq!(Profile::from_name("fix50"), None);
+  }
+}
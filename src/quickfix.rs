@@ -0,0 +1,426 @@
+//! Reading of the logs written by QuickFIX engines.
+//!
+//! Two layouts are understood, possibly mixed in one stream:
+//!
+//! * the file logs `messages.current.log` and `event.current.log`, one
+//!   record per line prefixed by a timestamp, as in
+//!   `20240604-07:58:28.123 : 8=FIX.4.2...` or, for QuickFIX/J,
+//!   `20240604-07:58:28.123: 8=FIX.4.2...`;
+//! * the screen log, a header line such as
+//!   `<20240604-07:58:28.123, FIX.4.2:BRKR->INVMGR, incoming>` followed by
+//!   the record in parentheses on the next line.
+//!
+//! The file logs do not say in which direction a message went; it is
+//! derived from the comp ids of the message when the local session is
+//! known, see `QuickFixReader::local_session`.
+//!
+//! The messages and sequence numbers kept by a QuickFIX `FileStore` are
+//! read and written by `FileStore`.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::fs::OpenOptions;
+use std::io;
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::str;
+use std::time::SystemTime;
+
+use super::{ErrorPolicy, ValidationOptions};
+use super::clock::{format_utc_timestamp, parse_utc_timestamp};
+use super::message::MessageView;
+use super::session::SessionId;
+use super::store::{Checkpoint, MessageStore};
+use super::stream::{check, Frame, StreamResult};
+
+/// Whether a message was received or sent by the engine writing the log.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Direction {
+  Incoming,
+  Outgoing,
+}
+
+/// A record of a QuickFIX log.
+#[derive(Debug, PartialEq)]
+pub enum QuickFixEntry {
+  /// A validated message.
+  Message {
+    /// Time the engine logged the message at.
+    time: Option<SystemTime>,
+    /// `None` when the log does not tell and no local session is set.
+    direction: Option<Direction>,
+    result: StreamResult,
+  },
+  /// An event such as `Created session` or `Received logon`.
+  Event {
+    time: Option<SystemTime>,
+    text: String,
+  },
+}
+
+/// Reads the records of QuickFIX logs, validating their messages. Lines in
+/// neither layout are skipped.
+///
+/// # Examples
+///
+/// ```
+/// use fix_checksum::ValidationOptions;
+/// use fix_checksum::quickfix::{Direction, QuickFixEntry, QuickFixReader};
+/// use fix_checksum::session::SessionId;
+/// use fix_checksum::stream::Outcome;
+///
+/// let log = "20240604-07:58:28.123 : 8=FIX.4.2|9=28|35=0|34=2|49=BRKR|56=INVMGR|10=005|\n\
+///   20240604-07:58:28.125 : Received logon\n";
+/// let reader = QuickFixReader::new(log.as_bytes(), ValidationOptions::new().delimiter('|'))
+///   .local_session(SessionId::new("INVMGR", "BRKR"));
+/// let entries: Vec<QuickFixEntry> = reader.map(|entry| entry.unwrap()).collect();
+/// match entries[0] {
+///   QuickFixEntry::Message { direction, ref result, .. } => {
+///     assert_eq!(direction, Some(Direction::Incoming));
+///     assert_eq!(result.outcome, Outcome::Valid);
+///     assert_eq!(result.offset, 24);
+///   },
+///   _ => panic!("expected a message"),
+/// }
+/// ```
+#[derive(Debug)]
+pub struct QuickFixReader<R> {
+  inner: R,
+  options: ValidationOptions,
+  local: Option<SessionId>,
+  line: Vec<u8>,
+  offset: usize,
+  header: Option<(Option<SystemTime>, Option<Direction>)>,
+  stopped: bool,
+}
+
+impl<R: BufRead> QuickFixReader<R> {
+  /// Creates a reader of `inner`, validating messages with `options`.
+  pub fn new(inner: R, options: ValidationOptions) -> QuickFixReader<R> {
+    QuickFixReader { inner, options, local: None, line: Vec::new(), offset: 0, header: None, stopped: false }
+  }
+
+  /// Sets the session of the engine writing the log, its SenderCompID
+  /// being the one of outgoing messages.
+  pub fn local_session(mut self, session: SessionId) -> QuickFixReader<R> {
+    self.local = Some(session);
+    self
+  }
+
+  /// Returns the direction of `message` relative to the local session.
+  fn direction(&self, message: &[u8]) -> Option<Direction> {
+    let local = self.local.as_ref()?;
+    let session = SessionId::from_message(&MessageView::parse(message, self.options.delimiter as char).ok()?)?;
+    if session == *local {
+      Some(Direction::Outgoing)
+    } else if session.sender_comp_id == local.target_comp_id && session.target_comp_id == local.sender_comp_id {
+      Some(Direction::Incoming)
+    } else {
+      None
+    }
+  }
+
+  /// Turns the record found at `start` of the current line into an entry.
+  fn entry(&self, start: usize, end: usize, time: Option<SystemTime>, direction: Option<Direction>)
+    -> QuickFixEntry {
+    let record = &self.line[start..end];
+    if !record.starts_with(b"8=") {
+      return QuickFixEntry::Event { time, text: String::from_utf8_lossy(record).into_owned() };
+    }
+    let direction = direction.or_else(|| self.direction(record));
+    let result = check(Frame { offset: self.offset + start, bytes: record.to_vec() }, &self.options);
+    QuickFixEntry::Message { time, direction, result }
+  }
+}
+
+impl<R: BufRead> Iterator for QuickFixReader<R> {
+  type Item = io::Result<QuickFixEntry>;
+
+  fn next(&mut self) -> Option<io::Result<QuickFixEntry>> {
+    while !self.stopped {
+      self.offset += self.line.len();
+      self.line.clear();
+      match self.inner.read_until(b'\n', &mut self.line) {
+        Ok(0) => return None,
+        Ok(_) => {},
+        Err(err) => {
+          self.stopped = true;
+          return Some(Err(err));
+        },
+      }
+      let end = self.line.len() - self.line.iter().rev().take_while(|&&b| b == b'\n' || b == b'\r').count();
+
+      let entry = if let Some((time, direction)) = self.header.take() {
+        match parenthesized(&self.line[..end]) {
+          Some((start, end)) => self.entry(start, end, time, direction),
+          None => continue,
+        }
+      } else if let Some(header) = screen_header(&self.line[..end]) {
+        self.header = Some(header);
+        continue;
+      } else {
+        match timestamped(&self.line[..end]) {
+          Some((time, start)) => self.entry(start, end, Some(time), None),
+          None => continue,
+        }
+      };
+
+      if let QuickFixEntry::Message { ref result, .. } = entry {
+        if result.outcome.is_failure() && self.options.error_policy == ErrorPolicy::Stop { self.stopped = true; }
+      }
+      return Some(Ok(entry));
+    }
+    None
+  }
+}
+
+/// Splits a file log line into its timestamp and the index of its record.
+fn timestamped(line: &[u8]) -> Option<(SystemTime, usize)> {
+  let run = line.iter().take_while(|&&b| b.is_ascii_digit() || b == b'-' || b == b':' || b == b'.').count();
+  let stamp = line[..run].strip_suffix(b":").unwrap_or(&line[..run]);
+  let time = parse_utc_timestamp(str::from_utf8(stamp).ok()?)?;
+  let spaces = |from: usize| from + line[from..].iter().take_while(|&&b| b == b' ').count();
+  let colon = spaces(stamp.len());
+  if line.get(colon) != Some(&b':') { return None; }
+  Some((time, spaces(colon + 1)))
+}
+
+/// Parses a screen log header, `<TIME, SESSION, incoming|outgoing|event>`.
+fn screen_header(line: &[u8]) -> Option<(Option<SystemTime>, Option<Direction>)> {
+  let header = str::from_utf8(line.strip_prefix(b"<")?.strip_suffix(b">")?).ok()?;
+  let mut parts = header.split(", ");
+  let time = parse_utc_timestamp(parts.next()?);
+  parts.next()?;
+  let direction = match parts.next()? {
+    "incoming" => Some(Direction::Incoming),
+    "outgoing" => Some(Direction::Outgoing),
+    "event" => None,
+    _ => return None,
+  };
+  Some((time, direction))
+}
+
+/// Returns the range of the record within the parentheses of a screen log
+/// record line.
+fn parenthesized(line: &[u8]) -> Option<(usize, usize)> {
+  let start = line.iter().position(|&b| b != b' ')?;
+  if line[start] != b'(' || line.last() != Some(&b')') || line.len() < start + 2 { return None; }
+  Some((start + 1, line.len() - 1))
+}
+
+/// A `MessageStore` in the layout of the QuickFIX `FileStore`, so that
+/// stores of existing engines can be replayed and audited. The files of a
+/// session are named after its BeginString and comp ids, as
+/// `FIX.4.2-BRKR-INVMGR`:
+///
+/// * `.body`, the messages one after the other;
+/// * `.header`, the sequence number, offset and size in the body of every
+///   message, as `1,0,64 `; the last entry of a number wins;
+/// * `.seqnums`, the next sender and target sequence numbers, as
+///   `0000000002 : 0000000001`;
+/// * `.session`, the creation time of the session.
+///
+/// The resend ranges of a `Checkpoint` have no place in the layout and are
+/// not kept.
+///
+/// # Examples
+///
+/// ```
+/// use std::env;
+/// use fix_checksum::quickfix::FileStore;
+/// use fix_checksum::session::SessionId;
+/// use fix_checksum::store::{Checkpoint, MessageStore};
+///
+/// let dir = env::temp_dir().join(format!("fix-checksum-filestore-doc-{}", std::process::id()));
+/// let session = SessionId::new("BRKR", "INVMGR");
+/// let mut store = FileStore::open(&dir, "FIX.4.2").unwrap();
+/// store.store(&session, 1, b"8=FIX.4.2|9=5|35=0|10=161|").unwrap();
+/// store.checkpoint(&session, &Checkpoint { last_sent: 1, last_received: 0, resend_ranges: vec![] }).unwrap();
+/// assert_eq!(store.messages(&session, 1, 1).unwrap().len(), 1);
+/// assert_eq!(store.restore(&session).unwrap().next_sender_seq_num(), 2);
+/// # std::fs::remove_dir_all(&dir).unwrap();
+/// ```
+#[derive(Clone, Debug)]
+pub struct FileStore {
+  dir: PathBuf,
+  begin_string: String,
+}
+
+impl FileStore {
+  /// Opens the store of the sessions of `begin_string` in `dir`, creating
+  /// the directory if needed.
+  pub fn open<P: AsRef<Path>>(dir: P, begin_string: &str) -> io::Result<FileStore> {
+    fs::create_dir_all(dir.as_ref())?;
+    Ok(FileStore { dir: dir.as_ref().to_path_buf(), begin_string: begin_string.to_string() })
+  }
+
+  fn path(&self, session: &SessionId, extension: &str) -> PathBuf {
+    self.dir.join(format!("{}-{}-{}.{}", self.begin_string, session.sender_comp_id, session.target_comp_id,
+      extension))
+  }
+
+  /// Returns the creation time of `session`, `None` if it has no files.
+  pub fn created(&self, session: &SessionId) -> io::Result<Option<SystemTime>> {
+    match fs::read_to_string(self.path(session, "session")) {
+      Ok(contents) => parse_utc_timestamp(contents.trim()).map(Some)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "invalid session creation time")),
+      Err(ref err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+      Err(err) => Err(err),
+    }
+  }
+
+  /// Returns the offset and size in the body of the messages of `session`
+  /// by sequence number.
+  fn headers(&self, session: &SessionId) -> io::Result<BTreeMap<u64, (u64, u64)>> {
+    let contents = match fs::read_to_string(self.path(session, "header")) {
+      Ok(contents) => contents,
+      Err(ref err) if err.kind() == io::ErrorKind::NotFound => return Ok(BTreeMap::new()),
+      Err(err) => return Err(err),
+    };
+    let mut headers = BTreeMap::new();
+    for entry in contents.split_whitespace() {
+      let mut numbers = entry.split(',').map(|number| number.parse::<u64>().ok());
+      match (numbers.next().flatten(), numbers.next().flatten(), numbers.next().flatten(), numbers.next()) {
+        (Some(seq_num), Some(offset), Some(size), None) => { headers.insert(seq_num, (offset, size)); },
+        _ => return Err(io::Error::new(io::ErrorKind::InvalidData, format!("invalid header entry {:?}", entry))),
+      }
+    }
+    Ok(headers)
+  }
+
+  fn ensure_created(&self, session: &SessionId) -> io::Result<()> {
+    if self.created(session)?.is_none() {
+      fs::write(self.path(session, "session"), format_utc_timestamp(SystemTime::now()))?;
+    }
+    Ok(())
+  }
+}
+
+impl MessageStore for FileStore {
+  fn store(&mut self, session: &SessionId, seq_num: u64, message: &[u8]) -> io::Result<()> {
+    self.ensure_created(session)?;
+    let mut body = OpenOptions::new().create(true).append(true).open(self.path(session, "body"))?;
+    let offset = body.metadata()?.len();
+    body.write_all(message)?;
+    let mut header = OpenOptions::new().create(true).append(true).open(self.path(session, "header"))?;
+    write!(header, "{},{},{} ", seq_num, offset, message.len())
+  }
+
+  fn messages(&self, session: &SessionId, begin: u64, end: u64) -> io::Result<Vec<(u64, Vec<u8>)>> {
+    if begin > end { return Ok(Vec::new()); }
+    let headers = self.headers(session)?;
+    if headers.is_empty() { return Ok(Vec::new()); }
+    let body = fs::read(self.path(session, "body"))?;
+    headers.range(begin..=end).map(|(&seq_num, &(offset, size))| {
+      let range = offset as usize..offset.saturating_add(size) as usize;
+      body.get(range).map(|message| (seq_num, message.to_vec()))
+        .ok_or_else(|| io::Error::new(io::ErrorKind::UnexpectedEof, format!("message {} is past the body", seq_num)))
+    }).collect()
+  }
+
+  fn checkpoint(&mut self, session: &SessionId, checkpoint: &Checkpoint) -> io::Result<()> {
+    self.ensure_created(session)?;
+    fs::write(self.path(session, "seqnums"),
+      format!("{:010} : {:010}", checkpoint.next_sender_seq_num(), checkpoint.next_target_seq_num()))
+  }
+
+  fn restore(&self, session: &SessionId) -> io::Result<Checkpoint> {
+    let contents = match fs::read_to_string(self.path(session, "seqnums")) {
+      Ok(contents) => contents,
+      Err(ref err) if err.kind() == io::ErrorKind::NotFound => return Ok(Checkpoint::new()),
+      Err(err) => return Err(err),
+    };
+    let next = contents.split_once(':')
+      .and_then(|(sender, target)| Some((sender.trim().parse::<u64>().ok()?, target.trim().parse::<u64>().ok()?)));
+    match next {
+      Some((sender, target)) => Ok(Checkpoint {
+        last_sent: sender.saturating_sub(1),
+        last_received: target.saturating_sub(1),
+        resend_ranges: Vec::new(),
+      }),
+      None => Err(io::Error::new(io::ErrorKind::InvalidData, "invalid seqnums file")),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::env;
+  use std::fs;
+  use super::{Direction, FileStore, QuickFixEntry, QuickFixReader};
+  use super::super::{ErrorPolicy, ValidationOptions};
+  use super::super::clock::parse_utc_timestamp;
+  use super::super::session::SessionId;
+  use super::super::store::{Checkpoint, MessageStore};
+  use super::super::stream::Outcome;
+
+  fn summarize(log: &str, options: ValidationOptions) -> Vec<String> {
+    QuickFixReader::new(log.as_bytes(), options).local_session(SessionId::new("BRKR", "INVMGR"))
+      .map(|entry| match entry.unwrap() {
+        QuickFixEntry::Message { direction, result, .. } =>
+          format!("{:?} {} {}", direction, result.offset, result.outcome.kind()),
+        QuickFixEntry::Event { text, .. } => text,
+      })
+      .collect()
+  }
+
+  #[test]
+  fn it_should_read_file_logs() {
+    let log = "20240604-07:58:28.123 : 8=FIX.4.2|9=28|35=0|34=2|49=BRKR|56=INVMGR|10=005|\r\n\
+      20240604-07:58:28.124: 8=FIX.4.2|9=28|35=0|34=3|49=INVMGR|56=BRKR|10=007|\n\
+      garbage\n\
+      20240604-07:58:29 : Disconnecting\n\
+      8=FIX.4.2|9=5|35=0|10=161|\n";
+    assert_eq!(summarize(log, ValidationOptions::new().delimiter('|').error_policy(ErrorPolicy::Skip)),
+      vec!["Some(Outgoing) 24 Valid", "Some(Incoming) 99 ChecksumMismatch", "Disconnecting"]);
+    assert_eq!(summarize(log, ValidationOptions::new().delimiter('|')).len(), 2);
+  }
+
+  #[test]
+  fn it_should_read_screen_logs() {
+    let log = "<20240604-07:58:28.123, FIX.4.2:INVMGR->BRKR, incoming>\n  \
+      (8=FIX.4.2|9=28|35=0|34=3|49=INVMGR|56=BRKR|10=006|)\n\
+      <20240604-07:58:28.125, FIX.4.2:INVMGR->BRKR, event>\n  (Received logon)\n";
+    let entries: Vec<QuickFixEntry> = QuickFixReader::new(log.as_bytes(), ValidationOptions::new().delimiter('|'))
+      .map(|entry| entry.unwrap()).collect();
+    match entries[0] {
+      QuickFixEntry::Message { time, direction, ref result } => {
+        assert_eq!(time, parse_utc_timestamp("20240604-07:58:28.123"));
+        assert_eq!(direction, Some(Direction::Incoming));
+        assert_eq!(result.offset, 59);
+        assert_eq!(result.outcome, Outcome::Valid);
+      },
+      _ => panic!("expected a message"),
+    }
+    assert_eq!(entries[1], QuickFixEntry::Event {
+      time: parse_utc_timestamp("20240604-07:58:28.125"),
+      text: "Received logon".to_string(),
+    });
+  }
+
+  #[test]
+  fn it_should_read_and_write_file_stores() {
+    let dir = env::temp_dir().join(format!("fix-checksum-filestore-{}", ::std::process::id()));
+    let session = SessionId::new("BRKR", "INVMGR");
+    let path = |extension: &str| dir.join(format!("FIX.4.2-BRKR-INVMGR.{}", extension));
+    let mut store = FileStore::open(&dir, "FIX.4.2").unwrap();
+    assert_eq!(store.restore(&session).unwrap(), Checkpoint::new());
+    assert_eq!(store.created(&session).unwrap(), None);
+
+    store.store(&session, 1, b"first").unwrap();
+    store.store(&session, 2, b"second").unwrap();
+    store.store(&session, 1, b"again").unwrap();
+    store.checkpoint(&session, &Checkpoint { last_sent: 2, last_received: 9, resend_ranges: vec![(3, 4)] }).unwrap();
+    assert_eq!(fs::read_to_string(path("header")).unwrap(), "1,0,5 2,5,6 1,11,5 ");
+    assert_eq!(fs::read_to_string(path("seqnums")).unwrap(), "0000000003 : 0000000010");
+    assert!(store.created(&session).unwrap().is_some());
+
+    assert_eq!(store.messages(&session, 1, 5).unwrap(), vec![(1, b"again".to_vec()), (2, b"second".to_vec())]);
+    assert_eq!(store.restore(&session).unwrap(),
+      Checkpoint { last_sent: 2, last_received: 9, resend_ranges: vec![] });
+
+    fs::write(path("header"), "1,0,64 ").unwrap();
+    assert!(store.messages(&session, 1, 1).is_err());
+    fs::remove_dir_all(&dir).unwrap();
+  }
+}
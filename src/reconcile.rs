@@ -0,0 +1,174 @@
+//! Reconciliation of fills between a primary session and its drop copy.
+//!
+//! Fills are execution reports reporting a trade, matched between the two
+//! logs by OrderID (37) and ExecID (17). Resent duplicates of a fill are
+//! counted once.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io;
+use std::io::BufRead;
+
+use super::{ErrorPolicy, ValidationOptions};
+use super::execution::ExecutionReport;
+use super::message::MessageView;
+use super::stream::{StreamValidator, Outcome};
+
+/// Fields which must agree between both copies of a fill.
+const COMPARED_TAGS: &[u32] = &[55, 54, 150, 39, 32, 31, 14, 151, 6];
+
+/// A difference between the two logs.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Discrepancy {
+  /// A fill of the primary log is missing from the drop copy.
+  MissingInDropCopy { order_id: String, exec_id: String },
+  /// A fill of the drop copy is missing from the primary log.
+  MissingInPrimary { order_id: String, exec_id: String },
+  /// Both logs have the fill with a different value of `tag`.
+  Divergent { order_id: String, exec_id: String, tag: u32, primary: String, drop_copy: String },
+}
+
+impl fmt::Display for Discrepancy {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      Discrepancy::MissingInDropCopy { ref order_id, ref exec_id } =>
+        write!(f, "order {} exec {}: missing in drop copy", order_id, exec_id),
+      Discrepancy::MissingInPrimary { ref order_id, ref exec_id } =>
+        write!(f, "order {} exec {}: missing in primary", order_id, exec_id),
+      Discrepancy::Divergent { ref order_id, ref exec_id, tag, ref primary, ref drop_copy } =>
+        write!(f, "order {} exec {}: tag {} is {:?} in primary and {:?} in drop copy", order_id, exec_id, tag,
+          primary, drop_copy),
+    }
+  }
+}
+
+/// Outcome of a reconciliation.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Reconciliation {
+  /// Fills present and equal in both logs.
+  pub matched: usize,
+  /// Messages of either log failing validation or parsing, left out.
+  pub invalid: usize,
+  pub discrepancies: Vec<Discrepancy>,
+}
+
+impl Reconciliation {
+  /// Returns `true` if both logs agree.
+  pub fn is_clean(&self) -> bool {
+    self.discrepancies.is_empty()
+  }
+}
+
+type Fills = BTreeMap<(String, String), Vec<(u32, String)>>;
+
+/// This function matches the fills of a primary session log against a
+/// drop-copy log. Both logs are framed and validated with `options`.
+///
+/// # Examples
+///
+/// ```
+/// use fix_checksum::ValidationOptions;
+/// use fix_checksum::reconcile::reconcile;
+///
+/// let primary = &b"8=FIX.4.2|9=42|35=8|37=O-1|17=E-1|150=2|39=2|14=100|6=10|10=099|\n"[..];
+/// let drop_copy = &b""[..];
+/// let reconciliation = reconcile(primary, drop_copy, &ValidationOptions::new().delimiter('|')).unwrap();
+/// assert_eq!(reconciliation.discrepancies[0].to_string(), "order O-1 exec E-1: missing in drop copy");
+/// ```
+pub fn reconcile<P: BufRead, D: BufRead>(primary: P, drop_copy: D, options: &ValidationOptions)
+  -> io::Result<Reconciliation> {
+  let mut reconciliation = Reconciliation::default();
+  let primary = read_fills(primary, options, &mut reconciliation.invalid)?;
+  let drop_copy = read_fills(drop_copy, options, &mut reconciliation.invalid)?;
+
+  for (key, fields) in &primary {
+    let (order_id, exec_id) = (key.0.clone(), key.1.clone());
+    let copy = match drop_copy.get(key) {
+      Some(copy) => copy,
+      None => { reconciliation.discrepancies.push(Discrepancy::MissingInDropCopy { order_id, exec_id }); continue; },
+    };
+    let before = reconciliation.discrepancies.len();
+    for ((tag, primary), (_, drop_copy)) in fields.iter().zip(copy) {
+      if primary != drop_copy {
+        reconciliation.discrepancies.push(Discrepancy::Divergent { order_id: order_id.clone(),
+          exec_id: exec_id.clone(), tag: *tag, primary: primary.clone(), drop_copy: drop_copy.clone() });
+      }
+    }
+    if reconciliation.discrepancies.len() == before { reconciliation.matched += 1; }
+  }
+  for key in drop_copy.keys().filter(|key| !primary.contains_key(*key)) {
+    let (order_id, exec_id) = (key.0.clone(), key.1.clone());
+    reconciliation.discrepancies.push(Discrepancy::MissingInPrimary { order_id, exec_id });
+  }
+  Ok(reconciliation)
+}
+
+fn read_fills<R: BufRead>(input: R, options: &ValidationOptions, invalid: &mut usize) -> io::Result<Fills> {
+  let mut fills = Fills::new();
+  for result in StreamValidator::new(input, options.clone().error_policy(ErrorPolicy::Skip)) {
+    let result = result?;
+    let message = match result.outcome {
+      Outcome::Valid => MessageView::parse(&result.message, options.delimiter as char),
+      _ => { *invalid += 1; continue; },
+    };
+    let message = match message {
+      Ok(message) => message,
+      Err(_) => { *invalid += 1; continue; },
+    };
+    if message.get(35) != Some(b"8") { continue; }
+    let report = match ExecutionReport::from_view(&message) {
+      Ok(report) => report,
+      Err(_) => { *invalid += 1; continue; },
+    };
+    if !report.exec_type.is_fill() { continue; }
+
+    let fields = COMPARED_TAGS.iter()
+      .map(|tag| (*tag, String::from_utf8_lossy(message.get(*tag).unwrap_or(b"")).into_owned()))
+      .collect();
+    fills.entry((report.order_id.to_string(), report.exec_id.to_string())).or_insert(fields);
+  }
+  Ok(fills)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{reconcile, Discrepancy};
+  use super::super::ValidationOptions;
+
+  const FILL_1: &str = "8=FIX.4.2|35=8|37=O-1|17=E-1|150=1|39=1|55=IBM|32=40|31=10|14=40|6=10|";
+  const FILL_2: &str = "8=FIX.4.2|35=8|37=O-1|17=E-2|150=2|39=2|55=IBM|32=60|31=11|14=100|6=10.6|";
+  const NEW: &str = "8=FIX.4.2|35=8|37=O-2|17=E-3|150=0|39=0|55=IBM|14=0|6=0|";
+
+  fn finalize(messages: &[&str]) -> Vec<u8> {
+    let options = ValidationOptions::new().delimiter('|');
+    messages.iter().flat_map(|message| {
+      let mut message = super::super::finalize(message.as_bytes(), &options).unwrap();
+      message.push(b'\n');
+      message
+    }).collect()
+  }
+
+  #[test]
+  fn it_should_match_identical_logs() {
+    let log = finalize(&[FILL_1, NEW, FILL_2, FILL_2]);
+    let reconciliation = reconcile(&log[..], &finalize(&[FILL_2, FILL_1])[..], &ValidationOptions::new().delimiter('|'))
+      .unwrap();
+    assert!(reconciliation.is_clean());
+    assert_eq!((reconciliation.matched, reconciliation.invalid), (2, 0));
+  }
+
+  #[test]
+  fn it_should_report_missing_and_divergent_fills() {
+    let primary = finalize(&[FILL_1]);
+    let mut drop_copy = finalize(&[&FILL_1.replace("31=10|", "31=10.5|"), FILL_2]);
+    drop_copy.extend_from_slice(b"8=FIX.4.2|9=5|35=8|10=000|\n");
+    let reconciliation = reconcile(&primary[..], &drop_copy[..], &ValidationOptions::new().delimiter('|')).unwrap();
+    assert_eq!(reconciliation.matched, 0);
+    assert_eq!(reconciliation.invalid, 1);
+    assert_eq!(reconciliation.discrepancies, vec![
+      Discrepancy::Divergent { order_id: "O-1".to_string(), exec_id: "E-1".to_string(), tag: 31,
+        primary: "10".to_string(), drop_copy: "10.5".to_string() },
+      Discrepancy::MissingInPrimary { order_id: "O-1".to_string(), exec_id: "E-2".to_string() },
+    ]);
+  }
+}
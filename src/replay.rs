@@ -0,0 +1,320 @@
+//! Deterministic replay of captured sessions.
+//!
+//! A `Simulator` feeds the messages a counterparty sent in a captured log
+//! to a `Connection`, on a `VirtualClock` following their SendingTime (52):
+//! the connection sends its heartbeats, ResendRequests and answers at the
+//! virtual times a production engine did, whatever the speed of the replay.
+//! A replay runs as fast as possible, or paced against the wall clock with
+//! `speed`, and stops at the breakpoints set with `pause_at` to be resumed
+//! message by message with `step`, so that an incident can be reproduced
+//! in a test.
+//!
+//! # Examples
+//!
+//! ```
+//! use fix_checksum::{finalize, ValidationOptions};
+//! use fix_checksum::connection::Event;
+//! use fix_checksum::replay::Simulator;
+//! use fix_checksum::session::SessionId;
+//! use fix_checksum::store::MemoryStore;
+//!
+//! let log = ["8=FIX.4.2|35=A|49=EXCH|56=BRKR|34=1|52=20240102-09:30:00|98=0|108=30|",
+//!   "8=FIX.4.2|35=8|49=EXCH|56=BRKR|34=2|52=20240102-09:31:00|11=ORD-1|"].iter()
+//!   .map(|message| finalize(message.replace('|', "\x01").as_bytes(), &ValidationOptions::new()).unwrap());
+//! let mut simulator = Simulator::new(log, SessionId::new("BRKR", "EXCH"), MemoryStore::new()).pause_at(2);
+//! assert!(simulator.run().unwrap().is_empty());
+//! assert!(matches!(simulator.step().unwrap(), Some(Event::Application(_))));
+//! assert!(simulator.is_finished());
+//! ```
+
+use std::io;
+use std::io::{Read, Write};
+use std::collections::BTreeSet;
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use super::clock::{Clock, VirtualClock, parse_utc_timestamp};
+use super::connection::{Connection, Event};
+use super::message::MessageView;
+use super::session::SessionId;
+use super::store::MessageStore;
+
+/// Longest move of the virtual clock between two heartbeat checks.
+const TICK: Duration = Duration::from_secs(1);
+
+/// The stream of a simulated connection, keeping what it writes.
+#[derive(Debug, Default)]
+pub struct Outbox {
+  written: Vec<u8>,
+}
+
+impl Outbox {
+  /// Returns the bytes written by the connection.
+  pub fn written(&self) -> &[u8] {
+    &self.written
+  }
+}
+
+/// Nothing is read from the stream: the simulator hands the messages over.
+impl Read for Outbox {
+  fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+    Err(io::Error::new(io::ErrorKind::WouldBlock, "messages are delivered by the simulator"))
+  }
+}
+
+impl Write for Outbox {
+  fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+    self.written.extend_from_slice(buf);
+    Ok(buf.len())
+  }
+
+  fn flush(&mut self) -> io::Result<()> {
+    Ok(())
+  }
+}
+
+/// Replays the messages of a counterparty through the session layer.
+pub struct Simulator<M> {
+  connection: Connection<Outbox, M, VirtualClock>,
+  clock: VirtualClock,
+  // Messages of the counterparty with the virtual time they are due at
+  log: Vec<(SystemTime, Vec<u8>)>,
+  position: usize,
+  speed: Option<f64>,
+  breakpoints: BTreeSet<u64>,
+  paused: Option<usize>,
+}
+
+impl<M: MessageStore> Simulator<M> {
+  /// Creates the simulator of `session` replaying the SOH delimited
+  /// messages of `log` sent to it by its counterparty, other messages being
+  /// skipped. The virtual clock starts at the SendingTime of the first
+  /// message, messages without one are due with the message before.
+  ///
+  /// The session starts from the checkpoint of `store`, or right before
+  /// the first message of the log for a new session, with the HeartBtInt
+  /// (108) of the first Logon (A) of the log.
+  pub fn new<I: IntoIterator<Item = Vec<u8>>>(log: I, session: SessionId, store: M) -> Simulator<M> {
+    let mut due = None;
+    let mut messages = Vec::new();
+    let mut first_seq_num = None;
+    let mut heartbeat_interval = None;
+    for message in log {
+      {
+        let view = match MessageView::parse(&message, '\x01') {
+          Ok(view) => view,
+          Err(_) => continue,
+        };
+        let (sender, target) = (view.get_str(49), view.get_str(56));
+        if sender != Some(&session.target_comp_id[..]) || target != Some(&session.sender_comp_id[..]) { continue; }
+        due = view.get_str(52).and_then(parse_utc_timestamp).or(due);
+        first_seq_num = first_seq_num.or_else(|| view.get_str(34).and_then(|value| value.parse::<u64>().ok()));
+        if view.get(35) == Some(b"A") {
+          heartbeat_interval = heartbeat_interval.or_else(|| view.get_str(108).and_then(|value| value.parse().ok()));
+        }
+      }
+      messages.push((due.unwrap_or(UNIX_EPOCH), message));
+    }
+
+    let clock = VirtualClock::new(messages.first().map_or(UNIX_EPOCH, |&(due, _)| due));
+    let mut connection = Connection::new(Outbox::default(), session, store).clock(clock.clone());
+    connection.checkpoint = connection.store.restore(&connection.session).unwrap_or_default();
+    if connection.checkpoint.last_received == 0 {
+      connection.checkpoint.last_received = first_seq_num.map_or(0, |seq_num| seq_num.saturating_sub(1));
+    }
+    if let Some(interval) = heartbeat_interval { connection.heartbeat_interval = Duration::from_secs(interval); }
+    Simulator { connection, clock, log: messages, position: 0, speed: None, breakpoints: BTreeSet::new(),
+      paused: None }
+  }
+
+  /// Paces the replay against the wall clock, `speed` times as fast as the
+  /// session was: 1 replays in real time, 10 ten times as fast.
+  pub fn speed(mut self, speed: f64) -> Simulator<M> {
+    self.speed = if speed > 0.0 { Some(speed) } else { None };
+    self
+  }
+
+  /// Stops `run` and `run_for` before the message numbered `seq_num`.
+  pub fn pause_at(mut self, seq_num: u64) -> Simulator<M> {
+    self.breakpoints.insert(seq_num);
+    self
+  }
+
+  /// Returns the virtual time.
+  pub fn now(&self) -> SystemTime {
+    self.clock.now()
+  }
+
+  /// Returns `true` once every message of the log was replayed.
+  pub fn is_finished(&self) -> bool {
+    self.position == self.log.len()
+  }
+
+  /// Returns the connection, to check its state or what it wrote with
+  /// `connection().get_ref().written()`.
+  pub fn connection(&self) -> &Connection<Outbox, M, VirtualClock> {
+    &self.connection
+  }
+
+  /// Returns the connection mutably, to send messages of the application
+  /// during the replay.
+  pub fn connection_mut(&mut self) -> &mut Connection<Outbox, M, VirtualClock> {
+    &mut self.connection
+  }
+
+  /// Moves the virtual clock to the next message and replays it, whatever
+  /// the breakpoints. Returns `None` for messages making no event, such as
+  /// heartbeats or garbled messages, and at the end of the log.
+  pub fn step(&mut self) -> io::Result<Option<Event>> {
+    let (due, message) = match self.log.get(self.position) {
+      Some(&(due, ref message)) => (due, message.clone()),
+      None => return Ok(None),
+    };
+    self.advance_to(due)?;
+    self.position += 1;
+    self.connection.deliver(message)
+  }
+
+  /// Replays messages up to the end of the log or the next breakpoint, and
+  /// returns the events they make.
+  pub fn run(&mut self) -> io::Result<Vec<Event>> {
+    self.run_until(None).map(|(events, _)| events)
+  }
+
+  /// Replays the messages due within `duration` of virtual time, stopping
+  /// at the next breakpoint, and moves the clock to the end of `duration`
+  /// unless stopped.
+  pub fn run_for(&mut self, duration: Duration) -> io::Result<Vec<Event>> {
+    let end = self.clock.now() + duration;
+    let (events, paused) = self.run_until(Some(end))?;
+    if !paused { self.advance_to(end)?; }
+    Ok(events)
+  }
+
+  /// Replays the messages due by `end`, returning their events and whether
+  /// a breakpoint stopped the replay.
+  fn run_until(&mut self, end: Option<SystemTime>) -> io::Result<(Vec<Event>, bool)> {
+    let mut events = Vec::new();
+    while let Some(&(due, _)) = self.log.get(self.position) {
+      if end.is_some_and(|end| due > end) { break; }
+      // The breakpoint stopped at last is passed by the next run
+      if self.at_breakpoint() && self.paused != Some(self.position) {
+        self.paused = Some(self.position);
+        return Ok((events, true));
+      }
+      events.extend(self.step()?);
+    }
+    Ok((events, false))
+  }
+
+  /// Returns `true` if the next message is numbered as a breakpoint.
+  fn at_breakpoint(&self) -> bool {
+    self.log.get(self.position).is_some_and(|(_, message)| {
+      MessageView::parse(message, '\x01').ok().and_then(|view| view.get_str(34).and_then(|value| value.parse().ok()))
+        .is_some_and(|seq_num: u64| self.breakpoints.contains(&seq_num))
+    })
+  }
+
+  /// Moves the virtual clock to `time` a tick at a time, letting the
+  /// connection send its heartbeats on the way.
+  fn advance_to(&mut self, time: SystemTime) -> io::Result<()> {
+    while self.clock.now() < time {
+      let now = self.clock.now();
+      let next = (now + TICK).min(time);
+      if let Some(speed) = self.speed {
+        thread::sleep(next.duration_since(now).unwrap_or_default().div_f64(speed));
+      }
+      self.clock.set(next);
+      self.connection.heartbeat_if_due()?;
+    }
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::time::Duration;
+  use super::Simulator;
+  use super::super::{ValidationOptions, finalize};
+  use super::super::clock::{format_utc_timestamp, parse_utc_timestamp};
+  use super::super::connection::Event;
+  use super::super::connection::tests::{frames, message};
+  use super::super::message::MessageView;
+  use super::super::session::SessionId;
+  use super::super::store::MemoryStore;
+
+  /// Returns a message of EXCH to BRKR sent at `sending_time`.
+  fn captured(seq_num: u64, msg_type: &str, sending_time: &str, fields: &[(u32, &str)]) -> Vec<u8> {
+    let message = String::from_utf8(message("EXCH", "BRKR", seq_num, msg_type, fields)).unwrap();
+    let message = message.replace("52=20240102-09:30:00", &format!("52={}", sending_time));
+    finalize(message.as_bytes(), &ValidationOptions::new()).unwrap()
+  }
+
+  /// Returns the MsgType (35), MsgSeqNum (34) and SendingTime (52) of the
+  /// messages written by the simulated connection.
+  fn written(simulator: &Simulator<MemoryStore>) -> Vec<(String, String, String)> {
+    frames(simulator.connection().get_ref().written()).into_iter().map(|message| {
+      let view = MessageView::parse(message, '\x01').unwrap();
+      (view.get_str(35).unwrap().to_string(), view.get_str(34).unwrap().to_string(),
+        view.get_str(52).unwrap()[9..17].to_string())
+    }).collect()
+  }
+
+  fn simulator() -> Simulator<MemoryStore> {
+    let log = vec![
+      captured(7, "A", "20240102-09:30:00", &[(98, "0"), (108, "30")]),
+      message("BRKR", "EXCH", 1, "A", &[(98, "0"), (108, "30")]),
+      captured(8, "8", "20240102-09:30:10", &[(11, "ORD-1")]),
+      captured(10, "8", "20240102-09:31:10", &[(11, "ORD-3")]),
+      captured(9, "8", "20240102-09:31:11", &[(43, "Y"), (11, "ORD-2")]),
+      captured(11, "5", "20240102-09:31:20", &[]),
+    ];
+    Simulator::new(log, SessionId::new("BRKR", "EXCH"), MemoryStore::new())
+  }
+
+  #[test]
+  fn it_should_replay_on_virtual_time() {
+    let mut simulator = simulator();
+    let events = simulator.run().unwrap();
+    assert_eq!(events.len(), 4);
+    assert_eq!(events[3], Event::LoggedOut);
+    assert!(simulator.is_finished());
+    assert_eq!(format_utc_timestamp(simulator.now()), "20240102-09:31:20.000");
+    assert_eq!(simulator.connection().checkpoint().last_received, 11);
+    assert!(simulator.connection().checkpoint().resend_ranges.is_empty());
+
+    let written: Vec<_> = written(&simulator).into_iter().map(|(msg_type, _, time)| (msg_type, time)).collect();
+    let expected = [("0", "09:30:01"), ("0", "09:30:31"), ("0", "09:31:01"), ("2", "09:31:10"), ("5", "09:31:20")];
+    assert_eq!(written, expected.iter().map(|&(msg_type, time)| (msg_type.to_string(), time.to_string()))
+      .collect::<Vec<_>>());
+  }
+
+  #[test]
+  fn it_should_pause_and_step() {
+    let mut simulator = simulator().pause_at(10);
+    assert_eq!(simulator.run().unwrap().len(), 1);
+    assert_eq!(format_utc_timestamp(simulator.now()), "20240102-09:30:10.000");
+    match simulator.step().unwrap() {
+      Some(Event::Application(message)) => assert!(message.windows(8).any(|window| window == b"11=ORD-3")),
+      event => panic!("unexpected {:?}", event),
+    }
+    assert_eq!(simulator.connection().checkpoint().resend_ranges, vec![(9, 9)]);
+
+    let events = simulator.run_for(Duration::from_secs(5)).unwrap();
+    assert_eq!(events.len(), 1);
+    assert_eq!(simulator.now(), parse_utc_timestamp("20240102-09:31:15").unwrap());
+    assert!(!simulator.is_finished());
+    assert_eq!(simulator.run().unwrap(), vec![Event::LoggedOut]);
+    assert_eq!(simulator.step().unwrap(), None);
+  }
+
+  #[test]
+  fn it_should_pace_against_wall_clock() {
+    let log = vec![captured(1, "0", "20240102-09:30:00", &[]), captured(2, "0", "20240102-09:30:00.200", &[])];
+    let mut simulator = Simulator::new(log, SessionId::new("BRKR", "EXCH"), MemoryStore::new()).speed(4.0);
+    let started = ::std::time::Instant::now();
+    assert!(simulator.run().unwrap().is_empty());
+    assert!(started.elapsed() >= Duration::from_millis(50));
+    assert_eq!(simulator.connection().checkpoint().last_received, 2);
+  }
+}
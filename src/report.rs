@@ -0,0 +1,437 @@
+//! Reports of stream validation runs for people and programs outside the
+//! tooling.
+//!
+//! An `HtmlReport` renders a `ValidationSummary` as a standalone HTML page,
+//! with no external styles or scripts, followed by the worst failed
+//! messages recorded into it, pretty-printed. Failures which could not be
+//! validated at all rank first, then checksum mismatches, then repaired
+//! messages, each in stream order. A hex dump of the bytes around the
+//! likely fault shows control characters the pretty-printed fields hide.
+//! The page suits attachments to incident tickets and compliance reviews.
+//!
+//! # Examples
+//!
+//! ```
+//! use fix_checksum::{ValidationOptions, ErrorPolicy};
+//! use fix_checksum::report::HtmlReport;
+//! use fix_checksum::stream::StreamValidator;
+//! use fix_checksum::summary::ValidationSummary;
+//!
+//! let log = "8=FIX.4.2|9=5|35=0|10=161|\n8=FIX.4.2|9=5|35=0|10=999|\n";
+//! let options = ValidationOptions::new().delimiter('|').error_policy(ErrorPolicy::Skip);
+//! let mut summary = ValidationSummary::new(&options);
+//! let mut offenders = Vec::new();
+//! for result in StreamValidator::new(log.as_bytes(), options) {
+//!   let result = result.unwrap();
+//!   summary.record(&result);
+//!   offenders.push(result);
+//! }
+//! let mut report = HtmlReport::new(&summary).title("Night session");
+//! for result in &offenders {
+//!   report.record(result);
+//! }
+//! let page = report.to_string();
+//! assert!(page.starts_with("<!DOCTYPE html>") && page.contains("<title>Night session</title>"));
+//! assert!(page.contains("<h3>Offset 27: ChecksumMismatch</h3>"));
+//! ```
+//!
+//! `MessageRecord` and `summary_json` write the same results as JSON, in a
+//! structure versioned by `SCHEMA_VERSION` for systems consuming the output
+//! of the command line tool. Every object carries a `version` member.
+//! Members are only added within a version; renaming, removing or retyping
+//! one bumps it.
+//!
+//! ```
+//! use fix_checksum::{ValidationOptions, ErrorPolicy};
+//! use fix_checksum::report::MessageRecord;
+//! use fix_checksum::stream::StreamValidator;
+//!
+//! let options = ValidationOptions::new().delimiter('|').error_policy(ErrorPolicy::Skip);
+//! let result = StreamValidator::new(&b"8=FIX.4.2|9=5|35=0|\n"[..], options).next().unwrap().unwrap();
+//! assert_eq!(MessageRecord::new(&result).to_json(), "{\"version\":1,\"offset\":0,\
+//!   \"outcome\":\"ChecksumFieldNotFound\",\"code\":2,\"message\":\"8=FIX.4.2|9=5|35=0|\",\"hypotheses\":[]}");
+//! ```
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use super::ValidationOptions;
+use super::diagnose::failure_offset;
+use super::digest::Digests;
+use super::json;
+use super::pretty::{hex_dump_around, pretty_print};
+use super::session::DuplicateKind;
+use super::stream::{Outcome, StreamResult};
+use super::summary::ValidationSummary;
+
+/// Version of the JSON structures of this module.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Number of failed messages an `HtmlReport` shows by default.
+const DEFAULT_MAX_MESSAGES: usize = 10;
+
+/// Bytes per line of the hex dumps of an `HtmlReport` by default.
+const DEFAULT_HEX_DUMP_WIDTH: usize = 16;
+
+/// Inline styles of the page.
+const STYLE: &str = "body{font-family:sans-serif;margin:2em;color:#222}\
+  table{border-collapse:collapse;margin-bottom:1.5em}\
+  th,td{border:1px solid #ccc;padding:.3em .8em;text-align:left}\
+  th{background:#f2f2f2}\
+  pre{background:#f8f8f8;border:1px solid #ddd;padding:.8em;overflow-x:auto}";
+
+/// A failed message kept for the report.
+#[derive(Clone, Debug, PartialEq)]
+struct Offender {
+  severity: u8,
+  offset: usize,
+  kind: &'static str,
+  rendered: String,
+  hypotheses: Vec<String>,
+  /// Offset of the likely fault within the message and the hex dump around
+  /// it.
+  dump: Option<(usize, String)>,
+}
+
+/// A standalone HTML page of a summary, see the module documentation.
+#[derive(Clone, Debug)]
+pub struct HtmlReport<'a> {
+  summary: &'a ValidationSummary,
+  title: String,
+  max_messages: usize,
+  hex_dump_width: usize,
+  offenders: Vec<Offender>,
+}
+
+impl<'a> HtmlReport<'a> {
+  /// Creates a report of `summary` showing up to ten failed messages.
+  pub fn new(summary: &'a ValidationSummary) -> HtmlReport<'a> {
+    HtmlReport {
+      summary,
+      title: "FIX validation report".to_string(),
+      max_messages: DEFAULT_MAX_MESSAGES,
+      hex_dump_width: DEFAULT_HEX_DUMP_WIDTH,
+      offenders: Vec::new(),
+    }
+  }
+
+  /// Sets the title of the page.
+  pub fn title(mut self, title: &str) -> HtmlReport<'a> {
+    self.title = title.to_string();
+    self
+  }
+
+  /// Sets the number of failed messages shown, zero for none.
+  pub fn max_messages(mut self, max_messages: usize) -> HtmlReport<'a> {
+    self.max_messages = max_messages;
+    self.offenders.truncate(max_messages);
+    self
+  }
+
+  /// Sets the bytes per line of the hex dumps of messages recorded after,
+  /// zero for no dumps.
+  pub fn hex_dump_width(mut self, width: usize) -> HtmlReport<'a> {
+    self.hex_dump_width = width;
+    self
+  }
+
+  /// Keeps the message of `result` if it failed and ranks among the worst
+  /// recorded. Valid messages are ignored.
+  pub fn record(&mut self, result: &StreamResult) {
+    let severity = match result.outcome {
+      Outcome::Valid => return,
+      Outcome::Error(_) => 2,
+      Outcome::ChecksumMismatch => 1,
+      Outcome::Repaired => 0,
+    };
+    let position = self.offenders.iter()
+      .position(|offender| (severity, offender.offset) > (offender.severity, result.offset))
+      .unwrap_or(self.offenders.len());
+    if position >= self.max_messages { return; }
+    let delimiter = self.summary.delimiter();
+    let rendered = pretty_print(&result.message, delimiter)
+      .unwrap_or_else(|_| String::from_utf8_lossy(&result.message).into_owned());
+    let options = ValidationOptions::new().delimiter(delimiter);
+    let dump = match failure_offset(&result.message, &result.outcome, &options) {
+      Some(offset) if self.hex_dump_width > 0 =>
+        Some((offset, hex_dump_around(&result.message, offset, self.hex_dump_width))),
+      _ => None,
+    };
+    self.offenders.insert(position, Offender {
+      severity,
+      offset: result.offset,
+      kind: result.outcome.kind(),
+      rendered,
+      hypotheses: result.hypotheses.iter().map(|hypothesis| hypothesis.to_string()).collect(),
+      dump,
+    });
+    self.offenders.truncate(self.max_messages);
+  }
+}
+
+impl<'a> fmt::Display for HtmlReport<'a> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let summary = self.summary;
+    let title = escape(&self.title);
+    writeln!(f, "<!DOCTYPE html>")?;
+    writeln!(f, "<html lang=\"en\">")?;
+    writeln!(f, "<head><meta charset=\"utf-8\"><title>{}</title><style>{}</style></head>", title, STYLE)?;
+    writeln!(f, "<body>")?;
+    writeln!(f, "<h1>{}</h1>", title)?;
+
+    writeln!(f, "<table>")?;
+    writeln!(f, "<tr><th>Messages</th><td>{}</td></tr>", summary.total)?;
+    writeln!(f, "<tr><th>Valid</th><td>{}</td></tr>", summary.valid)?;
+    writeln!(f, "<tr><th>Failed</th><td>{}</td></tr>", summary.failed())?;
+    if let (Some(first), Some(last)) = (summary.first_failure_offset, summary.last_failure_offset) {
+      writeln!(f, "<tr><th>First failure offset</th><td>{}</td></tr>", first)?;
+      writeln!(f, "<tr><th>Last failure offset</th><td>{}</td></tr>", last)?;
+    }
+    if !summary.duplicates.is_empty() {
+      let count = |kind| summary.duplicates.get(&kind).cloned().unwrap_or(0);
+      writeln!(f, "<tr><th>Duplicates</th><td>admin: {}, application: {}</td></tr>", count(DuplicateKind::Admin),
+        count(DuplicateKind::Application))?;
+    }
+    writeln!(f, "</table>")?;
+
+    if !summary.failures.is_empty() {
+      writeln!(f, "<h2>Failures</h2>")?;
+      writeln!(f, "<table>")?;
+      writeln!(f, "<tr><th>Kind</th><th>Messages</th></tr>")?;
+      for (kind, count) in &summary.failures {
+        writeln!(f, "<tr><td>{}</td><td>{}</td></tr>", escape(kind), count)?;
+      }
+      writeln!(f, "</table>")?;
+    }
+
+    if !summary.sessions.is_empty() {
+      writeln!(f, "<h2>Sessions</h2>")?;
+      writeln!(f, "<table>")?;
+      writeln!(f, "<tr><th>Session</th><th>Messages</th><th>Valid</th><th>Failed</th><th>Failures</th></tr>")?;
+      for (session, counters) in &summary.sessions {
+        let kinds = counters.failure_kinds.iter().map(|(kind, count)| format!("{}: {}", escape(kind), count))
+          .collect::<Vec<_>>();
+        writeln!(f, "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+          escape(&session.to_string()), counters.total, counters.valid, counters.failures, kinds.join(", "))?;
+      }
+      writeln!(f, "</table>")?;
+    }
+
+    if !self.offenders.is_empty() {
+      writeln!(f, "<h2>Worst offending messages</h2>")?;
+      for offender in &self.offenders {
+        writeln!(f, "<section>")?;
+        writeln!(f, "<h3>Offset {}: {}</h3>", offender.offset, escape(offender.kind))?;
+        if !offender.hypotheses.is_empty() {
+          writeln!(f, "<ul>")?;
+          for hypothesis in &offender.hypotheses {
+            writeln!(f, "<li>{}</li>", escape(hypothesis))?;
+          }
+          writeln!(f, "</ul>")?;
+        }
+        writeln!(f, "<pre>{}</pre>", escape(offender.rendered.trim_end_matches('\n')))?;
+        if let Some((offset, ref dump)) = offender.dump {
+          writeln!(f, "<p>Bytes around offset {} of the message:</p>", offset)?;
+          writeln!(f, "<pre>{}</pre>", escape(dump.trim_end_matches('\n')))?;
+        }
+        writeln!(f, "</section>")?;
+      }
+    }
+
+    writeln!(f, "</body>")?;
+    writeln!(f, "</html>")
+  }
+}
+
+/// A validated message of a stream as a JSON report line:
+///
+/// * `version`: `SCHEMA_VERSION`
+/// * `offset`: byte offset of the message in the stream
+/// * `outcome`: kind of the outcome, `Valid`, `ChecksumMismatch`, `Repaired`
+///   or the kind of the error, see `FIXChecksumValidatorError::kind`
+/// * `code`: error code of messages which could not be validated, see
+///   `FIXChecksumValidatorError::error_code`, `null` otherwise
+/// * `message`: the message bytes, invalid UTF-8 replaced
+/// * `hypotheses`: likely causes of the failure, as strings
+/// * `crc32` and `sha256`: digests of the message in hexadecimal, only if
+///   requested with `digests`
+#[derive(Clone, Debug, PartialEq)]
+pub struct MessageRecord {
+  pub offset: usize,
+  pub outcome: &'static str,
+  pub code: Option<u32>,
+  pub message: Vec<u8>,
+  pub hypotheses: Vec<String>,
+  pub digests: Option<Digests>,
+}
+
+impl MessageRecord {
+  /// Creates the record of `result`, without digests.
+  pub fn new(result: &StreamResult) -> MessageRecord {
+    MessageRecord {
+      offset: result.offset,
+      outcome: result.outcome.kind(),
+      code: match result.outcome {
+        Outcome::Error(ref err) => Some(err.error_code()),
+        _ => None,
+      },
+      message: result.message.clone(),
+      hypotheses: result.hypotheses.iter().map(|hypothesis| hypothesis.to_string()).collect(),
+      digests: None,
+    }
+  }
+
+  /// Adds the digests of the message.
+  pub fn digests(mut self) -> MessageRecord {
+    self.digests = Some(Digests::of(&self.message));
+    self
+  }
+
+  /// Returns the record as a JSON object.
+  pub fn to_json(&self) -> String {
+    let code = self.code.map_or_else(|| "null".to_string(), |code| code.to_string());
+    let mut object = format!("{{\"version\":{},\"offset\":{},\"outcome\":{},\"code\":{},\"message\":{},\
+      \"hypotheses\":{}",
+      SCHEMA_VERSION, self.offset, json::string(self.outcome), code, json::bytes(&self.message),
+      json::strings(self.hypotheses.iter().cloned()));
+    if let Some(digests) = self.digests {
+      object.push_str(&format!(",\"crc32\":\"{:08x}\",\"sha256\":\"{}\"", digests.crc32, digests.sha256_hex()));
+    }
+    object.push('}');
+    object
+  }
+}
+
+/// This function returns `summary` as a JSON object:
+///
+/// * `version`: `SCHEMA_VERSION`
+/// * `total`, `valid` and `failed`: numbers of messages
+/// * `failures`: numbers of failed messages by kind of failure
+/// * `first_failure_offset` and `last_failure_offset`: stream offsets, `null`
+///   without failures
+/// * `duplicates`: numbers of duplicates by layer, `admin` and `application`
+/// * `sessions`: array of the same counters by session, with the
+///   `sender_comp_id` and `target_comp_id` of the session, without version
+///   and offsets
+pub fn summary_json(summary: &ValidationSummary) -> String {
+  let offset = |offset: Option<usize>| offset.map_or_else(|| "null".to_string(), |offset| offset.to_string());
+  let sessions = summary.sessions.iter().map(|(session, counters)| {
+    format!("{{\"sender_comp_id\":{},\"target_comp_id\":{},\"total\":{},\"valid\":{},\"failed\":{},\
+      \"failures\":{},\"duplicates\":{}}}", json::string(&session.sender_comp_id),
+      json::string(&session.target_comp_id), counters.total, counters.valid, counters.failures,
+      counts_json(&counters.failure_kinds), duplicates_json(&counters.duplicates))
+  }).collect::<Vec<_>>();
+  format!("{{\"version\":{},\"total\":{},\"valid\":{},\"failed\":{},\"failures\":{},\"first_failure_offset\":{},\
+    \"last_failure_offset\":{},\"duplicates\":{},\"sessions\":[{}]}}", SCHEMA_VERSION, summary.total, summary.valid,
+    summary.failed(), counts_json(&summary.failures), offset(summary.first_failure_offset),
+    offset(summary.last_failure_offset), duplicates_json(&summary.duplicates), sessions.join(","))
+}
+
+fn counts_json(counts: &BTreeMap<&'static str, usize>) -> String {
+  let members = counts.iter().map(|(kind, count)| format!("{}:{}", json::string(kind), count)).collect::<Vec<_>>();
+  format!("{{{}}}", members.join(","))
+}
+
+fn duplicates_json(duplicates: &BTreeMap<DuplicateKind, usize>) -> String {
+  let count = |kind| duplicates.get(&kind).cloned().unwrap_or(0);
+  format!("{{\"admin\":{},\"application\":{}}}", count(DuplicateKind::Admin), count(DuplicateKind::Application))
+}
+
+/// Returns `text` escaped for HTML content and attributes, with control
+/// characters other than line breaks written as `\xNN`.
+fn escape(text: &str) -> String {
+  let mut escaped = String::with_capacity(text.len());
+  for c in text.chars() {
+    match c {
+      '&' => escaped.push_str("&amp;"),
+      '<' => escaped.push_str("&lt;"),
+      '>' => escaped.push_str("&gt;"),
+      '"' => escaped.push_str("&quot;"),
+      '\'' => escaped.push_str("&#39;"),
+      '\n' => escaped.push('\n'),
+      c if c.is_control() => escaped.push_str(&format!("\\x{:02x}", c as u32)),
+      c => escaped.push(c),
+    }
+  }
+  escaped
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{HtmlReport, MessageRecord, escape, summary_json};
+  use super::super::{ValidationOptions, ErrorPolicy};
+  use super::super::stream::{StreamResult, StreamValidator};
+  use super::super::summary::ValidationSummary;
+
+  const LOG: &str = "8=FIX.4.2|9=23|35=0|49=BRKR|56=INVMGR|10=041|\n\
+    8=FIX.4.2|9=23|35=0|49=BRKR|56=INVMGR|10=042|\n\
+    8=FIX.4.2|9=5|35=0|10=16|\n\
+    8=FIX.4.2|9=23|35=0|49=BRKR|56=INVMGR|10=043|\n";
+
+  fn validate() -> (ValidationSummary, Vec<StreamResult>) {
+    let options = ValidationOptions::new().delimiter('|').error_policy(ErrorPolicy::Skip);
+    let mut summary = ValidationSummary::new(&options);
+    let results = StreamValidator::new(LOG.as_bytes(), options).map(|result| result.unwrap()).collect::<Vec<_>>();
+    for result in &results { summary.record(result); }
+    (summary, results)
+  }
+
+  #[test]
+  fn it_should_render_summary_page() {
+    let (summary, results) = validate();
+    let mut report = HtmlReport::new(&summary).title("<Incident> & review");
+    for result in &results { report.record(result); }
+    let page = report.to_string();
+    assert!(page.contains("<title>&lt;Incident&gt; &amp; review</title>"), "{}", page);
+    assert!(page.contains("<tr><th>Failed</th><td>3</td></tr>"));
+    assert!(page.contains("<tr><td>ChecksumMismatch</td><td>2</td></tr>"));
+    assert!(page.contains("<tr><td>BRKR-&gt;INVMGR</td><td>3</td><td>1</td><td>2</td>\
+      <td>ChecksumMismatch: 2</td></tr>"));
+    assert!(page.contains("<pre>BeginString (8) = FIX.4.2\nBodyLength (9) = 23\n"));
+    assert!(page.ends_with("</body>\n</html>\n"));
+
+    let headings = page.lines().filter(|line| line.starts_with("<h3>")).collect::<Vec<_>>();
+    assert_eq!(headings, vec![
+      "<h3>Offset 92: ChecksumFieldInvalidFormat</h3>",
+      "<h3>Offset 46: ChecksumMismatch</h3>",
+      "<h3>Offset 118: ChecksumMismatch</h3>",
+    ]);
+  }
+
+  #[test]
+  fn it_should_keep_worst_messages() {
+    let (summary, results) = validate();
+    let mut report = HtmlReport::new(&summary).max_messages(1).hex_dump_width(8);
+    for result in &results { report.record(result); }
+    let page = report.to_string();
+    assert_eq!(page.matches("<h3>").count(), 1);
+    assert!(page.contains("<h3>Offset 92: ChecksumFieldInvalidFormat</h3>"));
+    assert!(page.contains("<p>Bytes around offset 22 of the message:</p>\n<pre>\
+      00000008  32 7c 39 3d 35 7c 33 35  |2|9=5|35|\n\
+      00000010  3d 30 7c 31 30 3d 31 36  |=0|10=16|\n\
+      \x20                           ^^\n\
+      00000018  7c                       |||</pre>\n"), "{}", page);
+
+    let mut report = HtmlReport::new(&summary).hex_dump_width(0);
+    for result in &results { report.record(result); }
+    assert!(!report.to_string().contains("Bytes around offset"));
+    assert!(!HtmlReport::new(&summary).max_messages(0).to_string().contains("<h2>Worst offending messages</h2>"));
+    assert_eq!(escape("a\x01b\n'\""), "a\\x01b\n&#39;&quot;");
+  }
+
+  #[test]
+  fn it_should_write_versioned_json() {
+    let (summary, results) = validate();
+    assert_eq!(summary_json(&summary), "{\"version\":1,\"total\":4,\"valid\":1,\"failed\":3,\
+      \"failures\":{\"ChecksumFieldInvalidFormat\":1,\"ChecksumMismatch\":2},\"first_failure_offset\":46,\
+      \"last_failure_offset\":118,\"duplicates\":{\"admin\":0,\"application\":0},\"sessions\":[\
+      {\"sender_comp_id\":\"BRKR\",\"target_comp_id\":\"INVMGR\",\"total\":3,\"valid\":1,\"failed\":2,\
+      \"failures\":{\"ChecksumMismatch\":2},\"duplicates\":{\"admin\":0,\"application\":0}}]}");
+    assert_eq!(MessageRecord::new(&results[1]).to_json(), "{\"version\":1,\"offset\":46,\
+      \"outcome\":\"ChecksumMismatch\",\"code\":null,\"message\":\"8=FIX.4.2|9=23|35=0|49=BRKR|56=INVMGR|10=042|\",\
+      \"hypotheses\":[]}");
+    let options = ValidationOptions::new().delimiter('|');
+    let empty = summary_json(&ValidationSummary::new(&options));
+    assert!(empty.contains("\"first_failure_offset\":null,\"last_failure_offset\":null"), "{}", empty);
+  }
+}
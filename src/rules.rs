@@ -0,0 +1,638 @@
+//! Built-in validation rules.
+
+use std::time::Duration;
+
+use super::clock::{Clock, parse_utc_timestamp};
+use super::dictionary::{Dictionary, TagClass, classify, is_currency_code, is_decimal, is_utc_timestamp, legal_values,
+  tag_name};
+use super::message::{GroupLayout, MessageView};
+use super::session::SessionSchedule;
+use super::validator::{ValidationReport, ValidationRule};
+
+/// Requires BeginString (8) to be one of the given versions.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BeginString(pub &'static [&'static str]);
+
+impl ValidationRule for BeginString {
+  fn check(&self, msg: &MessageView, report: &mut ValidationReport) {
+    match msg.get_str(8) {
+      Some(version) if self.0.contains(&version) => {},
+      Some(version) => report.error(Some(8), format!("BeginString {} is not {}", version, self.0.join(" or "))),
+      None => report.error(Some(8), "BeginString is missing"),
+    }
+  }
+}
+
+/// Requires BeginString (8), BodyLength (9) and MsgType (35) to be the
+/// first three fields, in this order.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct HeaderOrder;
+
+impl ValidationRule for HeaderOrder {
+  fn check(&self, msg: &MessageView, report: &mut ValidationReport) {
+    for (position, tag) in [8, 9, 35].iter().enumerate() {
+      if msg.fields.get(position).map(|field| field.0) != Some(*tag) {
+        report.error(Some(*tag), format!("tag {} must be field {} of the message", tag, position + 1));
+      }
+    }
+  }
+}
+
+/// Requires header fields to precede the body and trailer fields to come
+/// last. User-defined tags may appear anywhere after the header.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FieldOrder;
+
+impl ValidationRule for FieldOrder {
+  fn check(&self, msg: &MessageView, report: &mut ValidationReport) {
+    let mut part = TagClass::Header;
+    for &(tag, _) in &msg.fields {
+      let class = match classify(tag) {
+        TagClass::UserDefined if part == TagClass::Header => TagClass::Body,
+        TagClass::UserDefined => part,
+        class => class,
+      };
+      if class < part {
+        report.error(Some(tag), format!("{:?} tag {} follows {:?} fields", class, tag, part));
+        return;
+      }
+      part = class;
+    }
+  }
+}
+
+/// Requires every listed tag to be present.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RequiredFields(pub &'static [u32]);
+
+impl ValidationRule for RequiredFields {
+  fn check(&self, msg: &MessageView, report: &mut ValidationReport) {
+    for tag in self.0 {
+      if msg.get(*tag).is_none() { report.error(Some(*tag), format!("required tag {} is missing", tag)); }
+    }
+  }
+}
+
+/// Checks the formats of the standard session layer fields: MsgSeqNum (34),
+/// the timestamps SendingTime (52) and OrigSendingTime (122) and the flags
+/// PossDupFlag (43) and PossResend (97). Missing fields are not reported.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SessionFieldFormats;
+
+impl ValidationRule for SessionFieldFormats {
+  fn check(&self, msg: &MessageView, report: &mut ValidationReport) {
+    if let Some(value) = msg.get(34) {
+      if value.is_empty() || value[0] == b'0' || !value.iter().all(|b| b.is_ascii_digit()) {
+        report.error(Some(34), "MsgSeqNum must be a positive integer");
+      }
+    }
+    for tag in &[52, 122] {
+      if let Some(value) = msg.get(*tag) {
+        if !::std::str::from_utf8(value).is_ok_and(is_utc_timestamp) {
+          report.error(Some(*tag), "value must be a UTCTimestamp");
+        }
+      }
+    }
+    for tag in &[43, 97] {
+      if let Some(value) = msg.get(*tag) {
+        if value != b"Y" && value != b"N" { report.error(Some(*tag), "value must be Y or N"); }
+      }
+    }
+  }
+}
+
+/// Checks every field of a known type, standard or registered in the
+/// dictionary, against the format of its type.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FieldTypes(pub Dictionary);
+
+impl ValidationRule for FieldTypes {
+  fn check(&self, msg: &MessageView, report: &mut ValidationReport) {
+    for &(tag, value) in &msg.fields {
+      match self.0.field_type(tag) {
+        Some(field_type) if !field_type.is_valid(value) => {
+          let name = self.0.tag_name(tag).unwrap_or("");
+          report.error(Some(tag), format!("{} value is not a valid {}", name, field_type));
+        },
+        _ => {},
+      }
+    }
+  }
+}
+
+/// Requires enumerated fields, standard or registered in the dictionary, to
+/// have one of their legal values.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct EnumValues(pub Dictionary);
+
+impl ValidationRule for EnumValues {
+  fn check(&self, msg: &MessageView, report: &mut ValidationReport) {
+    for &(tag, value) in &msg.fields {
+      if self.0.is_legal_value(tag, value) != Some(false) { continue; }
+      let name = self.0.tag_name(tag).unwrap_or("field");
+      let message = match legal_values(tag) {
+        Some(values) => format!("{} value {} is not one of {}", name, String::from_utf8_lossy(value), values.join(", ")),
+        None => format!("{} value {} is not legal", name, String::from_utf8_lossy(value)),
+      };
+      report.error(Some(tag), message);
+    }
+  }
+}
+
+/// Requires Currency (15) and SettlCurrency (120) to be ISO 4217 codes.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CurrencyCodes;
+
+impl ValidationRule for CurrencyCodes {
+  fn check(&self, msg: &MessageView, report: &mut ValidationReport) {
+    for &(tag, value) in msg.fields.iter().filter(|field| field.0 == 15 || field.0 == 120) {
+      if !::std::str::from_utf8(value).is_ok_and(is_currency_code) {
+        report.error(Some(tag), format!("{} is not an ISO 4217 currency code", String::from_utf8_lossy(value)));
+      }
+    }
+  }
+}
+
+/// Requires SendingTime (52) to be within `tolerance` of the clock, flagging
+/// stale and future-dated messages.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+/// use fix_checksum::clock::{FixedClock, parse_utc_timestamp};
+/// use fix_checksum::rules::SendingTimeFreshness;
+/// use fix_checksum::validator::Validator;
+/// use fix_checksum::ValidationOptions;
+///
+/// let clock = FixedClock(parse_utc_timestamp("20240102-09:30:00").unwrap());
+/// let validator = Validator::new(ValidationOptions::new().delimiter('|'))
+///   .rule(SendingTimeFreshness::new(clock, Duration::from_secs(120)));
+/// let report = validator.validate(b"8=FIX.4.2|9=26|35=0|52=20240102-09:20:00|10=208|");
+/// assert_eq!(report.issues()[0].to_string(), "error (tag 52): SendingTime is 600000 ms stale");
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct SendingTimeFreshness<C: Clock> {
+  clock: C,
+  tolerance: Duration,
+}
+
+impl<C: Clock> SendingTimeFreshness<C> {
+  /// Creates the rule accepting messages sent up to `tolerance` before or
+  /// after the time of `clock`.
+  pub fn new(clock: C, tolerance: Duration) -> SendingTimeFreshness<C> {
+    SendingTimeFreshness { clock, tolerance }
+  }
+}
+
+impl<C: Clock> ValidationRule for SendingTimeFreshness<C> {
+  fn check(&self, msg: &MessageView, report: &mut ValidationReport) {
+    let sent = match msg.get(52) {
+      Some(value) => match ::std::str::from_utf8(value).ok().and_then(parse_utc_timestamp) {
+        Some(sent) => sent,
+        None => { report.error(Some(52), "SendingTime is not a UTCTimestamp"); return; },
+      },
+      None => return,
+    };
+    let now = self.clock.now();
+    match now.duration_since(sent) {
+      Ok(age) if age > self.tolerance =>
+        report.error(Some(52), format!("SendingTime is {} ms stale", age.as_millis())),
+      Err(ahead) if ahead.duration() > self.tolerance =>
+        report.error(Some(52), format!("SendingTime is {} ms in the future", ahead.duration().as_millis())),
+      _ => {},
+    }
+  }
+}
+
+/// Requires SendingTime (52) to fall within the hours of a session
+/// schedule. Messages without a valid SendingTime are not reported.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+/// use fix_checksum::rules::SessionHours;
+/// use fix_checksum::session::SessionSchedule;
+/// use fix_checksum::validator::Validator;
+/// use fix_checksum::ValidationOptions;
+///
+/// let schedule = SessionSchedule::new(Duration::from_secs(8 * 3_600), Duration::from_secs(17 * 3_600));
+/// let validator = Validator::new(ValidationOptions::new().delimiter('|')).rule(SessionHours(schedule));
+/// let report = validator.validate(b"8=FIX.4.2|9=26|35=0|52=20240102-07:59:00|10=218|");
+/// assert_eq!(report.issues()[0].to_string(), "error (tag 52): SendingTime is outside session hours");
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct SessionHours(pub SessionSchedule);
+
+impl ValidationRule for SessionHours {
+  fn check(&self, msg: &MessageView, report: &mut ValidationReport) {
+    if let Some(sent) = msg.get_str(52).and_then(parse_utc_timestamp) {
+      if !self.0.is_active(sent) { report.error(Some(52), "SendingTime is outside session hours"); }
+    }
+  }
+}
+
+/// Price and quantity tags checked by default by `DecimalFormat`.
+const DECIMAL_TAGS: &[u32] = &[6, 14, 31, 32, 38, 44, 151];
+
+/// Requires price and quantity fields to be plain decimals, without sign
+/// prefixes, exponents or trailing points, and optionally limits the number
+/// of digits after the decimal point.
+///
+/// # Examples
+///
+/// ```
+/// use fix_checksum::rules::DecimalFormat;
+/// use fix_checksum::validator::Validator;
+/// use fix_checksum::ValidationOptions;
+///
+/// let validator = Validator::new(ValidationOptions::new().delimiter('|'))
+///   .rule(DecimalFormat::new().max_precision(2));
+/// let report = validator.validate(b"8=FIX.4.2|9=15|35=D|44=10.125|10=179|");
+/// assert_eq!(report.issues()[0].to_string(), "error (tag 44): Price has more than 2 decimal places");
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct DecimalFormat {
+  tags: Vec<u32>,
+  max_precision: Option<usize>,
+}
+
+impl DecimalFormat {
+  /// Checks AvgPx, CumQty, LastPx, LastQty, OrderQty, Price and LeavesQty
+  /// without a precision limit.
+  pub fn new() -> DecimalFormat {
+    DecimalFormat { tags: DECIMAL_TAGS.to_vec(), max_precision: None }
+  }
+
+  /// Checks only the given tags.
+  pub fn tags(mut self, tags: &[u32]) -> DecimalFormat {
+    self.tags = tags.to_vec();
+    self
+  }
+
+  /// Limits the number of digits after the decimal point.
+  pub fn max_precision(mut self, max_precision: usize) -> DecimalFormat {
+    self.max_precision = Some(max_precision);
+    self
+  }
+}
+
+impl Default for DecimalFormat {
+  fn default() -> DecimalFormat {
+    DecimalFormat::new()
+  }
+}
+
+impl ValidationRule for DecimalFormat {
+  fn check(&self, msg: &MessageView, report: &mut ValidationReport) {
+    for &(tag, value) in msg.fields.iter().filter(|field| self.tags.contains(&field.0)) {
+      let name = tag_name(tag).unwrap_or("field");
+      let value = match ::std::str::from_utf8(value) {
+        Ok(value) if is_decimal(value) => value,
+        _ => { report.error(Some(tag), format!("{} is not a well-formed decimal", name)); continue; },
+      };
+      let precision = value.find('.').map_or(0, |point| value.len() - point - 1);
+      match self.max_precision {
+        Some(max_precision) if precision > max_precision =>
+          report.error(Some(tag), format!("{} has more than {} decimal places", name, max_precision)),
+        _ => {},
+      }
+    }
+  }
+}
+
+/// Requires the NumInGroup fields of repeating groups, such as
+/// NoPartyIDs (453) and NoMDEntries (268), to count the entries which
+/// follow them. Groups checked for one message type only are told apart
+/// by MsgType (35), as the entries of NoMDEntries differ between snapshots
+/// and incremental refreshes.
+///
+/// # Examples
+///
+/// ```
+/// use fix_checksum::rules::GroupCounts;
+/// use fix_checksum::validator::Validator;
+/// use fix_checksum::ValidationOptions;
+///
+/// let validator = Validator::new(ValidationOptions::new().delimiter('|')).rule(GroupCounts::new());
+/// let report = validator.validate(b"8=FIX.4.2|9=30|35=W|268=2|269=0|270=10|271=5|10=090|");
+/// assert_eq!(report.issues()[0].to_string(), "error (tag 268): NoMDEntries is 2 but 1 entries follow");
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct GroupCounts {
+  groups: Vec<(Option<&'static str>, GroupLayout)>,
+}
+
+impl GroupCounts {
+  /// Checks the standard groups with a `GroupLayout`: the parties of every
+  /// message and the market data groups of requests (V), snapshots (W) and
+  /// incremental refreshes (X).
+  pub fn new() -> GroupCounts {
+    GroupCounts { groups: vec![
+      (None, GroupLayout::PARTIES),
+      (Some("V"), GroupLayout::RELATED_SYMBOLS),
+      (Some("V"), GroupLayout::MD_ENTRY_TYPES),
+      (Some("W"), GroupLayout::MD_ENTRIES),
+      (Some("X"), GroupLayout::MD_INCREMENTAL_ENTRIES),
+    ] }
+  }
+
+  /// Checks `layout` in messages of every type too.
+  pub fn group(mut self, layout: GroupLayout) -> GroupCounts {
+    self.groups.push((None, layout));
+    self
+  }
+
+  /// Checks `layout` in messages of type `msg_type` too.
+  pub fn group_of(mut self, msg_type: &'static str, layout: GroupLayout) -> GroupCounts {
+    self.groups.push((Some(msg_type), layout));
+    self
+  }
+}
+
+impl Default for GroupCounts {
+  fn default() -> GroupCounts {
+    GroupCounts::new()
+  }
+}
+
+impl ValidationRule for GroupCounts {
+  fn check(&self, msg: &MessageView, report: &mut ValidationReport) {
+    let msg_type = msg.get_str(35);
+    for &(_, layout) in self.groups.iter().filter(|group| group.0.is_none_or(|only| Some(only) == msg_type)) {
+      let tag = layout.count_tag;
+      let name = tag_name(tag).unwrap_or("NumInGroup");
+      match msg.group_entries(tag, layout.members) {
+        Some((Some(count), ref entries)) if count == entries.len() => {},
+        Some((Some(count), entries)) =>
+          report.error(Some(tag), format!("{} is {} but {} entries follow", name, count, entries.len())),
+        Some((None, _)) => report.error(Some(tag), format!("{} is not a count of entries", name)),
+        None => {},
+      }
+    }
+  }
+}
+
+/// Requires the fields a message type calls for, such as OrigClOrdID (41)
+/// on cancel requests (F) and ExecType (150) and OrdStatus (39) on
+/// execution reports (8), as listed by the dictionary. Issues name the
+/// MsgType (35) they depend on, apart from the missing tags reported by
+/// `RequiredFields`.
+///
+/// # Examples
+///
+/// ```
+/// use fix_checksum::dictionary::Dictionary;
+/// use fix_checksum::rules::ConditionalFields;
+/// use fix_checksum::validator::Validator;
+/// use fix_checksum::ValidationOptions;
+///
+/// let validator = Validator::new(ValidationOptions::new().delimiter('|')).rule(ConditionalFields(Dictionary::new()));
+/// let report = validator.validate(b"8=FIX.4.2|9=26|35=F|11=ORD-2|54=1|55=IBM|10=038|");
+/// assert_eq!(report.issues()[0].to_string(), "error (tag 41): OrigClOrdID is required when MsgType is F");
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConditionalFields(pub Dictionary);
+
+impl ValidationRule for ConditionalFields {
+  fn check(&self, msg: &MessageView, report: &mut ValidationReport) {
+    let msg_type = match msg.get_str(35) {
+      Some(msg_type) => msg_type,
+      None => return,
+    };
+    for tag in self.0.required_fields(msg_type) {
+      if msg.get(tag).is_some() { continue; }
+      let name = self.0.tag_name(tag).map_or_else(|| format!("tag {}", tag), |name| name.to_string());
+      report.error(Some(tag), format!("{} is required when MsgType is {}", name, msg_type));
+    }
+  }
+}
+
+/// Forbids OrderQty (38) and CashOrderQty (152) on the same message, as an
+/// order is sized either in units or as an amount.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ExclusiveQuantities;
+
+impl ValidationRule for ExclusiveQuantities {
+  fn check(&self, msg: &MessageView, report: &mut ValidationReport) {
+    if msg.get(38).is_some() && msg.get(152).is_some() {
+      report.error(Some(152), "OrderQty and CashOrderQty are mutually exclusive");
+    }
+  }
+}
+
+/// Requires the quantities of execution reports (8) to add up: CumQty (14)
+/// and CumQty plus LeavesQty (151) within OrderQty (38), and LastQty (32)
+/// within CumQty. Missing and malformed quantities are not checked.
+///
+/// # Examples
+///
+/// ```
+/// use fix_checksum::rules::ExecutionQuantities;
+/// use fix_checksum::validator::Validator;
+/// use fix_checksum::ValidationOptions;
+///
+/// let validator = Validator::new(ValidationOptions::new().delimiter('|')).rule(ExecutionQuantities);
+/// let report = validator.validate(b"8=FIX.4.2|9=19|35=8|38=100|14=150|10=081|");
+/// assert_eq!(report.issues()[0].to_string(), "error (tag 14): CumQty 150 exceeds OrderQty 100");
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ExecutionQuantities;
+
+impl ValidationRule for ExecutionQuantities {
+  fn check(&self, msg: &MessageView, report: &mut ValidationReport) {
+    if msg.get_str(35) != Some("8") { return; }
+    let quantity = |tag| msg.get_str(tag).filter(|value| is_decimal(value))
+      .and_then(|value| value.parse::<f64>().ok().map(|parsed| (value, parsed)));
+    let (order, cum, leaves, last) = (quantity(38), quantity(14), quantity(151), quantity(32));
+    if let (Some((order_text, order)), Some((cum_text, cum))) = (order, cum) {
+      if exceeds(cum, order) {
+        report.error(Some(14), format!("CumQty {} exceeds OrderQty {}", cum_text, order_text));
+      }
+      if let Some((leaves_text, leaves)) = leaves {
+        if exceeds(cum + leaves, order) {
+          report.error(Some(151),
+            format!("CumQty {} plus LeavesQty {} exceed OrderQty {}", cum_text, leaves_text, order_text));
+        }
+      }
+    }
+    if let (Some((last_text, last)), Some((cum_text, cum))) = (last, cum) {
+      if exceeds(last, cum) {
+        report.error(Some(32), format!("LastQty {} exceeds CumQty {}", last_text, cum_text));
+      }
+    }
+  }
+}
+
+/// Returns `true` if `quantity` is above `limit` by more than rounding
+/// errors of decimal sums.
+fn exceeds(quantity: f64, limit: f64) -> bool {
+  quantity - limit > 1e-9 * limit.abs().max(1.0)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{BeginString, CurrencyCodes, DecimalFormat, EnumValues, FieldOrder, FieldTypes, HeaderOrder, RequiredFields, SessionFieldFormats};
+  use std::time::Duration;
+  use super::{ConditionalFields, ExclusiveQuantities, ExecutionQuantities, GroupCounts, SendingTimeFreshness,
+    SessionHours};
+  use super::super::clock::{FixedClock, parse_utc_timestamp};
+  use super::super::dictionary::{Dictionary, FieldType};
+  use super::super::message::{GroupLayout, MessageView};
+  use super::super::session::SessionSchedule;
+  use super::super::validator::{ValidationReport, ValidationRule};
+
+  fn check<R: ValidationRule>(rule: R, message: &[u8]) -> Vec<String> {
+    let mut report = ValidationReport::new();
+    rule.check(&MessageView::parse(message, '|').unwrap(), &mut report);
+    report.issues().iter().map(|issue| issue.to_string()).collect()
+  }
+
+  #[test]
+  fn it_should_check_header_structure() {
+    assert!(check(BeginString(&["FIX.4.2"]), b"8=FIX.4.2|9=5|35=0|10=161|").is_empty());
+    assert_eq!(check(BeginString(&["FIX.4.2", "FIX.4.3"]), b"8=FIX.4.4|9=5|35=0|10=161|"),
+      vec!["error (tag 8): BeginString FIX.4.4 is not FIX.4.2 or FIX.4.3"]);
+    assert!(check(HeaderOrder, b"8=FIX.4.2|9=5|35=0|10=161|").is_empty());
+    assert_eq!(check(HeaderOrder, b"8=FIX.4.2|35=0|9=5|10=161|"), vec![
+      "error (tag 9): tag 9 must be field 2 of the message",
+      "error (tag 35): tag 35 must be field 3 of the message",
+    ]);
+    assert!(check(FieldOrder, b"8=FIX.4.2|9=5|35=D|5001=1|55=IBM|6000=2|10=161|").is_empty());
+    assert_eq!(check(FieldOrder, b"8=FIX.4.2|9=5|35=D|55=IBM|49=A|10=161|"),
+      vec!["error (tag 49): Header tag 49 follows Body fields"]);
+    assert_eq!(check(FieldOrder, b"8=FIX.4.2|9=5|35=D|10=161|55=IBM|"),
+      vec!["error (tag 55): Body tag 55 follows Trailer fields"]);
+    assert_eq!(check(RequiredFields(&[35, 49]), b"8=FIX.4.2|9=5|35=0|10=161|"),
+      vec!["error (tag 49): required tag 49 is missing"]);
+  }
+
+  #[test]
+  fn it_should_check_group_counts() {
+    let rule = GroupCounts::new;
+    assert!(check(rule(), b"8=FIX.4.2|35=D|453=2|448=A|452=1|448=B|452=3|55=IBM|").is_empty());
+    assert!(check(rule(), b"8=FIX.4.2|35=X|268=1|279=0|269=0|270=10|").is_empty());
+    assert!(check(rule(), b"8=FIX.4.2|35=W|268=0|55=IBM|").is_empty());
+    assert_eq!(check(rule(), b"8=FIX.4.2|35=D|453=3|448=A|452=1|448=B|452=3|"),
+      vec!["error (tag 453): NoPartyIDs is 3 but 2 entries follow"]);
+    assert_eq!(check(rule(), b"8=FIX.4.2|35=W|268=1|279=0|269=0|270=10|"),
+      vec!["error (tag 268): NoMDEntries is 1 but 0 entries follow"]);
+    assert_eq!(check(rule(), b"8=FIX.4.2|35=V|146=x|267=1|269=0|"),
+      vec!["error (tag 146): NoRelatedSym is not a count of entries"]);
+
+    let legs = GroupLayout { count_tag: 555, members: &[600, 624] };
+    assert!(check(rule(), b"8=FIX.4.4|35=AB|555=2|600=IBM|").is_empty());
+    assert_eq!(check(rule().group_of("AB", legs), b"8=FIX.4.4|35=AB|555=2|600=IBM|"),
+      vec!["error (tag 555): NumInGroup is 2 but 1 entries follow"]);
+    assert!(check(rule().group_of("D", legs), b"8=FIX.4.4|35=AB|555=2|600=IBM|").is_empty());
+    assert_eq!(check(rule().group(legs), b"8=FIX.4.4|35=D|555=0|600=IBM|").len(), 1);
+  }
+
+  #[test]
+  fn it_should_check_session_field_formats() {
+    assert!(check(SessionFieldFormats, b"8=FIX.4.2|35=0|34=12|52=20240102-09:30:00|43=N|").is_empty());
+    assert_eq!(check(SessionFieldFormats, b"8=FIX.4.2|35=0|34=012|52=20240102|97=1|"), vec![
+      "error (tag 34): MsgSeqNum must be a positive integer",
+      "error (tag 52): value must be a UTCTimestamp",
+      "error (tag 97): value must be Y or N",
+    ]);
+  }
+
+  #[test]
+  fn it_should_check_field_types() {
+    let mut dictionary = Dictionary::new();
+    dictionary.register(5001, "StrategyID", FieldType::Int).unwrap();
+    assert!(check(FieldTypes(dictionary.clone()), b"8=FIX.4.2|35=D|38=100|5001=7|6000=x|").is_empty());
+    assert_eq!(check(FieldTypes(dictionary), b"8=FIX.4.2|35=D|38=1e2|5001=A|"), vec![
+      "error (tag 38): OrderQty value is not a valid decimal",
+      "error (tag 5001): StrategyID value is not a valid int",
+    ]);
+  }
+
+  #[test]
+  fn it_should_check_decimal_format() {
+    assert!(check(DecimalFormat::new(), b"8=FIX.4.2|35=8|6=0|14=100|31=-1.5|44=.25|").is_empty());
+    assert_eq!(check(DecimalFormat::new(), b"8=FIX.4.2|35=D|38=+100|44=1.|58=1e5|"), vec![
+      "error (tag 38): OrderQty is not a well-formed decimal",
+      "error (tag 44): Price is not a well-formed decimal",
+    ]);
+    assert_eq!(check(DecimalFormat::new().tags(&[38]).max_precision(0), b"8=FIX.4.2|35=D|38=1.0|44=1.|"),
+      vec!["error (tag 38): OrderQty has more than 0 decimal places"]);
+  }
+
+  #[test]
+  fn it_should_check_enum_values_and_currencies() {
+    let mut dictionary = Dictionary::new();
+    dictionary.register(5001, "Desk", FieldType::Char).unwrap();
+    dictionary.register_values(5001, &["A", "B"]).unwrap();
+    assert!(check(EnumValues(dictionary.clone()), b"8=FIX.4.2|35=D|54=1|40=2|59=0|5001=A|55=IBM|").is_empty());
+    assert_eq!(check(EnumValues(dictionary), b"8=FIX.4.2|35=D|54=0|59=9|5001=C|"), vec![
+      "error (tag 54): Side value 0 is not one of 1, 2, 3, 4, 5, 6, 7, 8, 9, A, B, C, D, E, F, G",
+      "error (tag 59): TimeInForce value 9 is not one of 0, 1, 2, 3, 4, 5, 6, 7",
+      "error (tag 5001): Desk value C is not legal",
+    ]);
+
+    assert!(check(CurrencyCodes, b"8=FIX.4.2|35=D|15=USD|120=JPY|").is_empty());
+    assert_eq!(check(CurrencyCodes, b"8=FIX.4.2|35=D|15=usd|120=XYZ|"), vec![
+      "error (tag 15): usd is not an ISO 4217 currency code",
+      "error (tag 120): XYZ is not an ISO 4217 currency code",
+    ]);
+  }
+
+  #[test]
+  fn it_should_check_sending_time_freshness() {
+    let clock = FixedClock(parse_utc_timestamp("20240102-09:30:00").unwrap());
+    let rule = || SendingTimeFreshness::new(clock, Duration::from_secs(2));
+    assert!(check(rule(), b"8=FIX.4.2|35=0|52=20240102-09:29:58|").is_empty());
+    assert!(check(rule(), b"8=FIX.4.2|35=0|52=20240102-09:30:01.999|").is_empty());
+    assert!(check(rule(), b"8=FIX.4.2|35=0|").is_empty());
+    assert_eq!(check(rule(), b"8=FIX.4.2|35=0|52=20240102-09:30:02.500|"),
+      vec!["error (tag 52): SendingTime is 2500 ms in the future"]);
+    assert_eq!(check(rule(), b"8=FIX.4.2|35=0|52=20240102|"),
+      vec!["error (tag 52): SendingTime is not a UTCTimestamp"]);
+  }
+
+  #[test]
+  fn it_should_check_session_hours() {
+    let schedule = SessionSchedule::new(Duration::from_secs(8 * 3_600), Duration::from_secs(17 * 3_600));
+    assert!(check(SessionHours(schedule.clone()), b"8=FIX.4.2|35=0|52=20240102-08:00:00|").is_empty());
+    assert!(check(SessionHours(schedule.clone()), b"8=FIX.4.2|35=0|52=2024|").is_empty());
+    assert_eq!(check(SessionHours(schedule), b"8=FIX.4.2|35=0|52=20240102-17:00:00|"),
+      vec!["error (tag 52): SendingTime is outside session hours"]);
+  }
+
+  #[test]
+  fn it_should_require_fields_by_message_type() {
+    let mut dictionary = Dictionary::new();
+    let rule = ConditionalFields(dictionary.clone());
+    assert!(check(rule.clone(), b"8=FIX.4.2|9=40|35=G|41=ORD-1|11=ORD-2|54=1|55=IBM|40=1|10=220|").is_empty());
+    assert!(check(rule.clone(), b"8=FIX.4.2|9=39|35=8|37=1|17=E1|150=0|39=0|54=1|55=IBM|10=011|").is_empty());
+    assert_eq!(check(rule.clone(), b"8=FIX.4.2|9=20|35=8|37=1|17=E1|54=1|55=IBM|10=000|"), vec![
+      "error (tag 150): ExecType is required when MsgType is 8",
+      "error (tag 39): OrdStatus is required when MsgType is 8",
+    ]);
+    assert!(check(rule.clone(), b"8=FIX.4.2|9=5|35=0|10=161|").is_empty());
+    assert!(check(rule, b"8=FIX.4.2|9=0|10=000|").is_empty());
+
+    dictionary.register_required("U1", &[5001]);
+    assert_eq!(check(ConditionalFields(dictionary), b"8=FIX.4.2|9=6|35=U1|10=000|"),
+      vec!["error (tag 5001): tag 5001 is required when MsgType is U1"]);
+  }
+
+  #[test]
+  fn it_should_check_cross_field_invariants() {
+    assert!(check(ExclusiveQuantities, b"8=FIX.4.2|9=19|35=D|14=150|38=100|10=093|").is_empty());
+    assert_eq!(check(ExclusiveQuantities, b"8=FIX.4.2|9=21|35=D|38=100|152=5000|10=184|"),
+      vec!["error (tag 152): OrderQty and CashOrderQty are mutually exclusive"]);
+
+    assert!(check(ExecutionQuantities, b"8=FIX.4.2|9=31|35=8|38=100|14=60|151=40|32=10|10=088|").is_empty());
+    assert!(check(ExecutionQuantities, b"8=FIX.4.2|9=27|35=8|38=0.3|14=0.1|151=0.2|10=174|").is_empty());
+    assert_eq!(check(ExecutionQuantities, b"8=FIX.4.2|9=31|35=8|38=100|14=60|151=50|32=70|10=095|"), vec![
+      "error (tag 151): CumQty 60 plus LeavesQty 50 exceed OrderQty 100",
+      "error (tag 32): LastQty 70 exceeds CumQty 60",
+    ]);
+    assert!(check(ExecutionQuantities, b"8=FIX.4.2|9=19|35=8|38=100|14=1e3|10=132|").is_empty());
+    assert!(check(ExecutionQuantities, b"8=FIX.4.2|9=19|35=D|14=150|38=100|10=093|").is_empty());
+  }
+}
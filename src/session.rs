@@ -0,0 +1,398 @@
+//! FIX session identification and scheduling.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use super::message::MessageView;
+
+const SENDER_COMP_ID: u32 = 49;
+const TARGET_COMP_ID: u32 = 56;
+
+/// A session as identified by the SenderCompID (49) and TargetCompID (56)
+/// of its messages.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SessionId {
+  pub sender_comp_id: String,
+  pub target_comp_id: String,
+}
+
+impl SessionId {
+  pub fn new(sender_comp_id: &str, target_comp_id: &str) -> SessionId {
+    SessionId { sender_comp_id: sender_comp_id.to_string(), target_comp_id: target_comp_id.to_string() }
+  }
+
+  /// Returns the session of `message`, if both comp ids are present.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use fix_checksum::message::MessageView;
+  /// use fix_checksum::session::SessionId;
+  ///
+  /// let message = MessageView::parse(b"8=FIX.4.2|9=23|35=0|49=BRKR|56=INVMGR|10=041|", '|').unwrap();
+  /// assert_eq!(SessionId::from_message(&message), Some(SessionId::new("BRKR", "INVMGR")));
+  /// ```
+  pub fn from_message(message: &MessageView) -> Option<SessionId> {
+    match (message.get_str(SENDER_COMP_ID), message.get_str(TARGET_COMP_ID)) {
+      (Some(sender), Some(target)) => Some(SessionId::new(sender, target)),
+      _ => None,
+    }
+  }
+}
+
+impl fmt::Display for SessionId {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{}->{}", self.sender_comp_id, self.target_comp_id)
+  }
+}
+
+/// A day of the week.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Weekday {
+  Monday,
+  Tuesday,
+  Wednesday,
+  Thursday,
+  Friday,
+  Saturday,
+  Sunday,
+}
+
+impl Weekday {
+  /// Monday to Friday.
+  pub const WORKDAYS: &'static [Weekday] =
+    &[Weekday::Monday, Weekday::Tuesday, Weekday::Wednesday, Weekday::Thursday, Weekday::Friday];
+  const ALL: [Weekday; 7] = [Weekday::Monday, Weekday::Tuesday, Weekday::Wednesday, Weekday::Thursday,
+    Weekday::Friday, Weekday::Saturday, Weekday::Sunday];
+
+  /// Returns the day `days` after 1970-01-01, a Thursday.
+  fn from_days(days: i64) -> Weekday {
+    Weekday::ALL[(days + 3).rem_euclid(7) as usize]
+  }
+}
+
+const MILLIS_PER_DAY: i64 = 86_400_000;
+
+/// The hours a session is up: from a start to an end time of day on some
+/// days of the week, at a fixed offset from UTC. A session ending at or
+/// before its start time ends on the next day; its day is the day it
+/// starts on.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+/// use fix_checksum::clock::parse_utc_timestamp;
+/// use fix_checksum::session::{SessionSchedule, Weekday};
+///
+/// let hour = |hours: u64| Duration::from_secs(hours * 3_600);
+/// let schedule = SessionSchedule::new(hour(8), hour(17)).days(Weekday::WORKDAYS).utc_offset(-5 * 60);
+/// assert!(schedule.is_active(parse_utc_timestamp("20240102-14:00:00").unwrap()));
+/// assert!(!schedule.is_active(parse_utc_timestamp("20240106-14:00:00").unwrap()));
+///
+/// let (logon, logout) = schedule.next_window(parse_utc_timestamp("20240105-23:00:00").unwrap()).unwrap();
+/// assert_eq!((logon, logout), (parse_utc_timestamp("20240108-13:00:00").unwrap(),
+///   parse_utc_timestamp("20240108-22:00:00").unwrap()));
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SessionSchedule {
+  start: Duration,
+  end: Duration,
+  days: Vec<Weekday>,
+  utc_offset: i32,
+}
+
+impl SessionSchedule {
+  /// Creates a schedule from `start` to `end`, both times since midnight,
+  /// every day in UTC.
+  pub fn new(start: Duration, end: Duration) -> SessionSchedule {
+    let day = Duration::from_millis(MILLIS_PER_DAY as u64);
+    SessionSchedule { start: start.min(day), end: end.min(day), days: Weekday::ALL.to_vec(), utc_offset: 0 }
+  }
+
+  /// Sets the days of the week the session starts on.
+  pub fn days(mut self, days: &[Weekday]) -> SessionSchedule {
+    self.days = days.to_vec();
+    self
+  }
+
+  /// Sets the offset of the times of day from UTC in minutes, e.g. `-300`
+  /// for New York in winter.
+  pub fn utc_offset(mut self, minutes: i32) -> SessionSchedule {
+    self.utc_offset = minutes;
+    self
+  }
+
+  /// Returns `true` if the session is up at `time`.
+  pub fn is_active(&self, time: SystemTime) -> bool {
+    self.window_at(time).is_some()
+  }
+
+  /// Returns the start and end of the session up at `time`.
+  pub fn window_at(&self, time: SystemTime) -> Option<(SystemTime, SystemTime)> {
+    let local = self.local_millis(time);
+    let day = local.div_euclid(MILLIS_PER_DAY);
+    (day - 1..day + 1).filter_map(|day| self.window(day))
+      .find(|&(start, end)| start <= local && local < end)
+      .map(|(start, end)| (self.system_time(start), self.system_time(end)))
+  }
+
+  /// Returns the start and end of the first session starting at or after
+  /// `time`, the expected Logon (A) and Logout (5) times.
+  pub fn next_window(&self, time: SystemTime) -> Option<(SystemTime, SystemTime)> {
+    let local = self.local_millis(time);
+    let day = local.div_euclid(MILLIS_PER_DAY);
+    (day..day + 8).filter_map(|day| self.window(day))
+      .find(|&(start, _)| start >= local)
+      .map(|(start, end)| (self.system_time(start), self.system_time(end)))
+  }
+
+  /// Local start and end in milliseconds of the session starting on `day`.
+  fn window(&self, day: i64) -> Option<(i64, i64)> {
+    if !self.days.contains(&Weekday::from_days(day)) { return None; }
+    let (start, end) = (self.start.as_millis() as i64, self.end.as_millis() as i64);
+    let end = if end <= start { end + MILLIS_PER_DAY } else { end };
+    Some((day * MILLIS_PER_DAY + start, day * MILLIS_PER_DAY + end))
+  }
+
+  fn local_millis(&self, time: SystemTime) -> i64 {
+    let millis = match time.duration_since(UNIX_EPOCH) {
+      Ok(since) => since.as_millis() as i64,
+      Err(before) => -(before.duration().as_millis() as i64),
+    };
+    millis + self.utc_offset as i64 * 60_000
+  }
+
+  fn system_time(&self, local: i64) -> SystemTime {
+    let millis = local - self.utc_offset as i64 * 60_000;
+    if millis >= 0 {
+      UNIX_EPOCH + Duration::from_millis(millis as u64)
+    } else {
+      UNIX_EPOCH - Duration::from_millis(millis.unsigned_abs())
+    }
+  }
+}
+
+/// What a message means for the sequence numbers of its session.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SeqNumEvent {
+  /// The message has the expected MsgSeqNum (34), or is the first one seen.
+  InSequence { seq_num: u64 },
+  /// Messages numbered `expected` up to before `received` are missing.
+  Gap { expected: u64, received: u64 },
+  /// A number below the expected one without PossDupFlag (43).
+  TooLow { expected: u64, received: u64 },
+  /// A number below the expected one resent with PossDupFlag (43) set.
+  PossDup { seq_num: u64 },
+  /// Numbering restarts at `new_seq_num`, by a Logon (A) with
+  /// ResetSeqNumFlag (141) or a SequenceReset (4) in reset mode.
+  Reset { new_seq_num: u64 },
+  /// A SequenceReset-GapFill (4) skipping `seq_num` up to before
+  /// `new_seq_num`.
+  GapFill { seq_num: u64, new_seq_num: u64 },
+}
+
+impl SeqNumEvent {
+  /// Returns `true` for events a correct session does not produce.
+  pub fn is_anomaly(&self) -> bool {
+    matches!(*self, SeqNumEvent::Gap { .. } | SeqNumEvent::TooLow { .. })
+  }
+}
+
+impl fmt::Display for SeqNumEvent {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      SeqNumEvent::InSequence { seq_num } => write!(f, "{} in sequence", seq_num),
+      SeqNumEvent::Gap { expected, received } => write!(f, "gap: expected {}, received {}", expected, received),
+      SeqNumEvent::TooLow { expected, received } =>
+        write!(f, "too low: expected {}, received {}", expected, received),
+      SeqNumEvent::PossDup { seq_num } => write!(f, "{} resent as possible duplicate", seq_num),
+      SeqNumEvent::Reset { new_seq_num } => write!(f, "reset to {}", new_seq_num),
+      SeqNumEvent::GapFill { seq_num, new_seq_num } => write!(f, "gap fill from {} to {}", seq_num, new_seq_num),
+    }
+  }
+}
+
+/// Follows the MsgSeqNum (34) of every session of a log, taking resets and
+/// gap fills into account so that they are not reported as gaps.
+///
+/// # Examples
+///
+/// ```
+/// use fix_checksum::message::MessageView;
+/// use fix_checksum::session::{SeqNumEvent, SeqNumTracker};
+///
+/// let mut tracker = SeqNumTracker::new();
+/// let mut track = |message: &[u8]| tracker.track(&MessageView::parse(message, '|').unwrap());
+/// assert_eq!(track(b"8=FIX.4.2|35=0|49=A|56=B|34=7|"), Some(SeqNumEvent::InSequence { seq_num: 7 }));
+/// assert_eq!(track(b"8=FIX.4.2|35=A|49=A|56=B|34=1|141=Y|"), Some(SeqNumEvent::Reset { new_seq_num: 1 }));
+/// assert_eq!(track(b"8=FIX.4.2|35=0|49=A|56=B|34=2|"), Some(SeqNumEvent::InSequence { seq_num: 2 }));
+/// assert_eq!(track(b"8=FIX.4.2|35=0|49=A|56=B|34=5|"), Some(SeqNumEvent::Gap { expected: 3, received: 5 }));
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SeqNumTracker {
+  expected: BTreeMap<SessionId, u64>,
+}
+
+impl SeqNumTracker {
+  /// Creates a tracker which has seen no sessions.
+  pub fn new() -> SeqNumTracker {
+    SeqNumTracker::default()
+  }
+
+  /// Returns the MsgSeqNum (34) expected next from `session`.
+  pub fn expected(&self, session: &SessionId) -> Option<u64> {
+    self.expected.get(session).cloned()
+  }
+
+  /// Follows `message`. Messages without session identifiers or a numeric
+  /// MsgSeqNum (34) are ignored.
+  pub fn track(&mut self, message: &MessageView) -> Option<SeqNumEvent> {
+    let session = SessionId::from_message(message)?;
+    let seq_num: u64 = message.get_str(34)?.parse().ok()?;
+    let flag = |tag| message.get(tag) == Some(b"Y");
+    let expected = self.expected.get(&session).cloned();
+
+    let (event, next) = match message.get(35) {
+      Some(b"A") if flag(141) => (SeqNumEvent::Reset { new_seq_num: seq_num }, seq_num + 1),
+      Some(b"4") => match message.get_str(36).and_then(|value| value.parse::<u64>().ok()) {
+        Some(new_seq_num) if flag(123) => match expected {
+          Some(expected) if seq_num > expected => (SeqNumEvent::Gap { expected, received: seq_num }, new_seq_num),
+          Some(expected) => (SeqNumEvent::GapFill { seq_num, new_seq_num }, new_seq_num.max(expected)),
+          None => (SeqNumEvent::GapFill { seq_num, new_seq_num }, new_seq_num),
+        },
+        Some(new_seq_num) => (SeqNumEvent::Reset { new_seq_num }, new_seq_num),
+        None => return None,
+      },
+      _ => match expected {
+        Some(expected) if seq_num > expected => (SeqNumEvent::Gap { expected, received: seq_num }, seq_num + 1),
+        Some(expected) if seq_num < expected && flag(43) => (SeqNumEvent::PossDup { seq_num }, expected),
+        Some(expected) if seq_num < expected => (SeqNumEvent::TooLow { expected, received: seq_num }, expected),
+        _ => (SeqNumEvent::InSequence { seq_num }, seq_num + 1),
+      },
+    };
+    self.expected.insert(session, next);
+    Some(event)
+  }
+}
+
+/// MsgType (35) values of the session layer.
+const ADMIN_MSG_TYPES: &[&[u8]] = &[b"0", b"1", b"2", b"3", b"4", b"5", b"A"];
+
+/// Returns `true` for the MsgType (35) of a session-layer message:
+/// Heartbeat, TestRequest, ResendRequest, Reject, SequenceReset, Logout
+/// and Logon.
+pub fn is_admin_msg_type(msg_type: &[u8]) -> bool {
+  ADMIN_MSG_TYPES.contains(&msg_type)
+}
+
+/// Which layer a duplicated message belongs to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum DuplicateKind {
+  /// A session-layer message, e.g. a Heartbeat resent by a gap fill.
+  Admin,
+  /// An application message, e.g. an order sent twice.
+  Application,
+}
+
+/// A message whose MsgSeqNum (34) was seen before on its session, or
+/// which was resent with PossDupFlag (43).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Duplicate {
+  pub session: SessionId,
+  pub seq_num: u64,
+  pub msg_type: String,
+  pub kind: DuplicateKind,
+  /// Whether the sender flagged the message as a possible duplicate.
+  pub poss_dup: bool,
+}
+
+/// Finds duplicated messages per session.
+///
+/// # Examples
+///
+/// ```
+/// use fix_checksum::message::MessageView;
+/// use fix_checksum::session::{DuplicateDetector, DuplicateKind};
+///
+/// let mut detector = DuplicateDetector::new();
+/// let mut check = |message: &[u8]| detector.check(&MessageView::parse(message, '|').unwrap());
+/// assert_eq!(check(b"8=FIX.4.2|35=0|49=A|56=B|34=3|"), None);
+/// assert_eq!(check(b"8=FIX.4.2|35=0|49=A|56=B|34=3|").unwrap().kind, DuplicateKind::Admin);
+/// assert_eq!(check(b"8=FIX.4.2|35=D|49=A|56=B|34=2|43=Y|").unwrap().kind, DuplicateKind::Application);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DuplicateDetector {
+  seen: BTreeMap<SessionId, BTreeSet<u64>>,
+}
+
+impl DuplicateDetector {
+  /// Creates a detector which has seen no messages.
+  pub fn new() -> DuplicateDetector {
+    DuplicateDetector::default()
+  }
+
+  /// Returns the duplicate `message` is, if any. Messages without session
+  /// identifiers or a numeric MsgSeqNum (34) are never duplicates.
+  pub fn check(&mut self, message: &MessageView) -> Option<Duplicate> {
+    let session = SessionId::from_message(message)?;
+    let seq_num: u64 = message.get_str(34)?.parse().ok()?;
+    let seen = !self.seen.entry(session.clone()).or_default().insert(seq_num);
+    let poss_dup = message.get(43) == Some(b"Y");
+    if !seen && !poss_dup { return None; }
+
+    let msg_type = message.get(35).unwrap_or(b"");
+    let kind = if is_admin_msg_type(msg_type) { DuplicateKind::Admin } else { DuplicateKind::Application };
+    Some(Duplicate { session, seq_num, msg_type: String::from_utf8_lossy(msg_type).into_owned(), kind, poss_dup })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::time::Duration;
+  use super::{SeqNumEvent, SeqNumTracker, SessionId, SessionSchedule, Weekday};
+  use super::super::clock::parse_utc_timestamp;
+  use super::super::message::MessageView;
+
+  #[test]
+  fn it_should_schedule_overnight_sessions() {
+    let hour = |hours: u64| Duration::from_secs(hours * 3_600);
+    let schedule = SessionSchedule::new(hour(22), hour(6)).days(&[Weekday::Sunday]);
+    let time = |value| parse_utc_timestamp(value).unwrap();
+    assert_eq!(schedule.window_at(time("20240108-05:59:59")), Some((time("20240107-22:00:00"),
+      time("20240108-06:00:00"))));
+    assert!(!schedule.is_active(time("20240108-06:00:00")));
+    assert!(!schedule.is_active(time("20240106-23:00:00")));
+    assert_eq!(schedule.next_window(time("20240108-00:00:00")).map(|window| window.0),
+      Some(time("20240114-22:00:00")));
+    assert_eq!(schedule.clone().days(&[]).next_window(time("20240108-00:00:00")), None);
+
+    let always = SessionSchedule::new(hour(0), hour(0)).utc_offset(90);
+    assert_eq!(always.window_at(time("20240108-00:00:00")), Some((time("20240107-22:30:00"),
+      time("20240108-22:30:00"))));
+  }
+
+  #[test]
+  fn it_should_follow_resets_and_gap_fills() {
+    let mut tracker = SeqNumTracker::new();
+    let mut track = |message: &str| {
+      let message = format!("8=FIX.4.2|49=A|56=B|{}", message);
+      tracker.track(&MessageView::parse(message.as_bytes(), '|').unwrap())
+    };
+    assert_eq!(track("35=0|34=10|"), Some(SeqNumEvent::InSequence { seq_num: 10 }));
+    assert_eq!(track("35=4|34=11|123=Y|36=15|"), Some(SeqNumEvent::GapFill { seq_num: 11, new_seq_num: 15 }));
+    assert_eq!(track("35=0|34=15|"), Some(SeqNumEvent::InSequence { seq_num: 15 }));
+    assert_eq!(track("35=8|34=12|43=Y|"), Some(SeqNumEvent::PossDup { seq_num: 12 }));
+    assert_eq!(track("35=4|34=12|43=Y|123=Y|36=14|"), Some(SeqNumEvent::GapFill { seq_num: 12, new_seq_num: 14 }));
+    assert_eq!(track("35=0|34=16|"), Some(SeqNumEvent::InSequence { seq_num: 16 }));
+    assert_eq!(track("35=4|34=99|36=100|"), Some(SeqNumEvent::Reset { new_seq_num: 100 }));
+    assert_eq!(track("35=0|34=100|"), Some(SeqNumEvent::InSequence { seq_num: 100 }));
+    assert_eq!(track("35=4|34=103|123=Y|36=110|"), Some(SeqNumEvent::Gap { expected: 101, received: 103 }));
+    assert_eq!(track("35=0|34=109|"), Some(SeqNumEvent::TooLow { expected: 110, received: 109 }));
+    assert_eq!(track("35=A|34=1|141=N|"), Some(SeqNumEvent::TooLow { expected: 110, received: 1 }));
+    assert_eq!(track("35=0|"), None);
+    assert_eq!(tracker.expected(&SessionId::new("A", "B")), Some(110));
+    assert_eq!(SeqNumEvent::Gap { expected: 101, received: 103 }.to_string(), "gap: expected 101, received 103");
+  }
+}
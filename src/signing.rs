@@ -0,0 +1,274 @@
+//! Ed25519 signatures carried in messages, for bilateral links needing
+//! non-repudiation.
+//!
+//! Unlike `auth`, where both counterparties hold the same key and either
+//! could have produced a message, only the holder of the signing key can
+//! sign: the counterparty verifies with the public key alone. The signature
+//! covers every field except BodyLength (9), CheckSum (10) and the signature
+//! field itself, in wire order, joined with SOH as in `auth`. It is written
+//! as 128 lowercase hexadecimal digits before the checksum, by default as
+//! Signature (89) after SignatureLength (93).
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use super::{FIXChecksumValidatorError, ValidationOptions, finalize, validate_bytes_with};
+use super::FIXChecksumValidatorError::{RequiredFieldMissing, FieldValueInvalid};
+use super::auth::write_field;
+use super::ed25519;
+use super::hash::to_hex;
+use super::message::MessageView;
+
+/// An Ed25519 public key.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct PublicKey([u8; 32]);
+
+impl PublicKey {
+  /// Creates a key from its 32 byte encoding.
+  pub fn from_bytes(bytes: [u8; 32]) -> PublicKey {
+    PublicKey(bytes)
+  }
+
+  /// Parses a key written as 64 hexadecimal digits.
+  pub fn from_hex(hex: &str) -> Option<PublicKey> {
+    let mut key = [0; 32];
+    if decode_hex(hex, &mut key) { Some(PublicKey(key)) } else { None }
+  }
+
+  /// Returns the 32 byte encoding of the key.
+  pub fn as_bytes(&self) -> &[u8; 32] {
+    &self.0
+  }
+}
+
+/// Formats the key as 64 lowercase hexadecimal digits.
+impl fmt::Display for PublicKey {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    f.write_str(&to_hex(&self.0))
+  }
+}
+
+/// An Ed25519 secret key and its public key.
+#[derive(Clone)]
+pub struct SigningKey {
+  seed: [u8; 32],
+  public: PublicKey,
+}
+
+impl SigningKey {
+  /// Creates a key from its 32 byte secret seed, which should come from a
+  /// cryptographically secure random source.
+  pub fn from_seed(seed: [u8; 32]) -> SigningKey {
+    SigningKey { seed, public: PublicKey(ed25519::public_key(&seed)) }
+  }
+
+  /// Returns the public key to hand to counterparties.
+  pub fn public_key(&self) -> PublicKey {
+    self.public
+  }
+}
+
+/// Shows the public key only.
+impl fmt::Debug for SigningKey {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    f.debug_struct("SigningKey").field("public", &self.public).finish()
+  }
+}
+
+/// Signs outbound messages.
+///
+/// # Examples
+///
+/// ```
+/// use fix_checksum::ValidationOptions;
+/// use fix_checksum::signing::{MessageSigner, MessageVerifier, SigningKey};
+///
+/// let options = ValidationOptions::new().delimiter('|');
+/// let key = SigningKey::from_seed([7; 32]);
+/// let signed = MessageSigner::new(key.clone()).sign(b"8=FIX.4.2|35=0|49=BRKR|56=EXCH|", &options).unwrap();
+/// assert!(String::from_utf8_lossy(&signed).contains("|93=128|89="));
+///
+/// let verifier = MessageVerifier::new().key("BRKR", key.public_key());
+/// assert!(verifier.verify(&signed, &options).unwrap());
+/// assert!(!MessageVerifier::new().key("BRKR", SigningKey::from_seed([8; 32]).public_key())
+///   .verify(&signed, &options).unwrap());
+/// ```
+#[derive(Clone, Debug)]
+pub struct MessageSigner {
+  key: SigningKey,
+  tag: u32,
+}
+
+impl MessageSigner {
+  /// Creates a signer writing the signature to Signature (89).
+  pub fn new(key: SigningKey) -> MessageSigner {
+    MessageSigner { key, tag: 89 }
+  }
+
+  /// Carries the signature in `tag` instead, usually a user-defined tag.
+  pub fn tag(mut self, tag: u32) -> MessageSigner {
+    self.tag = tag;
+    self
+  }
+
+  /// Returns `message` with its signature, replacing a previous one, and a
+  /// recomputed BodyLength and CheckSum.
+  pub fn sign(&self, message: &[u8], options: &ValidationOptions) -> Result<Vec<u8>, FIXChecksumValidatorError> {
+    let view = MessageView::parse(message, options.delimiter as char)?;
+    let body = signed_content(&view, self.tag);
+    let signature = to_hex(&ed25519::sign(&self.key.seed, &self.key.public.0, &body));
+
+    let mut signed = Vec::with_capacity(message.len() + 144);
+    for (tag, value) in view.fields().filter(|field| is_signed(field.0, self.tag)) {
+      write_field(&mut signed, tag, value, options.delimiter);
+    }
+    if self.tag == 89 { write_field(&mut signed, 93, signature.len().to_string().as_bytes(), options.delimiter); }
+    write_field(&mut signed, self.tag, signature.as_bytes(), options.delimiter);
+    finalize(&signed, options)
+  }
+}
+
+/// Verifies inbound messages against the public keys of their senders.
+#[derive(Clone, Debug)]
+pub struct MessageVerifier {
+  keys: BTreeMap<String, PublicKey>,
+  tag: u32,
+}
+
+impl MessageVerifier {
+  /// Creates a verifier knowing no keys, reading signatures from
+  /// Signature (89).
+  pub fn new() -> MessageVerifier {
+    MessageVerifier { keys: BTreeMap::new(), tag: 89 }
+  }
+
+  /// Verifies the messages of SenderCompID (49) `sender` against `key`.
+  pub fn key(mut self, sender: &str, key: PublicKey) -> MessageVerifier {
+    self.keys.insert(sender.to_string(), key);
+    self
+  }
+
+  /// Reads signatures from `tag` instead.
+  pub fn tag(mut self, tag: u32) -> MessageVerifier {
+    self.tag = tag;
+    self
+  }
+
+  /// Returns `true` if the checksum of `message` is valid and it is signed
+  /// by the key of its sender. Messages from senders without a key are not
+  /// verified. A message without a SenderCompID or a signature fails with
+  /// `RequiredFieldMissing`, one whose signature is not 128 hexadecimal
+  /// digits with `FieldValueInvalid`.
+  pub fn verify(&self, message: &[u8], options: &ValidationOptions) -> Result<bool, FIXChecksumValidatorError> {
+    if !validate_bytes_with(message, options)? { return Ok(false); }
+    let view = MessageView::parse(message, options.delimiter as char)?;
+    let sender = view.get_str(49).ok_or(RequiredFieldMissing(49))?;
+    let carried = view.get(self.tag).ok_or(RequiredFieldMissing(self.tag))?;
+    let mut signature = [0; 64];
+    let hex = ::std::str::from_utf8(carried).map_err(|_| FieldValueInvalid(self.tag))?;
+    if !decode_hex(hex, &mut signature) { return Err(FieldValueInvalid(self.tag)); }
+
+    Ok(match self.keys.get(sender) {
+      Some(key) => ed25519::verify(&key.0, &signed_content(&view, self.tag), &signature),
+      None => false,
+    })
+  }
+}
+
+impl Default for MessageVerifier {
+  fn default() -> MessageVerifier {
+    MessageVerifier::new()
+  }
+}
+
+/// Returns `false` for the fields written when signing.
+fn is_signed(tag: u32, signature_tag: u32) -> bool {
+  tag != 9 && tag != 10 && tag != signature_tag && !(signature_tag == 89 && tag == 93)
+}
+
+/// Returns the signed fields delimited by SOH, whatever the delimiter of
+/// the message.
+fn signed_content(view: &MessageView, signature_tag: u32) -> Vec<u8> {
+  let mut content = Vec::new();
+  for (tag, value) in view.fields().filter(|field| is_signed(field.0, signature_tag)) {
+    write_field(&mut content, tag, value, b'\x01');
+  }
+  content
+}
+
+/// Fills `bytes` from `hex`, returning `false` unless it is exactly twice
+/// as many hexadecimal digits.
+fn decode_hex(hex: &str, bytes: &mut [u8]) -> bool {
+  if hex.len() != bytes.len() * 2 || !hex.bytes().all(|b| b.is_ascii_hexdigit()) { return false; }
+  let nibble = |digit: u8| (digit as char).to_digit(16).unwrap_or(0) as u8;
+  for (byte, pair) in bytes.iter_mut().zip(hex.as_bytes().chunks(2)) {
+    *byte = nibble(pair[0]) << 4 | nibble(pair[1]);
+  }
+  true
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{MessageSigner, MessageVerifier, PublicKey, SigningKey};
+  use super::super::{ValidationOptions, finalize, validate_bytes_with};
+  use super::super::FIXChecksumValidatorError::{RequiredFieldMissing, FieldValueInvalid};
+
+  const HEARTBEAT: &[u8] = b"8=FIX.4.2|9=36|35=0|49=BRKR|56=EXCH|34=2|10=000|";
+
+  #[test]
+  fn it_should_sign_into_custom_tag() {
+    let options = ValidationOptions::new().delimiter('|').check_body_length(true).strict(true);
+    let key = SigningKey::from_seed([1; 32]);
+    let signer = MessageSigner::new(key.clone()).tag(5001);
+    let verifier = MessageVerifier::new().key("BRKR", key.public_key()).tag(5001);
+    let signed = signer.sign(HEARTBEAT, &options).unwrap();
+    assert!(validate_bytes_with(&signed, &options).unwrap());
+    assert!(verifier.verify(&signed, &options).unwrap());
+    assert_eq!(signer.sign(&signed, &options).unwrap(), signed);
+
+    let tampered = String::from_utf8(signed.clone()).unwrap().replace("34=2|", "34=3|");
+    assert!(!verifier.verify(&finalize(tampered.as_bytes(), &options).unwrap(), &options).unwrap());
+    let spoofed = String::from_utf8(signed).unwrap().replace("49=BRKR|", "49=OTHR|");
+    let spoofed = finalize(spoofed.as_bytes(), &options).unwrap();
+    assert!(!verifier.clone().key("OTHR", SigningKey::from_seed([2; 32]).public_key())
+      .verify(&spoofed, &options).unwrap());
+    assert!(!verifier.verify(&spoofed, &options).unwrap());
+  }
+
+  #[test]
+  fn it_should_verify_whatever_the_delimiter() {
+    let (soh, pipe) = (ValidationOptions::new(), ValidationOptions::new().delimiter('|'));
+    let key = SigningKey::from_seed([1; 32]);
+    let verifier = MessageVerifier::new().key("BRKR", key.public_key());
+    let message: Vec<u8> = HEARTBEAT.iter().map(|b| if *b == b'|' { 1 } else { *b }).collect();
+    let signed = MessageSigner::new(key).sign(&message, &soh).unwrap();
+    assert!(verifier.verify(&signed, &soh).unwrap());
+    let relayed: Vec<u8> = signed.iter().map(|b| if *b == 1 { b'|' } else { *b }).collect();
+    assert!(verifier.verify(&finalize(&relayed, &pipe).unwrap(), &pipe).unwrap());
+  }
+
+  #[test]
+  fn it_should_require_signature() {
+    let options = ValidationOptions::new().delimiter('|');
+    let verifier = MessageVerifier::new();
+    let message = finalize(HEARTBEAT, &options).unwrap();
+    assert_eq!(verifier.verify(&message, &options), Err(RequiredFieldMissing(89)));
+    assert_eq!(verifier.verify(&finalize(b"8=FIX.4.2|35=0|89=00|", &options).unwrap(), &options),
+      Err(RequiredFieldMissing(49)));
+    assert_eq!(verifier.verify(&finalize(b"8=FIX.4.2|35=0|49=A|89=00|", &options).unwrap(), &options),
+      Err(FieldValueInvalid(89)));
+  }
+
+  #[test]
+  fn it_should_format_keys() {
+    let key = SigningKey::from_seed([1; 32]);
+    let public = key.public_key();
+    assert_eq!(PublicKey::from_hex(&public.to_string()), Some(public));
+    assert_eq!(PublicKey::from_bytes(*public.as_bytes()), public);
+    assert_eq!(PublicKey::from_hex("00"), None);
+    assert_eq!(PublicKey::from_hex(&"+1".repeat(32)), None);
+    assert_eq!(PublicKey::from_hex(&"0g".repeat(32)), None);
+    assert_eq!(PublicKey::from_hex(&"aF".repeat(32)), Some(PublicKey::from_bytes([0xaf; 32])));
+    assert_eq!(format!("{:?}", key), format!("SigningKey {{ public: {:?} }}", public));
+  }
+}
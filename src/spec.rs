@@ -0,0 +1,208 @@
+//! Conversion between user structs and tag-value messages.
+//!
+//! `FixMessageSpec` is usually derived with the `derive` feature:
+//!
+//! ```ignore
+//! #[derive(FixMessageSpec)]
+//! #[fix(msg_type = "D")]
+//! struct Order {
+//!   #[fix(tag = 11)]
+//!   cl_ord_id: String,
+//!   #[fix(tag = 44)]
+//!   price: Option<f64>,
+//! }
+//! ```
+
+use super::FIXChecksumValidatorError;
+use super::FIXChecksumValidatorError::{RequiredFieldMissing, FieldValueInvalid};
+use super::message::MessageView;
+use super::orders::{FieldWriter, Header};
+
+/// A value which can be stored in a field.
+pub trait FixField: Sized {
+  /// Returns the wire value, `None` leaves the field out.
+  fn encode(&self) -> Option<String>;
+
+  /// Decodes the value of `tag`, `value` is `None` when the field is missing.
+  fn decode(tag: u32, value: Option<&[u8]>) -> Result<Self, FIXChecksumValidatorError>;
+}
+
+/// A message type with a fixed set of fields.
+///
+/// # Examples
+///
+/// ```
+/// use fix_checksum::FIXChecksumValidatorError;
+/// use fix_checksum::message::MessageView;
+/// use fix_checksum::orders::Header;
+/// use fix_checksum::spec::{FixField, FixMessageSpec};
+///
+/// struct TestRequest { test_req_id: String }
+///
+/// impl FixMessageSpec for TestRequest {
+///   const MSG_TYPE: &'static str = "1";
+///
+///   fn encode_fields(&self) -> Vec<(u32, String)> {
+///     vec![(112, self.test_req_id.clone())]
+///   }
+///
+///   fn decode_fields(view: &MessageView) -> Result<TestRequest, FIXChecksumValidatorError> {
+///     Ok(TestRequest { test_req_id: FixField::decode(112, view.get(112))? })
+///   }
+/// }
+///
+/// let header = Header::new("FIX.4.2", "A", "B", 1, "20240102-09:30:00");
+/// let message = TestRequest { test_req_id: "T-1".to_string() }.encode(&header).unwrap();
+/// assert_eq!(TestRequest::decode(&message, '\x01').unwrap().test_req_id, "T-1");
+/// ```
+pub trait FixMessageSpec: Sized {
+  /// MsgType (35) of the message.
+  const MSG_TYPE: &'static str;
+
+  /// Returns the body fields of the message in order.
+  fn encode_fields(&self) -> Vec<(u32, String)>;
+
+  /// Decodes the body fields from a parsed message.
+  fn decode_fields(view: &MessageView) -> Result<Self, FIXChecksumValidatorError>;
+
+  /// Returns the finalized message with `header` and the body fields.
+  fn encode(&self, header: &Header) -> Result<Vec<u8>, FIXChecksumValidatorError> {
+    let mut message = FieldWriter::new(header, Self::MSG_TYPE)?;
+    for (tag, value) in self.encode_fields() { message.text(tag, &value)?; }
+    message.finish()
+  }
+
+  /// Parses `message` and decodes it. Fails with `FieldValueInvalid(35)`
+  /// for other message types.
+  fn decode(message: &[u8], delimiter: char) -> Result<Self, FIXChecksumValidatorError> {
+    let view = MessageView::parse(message, delimiter)?;
+    if view.get(35).ok_or(RequiredFieldMissing(35))? != Self::MSG_TYPE.as_bytes() {
+      return Err(FieldValueInvalid(35));
+    }
+    Self::decode_fields(&view)
+  }
+}
+
+impl FixField for String {
+  fn encode(&self) -> Option<String> {
+    Some(self.clone())
+  }
+
+  fn decode(tag: u32, value: Option<&[u8]>) -> Result<String, FIXChecksumValidatorError> {
+    let value = value.ok_or(RequiredFieldMissing(tag))?;
+    String::from_utf8(value.to_vec()).map_err(|_| FieldValueInvalid(tag))
+  }
+}
+
+impl FixField for char {
+  fn encode(&self) -> Option<String> {
+    Some(self.to_string())
+  }
+
+  fn decode(tag: u32, value: Option<&[u8]>) -> Result<char, FIXChecksumValidatorError> {
+    match value.ok_or(RequiredFieldMissing(tag))? {
+      [byte] if byte.is_ascii() => Ok(*byte as char),
+      _ => Err(FieldValueInvalid(tag)),
+    }
+  }
+}
+
+/// Booleans are `Y` and `N` on the wire.
+impl FixField for bool {
+  fn encode(&self) -> Option<String> {
+    Some(if *self { "Y" } else { "N" }.to_string())
+  }
+
+  fn decode(tag: u32, value: Option<&[u8]>) -> Result<bool, FIXChecksumValidatorError> {
+    match value.ok_or(RequiredFieldMissing(tag))? {
+      b"Y" => Ok(true),
+      b"N" => Ok(false),
+      _ => Err(FieldValueInvalid(tag)),
+    }
+  }
+}
+
+impl<T: FixField> FixField for Option<T> {
+  fn encode(&self) -> Option<String> {
+    self.as_ref().and_then(FixField::encode)
+  }
+
+  fn decode(tag: u32, value: Option<&[u8]>) -> Result<Option<T>, FIXChecksumValidatorError> {
+    match value {
+      Some(value) => T::decode(tag, Some(value)).map(Some),
+      None => Ok(None),
+    }
+  }
+}
+
+macro_rules! numeric_field {
+  ($($numeric:ty),*) => {
+    $(impl FixField for $numeric {
+      fn encode(&self) -> Option<String> {
+        Some(self.to_string())
+      }
+
+      fn decode(tag: u32, value: Option<&[u8]>) -> Result<$numeric, FIXChecksumValidatorError> {
+        let value = value.ok_or(RequiredFieldMissing(tag))?;
+        if value.first() == Some(&b'+') { return Err(FieldValueInvalid(tag)); }
+        ::std::str::from_utf8(value).ok().and_then(|value| value.parse().ok()).ok_or(FieldValueInvalid(tag))
+      }
+    })*
+  };
+}
+
+numeric_field!(u8, u16, u32, u64, usize, i32, i64, f64);
+
+#[cfg(test)]
+mod tests {
+  use super::{FixField, FixMessageSpec};
+  use super::super::FIXChecksumValidatorError;
+  use super::super::FIXChecksumValidatorError::{RequiredFieldMissing, FieldValueInvalid};
+  use super::super::message::MessageView;
+  use super::super::orders::Header;
+
+  #[derive(Debug, PartialEq)]
+  struct Heartbeat {
+    test_req_id: Option<String>,
+    gap_fill: bool,
+  }
+
+  impl FixMessageSpec for Heartbeat {
+    const MSG_TYPE: &'static str = "0";
+
+    fn encode_fields(&self) -> Vec<(u32, String)> {
+      let mut fields = Vec::new();
+      if let Some(value) = self.test_req_id.encode() { fields.push((112, value)); }
+      if let Some(value) = self.gap_fill.encode() { fields.push((123, value)); }
+      fields
+    }
+
+    fn decode_fields(view: &MessageView) -> Result<Heartbeat, FIXChecksumValidatorError> {
+      Ok(Heartbeat { test_req_id: FixField::decode(112, view.get(112))?, gap_fill: FixField::decode(123, view.get(123))? })
+    }
+  }
+
+  #[test]
+  fn it_should_encode_and_decode_fields() {
+    assert_eq!(u32::decode(34, Some(b"12")), Ok(12));
+    assert_eq!(u32::decode(34, Some(b"+12")), Err(FieldValueInvalid(34)));
+    assert_eq!(f64::decode(44, None), Err(RequiredFieldMissing(44)));
+    assert_eq!(Option::<f64>::decode(44, None), Ok(None));
+    assert_eq!(char::decode(54, Some(b"12")), Err(FieldValueInvalid(54)));
+    assert_eq!(bool::decode(43, Some(b"y")), Err(FieldValueInvalid(43)));
+    assert_eq!(Some(1.5).encode(), Some("1.5".to_string()));
+    assert_eq!(None::<u32>.encode(), None);
+  }
+
+  #[test]
+  fn it_should_encode_and_decode_messages() {
+    let header = Header::new("FIX.4.2", "A", "B", 9, "20240102-09:30:00");
+    let heartbeat = Heartbeat { test_req_id: None, gap_fill: true };
+    let message = heartbeat.encode(&header).unwrap();
+    assert!(String::from_utf8(message.clone()).unwrap().contains("52=20240102-09:30:00\x01123=Y\x0110="));
+    assert_eq!(Heartbeat::decode(&message, '\x01').unwrap(), heartbeat);
+
+    assert_eq!(Heartbeat::decode(b"8=FIX.4.2|35=1|123=N|", '|').unwrap_err(), FieldValueInvalid(35));
+    assert_eq!(Heartbeat::decode(b"8=FIX.4.2|35=0|", '|').unwrap_err(), RequiredFieldMissing(123));
+  }
+}
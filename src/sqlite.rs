@@ -0,0 +1,222 @@
+//! `MessageStore` in an SQLite database, enabled by the `sqlite` feature.
+//!
+//! The store links the system `libsqlite3` through its C interface. Messages
+//! are kept in a `messages` table keyed by session and sequence number, and
+//! checkpoints in a `checkpoints` table keyed by session, so both can be
+//! queried with the `sqlite3` shell:
+//!
+//! ```sql
+//! SELECT seq_num, message FROM messages WHERE sender = 'BRKR' AND target = 'EXCH' AND seq_num >= 40;
+//! ```
+
+use std::ffi::{CStr, CString};
+use std::io;
+use std::os::raw::{c_char, c_int, c_void};
+use std::path::Path;
+use std::ptr;
+
+use super::session::SessionId;
+use super::store::{Checkpoint, MessageStore};
+
+#[allow(non_camel_case_types)]
+type sqlite3 = c_void;
+#[allow(non_camel_case_types)]
+type sqlite3_stmt = c_void;
+
+const SQLITE_OK: c_int = 0;
+const SQLITE_ROW: c_int = 100;
+const SQLITE_DONE: c_int = 101;
+const SQLITE_OPEN_READWRITE: c_int = 0x02;
+const SQLITE_OPEN_CREATE: c_int = 0x04;
+/// `SQLITE_TRANSIENT`, making SQLite copy bound values.
+const SQLITE_TRANSIENT: isize = -1;
+
+#[link(name = "sqlite3")]
+extern "C" {
+  fn sqlite3_open_v2(filename: *const c_char, db: *mut *mut sqlite3, flags: c_int, vfs: *const c_char) -> c_int;
+  fn sqlite3_close(db: *mut sqlite3) -> c_int;
+  fn sqlite3_errmsg(db: *mut sqlite3) -> *const c_char;
+  fn sqlite3_exec(db: *mut sqlite3, sql: *const c_char, callback: *const c_void, arg: *mut c_void,
+    errmsg: *mut *mut c_char) -> c_int;
+  fn sqlite3_prepare_v2(db: *mut sqlite3, sql: *const c_char, bytes: c_int, stmt: *mut *mut sqlite3_stmt,
+    tail: *mut *const c_char) -> c_int;
+  fn sqlite3_bind_text(stmt: *mut sqlite3_stmt, index: c_int, value: *const c_char, bytes: c_int,
+    destructor: isize) -> c_int;
+  fn sqlite3_bind_blob(stmt: *mut sqlite3_stmt, index: c_int, value: *const c_void, bytes: c_int,
+    destructor: isize) -> c_int;
+  fn sqlite3_bind_int64(stmt: *mut sqlite3_stmt, index: c_int, value: i64) -> c_int;
+  fn sqlite3_step(stmt: *mut sqlite3_stmt) -> c_int;
+  fn sqlite3_column_int64(stmt: *mut sqlite3_stmt, column: c_int) -> i64;
+  fn sqlite3_column_blob(stmt: *mut sqlite3_stmt, column: c_int) -> *const c_void;
+  fn sqlite3_column_bytes(stmt: *mut sqlite3_stmt, column: c_int) -> c_int;
+  fn sqlite3_finalize(stmt: *mut sqlite3_stmt) -> c_int;
+}
+
+const SCHEMA: &str = "\
+  CREATE TABLE IF NOT EXISTS messages (sender TEXT NOT NULL, target TEXT NOT NULL, seq_num INTEGER NOT NULL, \
+    message BLOB NOT NULL, PRIMARY KEY (sender, target, seq_num)) WITHOUT ROWID;
+  CREATE TABLE IF NOT EXISTS checkpoints (sender TEXT NOT NULL, target TEXT NOT NULL, state TEXT NOT NULL, \
+    PRIMARY KEY (sender, target)) WITHOUT ROWID;";
+
+/// Value bound to a statement parameter.
+enum Value<'a> {
+  Text(&'a str),
+  Blob(&'a [u8]),
+  Integer(u64),
+}
+
+/// A `MessageStore` in an SQLite database, created on first use.
+///
+/// # Examples
+///
+/// ```
+/// use fix_checksum::session::SessionId;
+/// use fix_checksum::sqlite::SqliteMessageStore;
+/// use fix_checksum::store::MessageStore;
+///
+/// let session = SessionId::new("BRKR", "EXCH");
+/// let mut store = SqliteMessageStore::open(":memory:").unwrap();
+/// store.store(&session, 1, b"8=FIX.4.2|9=5|35=0|10=161|").unwrap();
+/// assert_eq!(store.messages(&session, 1, 1).unwrap()[0].1, b"8=FIX.4.2|9=5|35=0|10=161|".to_vec());
+/// ```
+#[derive(Debug)]
+pub struct SqliteMessageStore {
+  db: *mut sqlite3,
+}
+
+impl SqliteMessageStore {
+  /// Opens the database at `path`, or an in-memory one for `:memory:`.
+  pub fn open<P: AsRef<Path>>(path: P) -> io::Result<SqliteMessageStore> {
+    let path = path.as_ref().to_str().and_then(|path| CString::new(path).ok())
+      .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "database path is not valid UTF-8"))?;
+    let mut db = ptr::null_mut();
+    let code = unsafe { sqlite3_open_v2(path.as_ptr(), &mut db, SQLITE_OPEN_READWRITE | SQLITE_OPEN_CREATE,
+      ptr::null()) };
+    let store = SqliteMessageStore { db };
+    if code != SQLITE_OK { return Err(store.error()); }
+
+    let schema = CString::new(SCHEMA).unwrap();
+    let code = unsafe { sqlite3_exec(db, schema.as_ptr(), ptr::null(), ptr::null_mut(), ptr::null_mut()) };
+    if code != SQLITE_OK { return Err(store.error()); }
+    Ok(store)
+  }
+
+  fn error(&self) -> io::Error {
+    if self.db.is_null() { return io::Error::other("out of memory"); }
+    let message = unsafe { CStr::from_ptr(sqlite3_errmsg(self.db)) };
+    io::Error::other(message.to_string_lossy().into_owned())
+  }
+
+  /// Runs `sql` with `values` bound, calling `row` for every result row.
+  fn query<F: FnMut(*mut sqlite3_stmt)>(&self, sql: &str, values: &[Value], mut row: F) -> io::Result<()> {
+    let mut stmt = ptr::null_mut();
+    let code = unsafe { sqlite3_prepare_v2(self.db, sql.as_ptr() as *const c_char, sql.len() as c_int, &mut stmt,
+      ptr::null_mut()) };
+    if code != SQLITE_OK { return Err(self.error()); }
+
+    let result = (|| {
+      for (index, value) in values.iter().enumerate() {
+        let index = index as c_int + 1;
+        let code = unsafe {
+          match *value {
+            Value::Text(text) => sqlite3_bind_text(stmt, index, text.as_ptr() as *const c_char, text.len() as c_int,
+              SQLITE_TRANSIENT),
+            Value::Blob(blob) => sqlite3_bind_blob(stmt, index, blob.as_ptr() as *const c_void, blob.len() as c_int,
+              SQLITE_TRANSIENT),
+            Value::Integer(integer) => sqlite3_bind_int64(stmt, index, integer.min(i64::MAX as u64) as i64),
+          }
+        };
+        if code != SQLITE_OK { return Err(self.error()); }
+      }
+      loop {
+        match unsafe { sqlite3_step(stmt) } {
+          SQLITE_ROW => row(stmt),
+          SQLITE_DONE => return Ok(()),
+          _ => return Err(self.error()),
+        }
+      }
+    })();
+    unsafe { sqlite3_finalize(stmt) };
+    result
+  }
+}
+
+impl Drop for SqliteMessageStore {
+  fn drop(&mut self) {
+    unsafe { sqlite3_close(self.db) };
+  }
+}
+
+fn blob(stmt: *mut sqlite3_stmt, column: c_int) -> Vec<u8> {
+  unsafe {
+    let data = sqlite3_column_blob(stmt, column) as *const u8;
+    let length = sqlite3_column_bytes(stmt, column) as usize;
+    if data.is_null() { Vec::new() } else { ::std::slice::from_raw_parts(data, length).to_vec() }
+  }
+}
+
+impl MessageStore for SqliteMessageStore {
+  fn store(&mut self, session: &SessionId, seq_num: u64, message: &[u8]) -> io::Result<()> {
+    self.query("INSERT OR REPLACE INTO messages (sender, target, seq_num, message) VALUES (?, ?, ?, ?)",
+      &[Value::Text(&session.sender_comp_id), Value::Text(&session.target_comp_id), Value::Integer(seq_num),
+        Value::Blob(message)], |_| {})
+  }
+
+  fn messages(&self, session: &SessionId, begin: u64, end: u64) -> io::Result<Vec<(u64, Vec<u8>)>> {
+    let mut messages = Vec::new();
+    self.query("SELECT seq_num, message FROM messages WHERE sender = ? AND target = ? AND seq_num BETWEEN ? AND ? \
+      ORDER BY seq_num", &[Value::Text(&session.sender_comp_id), Value::Text(&session.target_comp_id),
+        Value::Integer(begin), Value::Integer(end)],
+      |stmt| messages.push((unsafe { sqlite3_column_int64(stmt, 0) } as u64, blob(stmt, 1))))?;
+    Ok(messages)
+  }
+
+  fn checkpoint(&mut self, session: &SessionId, checkpoint: &Checkpoint) -> io::Result<()> {
+    self.query("INSERT OR REPLACE INTO checkpoints (sender, target, state) VALUES (?, ?, ?)",
+      &[Value::Text(&session.sender_comp_id), Value::Text(&session.target_comp_id),
+        Value::Text(&checkpoint.to_string())], |_| {})
+  }
+
+  fn restore(&self, session: &SessionId) -> io::Result<Checkpoint> {
+    let mut state = None;
+    self.query("SELECT state FROM checkpoints WHERE sender = ? AND target = ?",
+      &[Value::Text(&session.sender_comp_id), Value::Text(&session.target_comp_id)],
+      |stmt| state = Some(blob(stmt, 0)))?;
+    match state {
+      Some(state) => String::from_utf8_lossy(&state).parse()
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err)),
+      None => Ok(Checkpoint::new()),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::env;
+  use std::fs;
+  use super::SqliteMessageStore;
+  use super::super::session::SessionId;
+  use super::super::store::{Checkpoint, MessageStore};
+
+  #[test]
+  fn it_should_persist_messages_and_checkpoints() {
+    let path = env::temp_dir().join(format!("fix-checksum-store-{}.db", ::std::process::id()));
+    let session = SessionId::new("BRKR", "EXCH");
+    let checkpoint = Checkpoint { last_sent: 3, last_received: 9, resend_ranges: vec![(7, 8)] };
+    {
+      let mut store = SqliteMessageStore::open(&path).unwrap();
+      for seq_num in 1..4 { store.store(&session, seq_num, format!("message {}", seq_num).as_bytes()).unwrap(); }
+      store.store(&session, 2, b"resent\x01").unwrap();
+      store.store(&SessionId::new("EXCH", "BRKR"), 2, b"other").unwrap();
+      store.checkpoint(&session, &checkpoint).unwrap();
+    }
+
+    let store = SqliteMessageStore::open(&path).unwrap();
+    assert_eq!(store.messages(&session, 2, 10).unwrap(), vec![(2, b"resent\x01".to_vec()),
+      (3, b"message 3".to_vec())]);
+    assert_eq!(store.restore(&session).unwrap(), checkpoint);
+    assert_eq!(store.restore(&SessionId::new("EXCH", "BRKR")).unwrap(), Checkpoint::new());
+    drop(store);
+    fs::remove_file(&path).unwrap();
+  }
+}
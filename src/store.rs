@@ -0,0 +1,304 @@
+//! Persistent session state.
+//!
+//! A session resumes after a restart from its `Checkpoint`: the last
+//! sequence numbers sent and received, and the resend ranges still open.
+//! A `MessageStore` keeps the checkpoints of sessions together with their
+//! messages by sequence number, to be sent again on a ResendRequest (2).
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::str::FromStr;
+
+use super::session::SessionId;
+
+/// Storage of sent messages and checkpoints per session.
+pub trait MessageStore {
+  /// Stores `message` as the one sent with `seq_num` in `session`,
+  /// replacing a message stored before with the same number.
+  fn store(&mut self, session: &SessionId, seq_num: u64, message: &[u8]) -> io::Result<()>;
+
+  /// Returns the messages of `session` numbered `begin` to `end` inclusive,
+  /// sorted by sequence number.
+  fn messages(&self, session: &SessionId, begin: u64, end: u64) -> io::Result<Vec<(u64, Vec<u8>)>>;
+
+  /// Saves the checkpoint of `session`.
+  fn checkpoint(&mut self, session: &SessionId, checkpoint: &Checkpoint) -> io::Result<()>;
+
+  /// Returns the last checkpoint of `session`, or a new one if there is none.
+  fn restore(&self, session: &SessionId) -> io::Result<Checkpoint>;
+}
+
+/// Stores through a mutable reference, to keep the store of a connection
+/// once the connection is dropped.
+impl<M: MessageStore> MessageStore for &mut M {
+  fn store(&mut self, session: &SessionId, seq_num: u64, message: &[u8]) -> io::Result<()> {
+    (**self).store(session, seq_num, message)
+  }
+
+  fn messages(&self, session: &SessionId, begin: u64, end: u64) -> io::Result<Vec<(u64, Vec<u8>)>> {
+    (**self).messages(session, begin, end)
+  }
+
+  fn checkpoint(&mut self, session: &SessionId, checkpoint: &Checkpoint) -> io::Result<()> {
+    (**self).checkpoint(session, checkpoint)
+  }
+
+  fn restore(&self, session: &SessionId) -> io::Result<Checkpoint> {
+    (**self).restore(session)
+  }
+}
+
+/// A `MessageStore` in memory, lost with the process.
+///
+/// # Examples
+///
+/// ```
+/// use fix_checksum::session::SessionId;
+/// use fix_checksum::store::{MemoryStore, MessageStore};
+///
+/// let session = SessionId::new("BRKR", "EXCH");
+/// let mut store = MemoryStore::new();
+/// store.store(&session, 1, b"8=FIX.4.2|9=5|35=0|10=161|").unwrap();
+/// assert_eq!(store.messages(&session, 1, 10).unwrap().len(), 1);
+/// assert_eq!(store.restore(&session).unwrap().last_sent, 0);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct MemoryStore {
+  messages: BTreeMap<(SessionId, u64), Vec<u8>>,
+  checkpoints: BTreeMap<SessionId, Checkpoint>,
+}
+
+impl MemoryStore {
+  /// Creates an empty store.
+  pub fn new() -> MemoryStore {
+    MemoryStore::default()
+  }
+}
+
+impl MessageStore for MemoryStore {
+  fn store(&mut self, session: &SessionId, seq_num: u64, message: &[u8]) -> io::Result<()> {
+    self.messages.insert((session.clone(), seq_num), message.to_vec());
+    Ok(())
+  }
+
+  fn messages(&self, session: &SessionId, begin: u64, end: u64) -> io::Result<Vec<(u64, Vec<u8>)>> {
+    if begin > end { return Ok(Vec::new()); }
+    let range = (session.clone(), begin)..=(session.clone(), end);
+    Ok(self.messages.range(range).map(|(&(_, seq_num), message)| (seq_num, message.clone())).collect())
+  }
+
+  fn checkpoint(&mut self, session: &SessionId, checkpoint: &Checkpoint) -> io::Result<()> {
+    self.checkpoints.insert(session.clone(), checkpoint.clone());
+    Ok(())
+  }
+
+  fn restore(&self, session: &SessionId) -> io::Result<Checkpoint> {
+    Ok(self.checkpoints.get(session).cloned().unwrap_or_default())
+  }
+}
+
+/// Sequence number state of a session at one point in time.
+///
+/// # Examples
+///
+/// ```
+/// use fix_checksum::store::Checkpoint;
+///
+/// let mut checkpoint = Checkpoint::new();
+/// checkpoint.sent(1);
+/// checkpoint.received(5);
+/// checkpoint.open_resend(2, 4);
+/// let restored: Checkpoint = checkpoint.to_string().parse().unwrap();
+/// assert_eq!(restored, checkpoint);
+/// assert_eq!((restored.next_sender_seq_num(), restored.next_target_seq_num()), (2, 6));
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Checkpoint {
+  /// MsgSeqNum (34) of the last message sent, 0 before the first one.
+  pub last_sent: u64,
+  /// MsgSeqNum (34) of the last message received, 0 before the first one.
+  pub last_received: u64,
+  /// Inclusive ranges of received sequence numbers requested again with a
+  /// ResendRequest (2) and not filled yet, sorted and disjoint.
+  pub resend_ranges: Vec<(u64, u64)>,
+}
+
+impl Checkpoint {
+  /// Creates the checkpoint of a session which exchanged no messages.
+  pub fn new() -> Checkpoint {
+    Checkpoint::default()
+  }
+
+  /// Records a message sent with `seq_num`.
+  pub fn sent(&mut self, seq_num: u64) {
+    self.last_sent = self.last_sent.max(seq_num);
+  }
+
+  /// Records a message received with `seq_num`, closing it in the open
+  /// resend ranges.
+  pub fn received(&mut self, seq_num: u64) {
+    self.last_received = self.last_received.max(seq_num);
+    self.close_resend(seq_num, seq_num);
+  }
+
+  /// Records a resend of `begin` to `end` requested from the counterparty.
+  pub fn open_resend(&mut self, begin: u64, end: u64) {
+    if begin > end { return; }
+    self.resend_ranges.push((begin, end));
+    self.resend_ranges.sort();
+    let mut merged: Vec<(u64, u64)> = Vec::with_capacity(self.resend_ranges.len());
+    for &(begin, end) in &self.resend_ranges {
+      match merged.last_mut() {
+        Some(last) if begin <= last.1.saturating_add(1) => last.1 = last.1.max(end),
+        _ => merged.push((begin, end)),
+      }
+    }
+    self.resend_ranges = merged;
+  }
+
+  /// Removes `begin` to `end` from the open resend ranges, as after a
+  /// SequenceReset-GapFill (4) covering them.
+  pub fn close_resend(&mut self, begin: u64, end: u64) {
+    let mut ranges = Vec::with_capacity(self.resend_ranges.len() + 1);
+    for &(first, last) in &self.resend_ranges {
+      if last < begin || first > end {
+        ranges.push((first, last));
+        continue;
+      }
+      if first < begin { ranges.push((first, begin - 1)); }
+      if last > end { ranges.push((end + 1, last)); }
+    }
+    self.resend_ranges = ranges;
+  }
+
+  /// Returns the MsgSeqNum (34) of the next message to send.
+  pub fn next_sender_seq_num(&self) -> u64 {
+    self.last_sent + 1
+  }
+
+  /// Returns the MsgSeqNum (34) expected on the next message received.
+  pub fn next_target_seq_num(&self) -> u64 {
+    self.last_received + 1
+  }
+
+  /// Writes the checkpoint to `path`, through a temporary file renamed over
+  /// it so that a crash leaves either the old or the new checkpoint.
+  pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+    let path = path.as_ref();
+    let mut temporary = path.as_os_str().to_owned();
+    temporary.push(".tmp");
+    fs::write(&temporary, self.to_string())?;
+    fs::File::open(&temporary)?.sync_all()?;
+    fs::rename(&temporary, path)
+  }
+
+  /// Reads the checkpoint saved at `path`, or a new one if there is none.
+  pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Checkpoint> {
+    match fs::read_to_string(path) {
+      Ok(contents) => contents.parse().map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err)),
+      Err(ref err) if err.kind() == io::ErrorKind::NotFound => Ok(Checkpoint::new()),
+      Err(err) => Err(err),
+    }
+  }
+}
+
+impl fmt::Display for Checkpoint {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    writeln!(f, "last_sent={}", self.last_sent)?;
+    writeln!(f, "last_received={}", self.last_received)?;
+    for &(begin, end) in &self.resend_ranges { writeln!(f, "resend={}-{}", begin, end)?; }
+    Ok(())
+  }
+}
+
+/// Error of parsing a checkpoint.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseCheckpointError {
+  pub line: usize,
+}
+
+impl fmt::Display for ParseCheckpointError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "Invalid checkpoint at line {}.", self.line)
+  }
+}
+
+impl ::std::error::Error for ParseCheckpointError {}
+
+impl FromStr for Checkpoint {
+  type Err = ParseCheckpointError;
+
+  fn from_str(s: &str) -> Result<Checkpoint, ParseCheckpointError> {
+    let mut checkpoint = Checkpoint::new();
+    for (index, line) in s.lines().enumerate().filter(|&(_, line)| !line.trim().is_empty()) {
+      let error = ParseCheckpointError { line: index + 1 };
+      let (key, value) = line.trim().split_once('=').ok_or(error.clone())?;
+      match key {
+        "last_sent" => checkpoint.last_sent = value.parse().map_err(|_| error)?,
+        "last_received" => checkpoint.last_received = value.parse().map_err(|_| error)?,
+        "resend" => {
+          let range = value.split_once('-').and_then(|(begin, end)| Some((begin.parse().ok()?, end.parse().ok()?)));
+          match range {
+            Some((begin, end)) if begin <= end => checkpoint.open_resend(begin, end),
+            _ => return Err(error),
+          }
+        },
+        _ => return Err(error),
+      }
+    }
+    Ok(checkpoint)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::env;
+  use std::fs;
+  use super::{Checkpoint, MemoryStore, MessageStore, ParseCheckpointError};
+  use super::super::session::SessionId;
+
+  #[test]
+  fn it_should_track_resend_ranges() {
+    let mut checkpoint = Checkpoint::new();
+    checkpoint.open_resend(5, 9);
+    checkpoint.open_resend(10, 12);
+    checkpoint.open_resend(20, 21);
+    assert_eq!(checkpoint.resend_ranges, vec![(5, 12), (20, 21)]);
+    checkpoint.received(7);
+    checkpoint.close_resend(20, 30);
+    assert_eq!(checkpoint.resend_ranges, vec![(5, 6), (8, 12)]);
+    assert_eq!(checkpoint.last_received, 7);
+  }
+
+  #[test]
+  fn it_should_save_and_restore_checkpoints() {
+    let path = env::temp_dir().join(format!("fix-checksum-checkpoint-{}", ::std::process::id()));
+    assert_eq!(Checkpoint::load(&path).unwrap(), Checkpoint::new());
+    let checkpoint = Checkpoint { last_sent: 42, last_received: 17, resend_ranges: vec![(3, 4)] };
+    checkpoint.save(&path).unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), "last_sent=42\nlast_received=17\nresend=3-4\n");
+    assert_eq!(Checkpoint::load(&path).unwrap(), checkpoint);
+    fs::remove_file(&path).unwrap();
+
+    assert_eq!("last_sent=1\nresend=4-2\n".parse::<Checkpoint>(), Err(ParseCheckpointError { line: 2 }));
+  }
+
+  #[test]
+  fn it_should_keep_sessions_apart_in_memory() {
+    let (brkr, exch) = (SessionId::new("BRKR", "EXCH"), SessionId::new("EXCH", "BRKR"));
+    let mut store = MemoryStore::new();
+    for seq_num in 1..6 { store.store(&brkr, seq_num, format!("{}", seq_num).as_bytes()).unwrap(); }
+    store.store(&exch, 3, b"other").unwrap();
+    let messages = store.messages(&brkr, 2, 3).unwrap();
+    assert_eq!(messages, vec![(2, b"2".to_vec()), (3, b"3".to_vec())]);
+    assert!(store.messages(&brkr, 4, 2).unwrap().is_empty());
+
+    let checkpoint = Checkpoint { last_sent: 5, last_received: 0, resend_ranges: vec![] };
+    store.checkpoint(&brkr, &checkpoint).unwrap();
+    assert_eq!(store.restore(&brkr).unwrap(), checkpoint);
+    assert_eq!(store.restore(&exch).unwrap(), Checkpoint::new());
+  }
+}
@@ -0,0 +1,589 @@
+//! Validation of streams of concatenated FIX messages, such as log files or
+//! captured network traffic.
+//!
+//! # Examples
+//!
+//! ```
+//! use fix_checksum::{ValidationOptions, ErrorPolicy};
+//! use fix_checksum::stream::{StreamValidator, Outcome};
+//!
+//! let log = "8=FIX.4.2|9=5|35=0|10=161|\n8=FIX.4.2|9=5|35=0|10=999|\n8=FIX.4.2|9=5|35=0|10=161|\n";
+//! let options = ValidationOptions::new().delimiter('|').error_policy(ErrorPolicy::Skip);
+//! let outcomes: Vec<Outcome> = StreamValidator::new(log.as_bytes(), options)
+//!   .map(|result| result.unwrap().outcome)
+//!   .collect();
+//! assert_eq!(outcomes, vec![Outcome::Valid, Outcome::ChecksumMismatch, Outcome::Valid]);
+//! ```
+
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::io::BufRead;
+use std::ops::Range;
+
+use super::cache::ValidationCache;
+use super::diagnose::{diagnose, Hypothesis};
+use super::message::{data_length, field_tag};
+use super::{FIXChecksumValidatorError, ValidationOptions, ErrorPolicy, LinePrefix, validate_bytes_with,
+  checksum_bytes, checksum_digits, checksum_field_start, find};
+
+const FIX_BEGIN_STRING_FIELD: &[u8] = b"8=";
+const FIX_CHECKSUM_FIELD: &[u8] = b"10=";
+
+/// A single message cut from a stream.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Frame {
+  /// Byte offset of the first byte of the message in the stream.
+  pub offset: usize,
+  /// Raw message bytes, from BeginString up to and including the delimiter
+  /// closing the checksum field.
+  pub bytes: Vec<u8>,
+}
+
+/// Splits a byte stream into FIX messages.
+///
+/// Bytes between messages which can not start a message (line breaks,
+/// padding, garbage) are skipped. A message is closed by its checksum field;
+/// a message cut short by the end of the stream or by the BeginString of the
+/// next message is still returned so it can be reported as invalid.
+pub struct MessageReader<R> {
+  inner: R,
+  delimiter: u8,
+  offset: usize,
+  pending: Option<Frame>,
+  line_prefix: Option<LinePrefix>,
+}
+
+impl<R: BufRead> MessageReader<R> {
+  /// Creates a reader splitting messages on `delimiter`.
+  pub fn new(inner: R, delimiter: char) -> MessageReader<R> {
+    assert!(delimiter.is_ascii(), "FIX delimiter must be an ASCII character");
+    MessageReader { inner, delimiter: delimiter as u8, offset: 0, pending: None, line_prefix: None }
+  }
+
+  /// Skips `prefix` before messages starting a line, see
+  /// `ValidationOptions::line_prefix`.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use fix_checksum::LinePrefix;
+  /// use fix_checksum::stream::MessageReader;
+  ///
+  /// let log = "20240604-07:58:28.123 : 8=FIX.4.2|9=5|35=0|10=161|\n\
+  ///   20240604-07:58:29.001 : 8=FIX.4.2|9=5|35=0|10=161|\n";
+  /// let frames: Vec<_> = MessageReader::new(log.as_bytes(), '|')
+  ///   .line_prefix(LinePrefix::Separator(" : ".to_string()))
+  ///   .map(|frame| frame.unwrap())
+  ///   .collect();
+  /// assert_eq!(frames.len(), 2);
+  /// assert_eq!((frames[1].offset, &frames[1].bytes[..]), (75, &b"8=FIX.4.2|9=5|35=0|10=161|"[..]));
+  /// ```
+  pub fn line_prefix(mut self, prefix: LinePrefix) -> MessageReader<R> {
+    self.line_prefix = Some(prefix);
+    self
+  }
+
+  /// Returns the number of bytes consumed from the underlying reader.
+  pub fn offset(&self) -> usize {
+    self.offset
+  }
+
+  fn read_frame(&mut self) -> io::Result<Option<Frame>> {
+    let mut frame = self.pending.take().unwrap_or(Frame { offset: self.offset, bytes: Vec::new() });
+    let mut field = Vec::new();
+    let mut data = None;
+
+    loop {
+      field.clear();
+      let read = self.inner.read_until(self.delimiter, &mut field)?;
+      if read == 0 {
+        return Ok(if frame.bytes.is_empty() { None } else { Some(truncated(frame)) });
+      }
+      self.offset += read;
+
+      // The value of a data field may contain the delimiter, a line break or
+      // a BeginString, read it whole by its declared length
+      if let Some((data_tag, length)) = data.take() {
+        if let Some((_, value_offset)) = field_tag(&field, self.delimiter).filter(|field| field.0 == data_tag) {
+          while field.len() <= value_offset.saturating_add(length) {
+            let read = self.inner.read_until(self.delimiter, &mut field)?;
+            if read == 0 { break; }
+            self.offset += read;
+          }
+          frame.bytes.extend_from_slice(&field);
+          continue;
+        }
+      }
+
+      let line_start = self.offset == read;
+      match find_begin_string(&field, self.line_prefix.as_ref(), line_start) {
+        Some(start) => {
+          let begin = Frame { offset: self.offset - read + start, bytes: field[start..].to_vec() };
+          if !frame.bytes.is_empty() {
+            // A new message starts before the current one got its checksum field
+            frame.bytes.extend_from_slice(&field[..start]);
+            self.pending = Some(begin);
+            return Ok(Some(truncated(frame)));
+          }
+          frame = begin;
+        },
+        None if frame.bytes.is_empty() => continue,
+        None => frame.bytes.extend_from_slice(&field),
+      }
+
+      data = field_tag(&field, self.delimiter).and_then(|(tag, value_offset)| {
+        data_length(tag, field[value_offset..].strip_suffix(&[self.delimiter]).unwrap_or(&field[value_offset..]))
+      });
+
+      if field.starts_with(FIX_CHECKSUM_FIELD) { return Ok(Some(frame)); }
+    }
+  }
+}
+
+/// Creates a reader framing messages as configured by `options`.
+fn reader<R: BufRead>(inner: R, options: &ValidationOptions) -> MessageReader<R> {
+  let reader = MessageReader::new(inner, options.delimiter as char);
+  match options.line_prefix {
+    Some(ref prefix) => reader.line_prefix(prefix.clone()),
+    None => reader,
+  }
+}
+
+/// Drops the line break or padding following a message cut short.
+fn truncated(mut frame: Frame) -> Frame {
+  while frame.bytes.last().is_some_and(|b| b.is_ascii_whitespace()) {
+    frame.bytes.pop();
+  }
+  frame
+}
+
+/// Finds a BeginString field at the start of `field` or at the start of a
+/// line within it, after the prefix of the line if there is one. The start
+/// of `field` starts a line if `line_start` is set.
+fn find_begin_string(field: &[u8], prefix: Option<&LinePrefix>, line_start: bool) -> Option<usize> {
+  let start = field.iter().position(|b| !b.is_ascii_whitespace())?;
+  if field[start..].starts_with(FIX_BEGIN_STRING_FIELD) { return Some(start); }
+  if let Some(line_break) = find(field, b"\n8=") { return Some(line_break + 1); }
+
+  let prefix = prefix?;
+  let line_breaks = field.iter().enumerate().filter(|&(_, &b)| b == b'\n').map(|(index, _)| index + 1);
+  (if line_start { Some(0) } else { None }).into_iter().chain(line_breaks).find_map(|line| {
+    let rest = &field[line..];
+    let end = rest.iter().position(|&b| b == b'\n').unwrap_or(rest.len());
+    let begin = line + prefix.length(&rest[..end])?;
+    if field[begin..].starts_with(FIX_BEGIN_STRING_FIELD) { Some(begin) } else { None }
+  })
+}
+
+impl<R: BufRead> Iterator for MessageReader<R> {
+  type Item = io::Result<Frame>;
+
+  fn next(&mut self) -> Option<io::Result<Frame>> {
+    match self.read_frame() {
+      Ok(Some(frame)) => Some(Ok(frame)),
+      Ok(None) => None,
+      Err(err) => Some(Err(err)),
+    }
+  }
+}
+
+/// Result of validating one message of a stream.
+#[derive(Debug, PartialEq)]
+pub enum Outcome {
+  /// The checksum matches.
+  Valid,
+  /// The checksum is well formed but does not match the message.
+  ChecksumMismatch,
+  /// The message could not be validated.
+  Error(FIXChecksumValidatorError),
+  /// The checksum was wrong and has been rewritten, see `ErrorPolicy::Repair`.
+  Repaired,
+}
+
+impl Outcome {
+  /// Returns `true` unless the message was valid as read.
+  pub fn is_failure(&self) -> bool {
+    *self != Outcome::Valid
+  }
+
+  /// Returns a short name of the outcome, suitable for grouping results.
+  pub fn kind(&self) -> &'static str {
+    match *self {
+      Outcome::Valid => "Valid",
+      Outcome::ChecksumMismatch => "ChecksumMismatch",
+      Outcome::Error(ref err) => err.kind(),
+      Outcome::Repaired => "Repaired",
+    }
+  }
+}
+
+/// A validated message of a stream.
+#[derive(Debug, PartialEq)]
+pub struct StreamResult {
+  /// Byte offset of the message in the stream.
+  pub offset: usize,
+  /// Message bytes. For repaired messages these are the corrected bytes.
+  pub message: Vec<u8>,
+  pub outcome: Outcome,
+  /// Likely causes of the failure, filled when
+  /// `ValidationOptions::diagnose_corruption` is enabled.
+  pub hypotheses: Vec<Hypothesis>,
+}
+
+/// Validates every message of a stream, applying the configured
+/// `ErrorPolicy` to failures.
+pub struct StreamValidator<R> {
+  reader: MessageReader<R>,
+  options: ValidationOptions,
+  cache: Option<ValidationCache>,
+  stopped: bool,
+}
+
+impl<R: BufRead> StreamValidator<R> {
+  pub fn new(inner: R, options: ValidationOptions) -> StreamValidator<R> {
+    let cache = if options.cache_size == 0 { None } else {
+      Some(ValidationCache::new(options.cache_size, options.clone()))
+    };
+    StreamValidator {
+      reader: reader(inner, &options),
+      cache,
+      options,
+      stopped: false,
+    }
+  }
+
+  /// Returns the cache of validation results, if
+  /// `ValidationOptions::cache_size` is set.
+  pub fn cache(&self) -> Option<&ValidationCache> {
+    self.cache.as_ref()
+  }
+}
+
+/// Validates a frame, applying the diagnosis and repair of `options`.
+pub(crate) fn check(frame: Frame, options: &ValidationOptions) -> StreamResult {
+  let validated = validate_bytes_with(&frame.bytes, options);
+  resolve(frame, options, validated)
+}
+
+/// Applies the diagnosis and repair of `options` to a frame validated as
+/// `validated`.
+fn resolve(frame: Frame, options: &ValidationOptions, validated: Result<bool, FIXChecksumValidatorError>)
+  -> StreamResult {
+  let outcome = match validated {
+    Ok(true) => Outcome::Valid,
+    Ok(false) => Outcome::ChecksumMismatch,
+    Err(err) => Outcome::Error(err),
+  };
+
+  let hypotheses = if outcome.is_failure() && options.diagnose_corruption {
+    diagnose(&frame.bytes, options)
+  } else {
+    Vec::new()
+  };
+
+  if outcome.is_failure() && options.error_policy == ErrorPolicy::Repair {
+    if let Some(repaired) = repair(&frame.bytes, options) {
+      return StreamResult { offset: frame.offset, message: repaired, outcome: Outcome::Repaired, hypotheses };
+    }
+  }
+  StreamResult { offset: frame.offset, message: frame.bytes, outcome, hypotheses }
+}
+
+impl<R: BufRead> Iterator for StreamValidator<R> {
+  type Item = io::Result<StreamResult>;
+
+  fn next(&mut self) -> Option<io::Result<StreamResult>> {
+    if self.stopped { return None; }
+
+    let result = match self.reader.next()? {
+      Ok(frame) => match self.cache {
+        Some(ref mut cache) => {
+          let validated = cache.validate(&frame.bytes);
+          resolve(frame, &self.options, validated)
+        },
+        None => check(frame, &self.options),
+      },
+      Err(err) => {
+        self.stopped = true;
+        return Some(Err(err));
+      },
+    };
+
+    if result.outcome.is_failure() && self.options.error_policy == ErrorPolicy::Stop {
+      self.stopped = true;
+    }
+    Some(Ok(result))
+  }
+}
+
+/// Rewrites the checksum field of `message`. Returns `None` when the message
+/// has no checksum field or fails a check unrelated to the checksum.
+pub(crate) fn repair(message: &[u8], options: &ValidationOptions) -> Option<Vec<u8>> {
+  let split_index = checksum_field_start(message, options.delimiter)?;
+  let checksum = checksum_bytes(&message[..split_index], options.delimiter);
+
+  let mut repaired = message[..split_index].to_vec();
+  repaired.extend_from_slice(b"10=");
+  repaired.extend_from_slice(checksum_digits(checksum));
+  repaired.push(options.delimiter);
+
+  match validate_bytes_with(&repaired, options) {
+    Ok(true) => Some(repaired),
+    _ => None,
+  }
+}
+
+/// The first failing message of a buffer.
+#[derive(Debug, PartialEq)]
+pub struct BufferError {
+  /// Position of the message in the buffer, starting at 0.
+  pub index: usize,
+  /// Byte offset of the message in the buffer.
+  pub offset: usize,
+  /// Why the message failed, never `Outcome::Valid`.
+  pub outcome: Outcome,
+}
+
+impl fmt::Display for BufferError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "Message {} at byte {} failed: ", self.index, self.offset)?;
+    match self.outcome {
+      Outcome::Error(ref err) => write!(f, "{}", err),
+      _ => write!(f, "Checksum mismatch."),
+    }
+  }
+}
+
+impl Error for BufferError {
+  fn source(&self) -> Option<&(dyn Error + 'static)> {
+    match self.outcome {
+      Outcome::Error(ref err) => Some(err),
+      _ => None,
+    }
+  }
+}
+
+/// This function validates every message of a buffer of concatenated SOH
+/// delimited messages and returns how many there are, or where the first
+/// failing one is.
+///
+/// # Examples
+///
+/// ```
+/// use fix_checksum::stream::validate_buffer;
+///
+/// let archive = b"8=FIX.4.2\x019=5\x0135=0\x0110=161\x01\n8=FIX.4.2\x019=5\x0135=0\x0110=161\x01\n";
+/// assert_eq!(validate_buffer(archive), Ok(2));
+///
+/// let err = validate_buffer(b"8=FIX.4.2\x019=5\x0135=0\x0110=161\x018=FIX.4.2\x019=5\x0135=0\x0110=162\x01")
+///   .unwrap_err();
+/// assert_eq!((err.index, err.offset), (1, 26));
+/// ```
+pub fn validate_buffer(buffer: &[u8]) -> Result<usize, BufferError> {
+  validate_buffer_with(buffer, &ValidationOptions::new())
+}
+
+/// This function validates every message of a buffer using the given
+/// options. The error policy of the options is ignored.
+pub fn validate_buffer_with(buffer: &[u8], options: &ValidationOptions) -> Result<usize, BufferError> {
+  let mut count = 0;
+  for frame in reader(buffer, options) {
+    let frame = frame.expect("reading from a byte slice can not fail");
+    let outcome = match validate_bytes_with(&frame.bytes, options) {
+      Ok(true) => { count += 1; continue; },
+      Ok(false) => Outcome::ChecksumMismatch,
+      Err(err) => Outcome::Error(err),
+    };
+    return Err(BufferError { index: count, offset: frame.offset, outcome });
+  }
+  Ok(count)
+}
+
+/// Iterator over the validation results of the messages of a string, see
+/// `validate_stream`.
+pub struct StreamValidations<'a> {
+  reader: MessageReader<&'a [u8]>,
+  options: ValidationOptions,
+}
+
+impl<'a> Iterator for StreamValidations<'a> {
+  type Item = (Range<usize>, Result<bool, FIXChecksumValidatorError>);
+
+  fn next(&mut self) -> Option<(Range<usize>, Result<bool, FIXChecksumValidatorError>)> {
+    let frame = self.reader.next()?.expect("reading from a byte slice can not fail");
+    Some((frame.offset..frame.offset + frame.bytes.len(), validate_bytes_with(&frame.bytes, &self.options)))
+  }
+}
+
+/// This function validates every message of a string of concatenated SOH
+/// delimited messages, pairing each result with the byte range of the
+/// message in the string.
+///
+/// # Examples
+///
+/// ```
+/// use fix_checksum::stream::validate_stream;
+///
+/// let input = "8=FIX.4.2\x019=5\x0135=0\x0110=161\x01\n8=FIX.4.2\x019=5\x0135=0\x0110=162\x01";
+/// let results: Vec<_> = validate_stream(input).collect();
+/// assert_eq!(results, vec![(0..26, Ok(true)), (27..53, Ok(false))]);
+/// assert_eq!(&input[results[1].0.clone()], "8=FIX.4.2\x019=5\x0135=0\x0110=162\x01");
+/// ```
+pub fn validate_stream(input: &str) -> StreamValidations<'_> {
+  validate_stream_with(input, &ValidationOptions::new())
+}
+
+/// This function validates every message of a string using the given
+/// options. The error policy of the options is ignored.
+pub fn validate_stream_with<'a>(input: &'a str, options: &ValidationOptions) -> StreamValidations<'a> {
+  StreamValidations { reader: reader(input.as_bytes(), options), options: options.clone() }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{MessageReader, StreamValidator, Outcome, Frame, BufferError, validate_buffer_with, validate_stream_with};
+  use super::super::diagnose::Hypothesis;
+  use super::super::{ValidationOptions, ErrorPolicy, LinePrefix};
+  use super::super::FIXChecksumValidatorError::{ChecksumFieldNotFound, BodyLengthMismatch, ChecksumFieldInvalidFormat};
+
+  const LOG: &str = "8=FIX.4.2|9=5|35=0|10=161|\r\n8=FIX.4.2|9=5|35=0|10=16X|\n\
+    8=FIX.4.2|9=4|35=0|10=161|\n8=FIX.4.2|9=5|35=0|";
+
+  fn outcomes(policy: ErrorPolicy) -> Vec<(usize, Outcome)> {
+    let options = ValidationOptions::new().delimiter('|').check_body_length(true).error_policy(policy);
+    StreamValidator::new(LOG.as_bytes(), options)
+      .map(|result| result.unwrap())
+      .map(|result| (result.offset, result.outcome))
+      .collect()
+  }
+
+  #[test]
+  fn it_should_split_stream_into_messages() {
+    let frames: Vec<Frame> = MessageReader::new(LOG.as_bytes(), '|').map(|frame| frame.unwrap()).collect();
+    assert_eq!(frames.len(), 4);
+    assert_eq!(frames[0], Frame { offset: 0, bytes: b"8=FIX.4.2|9=5|35=0|10=161|".to_vec() });
+    assert_eq!(frames[1].offset, 28);
+    assert_eq!(frames[3].bytes, b"8=FIX.4.2|9=5|35=0|".to_vec());
+  }
+
+  #[test]
+  fn it_should_split_truncated_messages() {
+    let log = "8=FIX.4.2|9=5|35=
+8=FIX.4.2|9=5|35=0|10=161|
+8=FIX.4.2|9=5|35=0|
+";
+    let frames: Vec<Frame> = MessageReader::new(log.as_bytes(), '|').map(|frame| frame.unwrap()).collect();
+    assert_eq!(frames, vec![
+      Frame { offset: 0, bytes: b"8=FIX.4.2|9=5|35=".to_vec() },
+      Frame { offset: 18, bytes: b"8=FIX.4.2|9=5|35=0|10=161|".to_vec() },
+      Frame { offset: 45, bytes: b"8=FIX.4.2|9=5|35=0|".to_vec() },
+    ]);
+  }
+
+  #[test]
+  fn it_should_skip_line_prefixes() {
+    let log = "20240604-07:58:28.123 8=FIX.4.2|9=5|35=0|10=161|\n\
+      20240604-07:58:28.124 8=FIX.4.2|9=5|35=0|10=162|\n\
+      8=FIX.4.2|9=5|35=0|10=161|\n\
+      garbage\n\
+      20240604-07:58:28.125 8=FIX.4.2|9=5|35=0|10=161|";
+    let options = ValidationOptions::new().delimiter('|').error_policy(ErrorPolicy::Skip)
+      .line_prefix(LinePrefix::FixedWidth(22));
+    let outcomes: Vec<(usize, Outcome)> = StreamValidator::new(log.as_bytes(), options)
+      .map(|result| result.unwrap())
+      .map(|result| (result.offset, result.outcome))
+      .collect();
+    assert_eq!(outcomes, vec![(22, Outcome::Valid), (71, Outcome::ChecksumMismatch), (98, Outcome::Valid),
+      (155, Outcome::Valid)]);
+    assert_eq!(MessageReader::new(log.as_bytes(), '|').count(), 1);
+  }
+
+  #[test]
+  fn it_should_stop_at_first_error() {
+    let outcomes = outcomes(ErrorPolicy::Stop);
+    assert_eq!(outcomes.len(), 2);
+    assert_eq!(outcomes[0], (0, Outcome::Valid));
+    assert_eq!(outcomes[1].0, 28);
+  }
+
+  #[test]
+  fn it_should_skip_and_report_errors() {
+    let outcomes = outcomes(ErrorPolicy::Skip);
+    assert_eq!(outcomes.len(), 4);
+    assert_eq!(outcomes[2], (55, Outcome::Error(BodyLengthMismatch)));
+    assert_eq!(outcomes[3], (82, Outcome::Error(ChecksumFieldNotFound)));
+  }
+
+  #[test]
+  fn it_should_repair_checksums() {
+    let options = ValidationOptions::new().delimiter('|').error_policy(ErrorPolicy::Repair);
+    let results: Vec<_> = StreamValidator::new(LOG.as_bytes(), options).map(|result| result.unwrap()).collect();
+    assert_eq!(results[1].outcome, Outcome::Repaired);
+    assert_eq!(results[1].message, b"8=FIX.4.2|9=5|35=0|10=161|".to_vec());
+    assert_eq!(results[2].outcome, Outcome::Repaired);
+    assert_eq!(results[3].outcome, Outcome::Error(ChecksumFieldNotFound));
+    assert!(results.iter().all(|result| result.hypotheses.is_empty()));
+  }
+
+  #[test]
+  fn it_should_cache_repeated_messages() {
+    let log = "8=FIX.4.2|9=5|35=0|10=161|\n8=FIX.4.2|9=5|35=0|10=161|\n8=FIX.4.2|9=5|35=0|10=16X|\n\
+      8=FIX.4.2|9=5|35=0|10=16X|\n8=FIX.4.2|9=5|35=0|10=161|\n";
+    let options = ValidationOptions::new().delimiter('|').error_policy(ErrorPolicy::Repair).cache_size(4);
+    let mut validator = StreamValidator::new(log.as_bytes(), options);
+    let outcomes: Vec<_> = validator.by_ref().map(|result| result.unwrap().outcome).collect();
+    assert_eq!(outcomes, vec![Outcome::Valid, Outcome::Valid, Outcome::Repaired, Outcome::Repaired, Outcome::Valid]);
+    let cache = validator.cache().unwrap();
+    assert_eq!((cache.hits(), cache.misses()), (3, 2));
+    assert!(StreamValidator::new(log.as_bytes(), ValidationOptions::new()).cache().is_none());
+  }
+
+  #[test]
+  fn it_should_attach_corruption_hypotheses() {
+    let options = ValidationOptions::new().delimiter('|').diagnose_corruption(true).error_policy(ErrorPolicy::Skip);
+    let results: Vec<_> = StreamValidator::new(LOG.as_bytes(), options).map(|result| result.unwrap()).collect();
+    assert!(results[0].hypotheses.is_empty());
+    assert_eq!(results[1].hypotheses, vec![Hypothesis::ChecksumValueChanged { expected: "161".to_string() }]);
+  }
+
+  #[test]
+  fn it_should_locate_first_failure_in_buffer() {
+    let options = ValidationOptions::new().delimiter('|').check_body_length(true);
+    assert_eq!(validate_buffer_with(b"", &options), Ok(0));
+    assert_eq!(validate_buffer_with(&LOG.as_bytes()[..27], &options), Ok(1));
+
+    let err = validate_buffer_with(LOG.as_bytes(), &options).unwrap_err();
+    assert_eq!((err.index, err.offset), (1, 28));
+    assert_eq!(err.to_string(),
+      "Message 1 at byte 28 failed: Checksum value invalid format: invalid digit found in string");
+
+    let log = LOG.replace("10=16X", "10=162");
+    let err = validate_buffer_with(log.as_bytes(), &options).unwrap_err();
+    assert_eq!(err, BufferError { index: 1, offset: 28, outcome: Outcome::ChecksumMismatch });
+  }
+
+  #[test]
+  fn it_should_frame_secure_data_by_its_length() {
+    let message = "8=FIX.4.2|9=30|35=0|90=10|91=a|10=1\n8=Z|49=A|10=240|";
+    let log = format!("{}\n{}\n", message, message);
+    let options = ValidationOptions::new().delimiter('|').check_body_length(true).strict(true);
+    let results: Vec<_> = StreamValidator::new(log.as_bytes(), options).map(|result| result.unwrap()).collect();
+    assert_eq!(results.len(), 2);
+    assert_eq!((results[1].offset, &results[1].message[..]), (53, message.as_bytes()));
+    assert!(results.iter().all(|result| result.outcome == Outcome::Valid));
+  }
+
+  #[test]
+  fn it_should_pair_results_with_ranges() {
+    let options = ValidationOptions::new().delimiter('|');
+    let results: Vec<_> = validate_stream_with(LOG, &options).collect();
+    assert_eq!(results.len(), 4);
+    assert_eq!(results[0], (0..26, Ok(true)));
+    assert_eq!(results[3], (82..101, Err(ChecksumFieldNotFound)));
+    assert_eq!(results[1].0, 28..54);
+    assert!(matches!(results[1].1, Err(ChecksumFieldInvalidFormat(_))));
+    assert!(results.iter().all(|(range, _)| LOG[range.clone()].starts_with("8=")));
+    assert_eq!(validate_stream_with("", &options).count(), 0);
+  }
+}
@@ -0,0 +1,230 @@
+//! Aggregated results of a stream validation run.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use super::message::MessageView;
+use super::session::{DuplicateDetector, DuplicateKind, SessionId};
+use super::stream::StreamResult;
+use super::ValidationOptions;
+
+/// Counters of one session of a `ValidationSummary`, with the meaning of
+/// the counters of the same name of the summary.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SessionSummary {
+  pub total: usize,
+  pub valid: usize,
+  /// Number of failed messages.
+  pub failures: usize,
+  /// Number of failed messages by kind of failure.
+  pub failure_kinds: BTreeMap<&'static str, usize>,
+  pub first_failure_offset: Option<usize>,
+  pub last_failure_offset: Option<usize>,
+  pub duplicates: BTreeMap<DuplicateKind, usize>,
+}
+
+impl SessionSummary {
+  fn record(&mut self, result: &StreamResult, duplicate: Option<DuplicateKind>) {
+    self.total += 1;
+    if result.outcome.is_failure() {
+      self.failures += 1;
+      *self.failure_kinds.entry(result.outcome.kind()).or_insert(0) += 1;
+      if self.first_failure_offset.is_none() { self.first_failure_offset = Some(result.offset); }
+      self.last_failure_offset = Some(result.offset);
+    } else {
+      self.valid += 1;
+    }
+    if let Some(kind) = duplicate { *self.duplicates.entry(kind).or_insert(0) += 1; }
+  }
+}
+
+/// Accumulates the results of a stream validation.
+///
+/// # Examples
+///
+/// ```
+/// use fix_checksum::{ValidationOptions, ErrorPolicy};
+/// use fix_checksum::stream::StreamValidator;
+/// use fix_checksum::summary::ValidationSummary;
+///
+/// let log = "8=FIX.4.2|9=5|35=0|10=161|\n8=FIX.4.2|9=5|35=0|10=999|\n";
+/// let options = ValidationOptions::new().delimiter('|').error_policy(ErrorPolicy::Skip);
+/// let mut summary = ValidationSummary::new(&options);
+/// for result in StreamValidator::new(log.as_bytes(), options) {
+///   summary.record(&result.unwrap());
+/// }
+/// assert_eq!(summary.total, 2);
+/// assert_eq!(summary.failures.get("ChecksumMismatch"), Some(&1));
+/// assert_eq!(summary.first_failure_offset, Some(27));
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct ValidationSummary {
+  /// Number of messages recorded.
+  pub total: usize,
+  /// Number of messages valid as read.
+  pub valid: usize,
+  /// Number of failed messages by kind of failure.
+  pub failures: BTreeMap<&'static str, usize>,
+  /// Counters by session, for messages carrying SenderCompID and TargetCompID.
+  pub sessions: BTreeMap<SessionId, SessionSummary>,
+  /// Stream offset of the first failed message.
+  pub first_failure_offset: Option<usize>,
+  /// Stream offset of the last failed message.
+  pub last_failure_offset: Option<usize>,
+  /// Number of valid messages repeating a MsgSeqNum (34) of their session
+  /// or flagged PossDupFlag (43), by layer.
+  pub duplicates: BTreeMap<DuplicateKind, usize>,
+  detector: DuplicateDetector,
+  delimiter: char,
+}
+
+impl ValidationSummary {
+  /// Creates an empty summary for messages validated with `options`.
+  pub fn new(options: &ValidationOptions) -> ValidationSummary {
+    ValidationSummary {
+      total: 0,
+      valid: 0,
+      failures: BTreeMap::new(),
+      sessions: BTreeMap::new(),
+      first_failure_offset: None,
+      last_failure_offset: None,
+      duplicates: BTreeMap::new(),
+      detector: DuplicateDetector::new(),
+      delimiter: options.delimiter as char,
+    }
+  }
+
+  /// Adds the result of one message.
+  pub fn record(&mut self, result: &StreamResult) {
+    let failed = result.outcome.is_failure();
+    self.total += 1;
+
+    if failed {
+      *self.failures.entry(result.outcome.kind()).or_insert(0) += 1;
+      if self.first_failure_offset.is_none() { self.first_failure_offset = Some(result.offset); }
+      self.last_failure_offset = Some(result.offset);
+    } else {
+      self.valid += 1;
+    }
+
+    let message = match MessageView::parse(&result.message, self.delimiter) {
+      Ok(message) => message,
+      Err(_) => return,
+    };
+    let duplicate = if failed { None } else { self.detector.check(&message).map(|duplicate| duplicate.kind) };
+    if let Some(kind) = duplicate { *self.duplicates.entry(kind).or_insert(0) += 1; }
+    if let Some(session) = SessionId::from_message(&message) {
+      self.sessions.entry(session).or_default().record(result, duplicate);
+    }
+  }
+
+  /// Returns the number of failed messages.
+  pub fn failed(&self) -> usize {
+    self.total - self.valid
+  }
+
+  /// Returns the field delimiter of the messages recorded.
+  pub(crate) fn delimiter(&self) -> char {
+    self.delimiter
+  }
+}
+
+impl fmt::Display for ValidationSummary {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    writeln!(f, "messages: {}, valid: {}, failed: {}", self.total, self.valid, self.failed())?;
+    for (kind, count) in &self.failures {
+      writeln!(f, "  {}: {}", kind, count)?;
+    }
+    if let (Some(first), Some(last)) = (self.first_failure_offset, self.last_failure_offset) {
+      writeln!(f, "first failure at offset {}, last failure at offset {}", first, last)?;
+    }
+    write_duplicates(f, "", &self.duplicates)?;
+    for (session, counters) in &self.sessions {
+      writeln!(f, "session {}: messages: {}, failed: {}", session, counters.total, counters.failures)?;
+      for (kind, count) in &counters.failure_kinds {
+        writeln!(f, "    {}: {}", kind, count)?;
+      }
+      write_duplicates(f, "    ", &counters.duplicates)?;
+    }
+    Ok(())
+  }
+}
+
+fn write_duplicates(f: &mut fmt::Formatter, indent: &str, duplicates: &BTreeMap<DuplicateKind, usize>)
+  -> fmt::Result {
+  if duplicates.is_empty() { return Ok(()); }
+  let count = |kind| duplicates.get(&kind).cloned().unwrap_or(0);
+  writeln!(f, "{}duplicates: admin: {}, application: {}", indent, count(DuplicateKind::Admin),
+    count(DuplicateKind::Application))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{ValidationSummary, SessionSummary};
+  use super::super::{ValidationOptions, ErrorPolicy};
+  use super::super::session::{DuplicateKind, SessionId};
+  use super::super::stream::StreamValidator;
+
+  const LOG: &str = "8=FIX.4.2|9=23|35=0|49=BRKR|56=INVMGR|10=041|\n\
+    8=FIX.4.2|9=23|35=0|49=BRKR|56=INVMGR|10=042|\n\
+    8=FIX.4.2|9=23|35=0|49=INVMGR|56=BRKR|10=041|\n\
+    8=FIX.4.2|9=5|35=0|10=16|\n";
+
+  fn summarize(options: ValidationOptions) -> ValidationSummary {
+    let mut summary = ValidationSummary::new(&options);
+    for result in StreamValidator::new(LOG.as_bytes(), options) {
+      summary.record(&result.unwrap());
+    }
+    summary
+  }
+
+  #[test]
+  fn it_should_summarize_stream() {
+    let summary = summarize(ValidationOptions::new().delimiter('|').error_policy(ErrorPolicy::Skip));
+    assert_eq!(summary.total, 4);
+    assert_eq!(summary.valid, 2);
+    assert_eq!(summary.failed(), 2);
+    assert_eq!(summary.failures.get("ChecksumMismatch"), Some(&1));
+    assert_eq!(summary.failures.get("ChecksumFieldInvalidFormat"), Some(&1));
+    assert_eq!(summary.first_failure_offset, Some(46));
+    assert_eq!(summary.last_failure_offset, Some(138));
+    let brkr = &summary.sessions[&SessionId::new("BRKR", "INVMGR")];
+    assert_eq!((brkr.total, brkr.valid, brkr.failures), (2, 1, 1));
+    assert_eq!(brkr.failure_kinds.get("ChecksumMismatch"), Some(&1));
+    assert_eq!((brkr.first_failure_offset, brkr.last_failure_offset), (Some(46), Some(46)));
+    assert_eq!(summary.sessions.get(&SessionId::new("INVMGR", "BRKR")),
+      Some(&SessionSummary { total: 1, valid: 1, ..SessionSummary::default() }));
+    assert_eq!(summary.to_string(), "messages: 4, valid: 2, failed: 2\n  \
+      ChecksumFieldInvalidFormat: 1\n  ChecksumMismatch: 1\n\
+      first failure at offset 46, last failure at offset 138\n\
+      session BRKR->INVMGR: messages: 2, failed: 1\n    \
+      ChecksumMismatch: 1\n\
+      session INVMGR->BRKR: messages: 1, failed: 0\n");
+  }
+
+  #[test]
+  fn it_should_count_repaired_messages_as_failures() {
+    let summary = summarize(ValidationOptions::new().delimiter('|').error_policy(ErrorPolicy::Repair));
+    assert_eq!(summary.failures.get("Repaired"), Some(&2));
+    assert_eq!(summary.failed(), 2);
+  }
+
+  #[test]
+  fn it_should_count_duplicates_by_layer() {
+    let options = ValidationOptions::new().delimiter('|');
+    let log: Vec<u8> = ["35=0|49=A|56=B|34=1|", "35=0|49=A|56=B|34=1|", "35=D|49=A|56=B|34=2|",
+      "35=D|49=A|56=B|34=2|43=Y|", "35=D|49=B|56=A|34=2|"].iter()
+      .flat_map(|body| {
+        let mut message = super::super::finalize(format!("8=FIX.4.2|{}", body).as_bytes(), &options).unwrap();
+        message.push(b'\n');
+        message
+      }).collect();
+    let mut summary = ValidationSummary::new(&options);
+    for result in StreamValidator::new(&log[..], options.clone()) { summary.record(&result.unwrap()); }
+    assert_eq!(summary.duplicates.get(&DuplicateKind::Admin), Some(&1));
+    assert_eq!(summary.duplicates.get(&DuplicateKind::Application), Some(&1));
+    assert!(summary.to_string().contains("duplicates: admin: 1, application: 1\n"));
+    assert_eq!(summary.sessions[&SessionId::new("A", "B")].duplicates.get(&DuplicateKind::Admin), Some(&1));
+    assert!(summary.to_string().contains("session B->A: messages: 1, failed: 0\n"));
+  }
+}
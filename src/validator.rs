@@ -0,0 +1,251 @@
+//! Validation of a message against the checksum and user supplied rules.
+
+use std::fmt;
+use std::sync::Arc;
+
+use super::{FIXChecksumValidatorError, ValidationOptions, validate_bytes_with};
+use super::diagnose::body_length;
+use super::message::MessageView;
+
+/// How serious an issue is. Only errors make a message invalid.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+  Warning,
+  Error,
+}
+
+/// A problem found in a message.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Issue {
+  pub severity: Severity,
+  /// Tag of the offending field, if the issue is about a single field.
+  pub tag: Option<u32>,
+  pub message: String,
+}
+
+impl fmt::Display for Issue {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let severity = match self.severity {
+      Severity::Warning => "warning",
+      Severity::Error => "error",
+    };
+    match self.tag {
+      Some(tag) => write!(f, "{} (tag {}): {}", severity, tag, self.message),
+      None => write!(f, "{}: {}", severity, self.message),
+    }
+  }
+}
+
+/// Issues collected while validating one message.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ValidationReport {
+  issues: Vec<Issue>,
+}
+
+impl ValidationReport {
+  /// Creates an empty report.
+  pub fn new() -> ValidationReport {
+    ValidationReport { issues: Vec::new() }
+  }
+
+  /// Records an error.
+  pub fn error<S: Into<String>>(&mut self, tag: Option<u32>, message: S) {
+    self.issues.push(Issue { severity: Severity::Error, tag, message: message.into() });
+  }
+
+  /// Records a warning.
+  pub fn warning<S: Into<String>>(&mut self, tag: Option<u32>, message: S) {
+    self.issues.push(Issue { severity: Severity::Warning, tag, message: message.into() });
+  }
+
+  /// Records a validation error of the crate.
+  pub fn validation_error(&mut self, error: &FIXChecksumValidatorError) {
+    let tag = match *error {
+      FIXChecksumValidatorError::RequiredFieldMissing(tag) | FIXChecksumValidatorError::FieldValueInvalid(tag) =>
+        Some(tag),
+      _ => None,
+    };
+    self.error(tag, error.to_string());
+  }
+
+  /// Returns the issues in the order they were found.
+  pub fn issues(&self) -> &[Issue] {
+    &self.issues
+  }
+
+  /// Returns `true` if no error was recorded.
+  pub fn is_valid(&self) -> bool {
+    self.issues.iter().all(|issue| issue.severity != Severity::Error)
+  }
+}
+
+/// A check run on every message that passed checksum validation.
+///
+/// Closures taking the message and the report are rules too.
+pub trait ValidationRule {
+  fn check(&self, msg: &MessageView, report: &mut ValidationReport);
+}
+
+impl<F: Fn(&MessageView, &mut ValidationReport)> ValidationRule for F {
+  fn check(&self, msg: &MessageView, report: &mut ValidationReport) {
+    self(msg, report)
+  }
+}
+
+/// Validates messages with `ValidationOptions` and a registry of rules.
+///
+/// # Examples
+///
+/// ```
+/// use fix_checksum::ValidationOptions;
+/// use fix_checksum::message::MessageView;
+/// use fix_checksum::validator::{ValidationReport, Validator};
+///
+/// let validator = Validator::new(ValidationOptions::new().delimiter('|'))
+///   .rule(|msg: &MessageView, report: &mut ValidationReport| {
+///     if msg.get(35) == Some(b"D") && msg.get(1).is_none() {
+///       report.error(Some(1), "Account is required on orders");
+///     }
+///   });
+///
+/// let report = validator.validate(b"8=FIX.4.2|9=5|35=D|10=181|");
+/// assert!(!report.is_valid());
+/// assert_eq!(report.issues()[0].to_string(), "error (tag 1): Account is required on orders");
+/// ```
+///
+/// The options and rules are shared by the clones of a validator, so that
+/// one configured validator can be cloned cheaply into every worker thread
+/// of a gateway. Registering a rule on a clone leaves the others unchanged.
+///
+/// ```
+/// use std::thread;
+/// use fix_checksum::ValidationOptions;
+/// use fix_checksum::rules::HeaderOrder;
+/// use fix_checksum::validator::Validator;
+///
+/// let validator = Validator::new(ValidationOptions::new().delimiter('|')).rule(HeaderOrder);
+/// let workers: Vec<_> = (0..4).map(|_| {
+///   let validator = validator.clone();
+///   thread::spawn(move || validator.validate(b"8=FIX.4.2|9=5|35=0|10=161|").is_valid())
+/// }).collect();
+/// assert!(workers.into_iter().all(|worker| worker.join().unwrap()));
+/// ```
+#[derive(Clone)]
+pub struct Validator {
+  config: Arc<Config>,
+}
+
+/// The configuration shared by the clones of a `Validator`.
+#[derive(Clone)]
+struct Config {
+  options: ValidationOptions,
+  rules: Vec<Arc<dyn ValidationRule + Send + Sync>>,
+}
+
+impl Validator {
+  /// Creates a validator without rules.
+  pub fn new(options: ValidationOptions) -> Validator {
+    Validator { config: Arc::new(Config { options, rules: Vec::new() }) }
+  }
+
+  /// Registers a rule. Rules run in the order they were registered.
+  pub fn rule<R: ValidationRule + Send + Sync + 'static>(mut self, rule: R) -> Validator {
+    Arc::make_mut(&mut self.config).rules.push(Arc::new(rule));
+    self
+  }
+
+  /// Returns the options the checksum is validated with.
+  pub fn options(&self) -> &ValidationOptions {
+    &self.config.options
+  }
+
+  /// Validates the checksum of `message` and runs the rules on it. Rules
+  /// are skipped for messages failing the checksum or field parsing.
+  pub fn validate(&self, message: &[u8]) -> ValidationReport {
+    let mut report = ValidationReport::new();
+    let options = &self.config.options;
+    match validate_bytes_with(message, options) {
+      Ok(true) => {},
+      Ok(false) => { report.error(Some(10), "Checksum mismatch."); return report; },
+      Err(FIXChecksumValidatorError::BodyLengthMismatch) => {
+        match body_length(message, options) {
+          Some(mismatch) => report.error(Some(9), format!("Body length does not match message: {}.", mismatch)),
+          None => report.validation_error(&FIXChecksumValidatorError::BodyLengthMismatch),
+        }
+        return report;
+      },
+      Err(error) => { report.validation_error(&error); return report; },
+    }
+
+    match MessageView::parse(message, options.delimiter as char) {
+      Ok(view) => for rule in &self.config.rules { rule.check(&view, &mut report); },
+      Err(error) => report.validation_error(&error),
+    }
+    report
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{Severity, ValidationReport, ValidationRule, Validator};
+  use super::super::ValidationOptions;
+  use super::super::message::MessageView;
+
+  struct MaxFields(usize);
+
+  impl ValidationRule for MaxFields {
+    fn check(&self, msg: &MessageView, report: &mut ValidationReport) {
+      if msg.len() > self.0 { report.warning(None, format!("more than {} fields", self.0)); }
+    }
+  }
+
+  #[test]
+  fn it_should_run_rules_on_valid_messages() {
+    let validator = Validator::new(ValidationOptions::new().delimiter('|'))
+      .rule(MaxFields(3))
+      .rule(|msg: &MessageView, report: &mut ValidationReport| {
+        if msg.get(49).is_none() { report.error(Some(49), "SenderCompID is required"); }
+      });
+
+    let report = validator.validate(b"8=FIX.4.2|9=5|35=0|10=161|");
+    assert_eq!(report.issues().len(), 2);
+    assert_eq!(report.issues()[0].severity, Severity::Warning);
+    assert_eq!(report.issues()[0].to_string(), "warning: more than 3 fields");
+    assert_eq!(report.issues()[1].tag, Some(49));
+    assert!(!report.is_valid());
+
+    let report = Validator::new(ValidationOptions::new().delimiter('|')).rule(MaxFields(4))
+      .validate(b"8=FIX.4.2|9=5|35=0|10=161|");
+    assert!(report.is_valid() && report.issues().is_empty());
+  }
+
+  #[test]
+  fn it_should_skip_rules_on_invalid_messages() {
+    let validator = Validator::new(ValidationOptions::new().delimiter('|')).rule(MaxFields(0));
+
+    let report = validator.validate(b"8=FIX.4.2|9=5|35=0|10=162|");
+    assert_eq!(report.issues().len(), 1);
+    assert_eq!(report.issues()[0].to_string(), "error (tag 10): Checksum mismatch.");
+
+    let report = validator.validate(b"8=FIX.4.2|9=5|35=0|");
+    assert_eq!(report.issues()[0].to_string(), "error: Checksum field not found.");
+
+    let report = validator.validate(b"8=FIX.4.2|9=3|35|10=050|");
+    assert_eq!(report.issues().len(), 1);
+    assert!(!report.is_valid());
+
+    let report = Validator::new(ValidationOptions::new().delimiter('|').check_body_length(true))
+      .validate(b"8=FIX.4.2|9=9|35=0|10=161|");
+    assert_eq!(report.issues()[0].to_string(), "error (tag 9): Body length does not match message: declared 9, \
+      measured 5, body ends at offset 19 instead of 23: 4 missing byte(s).");
+  }
+
+  #[test]
+  fn it_should_keep_clones_unchanged() {
+    let validator = Validator::new(ValidationOptions::new().delimiter('|')).rule(MaxFields(4));
+    let stricter = validator.clone().rule(MaxFields(3));
+    assert!(validator.validate(b"8=FIX.4.2|9=5|35=0|10=161|").issues().is_empty());
+    assert_eq!(stricter.validate(b"8=FIX.4.2|9=5|35=0|10=161|").issues().len(), 1);
+    assert_eq!(stricter.options(), validator.options());
+  }
+}
@@ -0,0 +1,109 @@
+//! Exports for WebAssembly hosts, enabled by the `wasm` feature.
+//!
+//! The functions use plain C ABI so the module works with any wasm runtime
+//! and does not depend on a bindings generator: raw `extern "C"` exports
+//! stand in for `wasm-bindgen`, keeping the crate on std alone, and hosts
+//! copy strings in and out of memory themselves. Messages are passed as
+//! pointer/length pairs into linear memory obtained from
+//! `fix_checksum_alloc`; buffers handed back to the host are released with
+//! `fix_checksum_free`.
+//!
+//! The tests call the exports natively; the `wasm32-unknown-unknown` build
+//! itself is not exercised by them.
+
+use std::mem;
+use std::ptr;
+use std::slice;
+
+use super::{ValidationOptions, validate_bytes_with};
+use super::pretty::pretty_print;
+
+/// Allocates `len` bytes of linear memory for the host to write into.
+#[no_mangle]
+pub extern "C" fn fix_checksum_alloc(len: usize) -> *mut u8 {
+  let mut buffer = vec![0u8; len].into_boxed_slice();
+  let ptr = buffer.as_mut_ptr();
+  mem::forget(buffer);
+  ptr
+}
+
+/// Releases memory returned by `fix_checksum_alloc` or `fix_checksum_pretty_print`.
+///
+/// # Safety
+///
+/// `ptr` and `len` must come from one of those functions and must not be
+/// used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn fix_checksum_free(ptr: *mut u8, len: usize) {
+  drop(Box::from_raw(ptr::slice_from_raw_parts_mut(ptr, len)));
+}
+
+/// Validates the message at `ptr`. Returns 1 when the checksum is valid, 0
+/// when it does not match, and the negated error code otherwise. A non-ASCII
+/// `delimiter` is treated as SOH.
+///
+/// # Safety
+///
+/// `ptr` must point to `len` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn fix_checksum_validate(ptr: *const u8, len: usize, delimiter: u8) -> i32 {
+  let message = slice::from_raw_parts(ptr, len);
+  match validate_bytes_with(message, &options(delimiter)) {
+    Ok(valid) => valid as i32,
+    Err(err) => -(err.error_code() as i32),
+  }
+}
+
+/// Pretty prints the message at `ptr`. Returns a buffer of `*out_len` bytes
+/// of UTF-8 text, or null when the message can not be parsed.
+///
+/// # Safety
+///
+/// `ptr` must point to `len` readable bytes and `out_len` must be writable.
+#[no_mangle]
+pub unsafe extern "C" fn fix_checksum_pretty_print(ptr: *const u8, len: usize, delimiter: u8,
+  out_len: *mut usize) -> *mut u8 {
+  let message = slice::from_raw_parts(ptr, len);
+  let delimiter = options(delimiter).delimiter as char;
+  match pretty_print(message, delimiter) {
+    Ok(text) => {
+      let mut buffer = text.into_bytes().into_boxed_slice();
+      *out_len = buffer.len();
+      let ptr = buffer.as_mut_ptr();
+      mem::forget(buffer);
+      ptr
+    },
+    Err(_) => ::std::ptr::null_mut(),
+  }
+}
+
+fn options(delimiter: u8) -> ValidationOptions {
+  let options = ValidationOptions::new();
+  if delimiter.is_ascii() { options.delimiter(delimiter as char) } else { options }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::slice;
+
+  use super::{fix_checksum_alloc, fix_checksum_free, fix_checksum_validate, fix_checksum_pretty_print};
+
+  #[test]
+  fn it_should_validate_through_c_abi() {
+    let message = b"8=FIX.4.2|9=5|35=0|10=161|";
+    unsafe {
+      let ptr = fix_checksum_alloc(message.len());
+      ptr.copy_from_nonoverlapping(message.as_ptr(), message.len());
+      assert_eq!(fix_checksum_validate(ptr, message.len(), b'|'), 1);
+      assert_eq!(fix_checksum_validate(ptr, message.len(), 1), -2);
+
+      let mut len = 0;
+      let text = fix_checksum_pretty_print(ptr, message.len(), b'|', &mut len);
+      assert_eq!(slice::from_raw_parts(text, len), &b"BeginString (8) = FIX.4.2\nBodyLength (9) = 5\n\
+        MsgType (35) = 0\nCheckSum (10) = 161\n"[..]);
+      fix_checksum_free(text, len);
+      fix_checksum_free(ptr, message.len());
+      fix_checksum_free(fix_checksum_alloc(0), 0);
+    }
+  }
+}
@@ -0,0 +1,355 @@
+//! Writers completing every message with its checksum.
+//!
+//! Send paths write the fields of a message piece by piece, from several
+//! places of the application, and only know the message is complete at the
+//! end. `MessageWriter` keeps the message being written, appends
+//! `10=XXX<delimiter>` when `end_message` is called and hands the complete
+//! message to the underlying writer in one write. `MessageSink` is the
+//! counterpart of `stream::MessageReader` for applications building
+//! `FixMessage` values: it writes each one with a fresh BodyLength and
+//! CheckSum.
+//!
+//! For negative testing of a counterparty, the `fault_injection` feature
+//! lets both run middlewares over every finalized message before it is
+//! written, to damage it on purpose. The feature is meant for test builds
+//! only.
+//!
+//! # Examples
+//!
+//! ```
+//! use std::io::Write;
+//! use fix_checksum::ValidationOptions;
+//! use fix_checksum::writer::MessageWriter;
+//!
+//! let mut writer = MessageWriter::new(Vec::new(), &ValidationOptions::new().delimiter('|'));
+//! write!(writer, "8=FIX.4.2|9=5|").unwrap();
+//! writer.write_all(b"35=0|").unwrap();
+//! writer.end_message().unwrap();
+//! assert_eq!(writer.get_ref(), b"8=FIX.4.2|9=5|35=0|10=161|");
+//! ```
+
+#[cfg(feature = "fault_injection")]
+use std::fmt;
+use std::io;
+use std::io::Write;
+
+use super::{ValidationOptions, FIX_CHECKSUM_FIELD, checksum_bytes, checksum_digits, finalize};
+use super::message::FixMessage;
+
+/// Mutates a finalized message before it is written.
+#[cfg(feature = "fault_injection")]
+type Middleware = Box<dyn FnMut(&mut Vec<u8>) + Send>;
+
+/// The middlewares of a writer, in the order they run.
+#[cfg(feature = "fault_injection")]
+#[derive(Default)]
+struct Middlewares(Vec<Middleware>);
+
+#[cfg(feature = "fault_injection")]
+impl Middlewares {
+  fn apply(&mut self, message: &mut Vec<u8>) {
+    for middleware in &mut self.0 { middleware(message); }
+  }
+}
+
+#[cfg(feature = "fault_injection")]
+impl fmt::Debug for Middlewares {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "Middlewares({})", self.0.len())
+  }
+}
+
+/// Buffers the message being written and writes it with its CheckSum (10)
+/// once complete.
+#[derive(Debug)]
+pub struct MessageWriter<W: Write> {
+  inner: W,
+  message: Vec<u8>,
+  // Set once the checksum is appended, until the message is written
+  complete: bool,
+  delimiter: u8,
+  messages: u64,
+  #[cfg(feature = "fault_injection")]
+  middlewares: Middlewares,
+}
+
+impl<W: Write> MessageWriter<W> {
+  /// Creates a writer of messages delimited as `options` set to `inner`.
+  pub fn new(inner: W, options: &ValidationOptions) -> MessageWriter<W> {
+    MessageWriter {
+      inner,
+      message: Vec::new(),
+      complete: false,
+      delimiter: options.delimiter,
+      messages: 0,
+      #[cfg(feature = "fault_injection")]
+      middlewares: Middlewares::default(),
+    }
+  }
+
+  /// Adds a middleware run over every message once its checksum is
+  /// appended, in the order added, to damage outbound messages in tests.
+  #[cfg(feature = "fault_injection")]
+  pub fn middleware<F: FnMut(&mut Vec<u8>) + Send + 'static>(mut self, middleware: F) -> MessageWriter<W> {
+    self.middlewares.0.push(Box::new(middleware));
+    self
+  }
+
+  /// Appends the checksum field to the message written since the last
+  /// call, adding the delimiter before it if the message does not end with
+  /// one, then writes the message and flushes the underlying writer. Fails
+  /// with `InvalidInput` if nothing was written.
+  ///
+  /// A message the underlying writer failed to take is kept complete: the
+  /// next call writes it again and writes fail until then.
+  pub fn end_message(&mut self) -> io::Result<()> {
+    if self.message.is_empty() {
+      return Err(io::Error::new(io::ErrorKind::InvalidInput, "no message to end"));
+    }
+    if !self.complete {
+      if self.message.last() != Some(&self.delimiter) { self.message.push(self.delimiter); }
+      let checksum = checksum_digits(checksum_bytes(&self.message, self.delimiter));
+      self.message.extend_from_slice(FIX_CHECKSUM_FIELD.as_bytes());
+      self.message.extend_from_slice(checksum);
+      self.message.push(self.delimiter);
+      #[cfg(feature = "fault_injection")]
+      self.middlewares.apply(&mut self.message);
+      self.complete = true;
+    }
+
+    self.inner.write_all(&self.message)?;
+    self.message.clear();
+    self.complete = false;
+    self.messages += 1;
+    self.inner.flush()
+  }
+
+  /// Returns the bytes of the message being written.
+  pub fn pending(&self) -> &[u8] {
+    &self.message
+  }
+
+  /// Returns the number of messages written.
+  pub fn messages(&self) -> u64 {
+    self.messages
+  }
+
+  /// Returns the underlying writer.
+  pub fn get_ref(&self) -> &W {
+    &self.inner
+  }
+
+  /// Returns the underlying writer mutably, for example to take the
+  /// messages written to a `Vec<u8>` and send them by other means.
+  pub fn get_mut(&mut self) -> &mut W {
+    &mut self.inner
+  }
+
+  /// Returns the underlying writer, dropping a message not ended.
+  pub fn into_inner(self) -> W {
+    self.inner
+  }
+}
+
+/// Writes go to the message being written: nothing reaches the underlying
+/// writer before `end_message`.
+impl<W: Write> Write for MessageWriter<W> {
+  fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+    if self.complete {
+      return Err(io::Error::other("the previous message is not written yet, see `end_message`"));
+    }
+    self.message.extend_from_slice(buf);
+    Ok(buf.len())
+  }
+
+  /// Flushes the messages already ended.
+  fn flush(&mut self) -> io::Result<()> {
+    self.inner.flush()
+  }
+}
+
+/// Finalizes and writes messages to an underlying writer.
+///
+/// # Examples
+///
+/// ```
+/// use fix_checksum::ValidationOptions;
+/// use fix_checksum::message::FixMessage;
+/// use fix_checksum::writer::MessageSink;
+///
+/// let mut heartbeat = FixMessage::new('|');
+/// heartbeat.push(8, b"FIX.4.2");
+/// heartbeat.push(35, b"0");
+/// let mut sink = MessageSink::new(Vec::new(), ValidationOptions::new().delimiter('|'));
+/// sink.send(&heartbeat).unwrap();
+/// assert_eq!(sink.into_inner(), b"8=FIX.4.2|9=5|35=0|10=161|");
+/// ```
+#[derive(Debug)]
+pub struct MessageSink<W: Write> {
+  inner: W,
+  options: ValidationOptions,
+  messages: u64,
+  #[cfg(feature = "fault_injection")]
+  middlewares: Middlewares,
+}
+
+impl<W: Write> MessageSink<W> {
+  /// Creates a sink writing messages delimited as `options` set to `inner`.
+  pub fn new(inner: W, options: ValidationOptions) -> MessageSink<W> {
+    MessageSink {
+      inner,
+      options,
+      messages: 0,
+      #[cfg(feature = "fault_injection")]
+      middlewares: Middlewares::default(),
+    }
+  }
+
+  /// Adds a middleware run over every message once it is finalized, in the
+  /// order added, to damage outbound messages in tests.
+  #[cfg(feature = "fault_injection")]
+  pub fn middleware<F: FnMut(&mut Vec<u8>) + Send + 'static>(mut self, middleware: F) -> MessageSink<W> {
+    self.middlewares.0.push(Box::new(middleware));
+    self
+  }
+
+  /// Writes `message` with its BodyLength and CheckSum recomputed, see
+  /// `finalize`. Messages are written as they are sent: wrap the underlying
+  /// writer in a `BufWriter` to batch them, and flush it. A message that can
+  /// not be finalized fails with `InvalidInput` and nothing is written.
+  pub fn send(&mut self, message: &FixMessage) -> io::Result<()> {
+    #[cfg_attr(not(feature = "fault_injection"), allow(unused_mut))]
+    let mut finalized = finalize(&message.serialize(), &self.options)
+      .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+    #[cfg(feature = "fault_injection")]
+    self.middlewares.apply(&mut finalized);
+    self.inner.write_all(&finalized)?;
+    self.messages += 1;
+    Ok(())
+  }
+
+  /// Sends every message of `messages` in order, stopping at the first
+  /// failure.
+  pub fn send_all<'a, I: IntoIterator<Item = &'a FixMessage>>(&mut self, messages: I) -> io::Result<()> {
+    messages.into_iter().try_for_each(|message| self.send(message))
+  }
+
+  /// Flushes the underlying writer.
+  pub fn flush(&mut self) -> io::Result<()> {
+    self.inner.flush()
+  }
+
+  /// Returns the number of messages written.
+  pub fn messages(&self) -> u64 {
+    self.messages
+  }
+
+  /// Returns the underlying writer.
+  pub fn get_ref(&self) -> &W {
+    &self.inner
+  }
+
+  /// Returns the underlying writer.
+  pub fn into_inner(self) -> W {
+    self.inner
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::io;
+  use std::io::Write;
+  use super::{MessageSink, MessageWriter};
+  use super::super::{ValidationOptions, validate_bytes_with};
+  use super::super::message::FixMessage;
+  use super::super::stream::MessageReader;
+
+  struct Failing(usize);
+
+  impl Write for Failing {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+      if self.0 == 0 { return Err(io::Error::new(io::ErrorKind::BrokenPipe, "closed")); }
+      self.0 -= 1;
+      Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+      Ok(())
+    }
+  }
+
+  #[test]
+  fn it_should_end_messages_with_checksum() {
+    let options = ValidationOptions::new();
+    let mut writer = MessageWriter::new(Vec::new(), &options);
+    assert_eq!(writer.end_message().unwrap_err().kind(), io::ErrorKind::InvalidInput);
+
+    writer.write_all(b"8=FIX.4.2\x019=5\x0135=0").unwrap();
+    writer.flush().unwrap();
+    assert!(writer.get_ref().is_empty());
+    writer.end_message().unwrap();
+    writer.write_all(b"8=FIX.4.2\x019=5\x0135=1\x01").unwrap();
+    writer.end_message().unwrap();
+    assert_eq!(writer.messages(), 2);
+    assert_eq!(writer.get_mut().len(), 52);
+
+    let written = writer.into_inner();
+    assert_eq!(written, b"8=FIX.4.2\x019=5\x0135=0\x0110=161\x018=FIX.4.2\x019=5\x0135=1\x0110=162\x01".to_vec());
+    assert!(validate_bytes_with(&written[..26], &options).unwrap());
+  }
+
+  #[test]
+  fn it_should_keep_message_on_failed_write() {
+    let mut writer = MessageWriter::new(Failing(0), &ValidationOptions::new().delimiter('|'));
+    writer.write_all(b"8=FIX.4.2|9=5|35=0|").unwrap();
+    assert_eq!(writer.end_message().unwrap_err().kind(), io::ErrorKind::BrokenPipe);
+    assert_eq!(writer.pending(), b"8=FIX.4.2|9=5|35=0|10=161|");
+    assert!(writer.write_all(b"8=").is_err());
+    assert_eq!(writer.messages(), 0);
+
+    let mut writer = MessageWriter { inner: Failing(1), ..writer };
+    writer.end_message().unwrap();
+    assert_eq!(writer.messages(), 1);
+    assert!(writer.pending().is_empty());
+  }
+
+  #[test]
+  fn it_should_write_messages_read_back_by_reader() {
+    let options = ValidationOptions::new().check_body_length(true).strict(true);
+    let messages: Vec<_> = ["8=FIX.4.2\x019=99\x0135=0\x0110=000\x01", "8=FIX.4.2\x0135=1\x01112=TEST\x01"].iter()
+      .map(|message| FixMessage::parse(message.as_bytes(), '\x01').unwrap()).collect();
+    let mut sink = MessageSink::new(Vec::new(), options.clone());
+    sink.send_all(&messages).unwrap();
+    sink.flush().unwrap();
+    assert_eq!(sink.messages(), 2);
+    assert_eq!(sink.send(&FixMessage::new('\x01')).unwrap_err().kind(), io::ErrorKind::InvalidInput);
+    assert_eq!(sink.messages(), 2);
+
+    let written = sink.into_inner();
+    let frames: Vec<_> = MessageReader::new(&written[..], '\x01').map(|frame| frame.unwrap().bytes).collect();
+    assert_eq!(frames.len(), 2);
+    assert!(frames.iter().all(|frame| validate_bytes_with(frame, &options).unwrap()));
+  }
+
+  #[cfg(feature = "fault_injection")]
+  #[test]
+  fn it_should_run_middlewares_after_finalization() {
+    let options = ValidationOptions::new().delimiter('|');
+    let mut writer = MessageWriter::new(Vec::new(), &options)
+      .middleware(|message: &mut Vec<u8>| { let end = message.len() - 2; message[end] = b'0'; })
+      .middleware(|message: &mut Vec<u8>| message.extend_from_slice(b"\n"));
+    writer.write_all(b"8=FIX.4.2|9=5|35=0|").unwrap();
+    writer.end_message().unwrap();
+    assert_eq!(writer.get_ref(), b"8=FIX.4.2|9=5|35=0|10=160|\n");
+
+    let mut heartbeat = FixMessage::new('|');
+    heartbeat.push(8, b"FIX.4.2");
+    heartbeat.push(35, b"0");
+    let mut sink = MessageSink::new(Vec::new(), options.clone()).middleware(|message: &mut Vec<u8>| {
+      let trailer = message.len() - 7;
+      message.truncate(trailer);
+    });
+    sink.send(&heartbeat).unwrap();
+    assert_eq!(sink.into_inner(), b"8=FIX.4.2|9=5|35=0|");
+  }
+}
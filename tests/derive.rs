@@ -0,0 +1,40 @@
+#![cfg(feature = "derive")]
+
+extern crate fix_checksum;
+
+use fix_checksum::FixMessageSpec;
+use fix_checksum::FIXChecksumValidatorError::{RequiredFieldMissing, FieldValueInvalid};
+use fix_checksum::orders::Header;
+
+#[derive(Debug, PartialEq, FixMessageSpec)]
+#[fix(msg_type = "D")]
+struct Order {
+  /// Client order id.
+  #[fix(tag = 11)]
+  pub cl_ord_id: String,
+  #[fix(tag = 55)]
+  symbol: String,
+  #[fix(tag = 54)]
+  side: char,
+  #[fix(tag = 38)]
+  order_qty: u64,
+  #[fix(tag = 44)]
+  price: Option<f64>,
+}
+
+#[test]
+fn it_should_derive_message_spec() {
+  let header = Header::new("FIX.4.2", "BUYER", "SELLER", 1, "20240102-09:30:00");
+  let order = Order { cl_ord_id: "ORD-1".to_string(), symbol: "IBM".to_string(), side: '1', order_qty: 100,
+    price: None };
+  let message = order.encode(&header).unwrap();
+  assert!(String::from_utf8(message.clone()).unwrap().contains("35=D\x01"));
+  assert!(String::from_utf8(message.clone()).unwrap().contains("11=ORD-1\x0155=IBM\x0154=1\x0138=100\x0110="));
+  assert!(fix_checksum::validate(&String::from_utf8(message.clone()).unwrap()).unwrap());
+  assert_eq!(Order::decode(&message, '\x01').unwrap(), order);
+
+  let message = b"8=FIX.4.2|35=D|11=ORD-2|55=IBM|54=2|38=5|44=10.5|10=000|";
+  assert_eq!(Order::decode(message, '|').unwrap().price, Some(10.5));
+  assert_eq!(Order::decode(b"8=FIX.4.2|35=D|11=ORD-2|55=IBM|54=2|", '|').unwrap_err(), RequiredFieldMissing(38));
+  assert_eq!(Order::decode(b"8=FIX.4.2|35=F|", '|').unwrap_err(), FieldValueInvalid(35));
+}
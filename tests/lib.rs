@@ -1,13 +1,14 @@
 extern crate fix_checksum;
 
-use fix_checksum::{validate, generate};
+use fix_checksum::{validate, validate_with, generate, ValidationOptions};
 use fix_checksum::FIXChecksumValidatorError::{InvalidEmptyMessage, ChecksumFieldNotFound,
-  ChecksumFieldInvalidFormat};
+  ChecksumFieldInvalidFormat, MessageTooLarge, BodyLengthFieldNotFound, BodyLengthMismatch,
+  ChecksumFieldNotLast};
 
 fn brew_message(message_parts: Vec<&str>, delimiter: &str) -> String {
-  return message_parts
+  message_parts
     .iter()
-    .fold(String::new(), |message, message_part| message.to_string() + message_part + delimiter);
+    .fold(String::new(), |message, message_part| message.to_string() + message_part + delimiter)
 }
 
 #[test]
@@ -28,12 +29,41 @@ fn it_should_validate_fix_message_checksum() {
   message_parts = vec!["8=FIX.4.2", "9=73", "35=0", "49=BRKR", "56=INVMGR",
     "34=235", "52=19980604-07:58:28", "112=19980604-07:58:28", "10=231"];
   message = brew_message(message_parts, "\x01");
-  assert_eq!(validate(&message).unwrap(), false);
+  assert!(!validate(&message).unwrap());
 
   message_parts = vec!["8=FIX.4.2", "9=73", "35=0", "49=BRKR", "56=INVMGR",
     "34=235", "52=19980604-07:58:28", "112=19980604-07:58:28", "10=236"];
   message = brew_message(message_parts, "\x01");
-  assert_eq!(validate(&message).unwrap(), true);
+  assert!(validate(&message).unwrap());
+}
+
+#[test]
+fn it_should_validate_fix_message_checksum_with_options() {
+  let message_parts: Vec<&str> = vec!["8=FIX.4.2", "9=73", "35=0", "49=BRKR", "56=INVMGR",
+    "34=235", "52=19980604-07:58:28", "112=19980604-07:58:28", "10=236"];
+  let mut message: String = brew_message(message_parts, "|");
+  let mut options = ValidationOptions::new();
+  assert_eq!(validate_with(&message, &options).unwrap_err(), ChecksumFieldNotFound);
+
+  options = options.delimiter('|').check_body_length(true).strict(true);
+  assert!(validate_with(&message, &options).unwrap());
+
+  assert_eq!(validate_with(&message, &options.clone().max_message_size(32)).unwrap_err(),
+    MessageTooLarge);
+
+  message.push('\n');
+  assert_eq!(validate_with(&message, &options).unwrap_err(), ChecksumFieldNotLast);
+
+  message = brew_message(vec!["8=FIX.4.2", "9=72", "35=0", "49=BRKR", "56=INVMGR", "34=235",
+    "52=19980604-07:58:28", "112=19980604-07:58:28", "10=235"], "|");
+  assert_eq!(validate_with(&message, &options).unwrap_err(), BodyLengthMismatch);
+
+  message = brew_message(vec!["8=FIX.4.2", "35=0", "10=000"], "|");
+  assert_eq!(validate_with(&message, &options).unwrap_err(), BodyLengthFieldNotFound);
+
+  message = brew_message(vec!["8=FIX.4.2", "9=5", "35=0", "10=+61"], "|");
+  assert_eq!(validate_with(&message, &options).unwrap_err(),
+    ChecksumFieldInvalidFormat("+".parse::<u32>().unwrap_err()));
 }
 
 #[test]
@@ -43,3 +73,25 @@ fn it_should_generate_fix_message_checksum() {
   let message: String = brew_message(message_parts, "\x01");
   assert_eq!("236", generate(&message));
 }
+
+#[test]
+fn it_should_validate_fix_message_stream() {
+  use fix_checksum::ErrorPolicy;
+  use fix_checksum::stream::{StreamValidator, Outcome};
+
+  let valid = brew_message(vec!["8=FIX.4.2", "9=5", "35=0", "10=161"], "\x01");
+  let invalid = brew_message(vec!["8=FIX.4.2", "9=5", "35=0", "10=162"], "\x01");
+  let log = valid.clone() + "\n" + &invalid + "\n" + &valid;
+
+  let options = ValidationOptions::new().error_policy(ErrorPolicy::Stop);
+  let outcomes: Vec<Outcome> = StreamValidator::new(log.as_bytes(), options)
+    .map(|result| result.unwrap().outcome)
+    .collect();
+  assert_eq!(outcomes, vec![Outcome::Valid, Outcome::ChecksumMismatch]);
+
+  let options = ValidationOptions::new().error_policy(ErrorPolicy::Repair);
+  let messages: Vec<Vec<u8>> = StreamValidator::new(log.as_bytes(), options)
+    .map(|result| result.unwrap().message)
+    .collect();
+  assert_eq!(messages, vec![valid.clone().into_bytes(), valid.clone().into_bytes(), valid.into_bytes()]);
+}